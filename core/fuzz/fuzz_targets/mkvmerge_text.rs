@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use media_toc_core::metadata::{Duration, MKVMergeTextFormat, MediaInfo, Reader};
+
+fuzz_target!(|data: &[u8]| {
+    gst::init().unwrap();
+
+    let mut info = MediaInfo::default();
+    info.duration = Duration::from_secs(3_600);
+
+    // Errors are expected, panics are not
+    let _ = MKVMergeTextFormat {}.read(&info, &mut &data[..]);
+});
@@ -0,0 +1,67 @@
+//! The media and metadata layers of `media-toc-player`.
+//!
+//! This crate doesn't depend on GTK: it can be used to build other frontends
+//! on top of the GStreamer based [`PlaybackPipeline`](media::PlaybackPipeline)
+//! and of the toc handling tools from [`metadata`].
+//!
+//! GStreamer must be initialized before using the crate. The
+//! [`PlaybackPipeline`](media::PlaybackPipeline) attaches its bus watch to
+//! the default `glib::MainContext` which must be iterated by the application.
+//!
+//! # Examples
+//!
+//! Open a media and seek to its middle:
+//!
+//! ```no_run
+//! use media_toc_core::{
+//!     media::{PlaybackOptions, PlaybackPipeline, Timestamp},
+//!     metadata::MediaPath,
+//! };
+//!
+//! gst::init().unwrap();
+//!
+//! let path = MediaPath::new("/path/to/media.ogg");
+//! glib::MainContext::default().block_on(async {
+//!     let options = PlaybackOptions {
+//!         max_video_height: Some(720),
+//!         ..PlaybackOptions::default()
+//!     };
+//!     let mut pipeline = PlaybackPipeline::try_new(&path, &None, options)
+//!         .await
+//!         .expect("couldn't open the media");
+//!
+//!     println!("{}: {}", pipeline.info.name, pipeline.info.duration);
+//!
+//!     let middle = Timestamp::from(pipeline.info.duration / 2);
+//!     pipeline
+//!         .seek(middle, gst::SeekFlags::ACCURATE)
+//!         .await
+//!         .expect("couldn't seek");
+//!     pipeline.play().await.expect("couldn't play");
+//! });
+//! ```
+//!
+//! Build a toc and walk through its chapters:
+//!
+//! ```
+//! use media_toc_core::metadata::{Duration, TocBuilder, TocChapter, TocVisitor};
+//!
+//! gst::init().unwrap();
+//!
+//! let mut toc_builder = TocBuilder::new();
+//! toc_builder.add_chapter(TocChapter::new(Duration::default(), "Intro"));
+//! toc_builder.add_chapter(TocChapter::new(Duration::from_secs(90), "Verse"));
+//! let toc = toc_builder.build(Duration::from_secs(180)).unwrap();
+//!
+//! let mut toc_visitor = TocVisitor::new(&toc);
+//! assert!(toc_visitor.enter_chapters());
+//!
+//! let first = toc_visitor.next_chapter().unwrap();
+//! assert_eq!(Some((0, 90_000_000_000)), first.get_start_stop_times());
+//! let second = toc_visitor.next_chapter().unwrap();
+//! assert_eq!(Some((90_000_000_000, 180_000_000_000)), second.get_start_stop_times());
+//! assert!(toc_visitor.next_chapter().is_none());
+//! ```
+
+pub mod media;
+pub mod metadata;
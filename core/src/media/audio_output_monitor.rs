@@ -0,0 +1,117 @@
+use gst::prelude::*;
+
+use log::debug;
+
+const AUDIO_SINK_CLASSES: &str = "Audio/Sink";
+// `gst::MessageView::DeviceChanged` requires the `v1_16` feature
+const DEVICE_CHANGED_MSG: &str = "GstMessageDeviceChanged";
+
+fn is_default(device: &gst::Device) -> bool {
+    device.get_properties().map_or(false, |properties| {
+        properties.get_some::<bool>("is-default").unwrap_or(false)
+    })
+}
+
+// Identifies the output, including its active port when the provider exposes it
+fn signature(device: &gst::Device) -> String {
+    match device.get_properties() {
+        Some(properties) => properties.to_string(),
+        None => device.get_display_name().to_string(),
+    }
+}
+
+/// Detects when the default audio output disappears, e.g. when headphones are unplugged.
+///
+/// Relies on the GStreamer device providers (PulseAudio, PipeWire, ...).
+/// The monitor stops when dropped.
+pub struct AudioOutputMonitor {
+    device_monitor: gst::DeviceMonitor,
+    bus_watch_src_id: Option<glib::SourceId>,
+}
+
+impl AudioOutputMonitor {
+    /// Calls `on_output_lost` from the main context when the default output
+    /// is removed or is switched to another port.
+    pub fn start<F>(on_output_lost: F) -> Result<Self, glib::BoolError>
+    where
+        F: Fn() + 'static,
+    {
+        let device_monitor = gst::DeviceMonitor::new();
+        device_monitor.add_filter(Some(AUDIO_SINK_CLASSES), None);
+        device_monitor.start()?;
+
+        let mut default_output = device_monitor
+            .get_devices()
+            .iter()
+            .find(|device| is_default(device))
+            .map(signature);
+        debug!("default audio output: {:?}", default_output);
+
+        let bus_watch_src_id = device_monitor.get_bus().add_watch_local(move |_, msg| {
+            use gst::MessageView::*;
+
+            match msg.view() {
+                DeviceAdded(device_added) => {
+                    let device = device_added.get_device();
+                    if is_default(&device) {
+                        default_output = Some(signature(&device));
+                    }
+                }
+                DeviceRemoved(device_removed) => {
+                    let device = device_removed.get_device();
+                    if default_output.as_ref() == Some(&signature(&device)) {
+                        debug!("default audio output removed");
+                        default_output = None;
+                        on_output_lost();
+                    }
+                }
+                _ => {
+                    // Reported since GStreamer 1.16, e.g. when the active port changes
+                    let (device, prev_device) = match msg
+                        .get_structure()
+                        .filter(|structure| structure.get_name() == DEVICE_CHANGED_MSG)
+                        .and_then(|structure| {
+                            let device = structure.get_some::<gst::Device>("device").ok()?;
+                            let prev_device =
+                                structure.get_some::<gst::Device>("device-changed").ok()?;
+                            Some((device, prev_device))
+                        }) {
+                        Some(devices) => devices,
+                        None => return glib::Continue(true),
+                    };
+
+                    let is_prev_default = default_output.as_ref() == Some(&signature(&prev_device));
+                    let new_output = if is_default(&device) {
+                        Some(signature(&device))
+                    } else {
+                        None
+                    };
+
+                    if is_prev_default && new_output != default_output {
+                        debug!("default audio output changed");
+                        on_output_lost();
+                    }
+                    if is_prev_default || new_output.is_some() {
+                        default_output = new_output;
+                    }
+                }
+            }
+
+            glib::Continue(true)
+        })?;
+
+        Ok(AudioOutputMonitor {
+            device_monitor,
+            bus_watch_src_id: Some(bus_watch_src_id),
+        })
+    }
+}
+
+impl Drop for AudioOutputMonitor {
+    fn drop(&mut self) {
+        if let Some(bus_watch_src_id) = self.bus_watch_src_id.take() {
+            glib::source_remove(bus_watch_src_id);
+        }
+        self.device_monitor.stop();
+    }
+}
@@ -0,0 +1,66 @@
+use std::{fmt, str::FromStr};
+
+/// How the `PlaybackPipeline` is built and driven.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backend {
+    /// `decodebin3` with the audio and video branches built as the streams show up.
+    DecodeBin,
+    /// `playbin3` driven by the GStreamer Player API.
+    ///
+    /// Meant for comparing the robustness of both approaches when debugging regressions.
+    #[cfg(feature = "player")]
+    Player,
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::DecodeBin
+    }
+}
+
+impl Backend {
+    /// Names of the backends available in this build.
+    pub fn names() -> &'static [&'static str] {
+        &[
+            "decodebin",
+            #[cfg(feature = "player")]
+            "player",
+        ]
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::DecodeBin => f.write_str("decodebin"),
+            #[cfg(feature = "player")]
+            Backend::Player => f.write_str("player"),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "decodebin" => Ok(Backend::DecodeBin),
+            #[cfg(feature = "player")]
+            "player" => Ok(Backend::Player),
+            other => Err(format!("unknown backend {}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        for name in Backend::names() {
+            assert_eq!(*name, name.parse::<Backend>().unwrap().to_string());
+        }
+        assert!("playbin".parse::<Backend>().is_err());
+    }
+}
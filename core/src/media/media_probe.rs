@@ -0,0 +1,73 @@
+use gettextrs::gettext;
+
+use gst::prelude::*;
+
+use crate::metadata::{Duration, MediaPath};
+
+const PREROLL_TIMEOUT: u64 = 10_000_000_000; // 10 s
+
+/// Prerolls the media at `path` in order to retrieve its duration and its toc.
+///
+/// This is a blocking function which is expected to run off the UI thread.
+pub fn probe(path: &MediaPath) -> Result<(Duration, Option<gst::Toc>), String> {
+    let uri = path.uri()?;
+
+    let pipeline = gst::Pipeline::new(Some("media_probe"));
+    let decodebin = gst::ElementFactory::make("uridecodebin", None)
+        .map_err(|_| gettext("Missing plugin:\n{}").replacen("{}", "- uridecodebin", 1))?;
+    decodebin.set_property("uri", &uri).unwrap();
+    pipeline.add(&decodebin).unwrap();
+
+    // Each stream must reach a sink for the pipeline to preroll
+    let pipeline_weak = pipeline.downgrade();
+    decodebin.connect_pad_added(move |_decodebin, src_pad| {
+        let pipeline = match pipeline_weak.upgrade() {
+            Some(pipeline) => pipeline,
+            None => return,
+        };
+        let sink = match gst::ElementFactory::make("fakesink", None) {
+            Ok(sink) => sink,
+            Err(_) => return,
+        };
+
+        if pipeline.add(&sink).is_ok() {
+            let _ = sink.sync_state_with_parent();
+            let _ = src_pad.link(&sink.get_static_pad("sink").unwrap());
+        }
+    });
+
+    let bus = pipeline.get_bus().unwrap();
+    let mut toc = None;
+    let res = pipeline
+        .set_state(gst::State::Paused)
+        .map_err(|_| gettext("Failed to switch the media to Paused"))
+        .and_then(|_| loop {
+            let msg = match bus.timed_pop(gst::ClockTime::from_nseconds(PREROLL_TIMEOUT)) {
+                Some(msg) => msg,
+                None => break Err(gettext("Timeout while opening the media")),
+            };
+
+            use gst::MessageView::*;
+            match msg.view() {
+                AsyncDone(_) => {
+                    break pipeline
+                        .query_duration::<gst::ClockTime>()
+                        .and_then(|duration| duration.nanoseconds())
+                        .map(Duration::from_nanos)
+                        .ok_or_else(|| gettext("Couldn't determine the media duration"))
+                }
+                Error(err) => break Err(err.get_error().to_string()),
+                Toc(msg_toc) => {
+                    let (msg_toc, _updated) = msg_toc.get_toc();
+                    if msg_toc.get_scope() == gst::TocScope::Global {
+                        toc = Some(msg_toc);
+                    }
+                }
+                _ => (),
+            }
+        });
+
+    let _ = pipeline.set_state(gst::State::Null);
+
+    res.map(|duration| (duration, toc))
+}
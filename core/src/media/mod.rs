@@ -0,0 +1,43 @@
+mod audio_output_monitor;
+pub use self::audio_output_monitor::AudioOutputMonitor;
+
+mod backend;
+pub use self::backend::Backend;
+
+pub mod media_probe;
+
+#[cfg(feature = "player")]
+mod player_backend;
+#[cfg(feature = "player")]
+pub use self::player_backend::init_player_env;
+
+pub mod playback_pipeline;
+pub use self::playback_pipeline::{
+    AudioLevel, MediaMessage, MissingPlugins, OpenError, PlaybackOptions, PlaybackPipeline,
+    SeekError, SegmentAborted, SegmentDone, SelectStreamsError, StateChangeError,
+};
+
+mod position_tracker;
+use self::position_tracker::PositionTracker;
+
+mod qos_stats;
+pub use self::qos_stats::QosStats;
+
+pub mod silence_detector;
+
+pub mod snapshot;
+
+mod subtitle_style;
+pub use self::subtitle_style::SubtitleStyle;
+
+#[cfg(feature = "test-media")]
+pub mod test_media;
+
+pub mod timestamp;
+pub use self::timestamp::Timestamp;
+
+pub mod video_decoding;
+pub use self::video_decoding::VideoDecoding;
+
+pub mod waveform;
+pub use self::waveform::Waveform;
@@ -0,0 +1,1926 @@
+use futures::channel::{mpsc as async_mpsc, oneshot};
+use futures::prelude::*;
+
+use gettextrs::gettext;
+
+use gst::prelude::*;
+use gst::ClockTime;
+
+use log::{debug, info, warn};
+
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use crate::metadata::{
+    is_attached_picture, media_info, merge_current_toc, Duration, MediaContent, MediaInfo,
+    MediaPath, StreamSelectionDelta,
+};
+
+#[cfg(feature = "player")]
+use super::player_backend;
+use super::{
+    video_decoding::is_in_hardware_decoder, Backend, PositionTracker, QosStats, SubtitleStyle,
+    Timestamp,
+};
+
+const AUDIO_SINK_NAME: &str = "audio_playback_sink";
+const VOLUME_NAME: &str = "audio_playback_volume";
+const LEVEL_NAME: &str = "audio_playback_level";
+const LEVEL_INTERVAL: u64 = 100_000_000;
+const VIDEO_CONVERT_NAME: &str = "video_playback_convert";
+const VIDEO_SCALE_NAME: &str = "video_playback_scale";
+const VIDEO_CAPS_NAME: &str = "video_playback_caps";
+const SUBTITLE_OVERLAY_NAME: &str = "subtitle_playback_overlay";
+
+#[derive(Debug)]
+pub enum MediaMessage {
+    Eos,
+    Error(String),
+    /// The audio device is no longer usable, e.g. it was unplugged.
+    AudioDeviceLost(String),
+    /// The pipeline state was changed by someone else, e.g. on audio device loss.
+    UnexpectedStateChange(gst::State),
+    /// A global toc was received during playback, e.g. for live streams.
+    TocUpdated(gst::Toc),
+    /// Loudness of the audio being played, posted periodically during playback.
+    AudioLevel(AudioLevel),
+    /// A hardware video decoder failed, software decoding might work.
+    HardwareDecoderError(String),
+    /// Plugins are missing to decode a stream, e.g. after selecting another stream.
+    ///
+    /// Each plugin is only reported once.
+    MissingPlugins(MissingPlugins),
+}
+
+/// Loudness of the loudest channel in dB, 0 being the maximum.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AudioLevel {
+    pub peak: f64,
+    pub rms: f64,
+}
+
+impl AudioLevel {
+    /// Builds the `AudioLevel` from the message structure posted by the `level` element.
+    fn from_structure(structure: &gst::StructureRef) -> Option<Self> {
+        if structure.get_name() != "level" {
+            return None;
+        }
+
+        Some(AudioLevel {
+            peak: Self::max_channel_db(structure, "peak")?,
+            rms: Self::max_channel_db(structure, "rms")?,
+        })
+    }
+
+    fn max_channel_db(structure: &gst::StructureRef, field: &str) -> Option<f64> {
+        let values = structure.get::<glib::ValueArray>(field).ok()??;
+        (0..values.len() as u32)
+            .filter_map(|idx| values.get_nth(idx))
+            .filter_map(|value| value.get_some::<f64>().ok())
+            .fold(None, |max: Option<f64>, db| {
+                Some(max.map_or(db, |max| max.max(db)))
+            })
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct MissingPlugins {
+    names: HashSet<String>,
+    // Details for the distribution's plugin installer
+    installer_details: HashSet<String>,
+}
+
+impl MissingPlugins {
+    fn new() -> Self {
+        MissingPlugins::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Adds the plugins from `other`.
+    ///
+    /// Returns `true` if some of them were not already known.
+    pub fn extend(&mut self, other: MissingPlugins) -> bool {
+        let prev_len = self.names.len();
+        self.names.extend(other.names);
+        self.installer_details.extend(other.installer_details);
+
+        self.names.len() > prev_len
+    }
+
+    /// Builds the `MissingPlugins` from a `missing-plugin` element message.
+    fn from_message(msg: &gst::Message) -> Option<Self> {
+        let name = match msg.get_structure() {
+            Some(structure) if structure.get_name() == "missing-plugin" => {
+                structure.get::<String>("name").ok().flatten()?
+            }
+            _ => return None,
+        };
+        warn!("{}", gettext("Missing plugin: {}").replacen("{}", &name, 1));
+
+        let mut missing_plugins = MissingPlugins::new();
+        if let Some(detail) = gst_pbutils::missing_plugin_message_get_installer_detail(msg) {
+            missing_plugins.installer_details.insert(detail.to_string());
+        }
+        missing_plugins.names.insert(name);
+
+        Some(missing_plugins)
+    }
+
+    /// Whether the plugins can be installed using the distribution's installer.
+    pub fn can_install(&self) -> bool {
+        !self.installer_details.is_empty() && gst_pbutils::install_plugins_supported()
+    }
+
+    /// Requests the distribution's installer to install the missing plugins.
+    ///
+    /// `on_done` is called with `true` if the plugins were installed.
+    /// It might be called from another thread.
+    pub fn install<F>(&self, on_done: F) -> Result<(), String>
+    where
+        F: FnOnce(bool) + Send + 'static,
+    {
+        let details = self
+            .installer_details
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+
+        use gst_pbutils::InstallPluginsReturn::*;
+        match gst_pbutils::install_plugins_async(&details, None, move |res| {
+            // The registry must be updated for the new plugins to be usable
+            let is_installed = res == Success && gst::update_registry().is_ok();
+            on_done(is_installed);
+        }) {
+            StartedOk => Ok(()),
+            res => Err(format!("{:?}", res)),
+        }
+    }
+}
+
+impl fmt::Debug for MissingPlugins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner_res = fmt::Display::fmt(&self, f);
+        f.debug_tuple("MissingPlugins").field(&inner_res).finish()
+    }
+}
+
+impl fmt::Display for MissingPlugins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, plugin) in self.names.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_str("- ")?;
+            f.write_str(plugin)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Errors posted when no plugin is available to decode a stream
+fn is_missing_plugin_error(error: &glib::Error) -> bool {
+    error.kind::<gst::StreamError>() == Some(gst::StreamError::CodecNotFound)
+        || error.kind::<gst::CoreError>() == Some(gst::CoreError::MissingPlugin)
+}
+
+#[derive(Debug)]
+pub enum OpenError {
+    GLSinkError,
+    Generic(String),
+    /// A hardware video decoder failed, software decoding might work.
+    HardwareDecoder(String),
+    MissingPlugins(MissingPlugins),
+    StateChange,
+    TextOnly,
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use OpenError::*;
+
+        match self {
+            GLSinkError => write!(f, "Media: error with GL Sink"),
+            Generic(err) => write!(f, "Media: error opening media {}", err),
+            HardwareDecoder(err) => write!(f, "Media: hardware decoder error {}", err),
+            MissingPlugins(missing) => write!(f, "Media: found missing plugins {}", missing),
+            StateChange => write!(f, "Media: state change error opening media"),
+            TextOnly => write!(f, "Media: only text streams found"),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {}
+
+impl From<gst::StateChangeError> for OpenError {
+    fn from(_: gst::StateChangeError) -> Self {
+        OpenError::StateChange
+    }
+}
+
+#[derive(Debug)]
+struct PurgeError;
+
+#[derive(Debug)]
+pub struct StateChangeError;
+
+impl From<gst::StateChangeError> for StateChangeError {
+    fn from(_: gst::StateChangeError) -> Self {
+        StateChangeError
+    }
+}
+
+impl From<PurgeError> for StateChangeError {
+    fn from(_: PurgeError) -> Self {
+        StateChangeError
+    }
+}
+
+impl fmt::Display for StateChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Media: couldn't change state")
+    }
+}
+impl std::error::Error for StateChangeError {}
+
+#[derive(Debug)]
+pub enum SeekError {
+    Eos,
+    /// The seek was refused, e.g. a reverse or trick mode seek not supported
+    /// by the demuxer. Playback goes on as before.
+    Rejected,
+    Unrecoverable,
+}
+
+impl From<PurgeError> for SeekError {
+    fn from(_: PurgeError) -> Self {
+        SeekError::Unrecoverable
+    }
+}
+
+impl fmt::Display for SeekError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use SeekError::*;
+
+        match self {
+            Eos => write!(f, "Media: seeking past the end"),
+            Rejected => write!(f, "Media: seek rejected"),
+            Unrecoverable => write!(f, "Media: couldn't seek"),
+        }
+    }
+}
+impl std::error::Error for SeekError {}
+
+/// The segment was superseded before its stop was reached,
+/// e.g. by a flushing seek, or the media was closed.
+#[derive(Debug)]
+pub struct SegmentAborted;
+
+impl fmt::Display for SegmentAborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Media: segment aborted")
+    }
+}
+impl std::error::Error for SegmentAborted {}
+
+/// Resolves when playback reaches the stop of a segment.
+pub struct SegmentDone(oneshot::Receiver<()>);
+
+impl Future for SegmentDone {
+    type Output = Result<(), SegmentAborted>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.poll_unpin(cx).map_err(|_| SegmentAborted)
+    }
+}
+
+#[derive(Debug)]
+pub enum SelectStreamsError {
+    UnknownId(Arc<str>),
+    Unrecoverable,
+}
+
+impl From<media_info::SelectStreamError> for SelectStreamsError {
+    fn from(err: media_info::SelectStreamError) -> Self {
+        SelectStreamsError::UnknownId(Arc::clone(err.id()))
+    }
+}
+
+impl From<PurgeError> for SelectStreamsError {
+    fn from(_: PurgeError) -> Self {
+        SelectStreamsError::Unrecoverable
+    }
+}
+
+impl fmt::Display for SelectStreamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectStreamsError::UnknownId(id) => {
+                write!(f, "Media: select stream: unknown id {}", id.as_ref())
+            }
+            SelectStreamsError::Unrecoverable => write!(f, "Media: couldn't select stream"),
+        }
+    }
+}
+impl std::error::Error for SelectStreamsError {}
+
+// Retains the global toc, or merges the tocs for the current stream if the media has none
+#[derive(Clone, Copy, Debug)]
+struct TocCollector {
+    is_current_ignored: bool,
+    has_global: bool,
+}
+
+impl TocCollector {
+    fn new(is_current_ignored: bool) -> Self {
+        TocCollector {
+            is_current_ignored,
+            has_global: false,
+        }
+    }
+
+    // Returns the toc to use from now on, if any
+    fn collect(
+        &mut self,
+        toc: gst::Toc,
+        updated: bool,
+        prev_toc: Option<&gst::Toc>,
+    ) -> Option<gst::Toc> {
+        match toc.get_scope() {
+            gst::TocScope::Global => {
+                if self.has_global && !updated {
+                    return None;
+                }
+
+                self.has_global = true;
+                Some(toc)
+            }
+            gst::TocScope::Current if self.has_global => {
+                debug!("skipping current toc in favor of the global toc");
+                None
+            }
+            gst::TocScope::Current if !self.is_current_ignored => {
+                Some(merge_current_toc(prev_toc, &toc))
+            }
+            scope => {
+                warn!("skipping toc with scope: {:?}", scope);
+                None
+            }
+        }
+    }
+}
+
+/// Settings for opening a media with `PlaybackPipeline::try_new`.
+#[derive(Clone, Debug, Default)]
+pub struct PlaybackOptions {
+    /// The default audio sink is used if `None`.
+    pub audio_sink: Option<gst::Element>,
+    /// Ignore the tocs which only apply to the current stream, when the media has no global toc.
+    pub is_current_toc_ignored: bool,
+    /// Maximum height of the rendered video, the original resolution if `None`.
+    pub max_video_height: Option<u32>,
+    pub backend: Backend,
+}
+
+pub struct PlaybackPipeline {
+    pipeline: gst::Pipeline,
+    pub info: MediaInfo,
+    pub missing_plugins: MissingPlugins,
+    pub media_msg_rx: Option<async_mpsc::UnboundedReceiver<MediaMessage>>,
+    int_msg_rx: async_mpsc::UnboundedReceiver<gst::Message>,
+    bus_watch_src_id: Option<glib::SourceId>,
+    // State requested by this `PlaybackPipeline`, used to detect external state changes
+    expected_state: Arc<Mutex<gst::State>>,
+    audio_offset: i64,
+    qos_stats: Arc<Mutex<QosStats>>,
+    position_tracker: Arc<Mutex<PositionTracker>>,
+    // Notifies the `SegmentDone` of the segment being played
+    segment_done_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+    is_seekable: bool,
+    toc_collector: TocCollector,
+    max_video_height: Arc<Mutex<Option<u32>>>,
+    subtitle_style: Arc<Mutex<SubtitleStyle>>,
+    backend: Backend,
+    #[cfg(feature = "player")]
+    player: Option<gst_player::Player>,
+}
+
+/// Initialization
+impl PlaybackPipeline {
+    pub async fn try_new(
+        path: &MediaPath,
+        video_sink: &Option<gst::Element>,
+        options: PlaybackOptions,
+    ) -> Result<PlaybackPipeline, OpenError> {
+        info!(
+            "{}",
+            gettext("Opening {}...").replacen("{}", &path.to_string(), 1)
+        );
+
+        // Use the URI so that file names which are not valid UTF-8 can be opened
+        let uri = path.uri().map_err(OpenError::Generic)?;
+
+        // Required to get the installer details of the missing plugins
+        gst_pbutils::pb_utils_init();
+
+        let (ext_msg_tx, ext_msg_rx) = async_mpsc::unbounded();
+        let (int_msg_tx, int_msg_rx) = async_mpsc::unbounded();
+
+        let mut this = PlaybackPipeline {
+            pipeline: gst::Pipeline::new(Some("playback_pipeline")),
+            info: MediaInfo::new(path),
+            missing_plugins: MissingPlugins::new(),
+            media_msg_rx: Some(ext_msg_rx),
+            int_msg_rx,
+            bus_watch_src_id: None,
+            expected_state: Arc::new(Mutex::new(gst::State::Paused)),
+            audio_offset: 0,
+            qos_stats: Arc::new(Mutex::new(QosStats::default())),
+            position_tracker: Arc::new(Mutex::new(PositionTracker::default())),
+            segment_done_tx: Arc::new(Mutex::new(None)),
+            is_seekable: false,
+            toc_collector: TocCollector::new(options.is_current_toc_ignored),
+            max_video_height: Arc::new(Mutex::new(options.max_video_height)),
+            subtitle_style: Arc::new(Mutex::new(SubtitleStyle::default())),
+            backend: options.backend,
+            #[cfg(feature = "player")]
+            player: None,
+        };
+
+        match options.backend {
+            Backend::DecodeBin => this.build_pipeline(&uri, video_sink, options.audio_sink),
+            #[cfg(feature = "player")]
+            Backend::Player => this.build_player(&uri, video_sink, options.audio_sink),
+        }
+        this.style_subtitles();
+        Self::open(this, ext_msg_tx, int_msg_tx).await
+    }
+
+    pub fn check_requirements() -> Result<(), String> {
+        gst::ElementFactory::make("decodebin3", None)
+            .map(drop)
+            .map_err(|_| gettext("Missing `decodebin3`\ncheck your gst-plugins-base install"))?;
+        gst::ElementFactory::make("gtksink", None)
+            .map(drop)
+            .map_err(|_| {
+                let (major, minor, _micro, _nano) = gst::version();
+                let (variant1, variant2) = if major >= 1 && minor >= 14 {
+                    ("gstreamer1-plugins-base", "gstreamer1.0-plugins-base")
+                } else {
+                    (
+                        "gstreamer1-plugins-bad-free-gtk",
+                        "gstreamer1.0-plugins-bad",
+                    )
+                };
+                format!(
+                    "{} {}\n{}",
+                    gettext("Couldn't find GStreamer GTK video sink."),
+                    gettext("Video playback will be disabled."),
+                    gettext("Please install {} or {}, depending on your distribution.")
+                        .replacen("{}", variant1, 1)
+                        .replacen("{}", variant2, 1),
+                )
+            })
+    }
+
+    /// Builds an audio sink from a `gst-launch` like description,
+    /// e.g. `audioconvert ! pulsesink device=my_sink`.
+    pub fn audio_sink_from_description(description: &str) -> Result<gst::Element, String> {
+        let bin =
+            gst::parse_bin_from_description(description, true).map_err(|err| err.to_string())?;
+        bin.set_name(AUDIO_SINK_NAME)
+            .map_err(|err| err.to_string())?;
+
+        Ok(bin.upcast())
+    }
+
+    fn default_audio_sink() -> gst::Element {
+        gst::ElementFactory::make("autoaudiosink", Some(AUDIO_SINK_NAME)).unwrap()
+    }
+
+    fn build_pipeline(
+        &mut self,
+        uri: &str,
+        video_sink: &Option<gst::Element>,
+        audio_sink: Option<gst::Element>,
+    ) {
+        let file_src = gst::ElementFactory::make("filesrc", None).unwrap();
+        file_src
+            .dynamic_cast_ref::<gst::URIHandler>()
+            .unwrap()
+            .set_uri(uri)
+            .unwrap();
+
+        let decodebin = gst::ElementFactory::make("decodebin3", Some("decodebin")).unwrap();
+
+        let elements = &[&file_src, &decodebin];
+        self.pipeline.add_many(elements).unwrap();
+
+        file_src.link(&decodebin).unwrap();
+
+        Self::skip_attached_pictures(&decodebin);
+
+        let audio_sink = audio_sink.unwrap_or_else(Self::default_audio_sink);
+
+        // Prepare pad configuration callback
+        let pipeline_clone = self.pipeline.clone();
+        let video_sink = video_sink.clone();
+        let max_video_height = Arc::clone(&self.max_video_height);
+        decodebin.connect_pad_added(move |_decodebin, src_pad| {
+            let pipeline = &pipeline_clone;
+            let name = src_pad.get_name();
+
+            if name.starts_with("audio_") {
+                let elements = Self::audio_branch(&audio_sink);
+                let elements: Vec<&gst::Element> = elements.iter().collect();
+
+                pipeline.add_many(&elements).unwrap();
+                gst::Element::link_many(&elements).unwrap();
+
+                for e in &elements {
+                    e.sync_state_with_parent().unwrap();
+                }
+
+                let sink_pad = elements[0].get_static_pad("sink").unwrap();
+                src_pad.link(&sink_pad).unwrap();
+            } else if name.starts_with("video_") {
+                if let Some(video_sink) = &video_sink {
+                    let max_video_height = *max_video_height.lock().unwrap();
+                    Self::attach_video_branch(pipeline, video_sink, src_pad, max_video_height);
+                }
+            } else if name.starts_with("text_") {
+                Self::link_subtitles(pipeline, src_pad);
+            }
+        });
+
+        // decodebin3 removes the video pad when the video stream is deselected.
+        // Changing the state of the branch from the streaming thread could deadlock,
+        // so it is detached from the main context, where the bus watches are attached.
+        let pipeline_clone = self.pipeline.clone();
+        decodebin.connect_pad_removed(move |_decodebin, src_pad| {
+            if src_pad.get_name().starts_with("video_") {
+                let pipeline = pipeline_clone.clone();
+                glib::MainContext::default().invoke(move || {
+                    // A new branch might have been attached in the meantime
+                    let is_linked = pipeline
+                        .get_by_name(SUBTITLE_OVERLAY_NAME)
+                        .and_then(|overlay| overlay.get_static_pad("video_sink"))
+                        .map_or(false, |sink_pad| sink_pad.is_linked());
+                    if !is_linked {
+                        Self::detach_video_branch(&pipeline);
+                    }
+                });
+            }
+        });
+    }
+
+    // Attached pictures are displayed as the cover art, don't decode them as a video
+    fn skip_attached_pictures(decodebin: &gst::Element) {
+        decodebin
+            .connect("select-stream", false, |args| {
+                let is_cover = args[2]
+                    .get::<gst::Stream>()
+                    .ok()
+                    .flatten()
+                    .map_or(false, |stream| is_attached_picture(&stream));
+                // -1 lets decodebin3 apply its default selection
+                let selection: i32 = if is_cover { 0 } else { -1 };
+
+                Some(selection.to_value())
+            })
+            .unwrap();
+    }
+
+    /// Returns the audio elements up to the `audio_sink`, in linking order.
+    fn audio_branch(audio_sink: &gst::Element) -> Vec<gst::Element> {
+        let convert = gst::ElementFactory::make("audioconvert", None).unwrap();
+        let resample = gst::ElementFactory::make("audioresample", None).unwrap();
+        let volume = gst::ElementFactory::make("volume", Some(VOLUME_NAME)).unwrap();
+        // `level` is optional, it is only used for display purposes
+        let level = gst::ElementFactory::make("level", Some(LEVEL_NAME))
+            .map(|level| {
+                level.set_property("interval", &LEVEL_INTERVAL).unwrap();
+                level
+            })
+            .map_err(|_| warn!("couldn't find the `level` element"))
+            .ok();
+
+        let mut elements = vec![convert, resample, volume];
+        elements.extend(level);
+        elements.push(audio_sink.clone());
+
+        elements
+    }
+
+    /// Returns the video elements up to the `video_sink`, in linking order.
+    fn video_branch(video_sink: &gst::Element, max_video_height: Option<u32>) -> Vec<gst::Element> {
+        let convert = gst::ElementFactory::make("videoconvert", Some(VIDEO_CONVERT_NAME)).unwrap();
+        let scale = gst::ElementFactory::make("videoscale", Some(VIDEO_SCALE_NAME)).unwrap();
+        let caps_filter = gst::ElementFactory::make("capsfilter", Some(VIDEO_CAPS_NAME)).unwrap();
+        caps_filter
+            .set_property("caps", &Self::video_caps(max_video_height))
+            .unwrap();
+
+        vec![convert, scale, caps_filter, video_sink.clone()]
+    }
+
+    /// Builds the pipeline with the `Player` `Backend`.
+    ///
+    /// The branches are wrapped in bins which are used as `playbin3`'s sinks.
+    #[cfg(feature = "player")]
+    fn build_player(
+        &mut self,
+        uri: &str,
+        video_sink: &Option<gst::Element>,
+        audio_sink: Option<gst::Element>,
+    ) {
+        let (player, pipeline) = player_backend::new_player(uri);
+
+        let audio_sink = audio_sink.unwrap_or_else(Self::default_audio_sink);
+        let audio_bin = Self::sink_bin("audio_playback_bin", &Self::audio_branch(&audio_sink));
+        pipeline.set_property("audio-sink", &audio_bin).unwrap();
+
+        if let Some(video_sink) = video_sink {
+            let max_video_height = *self.max_video_height.lock().unwrap();
+            let video_bin = Self::sink_bin(
+                "video_playback_bin",
+                &Self::video_branch(video_sink, max_video_height),
+            );
+            pipeline.set_property("video-sink", &video_bin).unwrap();
+        }
+
+        // `decodebin3` is created by `playbin3` once the media is being opened
+        pipeline.connect_deep_element_added(|_, _, element| {
+            let is_decodebin = element
+                .get_factory()
+                .map_or(false, |factory| factory.get_name() == "decodebin3");
+            if is_decodebin {
+                Self::skip_attached_pictures(element);
+            }
+        });
+
+        self.pipeline = pipeline;
+        self.player = Some(player);
+    }
+
+    #[cfg(feature = "player")]
+    fn sink_bin(name: &str, elements: &[gst::Element]) -> gst::Element {
+        let bin = gst::Bin::new(Some(name));
+        let elements: Vec<&gst::Element> = elements.iter().collect();
+        bin.add_many(&elements).unwrap();
+        gst::Element::link_many(&elements).unwrap();
+
+        let sink_pad = elements[0].get_static_pad("sink").unwrap();
+        let ghost_pad = gst::GhostPad::new(Some("sink"), &sink_pad).unwrap();
+        bin.add_pad(&ghost_pad).unwrap();
+
+        bin.upcast()
+    }
+
+    /// Builds the video conversion branch up to the `video_sink` and links it to `src_pad`.
+    ///
+    /// The branch is only built when a video stream is actually decoded.
+    /// It starts with the overlay which renders the text subtitles.
+    fn attach_video_branch(
+        pipeline: &gst::Pipeline,
+        video_sink: &gst::Element,
+        src_pad: &gst::Pad,
+        max_video_height: Option<u32>,
+    ) {
+        if pipeline.get_by_name(SUBTITLE_OVERLAY_NAME).is_some() {
+            // Branch still attached, e.g. the pad was not removed yet
+            Self::detach_video_branch(pipeline);
+        }
+
+        let overlay =
+            gst::ElementFactory::make("subtitleoverlay", Some(SUBTITLE_OVERLAY_NAME)).unwrap();
+        let mut elements = vec![overlay];
+        elements.extend(Self::video_branch(video_sink, max_video_height));
+        let elements: Vec<&gst::Element> = elements.iter().collect();
+        pipeline.add_many(&elements).unwrap();
+        gst::Element::link_many(&elements).unwrap();
+
+        for e in &elements {
+            e.sync_state_with_parent().unwrap();
+        }
+
+        let sink_pad = elements[0].get_static_pad("video_sink").unwrap();
+        src_pad.link(&sink_pad).unwrap();
+
+        // The text pad might have been added before the video pad
+        if let Some(decodebin) = src_pad.get_parent_element() {
+            for text_pad in decodebin.get_src_pads() {
+                if text_pad.get_name().starts_with("text_") {
+                    Self::link_subtitles(pipeline, &text_pad);
+                }
+            }
+        }
+    }
+
+    // Subtitles are only rendered along with the video
+    fn link_subtitles(pipeline: &gst::Pipeline, src_pad: &gst::Pad) {
+        if src_pad.is_linked() {
+            return;
+        }
+
+        let sink_pad = pipeline
+            .get_by_name(SUBTITLE_OVERLAY_NAME)
+            .and_then(|overlay| overlay.get_static_pad("subtitle_sink"));
+        if let Some(sink_pad) = sink_pad {
+            if !sink_pad.is_linked() {
+                if let Err(err) = src_pad.link(&sink_pad) {
+                    warn!("couldn't link subtitles {}: {:?}", src_pad.get_name(), err);
+                }
+            }
+        }
+    }
+
+    // Applies the `SubtitleStyle` to the text renderers as they are created
+    fn style_subtitles(&self) {
+        let subtitle_style = Arc::clone(&self.subtitle_style);
+        self.pipeline
+            .connect_deep_element_added(move |_, _, element| {
+                subtitle_style.lock().unwrap().apply(element);
+            });
+    }
+
+    fn video_caps(max_height: Option<u32>) -> gst::Caps {
+        match max_height {
+            // `videoscale` keeps the display aspect ratio by adjusting the width
+            Some(max_height) => gst::Caps::builder("video/x-raw")
+                .field("height", &gst::IntRange::<i32>::new(1, max_height as i32))
+                .field("pixel-aspect-ratio", &gst::Fraction::new(1, 1))
+                .build(),
+            None => gst::Caps::new_any(),
+        }
+    }
+
+    /// Releases the video branch so that no resources are used while video is deselected.
+    ///
+    /// The video sink is kept alive so that it can be attached again.
+    fn detach_video_branch(pipeline: &gst::Pipeline) {
+        let video_sink = pipeline
+            .get_by_name(VIDEO_CAPS_NAME)
+            .and_then(|caps_filter| caps_filter.get_static_pad("src"))
+            .and_then(|src_pad| src_pad.get_peer())
+            .and_then(|sink_pad| sink_pad.get_parent_element());
+
+        let elements = [
+            pipeline.get_by_name(SUBTITLE_OVERLAY_NAME),
+            pipeline.get_by_name(VIDEO_CONVERT_NAME),
+            pipeline.get_by_name(VIDEO_SCALE_NAME),
+            pipeline.get_by_name(VIDEO_CAPS_NAME),
+            video_sink,
+        ];
+        for element in elements.iter().flatten() {
+            if let Err(err) = element.set_state(gst::State::Null) {
+                warn!(
+                    "couldn't stop video element {}: {}",
+                    element.get_name(),
+                    err
+                );
+            }
+            let _ = pipeline.remove(element);
+        }
+    }
+
+    async fn open(
+        mut self,
+        ext_msg_tx: async_mpsc::UnboundedSender<MediaMessage>,
+        int_msg_tx: async_mpsc::UnboundedSender<gst::Message>,
+    ) -> Result<Self, OpenError> {
+        let request_state = self.state_requester();
+
+        let (handler_res_tx, handler_res_rx) = oneshot::channel();
+        Self::register_open_bus_watch(self, handler_res_tx);
+
+        request_state(gst::State::Paused)?;
+        self = handler_res_rx.await.unwrap()?;
+
+        self.register_operations_bus_watch(ext_msg_tx, int_msg_tx);
+
+        Ok(self)
+    }
+
+    fn register_open_bus_watch(self, handler_res_tx: oneshot::Sender<Result<Self, OpenError>>) {
+        let mut handler_res_tx = Some(handler_res_tx);
+        let pipeline = self.pipeline.clone();
+        let backend = self.backend;
+        let mut this = Some(self);
+
+        let mut streams_selected = false;
+
+        Self::add_bus_watch(&pipeline, backend, move |msg| {
+            use gst::MessageView::*;
+
+            //println!("{:?}", msg);
+            match msg.view() {
+                Error(err) => {
+                    let mut this = this.take().unwrap();
+                    this.cleanup();
+
+                    if "sink" == err.get_src().unwrap().get_name() {
+                        // Failure detected on a sink, this occurs when the GL sink
+                        // can't operate properly
+                        let _ = handler_res_tx
+                            .take()
+                            .unwrap()
+                            .send(Err(OpenError::GLSinkError));
+
+                        return glib::Continue(false);
+                    }
+
+                    if err
+                        .get_src()
+                        .map_or(false, |src| is_in_hardware_decoder(&src))
+                    {
+                        let _ = handler_res_tx
+                            .take()
+                            .unwrap()
+                            .send(Err(OpenError::HardwareDecoder(err.get_error().to_string())));
+
+                        return glib::Continue(false);
+                    }
+
+                    let PlaybackPipeline {
+                        missing_plugins, ..
+                    } = this;
+                    if !missing_plugins.is_empty() {
+                        let _ = handler_res_tx
+                            .take()
+                            .unwrap()
+                            .send(Err(OpenError::MissingPlugins(missing_plugins)));
+
+                        return glib::Continue(false);
+                    }
+
+                    let _ = handler_res_tx
+                        .take()
+                        .unwrap()
+                        .send(Err(OpenError::Generic(err.get_error().to_string())));
+
+                    return glib::Continue(false);
+                }
+                Element(_) => {
+                    if let Some(plugins) = MissingPlugins::from_message(msg) {
+                        this.as_mut().unwrap().missing_plugins.extend(plugins);
+                    }
+                }
+                StreamCollection(stream_collection) => {
+                    let is_text_only = {
+                        let this = this.as_mut().unwrap();
+                        stream_collection
+                            .get_stream_collection()
+                            .iter()
+                            .for_each(|stream| this.info.add_stream(&stream));
+
+                        this.info.content == MediaContent::Text
+                    };
+
+                    // Text streams are not rendered on their own,
+                    // so the pipeline would never preroll
+                    if is_text_only {
+                        let mut this = this.take().unwrap();
+                        this.cleanup();
+                        let _ = this.pipeline.set_state(gst::State::Null);
+
+                        let _ = handler_res_tx
+                            .take()
+                            .unwrap()
+                            .send(Err(OpenError::TextOnly));
+
+                        return glib::Continue(false);
+                    }
+                }
+                // FIXME really still necessary can't we just use StateChanged?
+                StreamsSelected(_) => {
+                    streams_selected = true;
+                }
+                Tag(msg_tag) => {
+                    let tags = msg_tag.get_tags();
+                    if tags.get_scope() == gst::TagScope::Global {
+                        this.as_mut().unwrap().info.add_tags(&tags);
+                    }
+                }
+                Toc(msg_toc) => {
+                    let mut this = this.as_mut().unwrap();
+                    let (toc, updated) = msg_toc.get_toc();
+                    let updated = updated || this.info.toc.is_none();
+                    if let Some(toc) =
+                        this.toc_collector
+                            .collect(toc, updated, this.info.toc.as_ref())
+                    {
+                        this.info.toc = Some(toc);
+                    }
+                }
+                AsyncDone(_) => {
+                    // FIXME StateChanged?
+                    if streams_selected {
+                        let mut this = this.take().unwrap();
+
+                        let duration = Duration::from_nanos(
+                            this.pipeline
+                                .query_duration::<gst::ClockTime>()
+                                .unwrap_or_else(|| 0.into())
+                                .nanoseconds()
+                                .unwrap(),
+                        );
+                        this.info.duration = duration;
+                        this.info.cache_cover_art();
+
+                        // Some network or raw streams can't be seeked
+                        let mut seeking = gst::query::Seeking::new(gst::Format::Time);
+                        if this.pipeline.query(&mut seeking) {
+                            let (is_seekable, _start, _end) = seeking.get_result();
+                            this.is_seekable = is_seekable;
+                        }
+                        if !this.is_seekable {
+                            info!("{}", gettext("The media doesn't support seeking"));
+                        }
+
+                        let _ = handler_res_tx.take().unwrap().send(Ok(this));
+
+                        return glib::Continue(false);
+                    }
+                }
+                _ => (),
+            }
+
+            glib::Continue(true)
+        });
+    }
+
+    fn register_operations_bus_watch(
+        &mut self,
+        ext_msg_tx: async_mpsc::UnboundedSender<MediaMessage>,
+        int_msg_tx: async_mpsc::UnboundedSender<gst::Message>,
+    ) {
+        let expected_state = Arc::clone(&self.expected_state);
+        let qos_stats = Arc::clone(&self.qos_stats);
+        let position_tracker = Arc::clone(&self.position_tracker);
+        let segment_done_tx = Arc::clone(&self.segment_done_tx);
+        let mut toc_collector = self.toc_collector;
+        let mut last_toc = self.info.toc.clone();
+        // Those reported while opening the media are not reported again
+        let mut missing_plugins = self.missing_plugins.clone();
+        let pipeline = self.pipeline.clone();
+        let bus_watch_src_id = Self::add_bus_watch(&self.pipeline, self.backend, move |msg| {
+            use gst::MessageView::*;
+
+            let mut must_forward = false;
+            match msg.view() {
+                StateChanged(state_changed) => {
+                    // `playbin3` is a subclass of `Pipeline`, so compare the objects
+                    if state_changed
+                        .get_src()
+                        .map_or(false, |src| &src == pipeline.upcast_ref::<gst::Object>())
+                    {
+                        must_forward = true;
+
+                        // Transitional states occur e.g. while seeking
+                        let new_state = state_changed.get_current();
+                        position_tracker
+                            .lock()
+                            .unwrap()
+                            .set_playing(new_state == gst::State::Playing);
+                        if state_changed.get_pending() == gst::State::VoidPending {
+                            let mut expected_state = expected_state.lock().unwrap();
+                            if new_state != *expected_state {
+                                *expected_state = new_state;
+                                ext_msg_tx
+                                    .unbounded_send(MediaMessage::UnexpectedStateChange(new_state))
+                                    .unwrap();
+                            }
+                        }
+                    }
+                }
+                AsyncDone(_) => {
+                    position_tracker.lock().unwrap().reset();
+                    must_forward = true;
+                }
+                Eos(_) => {
+                    position_tracker.lock().unwrap().reset();
+                    ext_msg_tx.unbounded_send(MediaMessage::Eos).unwrap();
+                }
+                SegmentDone(_) => {
+                    if let Some(segment_done_tx) = segment_done_tx.lock().unwrap().take() {
+                        let _ = segment_done_tx.send(());
+                    }
+                }
+                Element(msg_element) => {
+                    if let Some(level) = msg_element
+                        .get_structure()
+                        .and_then(AudioLevel::from_structure)
+                    {
+                        ext_msg_tx
+                            .unbounded_send(MediaMessage::AudioLevel(level))
+                            .unwrap();
+                    } else if let Some(plugins) = MissingPlugins::from_message(msg) {
+                        // Several messages might be posted for the same plugin
+                        if missing_plugins.extend(plugins.clone()) {
+                            ext_msg_tx
+                                .unbounded_send(MediaMessage::MissingPlugins(plugins))
+                                .unwrap();
+                        }
+                    }
+                }
+                Qos(msg_qos) => {
+                    // Only video elements report their stats in buffers
+                    let (processed, dropped) = msg_qos.get_stats();
+                    if processed.get_format() == gst::Format::Buffers {
+                        let (jitter, _proportion, _quality) = msg_qos.get_values();
+                        let element = msg_qos
+                            .get_src()
+                            .map_or_else(String::new, |src| src.get_path_string().to_string());
+                        qos_stats.lock().unwrap().add(
+                            &element,
+                            processed.get_value().max(0) as u64,
+                            dropped.get_value().max(0) as u64,
+                            jitter,
+                        );
+                    }
+                }
+                Toc(msg_toc) => {
+                    let (toc, _updated) = msg_toc.get_toc();
+                    if let Some(toc) = toc_collector.collect(toc, true, last_toc.as_ref()) {
+                        last_toc = Some(toc.clone());
+                        ext_msg_tx
+                            .unbounded_send(MediaMessage::TocUpdated(toc))
+                            .unwrap();
+                    }
+                }
+                Error(err) => {
+                    let error = err.get_error();
+                    let is_audio_device_lost = error.is::<gst::ResourceError>()
+                        && err
+                            .get_src()
+                            .map_or(false, |src| Self::is_in_audio_sink(&src));
+
+                    if is_audio_device_lost {
+                        // Recoverable by rebuilding the audio sink
+                        ext_msg_tx
+                            .unbounded_send(MediaMessage::AudioDeviceLost(error.to_string()))
+                            .unwrap();
+                    } else if err
+                        .get_src()
+                        .map_or(false, |src| is_in_hardware_decoder(&src))
+                    {
+                        ext_msg_tx
+                            .unbounded_send(MediaMessage::HardwareDecoderError(error.to_string()))
+                            .unwrap();
+
+                        must_forward = true;
+                    } else if !missing_plugins.is_empty() && is_missing_plugin_error(&error) {
+                        // Already reported as `MissingPlugins`, the other streams are still usable
+                        warn!("{}", error);
+
+                        must_forward = true;
+                    } else {
+                        ext_msg_tx
+                            .unbounded_send(MediaMessage::Error(error.to_string()))
+                            .unwrap();
+
+                        must_forward = true;
+                    }
+                }
+                _ => (),
+            }
+
+            if must_forward {
+                int_msg_tx.unbounded_send(msg.clone()).unwrap();
+            }
+
+            glib::Continue(true)
+        });
+
+        self.bus_watch_src_id = Some(bus_watch_src_id);
+    }
+
+    fn add_bus_watch<F>(pipeline: &gst::Pipeline, backend: Backend, mut func: F) -> glib::SourceId
+    where
+        F: FnMut(&gst::Message) -> glib::Continue + Send + 'static,
+    {
+        let bus = pipeline.get_bus().unwrap();
+        match backend {
+            Backend::DecodeBin => bus.add_watch(move |_, msg| func(msg)).unwrap(),
+            #[cfg(feature = "player")]
+            Backend::Player => player_backend::add_bus_watch(&bus, func),
+        }
+    }
+
+    /// Returns a function which requests the pipeline to change its state.
+    ///
+    /// The `Player` changes the state from its own thread.
+    fn state_requester(&self) -> impl Fn(gst::State) -> Result<(), gst::StateChangeError> {
+        let pipeline = self.pipeline.clone();
+        #[cfg(feature = "player")]
+        let player = self.player.clone();
+
+        move |state| {
+            #[cfg(feature = "player")]
+            {
+                if let Some(player) = player.as_ref() {
+                    match state {
+                        gst::State::Playing => {
+                            player.play();
+                            return Ok(());
+                        }
+                        gst::State::Paused => {
+                            player.pause();
+                            return Ok(());
+                        }
+                        _ => (),
+                    }
+                }
+            }
+
+            pipeline.set_state(state).map(drop)
+        }
+    }
+
+    fn request_state(&self, state: gst::State) -> Result<(), gst::StateChangeError> {
+        (self.state_requester())(state)
+    }
+
+    fn is_in_audio_sink(src: &gst::Object) -> bool {
+        let mut object = Some(src.clone());
+        while let Some(cur_object) = object {
+            if cur_object.get_name() == AUDIO_SINK_NAME {
+                return true;
+            }
+            object = cur_object.get_parent();
+        }
+
+        false
+    }
+
+    fn cleanup(&mut self) {
+        // Release the video sink so that it can be used with the next media
+        if let Some(video_sink) = self.pipeline.get_by_name("video_sink") {
+            if let Some(bin) = Self::parent_bin(&video_sink) {
+                bin.remove(&video_sink).unwrap();
+            }
+        }
+    }
+
+    // The branches are in bins with the `Player` backend
+    fn parent_bin(element: &gst::Element) -> Option<gst::Bin> {
+        element
+            .get_parent()
+            .and_then(|parent| parent.downcast::<gst::Bin>().ok())
+    }
+
+    // `decodebin3` is nested in `playbin3` with the `Player` backend
+    fn decodebin(&self) -> Option<gst::Element> {
+        self.pipeline.iterate_recurse().find(|element| {
+            element
+                .get_factory()
+                .map_or(false, |factory| factory.get_name() == "decodebin3")
+        })
+    }
+}
+
+/// Operations
+impl PlaybackPipeline {
+    /// Returns the current position.
+    ///
+    /// The position is interpolated between periodic position queries
+    /// which are costly, particularly with network sources.
+    pub fn current_ts(&self) -> Option<Timestamp> {
+        let now = Instant::now();
+        let mut position_tracker = self.position_tracker.lock().unwrap();
+        if let Some(position) = position_tracker.position(now) {
+            return Some(position);
+        }
+
+        let position = self.query_position()?;
+        position_tracker.sync(position, now);
+
+        Some(position)
+    }
+
+    fn query_position(&self) -> Option<Timestamp> {
+        let mut position_query = gst::query::Position::new(gst::Format::Time);
+        self.pipeline.query(&mut position_query);
+        let position = position_query.get_result().get_value();
+        if position < 0 {
+            None
+        } else {
+            Some(position.into())
+        }
+    }
+
+    /// Purges previous internal messages if any.
+    fn purge_int_msg(&mut self) -> Result<(), PurgeError> {
+        while let Ok(msg) = self.int_msg_rx.try_next() {
+            match msg {
+                Some(msg) => {
+                    if let gst::MessageView::Error(_) = msg.view() {
+                        return Err(PurgeError);
+                    }
+                }
+                None => panic!("internal channel terminated"),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn pause(&mut self) -> Result<(), StateChangeError> {
+        self.purge_int_msg()?;
+
+        *self.expected_state.lock().unwrap() = gst::State::Paused;
+        self.request_state(gst::State::Paused)?;
+
+        while let Some(msg) = self.int_msg_rx.next().await {
+            use gst::MessageView::*;
+            match msg.view() {
+                StateChanged(_) => break,
+                Error(_) => return Err(StateChangeError),
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn play(&mut self) -> Result<(), StateChangeError> {
+        self.purge_int_msg()?;
+
+        *self.expected_state.lock().unwrap() = gst::State::Playing;
+        self.request_state(gst::State::Playing)?;
+
+        while let Some(msg) = self.int_msg_rx.next().await {
+            use gst::MessageView::*;
+            match msg.view() {
+                StateChanged(_) => break,
+                Error(_) => return Err(StateChangeError),
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn stop(&mut self) -> Result<(), StateChangeError> {
+        if let Some(bus_watch_src_id) = self.bus_watch_src_id.take() {
+            glib::source_remove(bus_watch_src_id);
+        }
+
+        self.abort_segment();
+
+        *self.expected_state.lock().unwrap() = gst::State::Null;
+        let res = self.pipeline.set_state(gst::State::Null);
+        self.cleanup();
+        #[cfg(feature = "player")]
+        {
+            self.player = None;
+        }
+        res?;
+
+        Ok(())
+    }
+
+    pub async fn seek(
+        &mut self,
+        target: Timestamp,
+        flags: gst::SeekFlags,
+    ) -> Result<(), SeekError> {
+        self.abort_segment();
+        self.seek_range(target, None, 1f64, flags).await
+    }
+
+    /// Seeks to `start` and plays at `rate` until `stop` instead of the end of the media.
+    ///
+    /// The returned `SegmentDone` resolves when `stop` is reached.
+    /// Use `chain_segment` from there in order to go on without interruption.
+    pub async fn seek_segment(
+        &mut self,
+        start: Timestamp,
+        stop: Timestamp,
+        rate: f64,
+        flags: gst::SeekFlags,
+    ) -> Result<SegmentDone, SeekError> {
+        let segment_done = self.expect_segment_done();
+        self.seek_range(start, Some(stop), rate, gst::SeekFlags::SEGMENT | flags)
+            .await?;
+
+        Ok(segment_done)
+    }
+
+    /// Plays from `position` at `rate` for a fast preview, backward if `rate` is negative.
+    ///
+    /// Only the key frames are decoded and the audio is skipped if the demuxer supports it.
+    /// Use `seek` in order to go back to normal playback.
+    pub async fn seek_trick(&mut self, position: Timestamp, rate: f64) -> Result<(), SeekError> {
+        self.abort_segment();
+
+        let flags = gst::SeekFlags::TRICKMODE
+            | gst::SeekFlags::TRICKMODE_KEY_UNITS
+            | gst::SeekFlags::TRICKMODE_NO_AUDIO;
+        if rate < 0f64 {
+            // Backward playback starts from the stop position
+            self.seek_range(Timestamp::default(), Some(position), rate, flags)
+                .await
+        } else {
+            self.seek_range(position, None, rate, flags).await
+        }
+    }
+
+    async fn seek_range(
+        &mut self,
+        target: Timestamp,
+        stop: Option<Timestamp>,
+        rate: f64,
+        flags: gst::SeekFlags,
+    ) -> Result<(), SeekError> {
+        self.purge_int_msg()?;
+        let prev_rate = self.position_tracker.lock().unwrap().rate();
+        self.position_tracker.lock().unwrap().set_rate(rate);
+
+        let (stop_type, stop) = Self::seek_stop(stop);
+        let res = self.pipeline.seek(
+            rate,
+            gst::SeekFlags::FLUSH | flags,
+            gst::SeekType::Set,
+            ClockTime::from(target.as_u64()),
+            stop_type,
+            stop,
+        );
+        if res.is_err() {
+            warn!(
+                "couldn't seek to {} at rate {}",
+                target.for_humans().to_string(),
+                rate,
+            );
+            self.position_tracker.lock().unwrap().set_rate(prev_rate);
+            return Err(SeekError::Rejected);
+        }
+
+        if target >= self.info.duration {
+            return Err(SeekError::Eos);
+        }
+
+        use gst::MessageView::*;
+        while let Some(msg) = self.int_msg_rx.next().await {
+            match msg.view() {
+                AsyncDone(_) => break,
+                Error(_) => return Err(SeekError::Unrecoverable),
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Plays from `start` to `stop` at `rate` right after the current segment.
+    ///
+    /// Playback goes on to the end of the media if `stop` is `None`, otherwise
+    /// the returned `SegmentDone` resolves when `stop` is reached.
+    ///
+    /// Must be called once the current segment is done: the seek doesn't flush
+    /// the pipeline so the transition is seamless.
+    pub fn chain_segment(
+        &self,
+        start: Timestamp,
+        stop: Option<Timestamp>,
+        rate: f64,
+    ) -> Option<SegmentDone> {
+        self.position_tracker.lock().unwrap().set_rate(rate);
+
+        let (flags, segment_done) = if stop.is_some() {
+            (
+                gst::SeekFlags::SEGMENT | gst::SeekFlags::ACCURATE,
+                Some(self.expect_segment_done()),
+            )
+        } else {
+            self.abort_segment();
+            (gst::SeekFlags::ACCURATE, None)
+        };
+
+        let (stop_type, stop) = Self::seek_stop(stop);
+        let res = self.pipeline.seek(
+            rate,
+            flags,
+            gst::SeekType::Set,
+            ClockTime::from(start.as_u64()),
+            stop_type,
+            stop,
+        );
+        if res.is_err() {
+            warn!(
+                "couldn't chain segment at {}",
+                start.for_humans().to_string()
+            );
+        }
+
+        segment_done
+    }
+
+    // Set before seeking so that the `SegmentDone` message can't be missed
+    fn expect_segment_done(&self) -> SegmentDone {
+        let (segment_done_tx, segment_done_rx) = oneshot::channel();
+        *self.segment_done_tx.lock().unwrap() = Some(segment_done_tx);
+
+        SegmentDone(segment_done_rx)
+    }
+
+    fn abort_segment(&self) {
+        self.segment_done_tx.lock().unwrap().take();
+    }
+
+    fn seek_stop(stop: Option<Timestamp>) -> (gst::SeekType, ClockTime) {
+        match stop {
+            Some(stop) => (gst::SeekType::Set, ClockTime::from(stop.as_u64())),
+            None => (gst::SeekType::None, ClockTime::none()),
+        }
+    }
+
+    pub fn is_seekable(&self) -> bool {
+        self.is_seekable
+    }
+
+    /// Returns the video quality of service statistics since the media was opened.
+    pub fn qos_stats(&self) -> QosStats {
+        self.qos_stats.lock().unwrap().clone()
+    }
+
+    /// Mutes the audio, e.g. to avoid glitches while scrubbing.
+    pub fn set_muted(&self, is_muted: bool) {
+        if let Some(volume) = self.pipeline.get_by_name(VOLUME_NAME) {
+            volume.set_property("mute", &is_muted).unwrap();
+        }
+    }
+
+    pub fn audio_offset(&self) -> i64 {
+        self.audio_offset
+    }
+
+    /// Delays the audio by `offset` nanoseconds, a negative `offset` plays it earlier.
+    pub fn set_audio_offset(&mut self, offset: i64) {
+        self.audio_offset = offset;
+        if let Some(audio_sink) = self.pipeline.get_by_name(AUDIO_SINK_NAME) {
+            Self::apply_audio_offset(&audio_sink, offset);
+        }
+    }
+
+    // The audio sink might be a bin, e.g. `autoaudiosink` or a user defined description
+    fn apply_audio_offset(audio_sink: &gst::Element, offset: i64) {
+        if audio_sink.find_property("ts-offset").is_some() {
+            audio_sink.set_property("ts-offset", &offset).unwrap();
+            return;
+        }
+
+        if let Some(bin) = audio_sink.downcast_ref::<gst::Bin>() {
+            let mut iter = bin.iterate_recurse();
+            while let Ok(Some(element)) = iter.next() {
+                if element.find_property("ts-offset").is_some() {
+                    element.set_property("ts-offset", &offset).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Replaces the audio sink, e.g. after the audio device was lost.
+    ///
+    /// The default audio sink is used if `audio_sink` is `None`.
+    pub fn reset_audio_sink(
+        &mut self,
+        audio_sink: Option<gst::Element>,
+    ) -> Result<(), StateChangeError> {
+        let new_sink = audio_sink.unwrap_or_else(Self::default_audio_sink);
+
+        let old_sink = match self.pipeline.get_by_name(AUDIO_SINK_NAME) {
+            Some(old_sink) => old_sink,
+            // No audio branch
+            None => return Ok(()),
+        };
+
+        let upstream = old_sink
+            .get_static_pad("sink")
+            .and_then(|sink_pad| sink_pad.get_peer())
+            .and_then(|src_pad| src_pad.get_parent_element())
+            .ok_or(StateChangeError)?;
+
+        let bin = Self::parent_bin(&old_sink).ok_or(StateChangeError)?;
+
+        upstream.unlink(&old_sink);
+        old_sink.set_state(gst::State::Null)?;
+        bin.remove(&old_sink).unwrap();
+
+        bin.add(&new_sink).map_err(|_| StateChangeError)?;
+        upstream.link(&new_sink).map_err(|_| StateChangeError)?;
+        new_sink
+            .sync_state_with_parent()
+            .map_err(|_| StateChangeError)?;
+        Self::apply_audio_offset(&new_sink, self.audio_offset);
+
+        Ok(())
+    }
+
+    pub async fn select_streams(
+        &mut self,
+        stream_ids: &[Arc<str>],
+    ) -> Result<StreamSelectionDelta, SelectStreamsError> {
+        self.purge_int_msg()?;
+
+        let stream_id_vec: Vec<&str> = stream_ids.iter().map(Borrow::<str>::borrow).collect();
+        let select_streams_evt = gst::event::SelectStreams::new(&stream_id_vec);
+        self.pipeline.send_event(select_streams_evt);
+
+        let delta = self.info.streams.select_streams(stream_ids)?;
+        if delta.video.new.is_some() && delta.video.is_changed() {
+            // The video sink would otherwise keep the previous stream's caps
+            // and wait for the next key frame. Flush at the current position
+            // so that the new stream is renegotiated from a clean state.
+            if let Some(position) = self.current_ts() {
+                let rate = self.position_tracker.lock().unwrap().rate();
+                let res = self
+                    .seek_range(position, None, rate, gst::SeekFlags::ACCURATE)
+                    .await;
+                if let Err(SeekError::Unrecoverable) = res {
+                    return Err(SelectStreamsError::Unrecoverable);
+                }
+            }
+        }
+
+        Ok(delta)
+    }
+
+    /// Limits the height of the rendered video, `None` for the original resolution.
+    ///
+    /// The video is downscaled before reaching the sink, which reduces the load
+    /// of the rendering. Applies immediately to the video being played.
+    pub fn set_max_video_height(&self, max_height: Option<u32>) {
+        *self.max_video_height.lock().unwrap() = max_height;
+
+        if let Some(caps_filter) = self.pipeline.get_by_name(VIDEO_CAPS_NAME) {
+            caps_filter
+                .set_property("caps", &Self::video_caps(max_height))
+                .unwrap();
+            // Renegotiate the branch from the upstream elements
+            let sink_pad = caps_filter.get_static_pad("sink").unwrap();
+            sink_pad.push_event(gst::event::Reconfigure::new());
+        }
+    }
+
+    /// Sets the rendering options of the text subtitles.
+    ///
+    /// Applies immediately to the subtitles being rendered.
+    pub fn set_subtitle_style(&self, style: SubtitleStyle) {
+        for element in self.pipeline.iterate_recurse().filter_map(Result::ok) {
+            style.apply(&element);
+        }
+
+        *self.subtitle_style.lock().unwrap() = style;
+    }
+}
+
+/// Debugging
+impl PlaybackPipeline {
+    /// Returns the pipeline graph in the Graphviz dot format.
+    pub fn dot_graph(&self) -> String {
+        gst::debug_bin_to_dot_data(&self.pipeline, gst::DebugGraphDetails::all()).to_string()
+    }
+
+    /// Returns the caps negotiated for the selected stream of type `type_`.
+    pub fn negotiated_caps(&self, type_: gst::StreamType) -> Option<gst::Caps> {
+        let prefix = match type_ {
+            gst::StreamType::AUDIO => "audio_",
+            gst::StreamType::VIDEO => "video_",
+            gst::StreamType::TEXT => "text_",
+            _ => return None,
+        };
+
+        self.decodebin()?
+            .get_src_pads()
+            .into_iter()
+            .find(|src_pad| src_pad.get_name().starts_with(prefix))
+            .and_then(|src_pad| src_pad.get_current_caps())
+    }
+}
+
+#[cfg(all(test, feature = "pipeline-tests"))]
+mod tests {
+    use futures::prelude::*;
+    use gst::prelude::*;
+    use lazy_static::lazy_static;
+
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    use super::{OpenError, PlaybackOptions, PlaybackPipeline, SeekError, SelectStreamsError};
+    use crate::{
+        media::{
+            test_media::{TestMedia, MEDIA_DURATION},
+            Backend, Timestamp,
+        },
+        metadata::{Duration, MediaContent},
+    };
+
+    const TOLERANCE: Duration = Duration::from_nanos(100_000_000);
+
+    lazy_static! {
+        // Tests share the default `MainContext` where the bus watches are attached
+        static ref MAIN_CONTEXT_LOCK: Mutex<()> = Mutex::new(());
+    }
+
+    fn lock_main_context() -> MutexGuard<'static, ()> {
+        gst::init().unwrap();
+        MAIN_CONTEXT_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn block_on<Fut: Future>(future: Fut) -> Fut::Output {
+        glib::MainContext::default().block_on(future)
+    }
+
+    fn video_sink() -> Option<gst::Element> {
+        Some(gst::ElementFactory::make("fakesink", Some("video_sink")).unwrap())
+    }
+
+    fn open(media: &TestMedia) -> PlaybackPipeline {
+        block_on(PlaybackPipeline::try_new(
+            media.path(),
+            &video_sink(),
+            PlaybackOptions::default(),
+        ))
+        .unwrap()
+    }
+
+    fn assert_ts_near(expected: Timestamp, actual: Timestamp) {
+        let delta = if actual > expected {
+            actual - expected
+        } else {
+            expected - actual
+        };
+        assert!(
+            delta <= TOLERANCE,
+            "expected {}, got {}",
+            expected.for_humans().to_string(),
+            actual.for_humans().to_string(),
+        );
+    }
+
+    #[test]
+    fn media_info() {
+        let _lock = lock_main_context();
+        let media = TestMedia::audio_video("media_info");
+
+        let mut pipeline = open(&media);
+        let info = &pipeline.info;
+
+        assert!(pipeline.missing_plugins.is_empty());
+        assert_eq!("media_info", info.name);
+        assert_eq!(MediaContent::AudioVideo, info.content);
+        assert_ts_near(MEDIA_DURATION.into(), info.duration.into());
+
+        assert_eq!(1, info.streams.video.sorted().count());
+        assert_eq!(2, info.streams.audio.sorted().count());
+        assert_eq!(0, info.streams.text.sorted().count());
+        assert!(info.streams.is_video_selected());
+        assert!(info.streams.selected_audio().is_some());
+        assert!(info.streams.selected_text().is_none());
+
+        let video_caps = &info.streams.selected_video().unwrap().caps;
+        let video_struct = video_caps.get_structure(0).unwrap();
+        assert_eq!(Some(320), video_struct.get::<i32>("width").unwrap());
+        assert_eq!(Some(240), video_struct.get::<i32>("height").unwrap());
+
+        pipeline.stop().unwrap();
+    }
+
+    #[test]
+    fn custom_audio_sink() {
+        let _lock = lock_main_context();
+        let media = TestMedia::audio("custom_audio_sink");
+
+        assert!(PlaybackPipeline::audio_sink_from_description("not_an_element").is_err());
+
+        let audio_sink =
+            PlaybackPipeline::audio_sink_from_description("audioconvert ! fakesink sync=true")
+                .unwrap();
+        let mut pipeline = block_on(PlaybackPipeline::try_new(
+            media.path(),
+            &None,
+            PlaybackOptions {
+                audio_sink: Some(audio_sink),
+                ..PlaybackOptions::default()
+            },
+        ))
+        .unwrap();
+        block_on(pipeline.play()).unwrap();
+
+        pipeline.stop().unwrap();
+    }
+
+    #[test]
+    fn audio_only() {
+        let _lock = lock_main_context();
+        let media = TestMedia::audio("audio_only");
+
+        let mut pipeline = open(&media);
+        let info = &pipeline.info;
+
+        assert_eq!(MediaContent::Audio, info.content);
+        assert_eq!(1, info.streams.audio.sorted().count());
+        assert!(!info.streams.is_video_selected());
+
+        pipeline.stop().unwrap();
+    }
+
+    #[cfg(feature = "player")]
+    #[test]
+    fn player_backend() {
+        let _lock = lock_main_context();
+        crate::media::init_player_env();
+        let media = TestMedia::audio_video("player_backend");
+
+        let mut pipeline = block_on(PlaybackPipeline::try_new(
+            media.path(),
+            &video_sink(),
+            PlaybackOptions {
+                backend: Backend::Player,
+                ..PlaybackOptions::default()
+            },
+        ))
+        .unwrap();
+        let info = &pipeline.info;
+
+        assert_eq!(MediaContent::AudioVideo, info.content);
+        assert!(info.streams.is_video_selected());
+        assert!(info.streams.selected_audio().is_some());
+
+        block_on(pipeline.play()).unwrap();
+        pipeline.stop().unwrap();
+    }
+
+    #[test]
+    fn text_only() {
+        let _lock = lock_main_context();
+        let media = TestMedia::text("text_only");
+
+        match block_on(PlaybackPipeline::try_new(
+            media.path(),
+            &video_sink(),
+            PlaybackOptions::default(),
+        )) {
+            Err(OpenError::TextOnly) => (),
+            Err(err) => panic!("unexpected error {}", err),
+            Ok(_) => panic!("text only media opened"),
+        }
+    }
+
+    #[test]
+    fn max_video_height() {
+        let _lock = lock_main_context();
+        let media = TestMedia::audio_video("max_video_height");
+
+        let mut pipeline = block_on(PlaybackPipeline::try_new(
+            media.path(),
+            &video_sink(),
+            PlaybackOptions {
+                max_video_height: Some(120),
+                ..PlaybackOptions::default()
+            },
+        ))
+        .unwrap();
+
+        let sink_caps = pipeline
+            .pipeline
+            .get_by_name("video_sink")
+            .and_then(|video_sink| video_sink.get_static_pad("sink"))
+            .and_then(|sink_pad| sink_pad.get_current_caps())
+            .unwrap();
+        let sink_struct = sink_caps.get_structure(0).unwrap();
+        assert_eq!(Some(160), sink_struct.get::<i32>("width").unwrap());
+        assert_eq!(Some(120), sink_struct.get::<i32>("height").unwrap());
+
+        pipeline.stop().unwrap();
+    }
+
+    #[test]
+    fn play_pause_seek() {
+        let _lock = lock_main_context();
+        let media = TestMedia::audio_video("play_pause_seek");
+
+        let mut pipeline = open(&media);
+        assert!(pipeline.is_seekable());
+
+        block_on(pipeline.play()).unwrap();
+        block_on(pipeline.pause()).unwrap();
+
+        let target = Timestamp::from(Duration::from_secs(1));
+        block_on(pipeline.seek(target, gst::SeekFlags::ACCURATE)).unwrap();
+        assert_ts_near(target, pipeline.current_ts().unwrap());
+
+        let past_end = Timestamp::from(Duration::from_secs(3));
+        match block_on(pipeline.seek(past_end, gst::SeekFlags::ACCURATE)) {
+            Err(SeekError::Eos) => (),
+            other => panic!("unexpected seek result {:?}", other),
+        }
+
+        pipeline.stop().unwrap();
+    }
+
+    #[test]
+    fn segment() {
+        let _lock = lock_main_context();
+        let media = TestMedia::audio_video("segment");
+
+        let mut pipeline = open(&media);
+
+        let start = Timestamp::from(Duration::from_nanos(500_000_000));
+        let stop = Timestamp::from(Duration::from_secs(1));
+        let segment_done =
+            block_on(pipeline.seek_segment(start, stop, 1f64, gst::SeekFlags::ACCURATE)).unwrap();
+        block_on(pipeline.play()).unwrap();
+        block_on(segment_done).unwrap();
+
+        let segment_done = pipeline.chain_segment(start, Some(stop), 2f64).unwrap();
+        block_on(segment_done).unwrap();
+        assert_ts_near(stop, pipeline.current_ts().unwrap());
+
+        // A flushing seek supersedes the pending segment
+        let segment_done = pipeline.chain_segment(start, Some(stop), 1f64).unwrap();
+        block_on(pipeline.seek(start, gst::SeekFlags::ACCURATE)).unwrap();
+        assert!(block_on(segment_done).is_err());
+
+        pipeline.stop().unwrap();
+    }
+
+    #[test]
+    fn select_streams() {
+        let _lock = lock_main_context();
+        let media = TestMedia::audio_video("select_streams");
+
+        let mut pipeline = open(&media);
+
+        let video_id = Arc::clone(&pipeline.info.streams.selected_video().unwrap().id);
+        let initial_audio_id = Arc::clone(&pipeline.info.streams.selected_audio().unwrap().id);
+        let other_audio_id = pipeline
+            .info
+            .streams
+            .audio
+            .sorted()
+            .map(|stream| Arc::clone(&stream.id))
+            .find(|id| *id != initial_audio_id)
+            .unwrap();
+
+        let delta = block_on(
+            pipeline.select_streams(&[Arc::clone(&video_id), Arc::clone(&other_audio_id)]),
+        )
+        .unwrap();
+        assert_eq!(
+            other_audio_id,
+            pipeline.info.streams.selected_audio().unwrap().id
+        );
+        assert_eq!(Some(&initial_audio_id), delta.audio.previous.as_ref());
+        assert_eq!(Some(&other_audio_id), delta.audio.new.as_ref());
+        assert!(!delta.video.is_changed());
+        assert!(!delta.text.is_changed());
+
+        // Deselect video
+        let delta = block_on(pipeline.select_streams(&[Arc::clone(&other_audio_id)])).unwrap();
+        assert!(!pipeline.info.streams.is_video_selected());
+        assert_eq!(Some(&video_id), delta.video.previous.as_ref());
+        assert_eq!(None, delta.video.new);
+        assert!(!delta.audio.is_changed());
+
+        // Select video again, the video branch is attached again
+        let delta = block_on(
+            pipeline.select_streams(&[Arc::clone(&video_id), Arc::clone(&other_audio_id)]),
+        )
+        .unwrap();
+        assert!(pipeline.info.streams.is_video_selected());
+        assert_eq!(Some(&video_id), delta.video.new.as_ref());
+
+        block_on(pipeline.select_streams(&[Arc::clone(&other_audio_id)])).unwrap();
+
+        // Same selection
+        let delta = block_on(pipeline.select_streams(&[Arc::clone(&other_audio_id)])).unwrap();
+        assert!(delta.is_empty());
+
+        let unknown_id: Arc<str> = "unknown".into();
+        match block_on(pipeline.select_streams(&[Arc::clone(&unknown_id)])) {
+            Err(SelectStreamsError::UnknownId(id)) => assert_eq!(unknown_id, id),
+            other => panic!("unexpected select streams result {:?}", other),
+        }
+
+        pipeline.stop().unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+//! Support for the `Player` `Backend`.
+
+use gst::prelude::*;
+
+use std::{env, sync::Mutex};
+
+/// Sets up the process environment for the `Player` `Backend`.
+///
+/// Changing the environment is not thread safe: this must be called
+/// on startup, before `gst::init()` and before any other thread is spawned.
+pub fn init_player_env() {
+    // Streams selection relies on the `StreamCollection` messages
+    env::set_var("GST_PLAYER_USE_PLAYBIN3", "1");
+}
+
+/// Builds a `Player` for `uri` and returns it along with the pipeline it drives.
+///
+/// Neither a video renderer nor a signal dispatcher are used: the sinks are set
+/// on the pipeline and the messages are received from its bus.
+/// See `init_player_env` for the required environment.
+pub fn new_player(uri: &str) -> (gst_player::Player, gst::Pipeline) {
+    let player = gst_player::Player::new(
+        None::<&gst_player::PlayerVideoRenderer>,
+        None::<&gst_player::PlayerSignalDispatcher>,
+    );
+
+    // The position is tracked by the `PlaybackPipeline`
+    let mut config = player.get_config();
+    config.set_position_update_interval(0);
+    player.set_config(config).unwrap();
+
+    player.set_uri(uri);
+
+    let pipeline = player
+        .get_pipeline()
+        .downcast::<gst::Pipeline>()
+        .expect("Player: `playbin3` is not a `Pipeline`");
+
+    (player, pipeline)
+}
+
+/// Handles the messages from `bus` with `func` on the main context.
+///
+/// The `Player` already watches the bus from its own thread, so the messages
+/// are received from the `message` signal instead of a second watch.
+pub fn add_bus_watch<F>(bus: &gst::Bus, mut func: F) -> glib::SourceId
+where
+    F: FnMut(&gst::Message) -> glib::Continue + 'static,
+{
+    let (msg_tx, msg_rx) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+    // The signal handler must be `Sync`
+    let msg_tx = Mutex::new(msg_tx);
+    let handler_id = bus.connect_message(None, move |_, msg| {
+        let _ = msg_tx.lock().unwrap().send(msg.clone());
+    });
+
+    let bus = bus.clone();
+    let mut handler_id = Some(handler_id);
+    msg_rx.attach(None, move |msg| {
+        let res = func(&msg);
+        if !res.0 {
+            if let Some(handler_id) = handler_id.take() {
+                bus.disconnect(handler_id);
+            }
+        }
+
+        res
+    })
+}
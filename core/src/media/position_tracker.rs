@@ -0,0 +1,117 @@
+use std::time::{Duration as StdDuration, Instant};
+
+use crate::metadata::Duration;
+
+use super::Timestamp;
+
+/// Period after which the position must be queried again.
+///
+/// The pipeline clock and the system clock drift apart,
+/// e.g. when the network source is buffering.
+const RESYNC_PERIOD: StdDuration = StdDuration::from_millis(500);
+
+/// Interpolates the playback position between accurate position queries.
+#[derive(Debug)]
+pub struct PositionTracker {
+    // Last queried position and when it was queried
+    reference: Option<(Timestamp, Instant)>,
+    is_playing: bool,
+    rate: f64,
+}
+
+impl Default for PositionTracker {
+    fn default() -> Self {
+        PositionTracker {
+            reference: None,
+            is_playing: false,
+            rate: 1f64,
+        }
+    }
+}
+
+impl PositionTracker {
+    /// Returns the interpolated position at `now`, `None` if a query is required.
+    pub fn position(&self, now: Instant) -> Option<Timestamp> {
+        let (position, queried_at) = self.reference?;
+        if !self.is_playing {
+            return Some(position);
+        }
+
+        // Don't bother interpolating backward playback
+        if self.rate <= 0f64 {
+            return None;
+        }
+
+        let elapsed = now.checked_duration_since(queried_at)?;
+        if elapsed >= RESYNC_PERIOD {
+            return None;
+        }
+
+        Some(position + Duration::from_nanos((elapsed.as_nanos() as f64 * self.rate) as u64))
+    }
+
+    /// Records the `position` returned by a query issued at `now`.
+    pub fn sync(&mut self, position: Timestamp, now: Instant) {
+        self.reference = Some((position, now));
+    }
+
+    /// Forces a query on next `position`, e.g. after a seek.
+    pub fn reset(&mut self) {
+        self.reference = None;
+    }
+
+    pub fn set_playing(&mut self, is_playing: bool) {
+        self.is_playing = is_playing;
+        self.reset();
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Sets the playback rate, e.g. 2.0 for twice the normal speed.
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate;
+        self.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolation() {
+        let mut tracker = PositionTracker::default();
+        let start = Instant::now();
+        assert_eq!(None, tracker.position(start));
+
+        // Paused: the position doesn't move
+        let position = Timestamp::new(1_000_000_000);
+        tracker.sync(position, start);
+        assert_eq!(
+            Some(position),
+            tracker.position(start + StdDuration::from_secs(10))
+        );
+
+        tracker.set_playing(true);
+        assert_eq!(None, tracker.position(start));
+
+        tracker.sync(position, start);
+        assert_eq!(
+            Some(Timestamp::new(1_100_000_000)),
+            tracker.position(start + StdDuration::from_millis(100)),
+        );
+        assert_eq!(None, tracker.position(start + RESYNC_PERIOD));
+
+        tracker.reset();
+        assert_eq!(None, tracker.position(start));
+
+        tracker.set_rate(2f64);
+        tracker.sync(position, start);
+        assert_eq!(
+            Some(Timestamp::new(1_200_000_000)),
+            tracker.position(start + StdDuration::from_millis(100)),
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::metadata::Duration;
+
+/// Video quality of service statistics reported by the pipeline.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QosStats {
+    // (processed, dropped) per element: the counters are cumulative
+    counters: HashMap<String, (u64, u64)>,
+    jitter_sum: u64,
+    jitter_count: u64,
+}
+
+impl QosStats {
+    /// Accounts for a QoS report from `element`.
+    ///
+    /// `jitter` is the difference between the buffer running time
+    /// and its expected running time in nanoseconds.
+    pub fn add(&mut self, element: &str, processed: u64, dropped: u64, jitter: i64) {
+        self.counters
+            .insert(element.to_owned(), (processed, dropped));
+        self.jitter_sum += jitter.abs() as u64;
+        self.jitter_count += 1;
+    }
+
+    pub fn processed(&self) -> u64 {
+        self.counters.values().map(|(processed, _)| processed).sum()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.counters.values().map(|(_, dropped)| dropped).sum()
+    }
+
+    pub fn average_jitter(&self) -> Option<Duration> {
+        if self.jitter_count == 0 {
+            return None;
+        }
+
+        Some(Duration::from_nanos(self.jitter_sum / self.jitter_count))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = QosStats::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulate() {
+        let mut stats = QosStats::default();
+        assert!(stats.is_empty());
+        assert_eq!(None, stats.average_jitter());
+
+        stats.add("sink", 10, 1, 2_000_000);
+        stats.add("sink", 20, 3, -4_000_000);
+        stats.add("decoder", 5, 2, 0);
+
+        assert_eq!(25, stats.processed());
+        assert_eq!(5, stats.dropped());
+        assert_eq!(
+            Some(Duration::from_nanos(2_000_000)),
+            stats.average_jitter()
+        );
+
+        stats.clear();
+        assert!(stats.is_empty());
+    }
+}
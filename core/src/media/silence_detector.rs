@@ -0,0 +1,207 @@
+use gettextrs::gettext;
+
+use gst::prelude::*;
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::metadata::{Duration, MediaPath};
+
+use super::Timestamp;
+
+/// Audio level under which the signal is considered silent.
+pub const SILENCE_THRESHOLD_DB: f64 = -50f64;
+/// Minimum duration for a silence to be considered as a chapter boundary.
+pub const MIN_SILENCE_DURATION: Duration = Duration::from_secs(2);
+
+const LEVEL_INTERVAL: u64 = 100_000_000; // 100 ms
+const POLL_PERIOD: u64 = 100_000_000; // 100 ms
+
+/// Collects the chapter boundaries from the audio levels.
+///
+/// Boundaries are placed in the middle of the silences lasting at least
+/// `min_duration`. Silences at the beginning or the end of the media
+/// don't separate chapters.
+#[derive(Debug)]
+pub struct SilenceTracker {
+    threshold_db: f64,
+    min_duration: Duration,
+    has_sound: bool,
+    silence_start: Option<u64>,
+    boundaries: Vec<Timestamp>,
+}
+
+impl SilenceTracker {
+    pub fn new(threshold_db: f64, min_duration: Duration) -> Self {
+        SilenceTracker {
+            threshold_db,
+            min_duration,
+            has_sound: false,
+            silence_start: None,
+            boundaries: Vec::new(),
+        }
+    }
+
+    /// Pushes the `level_db` measured for the interval starting at `ts`.
+    pub fn push(&mut self, ts: u64, level_db: f64) {
+        if level_db < self.threshold_db {
+            if self.has_sound && self.silence_start.is_none() {
+                self.silence_start = Some(ts);
+            }
+
+            return;
+        }
+
+        if let Some(silence_start) = self.silence_start.take() {
+            if ts - silence_start >= self.min_duration.as_u64() {
+                self.boundaries
+                    .push(Timestamp::new(silence_start + (ts - silence_start) / 2));
+            }
+        }
+
+        self.has_sound = true;
+    }
+
+    pub fn into_boundaries(self) -> Vec<Timestamp> {
+        self.boundaries
+    }
+}
+
+/// Decodes the audio from the media at `path` and looks for long silences.
+///
+/// This is a blocking function which is expected to run off the UI thread.
+/// Setting `is_cancelled` aborts the detection.
+pub fn detect(path: &MediaPath, is_cancelled: Arc<AtomicBool>) -> Result<Vec<Timestamp>, String> {
+    let uri = path.uri()?;
+
+    let pipeline = gst::Pipeline::new(Some("silence_detector"));
+    let make = |factory_name: &str| {
+        gst::ElementFactory::make(factory_name, None).map_err(|_| {
+            gettext("Missing plugin:\n{}").replacen("{}", &format!("- {}", factory_name), 1)
+        })
+    };
+
+    let decodebin = make("uridecodebin")?;
+    decodebin.set_property("uri", &uri).unwrap();
+    let convert = make("audioconvert")?;
+    let level = make("level")?;
+    level.set_property("interval", &LEVEL_INTERVAL).unwrap();
+    level.set_property("post-messages", &true).unwrap();
+    let sink = make("fakesink")?;
+    sink.set_property("sync", &false).unwrap();
+
+    pipeline
+        .add_many(&[&decodebin, &convert, &level, &sink])
+        .unwrap();
+    gst::Element::link_many(&[&convert, &level, &sink]).unwrap();
+
+    decodebin.connect_pad_added(move |_decodebin, src_pad| {
+        let is_audio = src_pad
+            .get_current_caps()
+            .and_then(|caps| {
+                caps.get_structure(0)
+                    .map(|structure| structure.get_name().starts_with("audio/"))
+            })
+            .unwrap_or(false);
+
+        // Only use the first audio stream
+        let sink_pad = convert.get_static_pad("sink").unwrap();
+        if is_audio && !sink_pad.is_linked() {
+            let _ = src_pad.link(&sink_pad);
+        }
+    });
+
+    pipeline
+        .set_state(gst::State::Playing)
+        .map_err(|_| gettext("Failed to start the silence detection"))?;
+
+    let bus = pipeline.get_bus().unwrap();
+    let mut tracker = SilenceTracker::new(SILENCE_THRESHOLD_DB, MIN_SILENCE_DURATION);
+    let res = loop {
+        if is_cancelled.load(Ordering::Relaxed) {
+            break Err(gettext("Silence detection cancelled"));
+        }
+
+        let msg = match bus.timed_pop(gst::ClockTime::from_nseconds(POLL_PERIOD)) {
+            Some(msg) => msg,
+            None => continue,
+        };
+
+        use gst::MessageView::*;
+        match msg.view() {
+            Eos(_) => break Ok(tracker.into_boundaries()),
+            Error(err) => {
+                break Err(gettext("Silence detection failed. {}").replacen(
+                    "{}",
+                    &err.get_error().to_string(),
+                    1,
+                ))
+            }
+            Element(_) => {
+                let structure = match msg.get_structure() {
+                    Some(structure) if structure.get_name() == "level" => structure,
+                    _ => continue,
+                };
+
+                let ts = match structure.get::<u64>("stream-time") {
+                    Ok(Some(ts)) => ts,
+                    _ => continue,
+                };
+                // Loudest channel
+                let level_db = match structure.get::<glib::ValueArray>("rms") {
+                    Ok(Some(rms)) => rms
+                        .iter()
+                        .filter_map(|value| value.get_some::<f64>().ok())
+                        .fold(std::f64::NEG_INFINITY, f64::max),
+                    _ => continue,
+                };
+
+                tracker.push(ts, level_db);
+            }
+            _ => (),
+        }
+    };
+
+    let _ = pipeline.set_state(gst::State::Null);
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u64 = 1_000_000_000;
+
+    fn track(levels: &[f64]) -> Vec<u64> {
+        let mut tracker = SilenceTracker::new(SILENCE_THRESHOLD_DB, MIN_SILENCE_DURATION);
+        for (idx, level_db) in levels.iter().enumerate() {
+            tracker.push(idx as u64 * SEC, *level_db);
+        }
+
+        tracker
+            .into_boundaries()
+            .into_iter()
+            .map(Timestamp::as_u64)
+            .collect()
+    }
+
+    #[test]
+    fn silence_tracker() {
+        const SILENT: f64 = -90f64;
+        const SOUND: f64 = -20f64;
+
+        // leading & trailing silences are ignored
+        assert!(track(&[SILENT, SILENT, SILENT, SOUND, SOUND, SILENT, SILENT, SILENT]).is_empty());
+
+        // short silences are ignored
+        assert!(track(&[SOUND, SILENT, SOUND, SOUND]).is_empty());
+
+        assert_eq!(
+            vec![2 * SEC, 7 * SEC + SEC / 2],
+            track(&[SOUND, SILENT, SILENT, SOUND, SOUND, SOUND, SILENT, SILENT, SILENT, SOUND]),
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use gettextrs::gettext;
+
+use gst::prelude::*;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::metadata::MediaPath;
+
+use super::Timestamp;
+
+const POLL_PERIOD: u64 = 100_000_000; // 100 ms
+
+/// A frame to capture.
+#[derive(Clone, Debug)]
+pub struct SnapshotTarget {
+    pub ts: Timestamp,
+    /// The file name for the snapshot, without the extension.
+    pub name: String,
+}
+
+impl SnapshotTarget {
+    /// Builds a target for the chapter at `idx` starting at `start`.
+    ///
+    /// The file names are prefixed with the chapter number
+    /// so that the snapshots are listed in the chapters order.
+    pub fn for_chapter(idx: usize, start: Timestamp, title: &str) -> Self {
+        let title = sanitize(title);
+        let name = if title.is_empty() {
+            format!("{:02}", idx + 1)
+        } else {
+            format!("{:02} - {}", idx + 1, title)
+        };
+
+        SnapshotTarget { ts: start, name }
+    }
+}
+
+/// Makes `name` suitable for a file name.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_start_matches('.')
+        .to_owned()
+}
+
+/// Captures the video frames at `targets` from the media at `path` as PNG files in `dir`.
+///
+/// This is a blocking function which is expected to run off the UI thread.
+/// `progress` is called with the number of snapshots written so far.
+/// Setting `is_cancelled` aborts the capture.
+pub fn capture(
+    path: &MediaPath,
+    targets: &[SnapshotTarget],
+    dir: &Path,
+    is_cancelled: Arc<AtomicBool>,
+    progress: impl Fn(usize),
+) -> Result<Vec<PathBuf>, String> {
+    let uri = path.uri()?;
+
+    fs::create_dir_all(dir).map_err(|err| {
+        gettext("Couldn't create directory {}. {}")
+            .replacen("{}", &dir.display().to_string(), 1)
+            .replacen("{}", &err.to_string(), 1)
+    })?;
+
+    let pipeline = gst::Pipeline::new(Some("snapshot"));
+    let make = |factory_name: &str| {
+        gst::ElementFactory::make(factory_name, None).map_err(|_| {
+            gettext("Missing plugin:\n{}").replacen("{}", &format!("- {}", factory_name), 1)
+        })
+    };
+
+    let decodebin = make("uridecodebin")?;
+    decodebin.set_property("uri", &uri).unwrap();
+    let convert = make("videoconvert")?;
+    let encoder = make("pngenc")?;
+    let sink = make("fakesink")?;
+    sink.set_property("sync", &false).unwrap();
+    sink.set_property("enable-last-sample", &true).unwrap();
+
+    pipeline
+        .add_many(&[&decodebin, &convert, &encoder, &sink])
+        .unwrap();
+    gst::Element::link_many(&[&convert, &encoder, &sink]).unwrap();
+
+    decodebin.connect_pad_added(move |_decodebin, src_pad| {
+        let is_video = src_pad
+            .get_current_caps()
+            .and_then(|caps| {
+                caps.get_structure(0)
+                    .map(|structure| structure.get_name().starts_with("video/"))
+            })
+            .unwrap_or(false);
+
+        // Only use the first video stream
+        let sink_pad = convert.get_static_pad("sink").unwrap();
+        if is_video && !sink_pad.is_linked() {
+            let _ = src_pad.link(&sink_pad);
+        }
+    });
+
+    let bus = pipeline.get_bus().unwrap();
+    let res = pipeline
+        .set_state(gst::State::Paused)
+        .map_err(|_| gettext("Failed to start the snapshot capture"))
+        .and_then(|_| wait_async_done(&bus, &is_cancelled))
+        .and_then(|_| {
+            let mut snapshots = Vec::with_capacity(targets.len());
+            for target in targets {
+                pipeline
+                    .seek_simple(
+                        gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+                        gst::ClockTime::from_nseconds(target.ts.as_u64()),
+                    )
+                    .map_err(|_| gettext("Failed to seek to the snapshot position"))?;
+                wait_async_done(&bus, &is_cancelled)?;
+
+                let snapshot = dir.join(format!("{}.png", target.name));
+                write_last_sample(&sink, &snapshot)?;
+                snapshots.push(snapshot);
+                progress(snapshots.len());
+            }
+
+            Ok(snapshots)
+        });
+
+    let _ = pipeline.set_state(gst::State::Null);
+
+    res
+}
+
+/// Waits for the pipeline to preroll after a state change or a flushing seek.
+fn wait_async_done(bus: &gst::Bus, is_cancelled: &AtomicBool) -> Result<(), String> {
+    loop {
+        if is_cancelled.load(Ordering::Relaxed) {
+            return Err(gettext("Snapshot capture cancelled"));
+        }
+
+        let msg = match bus.timed_pop(gst::ClockTime::from_nseconds(POLL_PERIOD)) {
+            Some(msg) => msg,
+            None => continue,
+        };
+
+        use gst::MessageView::*;
+        match msg.view() {
+            AsyncDone(_) => return Ok(()),
+            Eos(_) => return Err(gettext("No video frame to capture")),
+            Error(err) => {
+                return Err(gettext("Snapshot capture failed. {}").replacen(
+                    "{}",
+                    &err.get_error().to_string(),
+                    1,
+                ))
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Writes the PNG frame held by the `sink` to `snapshot`.
+fn write_last_sample(sink: &gst::Element, snapshot: &Path) -> Result<(), String> {
+    let sample = sink
+        .get_property("last-sample")
+        .ok()
+        .and_then(|value| value.get::<gst::Sample>().ok().flatten())
+        .ok_or_else(|| gettext("No video frame to capture"))?;
+    let buffer = sample
+        .get_buffer()
+        .and_then(|buffer| buffer.map_readable().ok())
+        .ok_or_else(|| gettext("No video frame to capture"))?;
+
+    fs::write(snapshot, buffer.as_slice()).map_err(|err| {
+        gettext("Failed to write snapshot {}. {}")
+            .replacen("{}", &snapshot.display().to_string(), 1)
+            .replacen("{}", &err.to_string(), 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chapter_names() {
+        let ts = Timestamp::new(0);
+
+        assert_eq!(
+            "01 - Intro",
+            SnapshotTarget::for_chapter(0, ts, "Intro").name
+        );
+        assert_eq!(
+            "12 - Part 1_ A_B",
+            SnapshotTarget::for_chapter(11, ts, " Part 1: A/B ").name,
+        );
+        assert_eq!("03", SnapshotTarget::for_chapter(2, ts, "").name);
+        assert_eq!(
+            "04 - hidden",
+            SnapshotTarget::for_chapter(3, ts, "..hidden").name
+        );
+    }
+}
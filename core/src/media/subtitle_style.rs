@@ -0,0 +1,45 @@
+use gst::prelude::*;
+
+// Elements which render the text subtitles on the video
+const TEXT_OVERLAYS: &[&str] = &["subtitleoverlay", "textoverlay"];
+
+/// Rendering options of the text subtitles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubtitleStyle {
+    /// Pango font description, e.g. `Sans Bold 18`, the renderer's default if `None`.
+    pub font_desc: Option<String>,
+    pub is_outline_drawn: bool,
+}
+
+impl Default for SubtitleStyle {
+    fn default() -> Self {
+        SubtitleStyle {
+            font_desc: None,
+            is_outline_drawn: true,
+        }
+    }
+}
+
+impl SubtitleStyle {
+    /// Applies the style to `element` if it renders text subtitles.
+    pub(super) fn apply(&self, element: &gst::Element) {
+        let is_text_overlay = element.get_factory().map_or(false, |factory| {
+            TEXT_OVERLAYS.contains(&factory.get_name().as_str())
+        });
+        if !is_text_overlay {
+            return;
+        }
+
+        if let Some(font_desc) = self.font_desc.as_ref() {
+            if element.find_property("font-desc").is_some() {
+                element.set_property("font-desc", font_desc).unwrap();
+            }
+        }
+        // `subtitleoverlay` delegates the rendering to a `textoverlay`
+        if element.find_property("draw-outline").is_some() {
+            element
+                .set_property("draw-outline", &self.is_outline_drawn)
+                .unwrap();
+        }
+    }
+}
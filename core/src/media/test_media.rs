@@ -0,0 +1,128 @@
+//! Short media files generated with GStreamer for the tests.
+
+use gst::prelude::*;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::metadata::{Duration, MediaPath};
+
+pub const MEDIA_DURATION: Duration = Duration::from_secs(2);
+
+pub struct TestMedia {
+    path: MediaPath,
+    toc_path: Option<PathBuf>,
+}
+
+impl TestMedia {
+    /// Generates a media with a 320x240 video stream and two audio streams.
+    pub fn audio_video(name: &str) -> Self {
+        Self::generate(
+            name,
+            "oggmux name=mux ! filesink name=sink \
+             videotestsrc num-buffers=50 \
+                ! video/x-raw,width=320,height=240,framerate=25/1 \
+                ! videoconvert ! theoraenc ! mux. \
+             audiotestsrc num-buffers=20 samplesperbuffer=4410 freq=440 \
+                ! audio/x-raw,rate=44100,channels=2 \
+                ! audioconvert ! vorbisenc ! mux. \
+             audiotestsrc num-buffers=20 samplesperbuffer=4410 freq=880 \
+                ! audio/x-raw,rate=44100,channels=1 \
+                ! audioconvert ! vorbisenc ! mux.",
+        )
+    }
+
+    pub fn audio(name: &str) -> Self {
+        Self::generate(
+            name,
+            "oggmux name=mux ! filesink name=sink \
+             audiotestsrc num-buffers=20 samplesperbuffer=4410 \
+                ! audio/x-raw,rate=44100,channels=2 \
+                ! audioconvert ! vorbisenc ! mux.",
+        )
+    }
+
+    /// Generates standalone subtitles.
+    pub fn text(name: &str) -> Self {
+        let path = Self::file_path(name, "srt");
+        fs::write(
+            &path,
+            "1\n00:00:00,000 --> 00:00:01,000\nfirst\n\n\
+             2\n00:00:01,000 --> 00:00:02,000\nsecond\n",
+        )
+        .unwrap();
+
+        TestMedia {
+            path: path.into(),
+            toc_path: None,
+        }
+    }
+
+    fn file_path(name: &str, extension: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("media-toc-player-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(format!("{}.{}", name, extension))
+    }
+
+    fn generate(name: &str, pipeline_desc: &str) -> Self {
+        let path = Self::file_path(name, "ogg");
+
+        let pipeline = gst::parse_launch(pipeline_desc)
+            .unwrap()
+            .downcast::<gst::Pipeline>()
+            .unwrap();
+        pipeline
+            .get_by_name("sink")
+            .unwrap()
+            .set_property("location", &path.to_str().unwrap())
+            .unwrap();
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+        let msg = pipeline
+            .get_bus()
+            .unwrap()
+            .timed_pop_filtered(
+                gst::CLOCK_TIME_NONE,
+                &[gst::MessageType::Eos, gst::MessageType::Error],
+            )
+            .unwrap();
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        if let gst::MessageView::Error(err) = msg.view() {
+            panic!("couldn't generate {:?}: {}", path, err.get_error());
+        }
+
+        TestMedia {
+            path: path.into(),
+            toc_path: None,
+        }
+    }
+
+    /// Adds a toc file in mkvmerge text format next to the media.
+    pub fn with_toc(mut self, content: &str) -> Self {
+        let toc_path = self.path.with_extension("txt");
+        fs::write(&toc_path, content).unwrap();
+        self.toc_path = Some(toc_path);
+
+        self
+    }
+
+    pub fn path(&self) -> &MediaPath {
+        &self.path
+    }
+
+    pub fn toc_path(&self) -> Option<&Path> {
+        self.toc_path.as_deref()
+    }
+}
+
+impl Drop for TestMedia {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        if let Some(toc_path) = self.toc_path.as_ref() {
+            let _ = fs::remove_file(toc_path);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use glib::translate::{FromGlib, ToGlib};
+use gst::prelude::*;
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+// Some hardware decoders don't advertise themselves in their klass
+const HARDWARE_DECODER_PREFIXES: [&str; 7] = ["d3d11", "msdk", "nv", "omx", "v4l2", "va", "vtdec"];
+
+fn is_video_decoder(factory: &gst::ElementFactory) -> bool {
+    factory.get_metadata("klass").map_or(false, |klass| {
+        klass.contains("Decoder") && klass.contains("Video")
+    })
+}
+
+/// Whether `factory` builds a video decoder backed by the GPU or a dedicated chip.
+pub fn is_hardware_decoder(factory: &gst::ElementFactory) -> bool {
+    if !is_video_decoder(factory) {
+        return false;
+    }
+
+    let is_hardware_klass = factory
+        .get_metadata("klass")
+        .map_or(false, |klass| klass.contains("Hardware"));
+    let name = factory.get_name();
+
+    is_hardware_klass
+        || HARDWARE_DECODER_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// Whether `object` is or belongs to a hardware video decoder.
+pub fn is_in_hardware_decoder(object: &gst::Object) -> bool {
+    let mut object = Some(object.clone());
+    while let Some(cur_object) = object {
+        let is_hardware_decoder = cur_object
+            .downcast_ref::<gst::Element>()
+            .and_then(gst::Element::get_factory)
+            .map_or(false, |factory| is_hardware_decoder(&factory));
+        if is_hardware_decoder {
+            return true;
+        }
+
+        object = cur_object.get_parent();
+    }
+
+    false
+}
+
+/// How the video streams are decoded.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum VideoDecoding {
+    /// Prefers the hardware decoders, falls back to software decoding on failure.
+    Auto,
+    Hardware,
+    Software,
+}
+
+impl Default for VideoDecoding {
+    fn default() -> Self {
+        VideoDecoding::Auto
+    }
+}
+
+impl VideoDecoding {
+    /// Ranks the hardware video decoders so that `decodebin3` picks them or not.
+    ///
+    /// Applies to the media opened afterwards.
+    pub fn apply(self) {
+        let rank = match self {
+            VideoDecoding::Auto | VideoDecoding::Hardware => {
+                gst::Rank::from_glib(gst::Rank::Primary.to_glib() + 1)
+            }
+            VideoDecoding::Software => gst::Rank::None,
+        };
+
+        for feature in gst::Registry::get().get_feature_list(gst::ElementFactory::static_type()) {
+            let factory = match feature.downcast::<gst::ElementFactory>() {
+                Ok(factory) => factory,
+                Err(_) => continue,
+            };
+            if !is_hardware_decoder(&factory) {
+                continue;
+            }
+
+            if factory.get_rank() != rank {
+                debug!("{}: rank {:?}", factory.get_name(), rank);
+                factory.set_rank(rank);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hardware_decoder_ranks() -> Vec<gst::Rank> {
+        gst::Registry::get()
+            .get_feature_list(gst::ElementFactory::static_type())
+            .into_iter()
+            .filter_map(|feature| feature.downcast::<gst::ElementFactory>().ok())
+            .filter(is_hardware_decoder)
+            .map(|factory| factory.get_rank())
+            .collect()
+    }
+
+    #[test]
+    fn ranks() {
+        gst::init().unwrap();
+
+        VideoDecoding::Software.apply();
+        assert!(hardware_decoder_ranks()
+            .into_iter()
+            .all(|rank| rank == gst::Rank::None));
+
+        VideoDecoding::Auto.apply();
+        assert!(hardware_decoder_ranks()
+            .into_iter()
+            .all(|rank| rank.to_glib() > gst::Rank::Primary.to_glib()));
+    }
+}
@@ -0,0 +1,244 @@
+use gettextrs::gettext;
+
+use gst::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::metadata::{Duration, MediaPath};
+
+use super::Timestamp;
+
+/// Maximum number of peaks in a `Waveform`, whatever the media duration.
+pub const MAX_PEAKS: u64 = 4096;
+/// Minimum duration covered by a peak.
+pub const MIN_PEAK_INTERVAL: u64 = 50_000_000; // 50 ms
+
+const POLL_PERIOD: u64 = 100_000_000; // 100 ms
+
+// Peaks below this are considered silent
+const MIN_PEAK_DB: f64 = -60f64;
+
+/// A coarse representation of the audio amplitude over the whole media.
+///
+/// Each peak covers `interval` nanoseconds. Peaks range from 0 (silence)
+/// to `u8::max_value()` (full scale) on a dB scale.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Waveform {
+    pub interval: u64,
+    pub peaks: Vec<u8>,
+}
+
+impl Waveform {
+    /// Returns the interval for the peaks of a media lasting `duration`.
+    pub fn interval_for(duration: Duration) -> u64 {
+        (duration.as_u64() / MAX_PEAKS).max(MIN_PEAK_INTERVAL)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peaks.is_empty()
+    }
+
+    /// Returns the highest peak between `start` and `end`, from 0 to 1.
+    pub fn peak(&self, start: Timestamp, end: Timestamp) -> f64 {
+        if self.interval == 0 {
+            return 0f64;
+        }
+
+        let first = (start.as_u64() / self.interval) as usize;
+        let last = (end.as_u64() / self.interval) as usize;
+        self.peaks
+            .iter()
+            .skip(first)
+            .take(last.saturating_sub(first) + 1)
+            .max()
+            .map_or(0f64, |peak| f64::from(*peak) / f64::from(u8::max_value()))
+    }
+}
+
+/// Collects the peaks of a `Waveform` from the audio levels.
+#[derive(Debug)]
+pub struct PeakCollector {
+    waveform: Waveform,
+}
+
+impl PeakCollector {
+    pub fn new(interval: u64) -> Self {
+        PeakCollector {
+            waveform: Waveform {
+                interval,
+                peaks: Vec::new(),
+            },
+        }
+    }
+
+    /// Pushes the `peak_db` measured for the interval starting at `ts`.
+    pub fn push(&mut self, ts: u64, peak_db: f64) {
+        let idx = (ts / self.waveform.interval) as usize;
+        if idx >= self.waveform.peaks.len() {
+            self.waveform.peaks.resize(idx + 1, 0);
+        }
+
+        let peak = ((peak_db.min(0f64) - MIN_PEAK_DB) / -MIN_PEAK_DB).max(0f64)
+            * f64::from(u8::max_value());
+        let peak = peak.round() as u8;
+        if peak > self.waveform.peaks[idx] {
+            self.waveform.peaks[idx] = peak;
+        }
+    }
+
+    pub fn into_waveform(self) -> Waveform {
+        self.waveform
+    }
+}
+
+/// Decodes the audio from the media at `path` and computes its `Waveform`.
+///
+/// This is a blocking function which is expected to run off the UI thread.
+/// Setting `is_cancelled` aborts the generation.
+pub fn generate(
+    path: &MediaPath,
+    duration: Duration,
+    is_cancelled: Arc<AtomicBool>,
+) -> Result<Waveform, String> {
+    let uri = path.uri()?;
+    let interval = Waveform::interval_for(duration);
+
+    let pipeline = gst::Pipeline::new(Some("waveform_generator"));
+    let make = |factory_name: &str| {
+        gst::ElementFactory::make(factory_name, None).map_err(|_| {
+            gettext("Missing plugin:\n{}").replacen("{}", &format!("- {}", factory_name), 1)
+        })
+    };
+
+    let decodebin = make("uridecodebin")?;
+    decodebin.set_property("uri", &uri).unwrap();
+    let convert = make("audioconvert")?;
+    let level = make("level")?;
+    level.set_property("interval", &interval).unwrap();
+    level.set_property("post-messages", &true).unwrap();
+    let sink = make("fakesink")?;
+    sink.set_property("sync", &false).unwrap();
+
+    pipeline
+        .add_many(&[&decodebin, &convert, &level, &sink])
+        .unwrap();
+    gst::Element::link_many(&[&convert, &level, &sink]).unwrap();
+
+    decodebin.connect_pad_added(move |_decodebin, src_pad| {
+        let is_audio = src_pad
+            .get_current_caps()
+            .and_then(|caps| {
+                caps.get_structure(0)
+                    .map(|structure| structure.get_name().starts_with("audio/"))
+            })
+            .unwrap_or(false);
+
+        // Only use the first audio stream
+        let sink_pad = convert.get_static_pad("sink").unwrap();
+        if is_audio && !sink_pad.is_linked() {
+            let _ = src_pad.link(&sink_pad);
+        }
+    });
+
+    pipeline
+        .set_state(gst::State::Playing)
+        .map_err(|_| gettext("Failed to start the waveform generation"))?;
+
+    let bus = pipeline.get_bus().unwrap();
+    let mut collector = PeakCollector::new(interval);
+    let res = loop {
+        if is_cancelled.load(Ordering::Relaxed) {
+            break Err(gettext("Waveform generation cancelled"));
+        }
+
+        let msg = match bus.timed_pop(gst::ClockTime::from_nseconds(POLL_PERIOD)) {
+            Some(msg) => msg,
+            None => continue,
+        };
+
+        use gst::MessageView::*;
+        match msg.view() {
+            Eos(_) => break Ok(collector.into_waveform()),
+            Error(err) => {
+                break Err(gettext("Waveform generation failed. {}").replacen(
+                    "{}",
+                    &err.get_error().to_string(),
+                    1,
+                ))
+            }
+            Element(_) => {
+                let structure = match msg.get_structure() {
+                    Some(structure) if structure.get_name() == "level" => structure,
+                    _ => continue,
+                };
+
+                let ts = match structure.get::<u64>("stream-time") {
+                    Ok(Some(ts)) => ts,
+                    _ => continue,
+                };
+                // Loudest channel
+                let peak_db = match structure.get::<glib::ValueArray>("peak") {
+                    Ok(Some(peak)) => peak
+                        .iter()
+                        .filter_map(|value| value.get_some::<f64>().ok())
+                        .fold(std::f64::NEG_INFINITY, f64::max),
+                    _ => continue,
+                };
+
+                collector.push(ts, peak_db);
+            }
+            _ => (),
+        }
+    };
+
+    let _ = pipeline.set_state(gst::State::Null);
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u64 = 1_000_000_000;
+
+    #[test]
+    fn interval_for() {
+        assert_eq!(
+            MIN_PEAK_INTERVAL,
+            Waveform::interval_for(Duration::from_secs(10))
+        );
+        assert_eq!(
+            3600 * SEC / MAX_PEAKS,
+            Waveform::interval_for(Duration::from_secs(3600))
+        );
+    }
+
+    #[test]
+    fn peak_collector() {
+        let mut collector = PeakCollector::new(SEC);
+        collector.push(0, -90f64);
+        collector.push(SEC / 2, 0f64);
+        collector.push(3 * SEC, -30f64);
+        collector.push(3 * SEC + SEC / 2, -40f64);
+
+        let waveform = collector.into_waveform();
+        assert_eq!(vec![255, 0, 0, 128], waveform.peaks);
+
+        assert_eq!(1f64, waveform.peak(Timestamp::new(0), Timestamp::new(SEC)));
+        assert_eq!(
+            0f64,
+            waveform.peak(Timestamp::new(SEC), Timestamp::new(2 * SEC))
+        );
+        assert!(waveform.peak(Timestamp::new(2 * SEC), Timestamp::new(3 * SEC)) > 0.5f64);
+        // Out of the waveform
+        assert_eq!(
+            0f64,
+            waveform.peak(Timestamp::new(5 * SEC), Timestamp::new(6 * SEC))
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     fmt,
-    ops::{Div, DivAssign, Mul, MulAssign},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub},
 };
 
 // FIXME: consider moving to std::time::Duration when `div_duration` is stabilized.
@@ -36,6 +36,28 @@ impl Into<u64> for Duration {
     }
 }
 
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, rhs: Duration) -> Self::Output {
+        Duration(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = Duration(self.0 + rhs.0);
+    }
+}
+
+impl Sub for Duration {
+    type Output = Duration;
+
+    fn sub(self, rhs: Duration) -> Self::Output {
+        Duration(self.0 - rhs.0)
+    }
+}
+
 impl Div for Duration {
     type Output = Duration;
 
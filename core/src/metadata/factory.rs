@@ -0,0 +1,199 @@
+use lazy_static::lazy_static;
+
+use std::{ffi::OsStr, path::Path, sync::RwLock};
+
+use super::{Format, MKVMergeTextFormat, Reader, Writer};
+
+/// Describes a toc format so that the `Factory` can detect, read and write it.
+#[derive(Clone, Copy)]
+pub struct FormatRegistration {
+    pub format: Format,
+    pub extension: &'static str,
+    pub mime_type: &'static str,
+    /// Whether the beginning of a file looks like this format.
+    pub sniff: fn(&[u8]) -> bool,
+    pub new_reader: fn() -> Box<dyn Reader>,
+    /// `None` if the format can only be read.
+    pub new_writer: Option<fn() -> Box<dyn Writer>>,
+}
+
+/// The formats known to the `Factory`.
+#[derive(Default)]
+struct Registry(Vec<FormatRegistration>);
+
+impl Registry {
+    fn register(&mut self, registration: FormatRegistration) {
+        match self
+            .0
+            .iter_mut()
+            .find(|registered| registered.format.id == registration.format.id)
+        {
+            Some(registered) => *registered = registration,
+            None => self.0.push(registration),
+        }
+    }
+
+    fn extensions(&self) -> Vec<&'static str> {
+        let mut extensions = Vec::<&'static str>::new();
+        for registration in self.0.iter() {
+            if !extensions.contains(&registration.extension) {
+                extensions.push(registration.extension);
+            }
+        }
+
+        extensions
+    }
+
+    fn detect(&self, path: &Path, content: &[u8]) -> Option<Format> {
+        if let Some(registration) = self
+            .0
+            .iter()
+            .find(|registration| (registration.sniff)(content))
+        {
+            return Some(registration.format);
+        }
+
+        let (content_type, is_uncertain) = gio::content_type_guess(Some(path), content);
+        if !is_uncertain {
+            if let Some(mime_type) = gio::content_type_get_mime_type(&content_type) {
+                if let Some(registration) = self
+                    .0
+                    .iter()
+                    .find(|registration| registration.mime_type == mime_type.as_str())
+                {
+                    return Some(registration.format);
+                }
+            }
+        }
+
+        let extension = path.extension()?;
+        self.0
+            .iter()
+            .find(|registration| extension == OsStr::new(registration.extension))
+            .map(|registration| registration.format)
+    }
+
+    fn reader(&self, format: Format) -> Option<Box<dyn Reader>> {
+        self.0
+            .iter()
+            .find(|registration| registration.format == format)
+            .map(|registration| (registration.new_reader)())
+    }
+
+    fn writer(&self, format: Format) -> Option<Box<dyn Writer>> {
+        self.0
+            .iter()
+            .find(|registration| registration.format == format)
+            .and_then(|registration| registration.new_writer)
+            .map(|new_writer| new_writer())
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<Registry> = RwLock::new(Registry::default());
+}
+
+/// Registers the formats supported by this build.
+pub fn register_formats() {
+    Factory::register(MKVMergeTextFormat::registration());
+}
+
+pub struct Factory {}
+
+impl Factory {
+    /// Registers a format, replacing any format registered with the same id.
+    pub fn register(registration: FormatRegistration) {
+        REGISTRY.write().unwrap().register(registration);
+    }
+
+    /// Returns the extensions of the registered formats, without duplicates.
+    pub fn get_extensions() -> Vec<&'static str> {
+        REGISTRY.read().unwrap().extensions()
+    }
+
+    /// Detects the format of the toc file at `path` starting with `content`.
+    ///
+    /// The content is sniffed first, then its mime type is guessed,
+    /// the extension is used as a last resort.
+    pub fn detect(path: &Path, content: &[u8]) -> Option<Format> {
+        REGISTRY.read().unwrap().detect(path, content)
+    }
+
+    pub fn get_reader(format: Format) -> Option<Box<dyn Reader>> {
+        REGISTRY.read().unwrap().reader(format)
+    }
+
+    pub fn get_writer(format: Format) -> Option<Box<dyn Writer>> {
+        REGISTRY.read().unwrap().writer(format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::metadata::{MediaInfo, ReadError};
+
+    const JSON_FORMAT: Format = Format {
+        id: "test_json",
+        name: "test json",
+    };
+
+    struct JsonReader;
+
+    impl Reader for JsonReader {
+        fn read(
+            &self,
+            _info: &MediaInfo,
+            _source: &mut dyn Read,
+        ) -> Result<Option<gst::Toc>, ReadError> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let mut registry = Registry::default();
+        registry.register(MKVMergeTextFormat::registration());
+        registry.register(FormatRegistration {
+            format: JSON_FORMAT,
+            extension: "txt",
+            mime_type: "application/json",
+            sniff: |content| content.starts_with(b"{"),
+            new_reader: || Box::new(JsonReader),
+            new_writer: None,
+        });
+
+        // Extensions are not duplicated
+        assert_eq!(vec!["txt"], registry.extensions());
+
+        let path = Path::new("media.txt");
+        assert_eq!(
+            Some(MKVMergeTextFormat::FORMAT),
+            registry.detect(path, b"CHAPTER01=00:00:00.000\nCHAPTER01NAME=intro\n"),
+        );
+        assert_eq!(
+            Some(JSON_FORMAT),
+            registry.detect(path, b"{\"chapters\": []}")
+        );
+
+        assert!(registry.reader(JSON_FORMAT).is_some());
+        assert!(registry.writer(JSON_FORMAT).is_none());
+        assert!(registry.writer(MKVMergeTextFormat::FORMAT).is_some());
+        assert!(registry
+            .reader(Format {
+                id: "unknown",
+                name: "unknown",
+            })
+            .is_none());
+
+        // Registering the same format again replaces it
+        registry.register(FormatRegistration {
+            new_writer: Some(|| Box::new(MKVMergeTextFormat {})),
+            ..registry.0[1]
+        });
+        assert_eq!(2, registry.0.len());
+        assert!(registry.writer(JSON_FORMAT).is_some());
+    }
+}
@@ -0,0 +1,63 @@
+use gettextrs::gettext;
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+use super::MediaInfo;
+
+/// An error reading a toc with a `Reader`.
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    /// A syntax error, `line` and `col` are 1-based.
+    Parse {
+        line: usize,
+        col: usize,
+        msg: String,
+    },
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ReadError::*;
+
+        match self {
+            Io(err) => f.write_str(&gettext("couldn't read the toc: {}").replacen(
+                "{}",
+                &err.to_string(),
+                1,
+            )),
+            Parse { line, col, msg } => f.write_str(
+                &gettext("line {}, column {}: {}")
+                    .replacen("{}", &line.to_string(), 1)
+                    .replacen("{}", &col.to_string(), 1)
+                    .replacen("{}", msg, 1),
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(err) => Some(err),
+            ReadError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+pub trait Reader {
+    fn read(&self, info: &MediaInfo, source: &mut dyn Read) -> Result<Option<gst::Toc>, ReadError>;
+}
+
+pub trait Writer {
+    fn write(&self, toc: &gst::Toc, destination: &mut dyn Write) -> Result<(), String>;
+}
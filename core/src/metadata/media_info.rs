@@ -2,14 +2,9 @@ use gettextrs::gettext;
 use gst::Tag;
 use lazy_static::lazy_static;
 
-use std::{
-    collections::HashMap,
-    fmt,
-    path::{Path, PathBuf},
-    sync::Arc,
-};
+use std::{collections::HashMap, fmt, mem, sync::Arc};
 
-use super::{Duration, MediaContent};
+use super::{Duration, MediaContent, MediaPath};
 
 #[derive(Debug)]
 pub struct SelectStreamError(Arc<str>);
@@ -35,6 +30,30 @@ pub fn get_default_chapter_title() -> String {
     gettext("untitled")
 }
 
+/// Whether `stream` is a picture attached to an audio media rather than an actual video.
+///
+/// Some demuxers expose the cover art of MP3 or FLAC files as a video stream,
+/// either with image caps or as a still (0 fps) MJPEG / PNG stream.
+pub fn is_attached_picture(stream: &gst::Stream) -> bool {
+    if stream.get_stream_type() != gst::StreamType::VIDEO {
+        return false;
+    }
+
+    let caps = match stream.get_caps() {
+        Some(caps) => caps,
+        None => return false,
+    };
+    let structure = match caps.get_structure(0) {
+        Some(structure) => structure,
+        None => return false,
+    };
+
+    structure.get_name().starts_with("image/")
+        || structure
+            .get_some::<gst::Fraction>("framerate")
+            .map_or(false, |framerate| *framerate.numer() == 0)
+}
+
 macro_rules! add_tag_names (
     ($($tag_type:path),+) => {
         {
@@ -153,6 +172,14 @@ impl StreamCollection {
         self.collection.contains_key(id.as_ref())
     }
 
+    pub fn len(&self) -> usize {
+        self.collection.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.collection.is_empty()
+    }
+
     pub fn sorted(&self) -> impl Iterator<Item = &'_ Stream> {
         SortedStreamCollectionIter::new(self)
     }
@@ -185,6 +212,37 @@ impl<'sc> Iterator for SortedStreamCollectionIter<'sc> {
     }
 }
 
+/// Selected stream id for a stream type, before & after a selection.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SelectedStreamChange {
+    pub previous: Option<Arc<str>>,
+    pub new: Option<Arc<str>>,
+}
+
+impl SelectedStreamChange {
+    fn new(previous: Option<Arc<str>>, new: Option<Arc<str>>) -> Self {
+        SelectedStreamChange { previous, new }
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.previous != self.new
+    }
+}
+
+/// Changes resulting from a stream selection.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StreamSelectionDelta {
+    pub audio: SelectedStreamChange,
+    pub video: SelectedStreamChange,
+    pub text: SelectedStreamChange,
+}
+
+impl StreamSelectionDelta {
+    pub fn is_empty(&self) -> bool {
+        !(self.audio.is_changed() || self.video.is_changed() || self.text.is_changed())
+    }
+}
+
 #[derive(Debug)]
 pub struct Streams {
     pub audio: StreamCollection,
@@ -192,11 +250,8 @@ pub struct Streams {
     pub text: StreamCollection,
 
     cur_audio_id: Option<Arc<str>>,
-    pub audio_changed: bool,
     cur_video_id: Option<Arc<str>>,
-    pub video_changed: bool,
     cur_text_id: Option<Arc<str>>,
-    pub text_changed: bool,
 }
 
 impl Default for Streams {
@@ -207,11 +262,8 @@ impl Default for Streams {
             text: StreamCollection::new(gst::StreamType::TEXT),
 
             cur_audio_id: None,
-            audio_changed: false,
             cur_video_id: None,
-            video_changed: false,
             cur_text_id: None,
-            text_changed: false,
         }
     }
 }
@@ -267,46 +319,52 @@ impl Streams {
             .and_then(|stream_id| self.text.get(stream_id))
     }
 
-    pub fn select_streams(&mut self, ids: &[Arc<str>]) -> Result<(), SelectStreamError> {
-        let mut is_audio_selected = false;
-        let mut is_text_selected = false;
-        let mut is_video_selected = false;
+    /// Returns the current selection as changes from no selected streams.
+    pub fn initial_selection(&self) -> StreamSelectionDelta {
+        StreamSelectionDelta {
+            audio: SelectedStreamChange::new(None, self.cur_audio_id.clone()),
+            video: SelectedStreamChange::new(None, self.cur_video_id.clone()),
+            text: SelectedStreamChange::new(None, self.cur_text_id.clone()),
+        }
+    }
+
+    /// Selects the streams with the given `ids`, deselecting the other types.
+    ///
+    /// The selection is left untouched if an id is unknown.
+    pub fn select_streams(
+        &mut self,
+        ids: &[Arc<str>],
+    ) -> Result<StreamSelectionDelta, SelectStreamError> {
+        let mut audio_id = None;
+        let mut text_id = None;
+        let mut video_id = None;
 
         for id in ids {
             if self.audio.contains(id) {
-                is_audio_selected = true;
-                self.audio_changed = self
-                    .selected_audio()
-                    .map_or(true, |prev_stream| *id != prev_stream.id);
-                self.cur_audio_id = Some(Arc::clone(id));
+                audio_id = Some(Arc::clone(id));
             } else if self.text.contains(id) {
-                is_text_selected = true;
-                self.text_changed = self
-                    .selected_text()
-                    .map_or(true, |prev_stream| *id != prev_stream.id);
-                self.cur_text_id = Some(Arc::clone(id));
+                text_id = Some(Arc::clone(id));
             } else if self.video.contains(id) {
-                is_video_selected = true;
-                self.video_changed = self
-                    .selected_video()
-                    .map_or(true, |prev_stream| *id != prev_stream.id);
-                self.cur_video_id = Some(Arc::clone(id));
+                video_id = Some(Arc::clone(id));
             } else {
                 return Err(SelectStreamError::new(id));
             }
         }
 
-        if !is_audio_selected {
-            self.audio_changed = self.cur_audio_id.take().map_or(false, |_| true);
-        }
-        if !is_text_selected {
-            self.text_changed = self.cur_text_id.take().map_or(false, |_| true);
-        }
-        if !is_video_selected {
-            self.video_changed = self.cur_video_id.take().map_or(false, |_| true);
-        }
-
-        Ok(())
+        Ok(StreamSelectionDelta {
+            audio: SelectedStreamChange::new(
+                mem::replace(&mut self.cur_audio_id, audio_id),
+                self.cur_audio_id.clone(),
+            ),
+            video: SelectedStreamChange::new(
+                mem::replace(&mut self.cur_video_id, video_id),
+                self.cur_video_id.clone(),
+            ),
+            text: SelectedStreamChange::new(
+                mem::replace(&mut self.cur_text_id, text_id),
+                self.cur_text_id.clone(),
+            ),
+        })
     }
 
     pub fn audio_codec(&self) -> Option<&str> {
@@ -340,15 +398,39 @@ impl Streams {
     }
 }
 
+/// Cover art embedded in the media.
+#[derive(Clone, Debug)]
+pub struct CoverArt {
+    pub data: glib::Bytes,
+    /// e.g. `image/jpeg`.
+    pub mime_type: Option<String>,
+}
+
+impl CoverArt {
+    /// File extension matching the mime type, if known.
+    pub fn extension(&self) -> Option<&str> {
+        match self.mime_type.as_deref()? {
+            "image/jpeg" => Some("jpg"),
+            "image/png" => Some("png"),
+            "image/gif" => Some("gif"),
+            "image/bmp" => Some("bmp"),
+            "image/webp" => Some("webp"),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct MediaInfo {
     pub name: String,
     pub file_name: String,
-    pub path: PathBuf,
+    pub path: MediaPath,
     pub content: MediaContent,
     pub tags: gst::TagList,
     pub toc: Option<gst::Toc>,
     pub chapter_count: Option<usize>,
+    /// Extracted from the tags once the media is open, see `cache_cover_art`.
+    pub cover_art: Option<CoverArt>,
 
     pub description: String,
     pub duration: Duration,
@@ -357,16 +439,33 @@ pub struct MediaInfo {
 }
 
 impl MediaInfo {
-    pub fn new(path: &Path) -> Self {
+    pub fn new(path: &MediaPath) -> Self {
         MediaInfo {
-            name: path.file_stem().unwrap().to_str().unwrap().to_owned(),
-            file_name: path.file_name().unwrap().to_str().unwrap().to_owned(),
-            path: path.to_owned(),
+            name: path.stem_lossy(),
+            file_name: path.file_name_lossy(),
+            path: path.clone(),
             ..MediaInfo::default()
         }
     }
 
     pub fn add_stream(&mut self, gst_stream: &gst::Stream) {
+        if is_attached_picture(gst_stream) {
+            // Only keep the picture as a candidate for the cover art
+            let image = gst_stream
+                .get_tags()
+                .and_then(|tags| tags.get_index::<gst::tags::Image>(0)?.get());
+            if let Some(image) = image {
+                let mut image_tags = gst::TagList::new();
+                image_tags
+                    .get_mut()
+                    .unwrap()
+                    .add::<gst::tags::Image>(&image, gst::TagMergeMode::Append);
+                self.add_tags(&image_tags);
+            }
+
+            return;
+        }
+
         self.streams.add_stream(gst_stream);
         self.content.add_stream_type(gst_stream.get_stream_type());
     }
@@ -417,6 +516,23 @@ impl MediaInfo {
         self.tag_for_display::<gst::tags::Image, gst::tags::PreviewImage>()
     }
 
+    /// Extracts the cover art from the tags so that it is available as `cover_art`.
+    pub fn cache_cover_art(&mut self) {
+        self.cover_art = self.media_image().and_then(|image| {
+            let mime_type = image.get_caps().and_then(|caps| {
+                caps.get_structure(0)
+                    .map(|structure| structure.get_name().to_owned())
+            });
+            let data = image
+                .get_buffer()?
+                .map_readable()
+                .ok()
+                .map(|image_map| glib::Bytes::from_owned(image_map.as_slice().to_vec()))?;
+
+            Some(CoverArt { data, mime_type })
+        });
+    }
+
     pub fn container(&self) -> Option<&str> {
         // in case of an mp3 audio file, container comes as `ID3 label`
         // => bypass it
@@ -433,3 +549,53 @@ impl MediaInfo {
             .and_then(|value| value.get())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video_stream(id: &str, caps: gst::Caps) -> gst::Stream {
+        gst::Stream::new(
+            Some(id),
+            Some(&caps),
+            gst::StreamType::VIDEO,
+            gst::StreamFlags::empty(),
+        )
+    }
+
+    #[test]
+    fn attached_picture() {
+        gst::init().unwrap();
+
+        let mut info = MediaInfo::new(&MediaPath::new("/media/test.mp3"));
+        info.add_stream(&gst::Stream::new(
+            Some("audio-0"),
+            Some(&gst::Caps::new_simple("audio/mpeg", &[])),
+            gst::StreamType::AUDIO,
+            gst::StreamFlags::empty(),
+        ));
+
+        let cover = video_stream("video-0", gst::Caps::new_simple("image/jpeg", &[]));
+        assert!(is_attached_picture(&cover));
+        info.add_stream(&cover);
+
+        let still = video_stream(
+            "video-1",
+            gst::Caps::new_simple("video/x-raw", &[("framerate", &gst::Fraction::new(0, 1))]),
+        );
+        assert!(is_attached_picture(&still));
+        info.add_stream(&still);
+
+        assert_eq!(MediaContent::Audio, info.content);
+        assert!(!info.streams.is_video_selected());
+        assert!(info.streams.video.is_empty());
+
+        let video = video_stream(
+            "video-2",
+            gst::Caps::new_simple("video/x-h264", &[("framerate", &gst::Fraction::new(25, 1))]),
+        );
+        assert!(!is_attached_picture(&video));
+        info.add_stream(&video);
+        assert_eq!(MediaContent::AudioVideo, info.content);
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    env, fmt,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Path to a media file.
+///
+/// File names are not necessarily valid UTF-8: the path is kept untouched
+/// for file system operations and converted lossily for display purposes.
+/// Cloning a `MediaPath` is cheap.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MediaPath(Arc<Path>);
+
+impl MediaPath {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        MediaPath(path.into().into())
+    }
+
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// The file name without its extension, suitable for display.
+    pub fn stem_lossy(&self) -> String {
+        self.0
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned())
+    }
+
+    /// The file name, suitable for display.
+    pub fn file_name_lossy(&self) -> String {
+        self.0.file_name().map_or_else(String::new, |file_name| {
+            file_name.to_string_lossy().into_owned()
+        })
+    }
+
+    /// The `file://` URI for this path, whatever the encoding of the file name.
+    pub fn uri(&self) -> Result<String, String> {
+        let path = if self.0.is_absolute() {
+            self.0.to_path_buf()
+        } else {
+            env::current_dir()
+                .map_err(|err| err.to_string())?
+                .join(&self.0)
+        };
+
+        glib::filename_to_uri(&path, None)
+            .map(|uri| uri.to_string())
+            .map_err(|err| err.to_string())
+    }
+}
+
+impl Default for MediaPath {
+    fn default() -> Self {
+        MediaPath::new(PathBuf::new())
+    }
+}
+
+impl Deref for MediaPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for MediaPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl From<PathBuf> for MediaPath {
+    fn from(path: PathBuf) -> Self {
+        MediaPath::new(path)
+    }
+}
+
+impl From<&Path> for MediaPath {
+    fn from(path: &Path) -> Self {
+        MediaPath::new(path)
+    }
+}
+
+impl fmt::Display for MediaPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.display().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MediaPath;
+
+    #[test]
+    fn lossy_names() {
+        let path = MediaPath::new("/media/an.example.mkv");
+        assert_eq!("an.example", path.stem_lossy());
+        assert_eq!("an.example.mkv", path.file_name_lossy());
+        assert_eq!("file:///media/an.example.mkv", path.uri().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = MediaPath::new(OsStr::from_bytes(b"/media/caf\xe9.ogg"));
+        assert_eq!("caf\u{FFFD}", path.stem_lossy());
+        assert_eq!("caf\u{FFFD}.ogg", path.file_name_lossy());
+        assert_eq!("file:///media/caf%E9.ogg", path.uri().unwrap());
+    }
+}
@@ -0,0 +1,272 @@
+use serde::Serialize;
+
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use super::{MediaInfo, Stream, Timestamp4Humans, TocVisitor};
+
+/// Output format of a `MediaReport`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Text,
+}
+
+impl ReportFormat {
+    /// Deduces the format from the extension of `path`, defaults to `Text`.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => ReportFormat::Json,
+            _ => ReportFormat::Text,
+        }
+    }
+}
+
+// Images are not worth reporting.
+fn tags_report(tags: &gst::TagList) -> BTreeMap<String, String> {
+    use gst::Tag;
+
+    tags.iter()
+        .filter(|(name, _)| {
+            *name != gst::tags::Image::tag_name() && *name != gst::tags::PreviewImage::tag_name()
+        })
+        .filter_map(|(name, value)| {
+            let value = value.transform::<String>()?.get::<String>().ok()??;
+            Some((name.to_owned(), value))
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreamReport {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: &'static str,
+    pub codec: String,
+    pub caps: String,
+    pub is_selected: bool,
+    pub tags: BTreeMap<String, String>,
+}
+
+impl StreamReport {
+    fn new(stream: &Stream, is_selected: bool) -> Self {
+        let type_ = match stream.type_ {
+            gst::StreamType::AUDIO => "audio",
+            gst::StreamType::VIDEO => "video",
+            gst::StreamType::TEXT => "text",
+            _ => "unknown",
+        };
+
+        StreamReport {
+            id: stream.id.to_string(),
+            type_,
+            codec: stream.codec_printable.clone(),
+            caps: stream.caps.to_string(),
+            is_selected,
+            tags: tags_report(&stream.tags),
+        }
+    }
+}
+
+/// Chapter boundaries are expressed in nanoseconds.
+#[derive(Debug, Serialize)]
+pub struct ChapterReport {
+    pub start: u64,
+    pub end: u64,
+    pub title: Option<String>,
+}
+
+/// A summary of a `MediaInfo` for bug reports and cataloguing.
+///
+/// Durations are expressed in nanoseconds.
+#[derive(Debug, Serialize)]
+pub struct MediaReport {
+    pub path: String,
+    pub duration: u64,
+    pub container: Option<String>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub tags: BTreeMap<String, String>,
+    pub streams: Vec<StreamReport>,
+    pub chapters: Vec<ChapterReport>,
+}
+
+impl MediaReport {
+    pub fn new(info: &MediaInfo) -> Self {
+        let mut streams = Vec::new();
+        for (type_, selected) in &[
+            (gst::StreamType::VIDEO, info.streams.selected_video()),
+            (gst::StreamType::AUDIO, info.streams.selected_audio()),
+            (gst::StreamType::TEXT, info.streams.selected_text()),
+        ] {
+            streams.extend(info.streams.collection(*type_).sorted().map(|stream| {
+                let is_selected = selected.map_or(false, |selected| selected.id == stream.id);
+                StreamReport::new(stream, is_selected)
+            }));
+        }
+
+        let mut chapters = Vec::new();
+        if let Some(toc) = info.toc.as_ref() {
+            let mut toc_visitor = TocVisitor::new(toc);
+            if toc_visitor.enter_chapters() {
+                while let Some(chapter) = toc_visitor.next_chapter() {
+                    if let Some((start, end)) = chapter.get_start_stop_times() {
+                        let title = chapter.get_tags().and_then(|tags| {
+                            tags.get::<gst::tags::Title>()
+                                .and_then(|tag| tag.get().map(ToString::to_string))
+                        });
+                        chapters.push(ChapterReport {
+                            start: start as u64,
+                            end: end as u64,
+                            title,
+                        });
+                    }
+                }
+            }
+        }
+
+        MediaReport {
+            path: info.path.to_string(),
+            duration: info.duration.as_u64(),
+            container: info.container().map(ToString::to_string),
+            title: info.media_title().map(ToString::to_string),
+            artist: info.media_artist().map(ToString::to_string),
+            tags: tags_report(&info.tags),
+            streams,
+            chapters,
+        }
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Json => self.to_json(),
+            ReportFormat::Text => self.to_text(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("couldn't serialize media report")
+    }
+
+    pub fn to_text(&self) -> String {
+        fn write_tags(text: &mut String, tags: &BTreeMap<String, String>, indent: &str) {
+            for (name, value) in tags {
+                let _ = writeln!(text, "{}{}: {}", indent, name, value);
+            }
+        }
+
+        let mut text = String::new();
+        let _ = writeln!(text, "path: {}", self.path);
+        let _ = writeln!(
+            text,
+            "duration: {}",
+            Timestamp4Humans::from_nano(self.duration).to_string()
+        );
+        if let Some(container) = self.container.as_ref() {
+            let _ = writeln!(text, "container: {}", container);
+        }
+        if let Some(title) = self.title.as_ref() {
+            let _ = writeln!(text, "title: {}", title);
+        }
+        if let Some(artist) = self.artist.as_ref() {
+            let _ = writeln!(text, "artist: {}", artist);
+        }
+        write_tags(&mut text, &self.tags, "  ");
+
+        for stream in &self.streams {
+            let _ = writeln!(
+                text,
+                "\n{} stream {}{}",
+                stream.type_,
+                stream.id,
+                if stream.is_selected {
+                    " (selected)"
+                } else {
+                    ""
+                },
+            );
+            let _ = writeln!(text, "  codec: {}", stream.codec);
+            let _ = writeln!(text, "  caps: {}", stream.caps);
+            write_tags(&mut text, &stream.tags, "    ");
+        }
+
+        if !self.chapters.is_empty() {
+            let _ = writeln!(text, "\nchapters");
+            for chapter in &self.chapters {
+                let _ = writeln!(
+                    text,
+                    "  {} - {} {}",
+                    Timestamp4Humans::from_nano(chapter.start).to_string(),
+                    Timestamp4Humans::from_nano(chapter.end).to_string(),
+                    chapter.title.as_deref().unwrap_or(""),
+                );
+            }
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::{Duration, MediaPath};
+
+    #[test]
+    fn report() {
+        gst::init().unwrap();
+
+        let mut info = MediaInfo::new(&MediaPath::new("/media/test.mka"));
+        info.duration = Duration::from_secs(90);
+
+        let caps = gst::Caps::new_simple("audio/x-opus", &[]);
+        info.add_stream(&gst::Stream::new(
+            Some("audio-0"),
+            Some(&caps),
+            gst::StreamType::AUDIO,
+            gst::StreamFlags::empty(),
+        ));
+        info.streams.select_streams(&["audio-0".into()]).unwrap();
+
+        let mut toc = gst::Toc::new(gst::TocScope::Global);
+        {
+            let mut edition = gst::TocEntry::new(gst::TocEntryType::Edition, "edition");
+            let mut chapter = gst::TocEntry::new(gst::TocEntryType::Chapter, "1");
+            chapter
+                .get_mut()
+                .unwrap()
+                .set_start_stop_times(0, 60_000_000_000);
+            let mut tags = gst::TagList::new();
+            tags.get_mut()
+                .unwrap()
+                .add::<gst::tags::Title>(&"intro", gst::TagMergeMode::Replace);
+            chapter.get_mut().unwrap().set_tags(tags);
+            edition.get_mut().unwrap().append_sub_entry(chapter);
+            toc.get_mut().unwrap().append_entry(edition);
+        }
+        info.toc = Some(toc);
+
+        let report = MediaReport::new(&info);
+        assert_eq!(1, report.streams.len());
+        assert!(report.streams[0].is_selected);
+        assert_eq!(1, report.chapters.len());
+        assert_eq!(Some("intro"), report.chapters[0].title.as_deref());
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!("audio", json["streams"][0]["type"]);
+        assert_eq!(90_000_000_000u64, json["duration"]);
+
+        let text = report.to_text();
+        assert!(text.contains("duration: 01:30.000"));
+        assert!(text.contains("audio stream audio-0 (selected)"));
+        assert!(text.contains("00:00.000 - 01:00.000 intro"));
+
+        assert_eq!(
+            ReportFormat::Json,
+            ReportFormat::from_path(Path::new("report.JSON"))
+        );
+        assert_eq!(
+            ReportFormat::Text,
+            ReportFormat::from_path(Path::new("report.txt"))
+        );
+    }
+}
@@ -0,0 +1,476 @@
+use gettextrs::gettext;
+
+use log::error;
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::{alpha1, line_ending, not_line_ending},
+    combinator::{opt, verify},
+    error::ErrorKind,
+    multi::many0,
+    sequence::{pair, preceded, separated_pair, terminated},
+    IResult,
+};
+
+use std::io::{Read, Write};
+
+use super::{
+    parse_timestamp, parse_to, Duration, Format, FormatRegistration, MediaInfo, ReadError, Reader,
+    Timestamp4Humans, TimestampDisplay, TocBuilder, TocChapter, TocVisitor, Writer,
+};
+
+static EXTENSION: &str = "txt";
+static UTF8_BOM: &str = "\u{feff}";
+static MIME_TYPE: &str = "text/plain";
+
+static CHAPTER_TAG: &str = "CHAPTER";
+static NAME_TAG: &str = "NAME";
+// Optional lines following the name, e.g. `CHAPTER01ARTIST=artist`
+static ARTIST_TAG: &str = "ARTIST";
+static PERFORMER_TAG: &str = "PERFORMER";
+
+pub struct MKVMergeTextFormat {}
+
+impl MKVMergeTextFormat {
+    pub const FORMAT: Format = Format {
+        id: "mkvmerge_text",
+        name: "mkvmerge text",
+    };
+
+    pub fn registration() -> FormatRegistration {
+        FormatRegistration {
+            format: Self::FORMAT,
+            extension: EXTENSION,
+            mime_type: MIME_TYPE,
+            sniff: Self::sniff,
+            new_reader: || Box::new(MKVMergeTextFormat {}),
+            new_writer: Some(|| Box::new(MKVMergeTextFormat {})),
+        }
+    }
+
+    // Files start with the first chapter's timestamp: `CHAPTER01=00:00:00.000`
+    fn sniff(content: &[u8]) -> bool {
+        let content = if content.starts_with(UTF8_BOM.as_bytes()) {
+            &content[UTF8_BOM.len()..]
+        } else {
+            content
+        };
+
+        content.starts_with(CHAPTER_TAG.as_bytes())
+    }
+}
+
+fn new_chapter(
+    start_ts: Timestamp4Humans,
+    title: &str,
+    extra_lines: &[(&str, &str)],
+) -> TocChapter {
+    let mut chapter = TocChapter::new(Duration::from_nanos(start_ts.nano_total()), title);
+
+    for (key, value) in extra_lines {
+        if *key == ARTIST_TAG {
+            chapter.artist = Some((*value).to_owned());
+        } else if *key == PERFORMER_TAG {
+            chapter.performer = Some((*value).to_owned());
+        }
+        // other keys are ignored
+    }
+
+    chapter
+}
+
+fn parse_chapter(i: &str) -> IResult<&str, TocChapter> {
+    let parse_first_line = terminated(
+        preceded(
+            tag(CHAPTER_TAG),
+            separated_pair(parse_to::<usize>, tag("="), parse_timestamp),
+        ),
+        line_ending,
+    );
+
+    let (i, (nb, start_ts)) = parse_first_line(i)?;
+
+    let parse_second_line = terminated(
+        preceded(
+            tag(CHAPTER_TAG),
+            separated_pair(
+                verify(parse_to::<usize>, |nb2| nb == *nb2),
+                pair(tag(NAME_TAG), tag("=")),
+                not_line_ending,
+            ),
+        ),
+        opt(line_ending),
+    );
+
+    let (i, (_, title)) = parse_second_line(i)?;
+
+    let parse_extra_line = terminated(
+        preceded(
+            pair(
+                tag(CHAPTER_TAG),
+                verify(parse_to::<usize>, |nb3| nb == *nb3),
+            ),
+            separated_pair(alpha1, tag("="), not_line_ending),
+        ),
+        opt(line_ending),
+    );
+
+    many0(parse_extra_line)(i)
+        .map(|(i, extra_lines)| (i, new_chapter(start_ts, title, &extra_lines)))
+}
+
+#[test]
+fn parse_chapter_test() {
+    use nom::{error::ErrorKind, InputLength};
+    gst::init().unwrap();
+
+    let res = parse_chapter("CHAPTER01=00:00:01.000\nCHAPTER01NAME=test\n");
+    let (i, chapter) = res.unwrap();
+    assert_eq!(0, i.input_len());
+    assert_eq!(Duration::from_nanos(1_000_000_000), chapter.start);
+    assert_eq!("test", chapter.title);
+
+    let res = parse_chapter("CHAPTER01=00:00:01.000\r\nCHAPTER01NAME=test\r\n");
+    let (i, chapter) = res.unwrap();
+    assert_eq!(0, i.input_len());
+    assert_eq!(Duration::from_nanos(1_000_000_000), chapter.start);
+    assert_eq!("test", chapter.title);
+
+    let res = parse_chapter("CHAPTER01=00:00:01,500\nCHAPTER01NAME=test\n");
+    let (i, chapter) = res.unwrap();
+    assert_eq!(0, i.input_len());
+    assert_eq!(Duration::from_nanos(1_500_000_000), chapter.start);
+
+    let res = parse_chapter(
+        "CHAPTER01=00:00:01.000\nCHAPTER01NAME=test\nCHAPTER01ARTIST=artist\nCHAPTER02=00:00:02.000\n",
+    );
+    let (i, chapter) = res.unwrap();
+    assert_eq!("CHAPTER02=00:00:02.000\n", i);
+    assert_eq!(Some("artist".to_owned()), chapter.artist);
+
+    let res = parse_chapter("CHAPTER0x=00:00:01.000");
+    let err = res.unwrap_err();
+    if let nom::Err::Error((i, error_kind)) = err {
+        assert_eq!("x=00:00:01.000", i);
+        assert_eq!(ErrorKind::Tag, error_kind);
+    } else {
+        panic!("unexpected error type returned");
+    }
+
+    let res = parse_chapter("CHAPTER01=00:00:01.000\nCHAPTER02NAME=test\n");
+    let err = res.unwrap_err();
+    if let nom::Err::Error((i, error_kind)) = err {
+        assert_eq!("02NAME=test\n", i);
+        assert_eq!(ErrorKind::Verify, error_kind);
+    } else {
+        panic!("unexpected error type returned");
+    }
+}
+
+/// Builds the error for the parser which failed on the `remaining` part of `content`.
+///
+/// The column is counted in characters.
+fn parse_error(content: &str, remaining: &str, error_kind: ErrorKind) -> ReadError {
+    let offset = content.len() - remaining.len();
+    let parsed = &content[..offset];
+    let line_start = parsed.rfind('\n').map_or(0, |pos| pos + 1);
+
+    let excerpt = |len: usize| {
+        remaining
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .take(len)
+            .collect::<String>()
+    };
+    let msg = match error_kind {
+        ErrorKind::Digit | ErrorKind::ParseTo => {
+            gettext("expecting a number, found: {}").replacen("{}", &excerpt(2), 1)
+        }
+        ErrorKind::Verify => {
+            gettext("chapter numbers don't match for: {}").replacen("{}", &excerpt(2), 1)
+        }
+        ErrorKind::CrLf => {
+            gettext("expecting the end of the line, found: {}").replacen("{}", &excerpt(10), 1)
+        }
+        _ => gettext("unexpected sequence starting with: {}").replacen("{}", &excerpt(10), 1),
+    };
+
+    ReadError::Parse {
+        line: parsed.matches('\n').count() + 1,
+        col: parsed[line_start..].chars().count() + 1,
+        msg,
+    }
+}
+
+/// Parses the chapters in `content`, the last chapter ends at `duration`.
+fn parse(content: &str, duration: Duration) -> Result<Option<gst::Toc>, ReadError> {
+    let mut toc_builder = TocBuilder::new();
+    let content = content.strip_prefix(UTF8_BOM).unwrap_or(content);
+    let mut input = content;
+
+    loop {
+        // Blank lines are tolerated between the chapters
+        input = input.trim_start_matches(|c| c == '\r' || c == '\n');
+        if input.is_empty() {
+            break;
+        }
+
+        let chapter = match parse_chapter(input) {
+            Ok((i, chapter)) => {
+                input = i;
+                chapter
+            }
+            Err(nom::Err::Error((i, error_kind))) | Err(nom::Err::Failure((i, error_kind))) => {
+                return Err(parse_error(content, i, error_kind));
+            }
+            Err(nom::Err::Incomplete(_)) => {
+                return Err(parse_error(content, input, ErrorKind::Complete));
+            }
+        };
+
+        // Chapters end where the next one starts
+        toc_builder.add_chapter(chapter);
+    }
+
+    Ok(toc_builder.build(duration))
+}
+
+#[test]
+fn parse_errors() {
+    gst::init().unwrap();
+
+    let parse_err = |content: &str| match parse(content, Duration::default()).unwrap_err() {
+        ReadError::Parse { line, col, msg } => (line, col, msg),
+        err => panic!("unexpected error {}", err),
+    };
+
+    // Malformed timestamps
+    let (line, col, msg) = parse_err("CHAPTER01=00:0a:01.000\nCHAPTER01NAME=test\n");
+    assert_eq!((1, 15), (line, col));
+    assert!(msg.ends_with("a:01.000"));
+
+    let (line, col, _) = parse_err("CHAPTER01=00:00:01.000\nCHAPTER01NAME=test\nCHAPTER02=0x:00\n");
+    assert_eq!((3, 12), (line, col));
+
+    let (line, col, _) = parse_err("CHAPTER01=:00:01\n");
+    assert_eq!((1, 11), (line, col));
+
+    // `s` exceeds a `u8`
+    let (line, _, _) = parse_err("CHAPTER01=00:00:300.000\n");
+    assert_eq!(1, line);
+
+    // Chapter numbers
+    let (line, col, msg) = parse_err("CHAPTER01=00:00:01.000\r\nCHAPTER02NAME=test\r\n");
+    assert_eq!((2, 8), (line, col));
+    assert!(msg.ends_with("02"));
+
+    // Nested chapters can't be expressed in this format
+    let (line, col, msg) = parse_err("CHAPTER01.01=00:00:01.000\nCHAPTER01.01NAME=test\n");
+    assert_eq!((1, 10), (line, col));
+    assert!(msg.ends_with(".01=00:00:"));
+
+    // Missing name
+    let (line, col, _) = parse_err("CHAPTER01=00:00:01.000\nCHAPTER01=00:00:02.000\n");
+    assert_eq!((2, 10), (line, col));
+
+    // Unexpected extra line
+    let (line, col, _) = parse_err("CHAPTER01=00:00:01.000\nCHAPTER01NAME=été\nCHAPTER01ÉTÉ=x\n");
+    assert_eq!((3, 10), (line, col));
+}
+
+#[test]
+fn parse_bom_crlf() {
+    gst::init().unwrap();
+
+    let titles = |toc: gst::Toc| {
+        let mut titles = Vec::new();
+        let mut toc_visitor = TocVisitor::new(&toc);
+        assert!(toc_visitor.enter_chapters());
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            let (start, end) = chapter.get_start_stop_times().unwrap();
+            let title = chapter
+                .get_tags()
+                .and_then(|tags| {
+                    tags.get::<gst::tags::Title>()
+                        .and_then(|tag| tag.get().map(ToString::to_string))
+                })
+                .unwrap();
+            titles.push((start, end, title));
+        }
+
+        titles
+    };
+
+    let expected = vec![
+        (0, 1_000_000_000, "first".to_owned()),
+        (1_000_000_000, 3_000_000_000, "second".to_owned()),
+    ];
+
+    let content = "\u{feff}CHAPTER01=00:00:00.000\r\nCHAPTER01NAME=first\r\n\
+                   CHAPTER02=00:00:01.000\r\nCHAPTER02NAME=second\r\n\r\n";
+    assert_eq!(
+        expected,
+        titles(parse(content, Duration::from_secs(3)).unwrap().unwrap())
+    );
+
+    // Blank lines between chapters & no final line ending
+    let content = "CHAPTER01=00:00:00.000\nCHAPTER01NAME=first\n\n\
+                   CHAPTER02=00:00:01.000\nCHAPTER02NAME=second";
+    assert_eq!(
+        expected,
+        titles(parse(content, Duration::from_secs(3)).unwrap().unwrap())
+    );
+
+    assert!(parse("", Duration::default()).unwrap().is_none());
+    assert!(parse("\u{feff}\r\n", Duration::default())
+        .unwrap()
+        .is_none());
+
+    // The BOM doesn't count as a column
+    match parse("\u{feff}CHAPTER0x=00:00:01.000\n", Duration::default()).unwrap_err() {
+        ReadError::Parse { line, col, .. } => assert_eq!((1, 9), (line, col)),
+        err => panic!("unexpected error {}", err),
+    }
+}
+
+impl Reader for MKVMergeTextFormat {
+    fn read(&self, info: &MediaInfo, source: &mut dyn Read) -> Result<Option<gst::Toc>, ReadError> {
+        let mut content = String::new();
+        source.read_to_string(&mut content)?;
+
+        parse(&content, info.duration)
+    }
+}
+
+impl Writer for MKVMergeTextFormat {
+    // The format can't represent nested chapters, only the innermost chapters are written
+    fn write(&self, toc: &gst::Toc, destination: &mut dyn Write) -> Result<(), String> {
+        if toc.get_entries().is_empty() {
+            return Ok(());
+        }
+
+        let mut content = String::new();
+        let mut toc_visitor = TocVisitor::new(toc);
+        if toc_visitor.enter_chapters() {
+            let display = TimestampDisplay {
+                with_ms: true,
+                with_hours: true,
+            };
+
+            let mut nb = 0;
+            while let Some(chapter) = toc_visitor.next_chapter() {
+                if !chapter.get_sub_entries().is_empty() {
+                    continue;
+                }
+                let start = match chapter.get_start_stop_times() {
+                    Some((start, _end)) => start,
+                    None => continue,
+                };
+
+                nb += 1;
+                let prefix = format!("{}{:02}", CHAPTER_TAG, nb);
+                content += &format!(
+                    "{}={}\n",
+                    prefix,
+                    Timestamp4Humans::from_nano(start as u64).display(display),
+                );
+
+                let tags = chapter.get_tags();
+                let title = tags
+                    .as_ref()
+                    .and_then(|tags| {
+                        tags.get::<gst::tags::Title>()
+                            .and_then(|tag| tag.get().map(ToString::to_string))
+                    })
+                    .unwrap_or_default();
+                content += &format!("{}{}={}\n", prefix, NAME_TAG, title);
+
+                let artist = tags.as_ref().and_then(|tags| {
+                    tags.get::<gst::tags::Artist>()
+                        .and_then(|tag| tag.get().map(ToString::to_string))
+                });
+                if let Some(artist) = artist {
+                    content += &format!("{}{}={}\n", prefix, ARTIST_TAG, artist);
+                }
+                let performer = tags.as_ref().and_then(|tags| {
+                    tags.get::<gst::tags::Performer>()
+                        .and_then(|tag| tag.get().map(ToString::to_string))
+                });
+                if let Some(performer) = performer {
+                    content += &format!("{}{}={}\n", prefix, PERFORMER_TAG, performer);
+                }
+            }
+        }
+
+        destination.write_all(content.as_bytes()).map_err(|err| {
+            let msg = gettext("Failed to write mkvmerge text file: {}").replacen(
+                "{}",
+                &err.to_string(),
+                1,
+            );
+            error!("{}", msg);
+            msg
+        })
+    }
+}
+
+#[test]
+fn write_read() {
+    use super::{Duration, TocBuilder, TocChapter};
+    gst::init().unwrap();
+
+    let mut toc_builder = TocBuilder::new();
+    toc_builder.add_chapter(TocChapter::new(Duration::default(), "intro"));
+    let mut chapter = TocChapter::new(Duration::from_secs(3_661), "second");
+    chapter.artist = Some("artist".to_owned());
+    toc_builder.add_chapter(chapter);
+    let toc = toc_builder.build(Duration::from_secs(4_000)).unwrap();
+
+    let mut content = Vec::new();
+    MKVMergeTextFormat {}.write(&toc, &mut content).unwrap();
+    assert_eq!(
+        "CHAPTER01=00:00:00.000\nCHAPTER01NAME=intro\n\
+         CHAPTER02=01:01:01.000\nCHAPTER02NAME=second\nCHAPTER02ARTIST=artist\n",
+        String::from_utf8(content.clone()).unwrap(),
+    );
+
+    let mut info = MediaInfo::default();
+    info.duration = Duration::from_secs(4_000);
+    let read_toc = MKVMergeTextFormat {}
+        .read(&info, &mut content.as_slice())
+        .unwrap()
+        .unwrap();
+    assert!(super::TocDiff::new(&toc, &read_toc).is_empty());
+
+    let invalid_utf8: &[u8] = b"CHAPTER01=00:00:00.000\nCHAPTER01NAME=\xff\n";
+    let res = MKVMergeTextFormat {}.read(&info, &mut &invalid_utf8[..]);
+    assert!(matches!(res, Err(ReadError::Io(_))));
+}
+
+#[test]
+fn write_nested() {
+    use super::{Duration, TocBuilder, TocChapter};
+    gst::init().unwrap();
+
+    let mut toc_builder = TocBuilder::new();
+    toc_builder.add_chapter(TocChapter::new(Duration::default(), "intro"));
+    let mut part = TocChapter::new(Duration::from_secs(10), "part");
+    part.children = vec![
+        TocChapter::new(Duration::from_secs(10), "first"),
+        TocChapter::new(Duration::from_secs(20), "second"),
+    ];
+    toc_builder.add_chapter(part);
+    let toc = toc_builder.build(Duration::from_secs(30)).unwrap();
+
+    // Only the innermost chapters are written, in sequence
+    let mut content = Vec::new();
+    MKVMergeTextFormat {}.write(&toc, &mut content).unwrap();
+    assert_eq!(
+        "CHAPTER01=00:00:00.000\nCHAPTER01NAME=intro\n\
+         CHAPTER02=00:00:10.000\nCHAPTER02NAME=first\n\
+         CHAPTER03=00:00:20.000\nCHAPTER03NAME=second\n",
+        String::from_utf8(content).unwrap(),
+    );
+}
@@ -1,27 +1,58 @@
+use std::fmt;
+
 mod duration;
 pub use duration::Duration;
 
 pub mod factory;
-pub use self::factory::Factory;
+pub use self::factory::{register_formats, Factory, FormatRegistration};
 
 mod format;
-pub use self::format::Reader;
+pub use self::format::{ReadError, Reader, Writer};
 
 pub mod media_info;
-pub use self::media_info::{get_default_chapter_title, MediaInfo, Stream, Streams};
+pub use self::media_info::{
+    get_default_chapter_title, is_attached_picture, CoverArt, MediaInfo, SelectedStreamChange,
+    Stream, StreamSelectionDelta, Streams,
+};
+
+mod media_path;
+pub use self::media_path::MediaPath;
+
+mod media_report;
+pub use self::media_report::{ChapterReport, MediaReport, ReportFormat, StreamReport};
 
 mod mkvmerge_text_format;
 pub use self::mkvmerge_text_format::MKVMergeTextFormat;
 
+pub mod playlist;
+pub use self::playlist::PlaylistFormat;
+
 mod timestamp_4_humans;
-pub use self::timestamp_4_humans::{parse_timestamp, Timestamp4Humans};
+pub use self::timestamp_4_humans::{parse_timestamp, Timestamp4Humans, TimestampDisplay};
+
+mod toc_builder;
+pub use self::toc_builder::{merge_current_toc, TocBuilder, TocChapter};
+
+mod toc_diff;
+pub use self::toc_diff::TocDiff;
 
 mod toc_visitor;
 pub use self::toc_visitor::{TocVisit, TocVisitor};
 
+mod virtual_book;
+pub use self::virtual_book::{BookPart, VirtualBook};
+
+/// Identifies a toc format, see `FormatRegistration`.
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub enum Format {
-    MKVMergeText,
+pub struct Format {
+    pub id: &'static str,
+    pub name: &'static str,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
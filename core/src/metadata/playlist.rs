@@ -0,0 +1,176 @@
+use gettextrs::gettext;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+static M3U_EXTENSIONS: &[&str] = &["m3u", "m3u8"];
+static PLS_EXTENSION: &str = "pls";
+
+static PLS_FILE_KEY: &str = "File";
+static URI_SCHEME_SEPARATOR: &str = "://";
+static FILE_URI_SCHEME: &str = "file://";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaylistFormat {
+    M3U,
+    PLS,
+}
+
+impl PlaylistFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        if M3U_EXTENSIONS.contains(&extension.as_str()) {
+            Some(PlaylistFormat::M3U)
+        } else if extension == PLS_EXTENSION {
+            Some(PlaylistFormat::PLS)
+        } else {
+            None
+        }
+    }
+}
+
+/// Reads the playlist at `path`.
+///
+/// Relative entries are resolved against the playlist location.
+pub fn read(path: &Path, format: PlaylistFormat) -> Result<Vec<PathBuf>, String> {
+    let content = fs::read(path)
+        .map_err(|err| gettext("Couldn't read playlist: {}").replacen("{}", &err.to_string(), 1))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let entries = parse(format, &String::from_utf8_lossy(&content), base_dir);
+    if entries.is_empty() {
+        return Err(gettext("No playable entries in playlist"));
+    }
+
+    Ok(entries)
+}
+
+pub fn parse(format: PlaylistFormat, content: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let locations: Vec<&str> = match format {
+        PlaylistFormat::M3U => parse_m3u(content),
+        PlaylistFormat::PLS => parse_pls(content),
+    };
+
+    locations
+        .into_iter()
+        .filter_map(|location| to_path(location, base_dir))
+        .collect()
+}
+
+fn parse_m3u(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .map(str::trim)
+        // `#EXTM3U`, `#EXTINF` & comments
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+fn parse_pls(content: &str) -> Vec<&str> {
+    let mut files: Vec<(usize, &str)> = content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().splitn(2, '=');
+            let key = fields.next()?.trim();
+            let value = fields.next()?.trim();
+            if !key.starts_with(PLS_FILE_KEY) || value.is_empty() {
+                return None;
+            }
+
+            key[PLS_FILE_KEY.len()..]
+                .parse::<usize>()
+                .ok()
+                .map(|idx| (idx, value))
+        })
+        .collect();
+    files.sort_by_key(|(idx, _)| *idx);
+
+    files.into_iter().map(|(_, value)| value).collect()
+}
+
+fn to_path(location: &str, base_dir: &Path) -> Option<PathBuf> {
+    let path = if location.starts_with(FILE_URI_SCHEME) {
+        glib::filename_from_uri(location).ok()?.0
+    } else if location.contains(URI_SCHEME_SEPARATOR) {
+        // Only local files are supported
+        return None;
+    } else {
+        PathBuf::from(location)
+    };
+
+    if path.is_relative() {
+        Some(base_dir.join(path))
+    } else {
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(
+            Some(PlaylistFormat::M3U),
+            PlaylistFormat::from_path(Path::new("list.m3u")),
+        );
+        assert_eq!(
+            Some(PlaylistFormat::M3U),
+            PlaylistFormat::from_path(Path::new("list.M3U8")),
+        );
+        assert_eq!(
+            Some(PlaylistFormat::PLS),
+            PlaylistFormat::from_path(Path::new("list.pls")),
+        );
+        assert_eq!(None, PlaylistFormat::from_path(Path::new("media.ogg")));
+        assert_eq!(None, PlaylistFormat::from_path(Path::new("m3u")));
+    }
+
+    #[test]
+    fn m3u() {
+        let content = "#EXTM3U\r\n\
+            #EXTINF:123,Artist - Title\r\n\
+            01 - first.ogg\r\n\
+            \r\n\
+            # a comment\n\
+            sub/second.mp3\n\
+            /abs/third.flac\n\
+            file:///abs/fourth%20track.opus\n\
+            http://example.com/stream.mp3\n";
+
+        assert_eq!(
+            vec![
+                PathBuf::from("/music/01 - first.ogg"),
+                PathBuf::from("/music/sub/second.mp3"),
+                PathBuf::from("/abs/third.flac"),
+                PathBuf::from("/abs/fourth track.opus"),
+            ],
+            parse(PlaylistFormat::M3U, content, Path::new("/music")),
+        );
+
+        assert!(parse(PlaylistFormat::M3U, "#EXTM3U\n", Path::new("/music")).is_empty());
+    }
+
+    #[test]
+    fn pls() {
+        let content = "[playlist]\n\
+            File2=second.ogg\n\
+            Title2=Second\n\
+            File1=/abs/first.ogg\n\
+            Length1=-1\n\
+            File3=\n\
+            NumberOfEntries=3\n\
+            Version=2\n";
+
+        assert_eq!(
+            vec![
+                PathBuf::from("/abs/first.ogg"),
+                PathBuf::from("/music/second.ogg"),
+            ],
+            parse(PlaylistFormat::PLS, content, Path::new("/music")),
+        );
+    }
+}
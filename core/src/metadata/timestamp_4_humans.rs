@@ -11,14 +11,19 @@ use std::{convert::TryFrom, fmt, string::ToString};
 
 use super::{parse_to, Duration};
 
+// `,` is common in SRT-like files and in many locales
+fn decimal_separator(i: &str) -> IResult<&str, &str> {
+    alt((tag("."), tag(",")))(i)
+}
+
 pub fn parse_timestamp(i: &str) -> IResult<&str, Timestamp4Humans> {
     let parse_timestamp_ = tuple((
         separated_pair(parse_to::<u8>, tag(":"), parse_to::<u8>),
         opt(tuple((
             // the next tag determines whether the 1st number is h or mn
-            alt((tag(":"), tag("."))),
+            alt((tag(":"), decimal_separator)),
             parse_to::<u16>,
-            opt(preceded(tag("."), parse_to::<u16>)),
+            opt(preceded(decimal_separator, parse_to::<u16>)),
         ))),
     ));
 
@@ -36,7 +41,7 @@ pub fn parse_timestamp(i: &str) -> IResult<&str, Timestamp4Humans> {
                 ..Timestamp4Humans::default()
             }
         }
-        ((m, s), Some((".", ms, us))) => Timestamp4Humans {
+        ((m, s), Some((_, ms, us))) => Timestamp4Humans {
             h: 0,
             m,
             s,
@@ -44,7 +49,6 @@ pub fn parse_timestamp(i: &str) -> IResult<&str, Timestamp4Humans> {
             us: us.unwrap_or(0),
             ..Timestamp4Humans::default()
         },
-        ((_, _), Some((_, _, _))) => unreachable!("unexpected separator returned by parser"),
         ((h, m), None) => Timestamp4Humans {
             h,
             m,
@@ -99,6 +103,23 @@ fn parse_string() {
         ((((42 * 60 + 20) * 1_000) + 10) * 1_000 + 15) * 1_000
     );
 
+    let ts_res = parse_timestamp("01:42:20,010");
+    assert!(ts_res.is_ok());
+    let ts = ts_res.unwrap().1;
+    assert_eq!(ts.h, 1);
+    assert_eq!(ts.m, 42);
+    assert_eq!(ts.s, 20);
+    assert_eq!(ts.ms, 10);
+
+    let ts_res = parse_timestamp("42:20,010,015");
+    assert!(ts_res.is_ok());
+    let ts = ts_res.unwrap().1;
+    assert_eq!(ts.h, 0);
+    assert_eq!(ts.m, 42);
+    assert_eq!(ts.s, 20);
+    assert_eq!(ts.ms, 10);
+    assert_eq!(ts.us, 15);
+
     assert!(parse_timestamp("abc:15").is_err());
     assert!(parse_timestamp("42:aa.015").is_err());
 
@@ -108,6 +129,23 @@ fn parse_string() {
     assert_eq!("a", i);
 }
 
+/// Display options for `Timestamp4Humans`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimestampDisplay {
+    pub with_ms: bool,
+    /// Display hours even when the timestamp is below one hour.
+    pub with_hours: bool,
+}
+
+impl Default for TimestampDisplay {
+    fn default() -> Self {
+        TimestampDisplay {
+            with_ms: true,
+            with_hours: false,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Timestamp4Humans {
     pub nano: u16,
@@ -152,18 +190,58 @@ impl Timestamp4Humans {
     pub fn from_duration(duration: Duration) -> Self {
         Self::from_nano(duration.into())
     }
+
+    pub fn display(&self, options: TimestampDisplay) -> String {
+        let mut res = if self.h == 0 && !options.with_hours {
+            format!("{:02}:{:02}", self.m, self.s)
+        } else {
+            format!("{:02}:{:02}:{:02}", self.h, self.m, self.s)
+        };
+
+        if options.with_ms {
+            res += &format!(".{:03}", self.ms);
+        }
+
+        res
+    }
 }
 
 impl ToString for Timestamp4Humans {
     fn to_string(&self) -> String {
-        if self.h == 0 {
-            format!("{:02}:{:02}.{:03}", self.m, self.s, self.ms)
-        } else {
-            format!("{:02}:{:02}:{:02}.{:03}", self.h, self.m, self.s, self.ms,)
-        }
+        self.display(TimestampDisplay::default())
     }
 }
 
+#[test]
+fn display() {
+    let ts = Timestamp4Humans::from_nano((((42 * 60 + 20) * 1_000) + 10) * 1_000 * 1_000);
+    assert_eq!("42:20.010", ts.to_string());
+    assert_eq!(
+        "42:20",
+        ts.display(TimestampDisplay {
+            with_ms: false,
+            with_hours: false,
+        }),
+    );
+    assert_eq!(
+        "00:42:20.010",
+        ts.display(TimestampDisplay {
+            with_ms: true,
+            with_hours: true,
+        }),
+    );
+
+    let ts = Timestamp4Humans::from_nano(((((60 + 42) * 60 + 20) * 1_000) + 10) * 1_000_000);
+    assert_eq!("01:42:20.010", ts.to_string());
+    assert_eq!(
+        "01:42:20",
+        ts.display(TimestampDisplay {
+            with_ms: false,
+            with_hours: false,
+        }),
+    );
+}
+
 impl fmt::Debug for Timestamp4Humans {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("Timestamp4Humans")
@@ -0,0 +1,265 @@
+use super::Duration;
+
+/// A chapter parsed by a `Reader`, see `TocBuilder`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TocChapter {
+    pub start: Duration,
+    /// `None` if the chapter ends where the next one starts.
+    pub end: Option<Duration>,
+    pub title: String,
+    pub artist: Option<String>,
+    pub performer: Option<String>,
+    pub children: Vec<TocChapter>,
+}
+
+impl TocChapter {
+    pub fn new<Title: Into<String>>(start: Duration, title: Title) -> Self {
+        TocChapter {
+            start,
+            title: title.into(),
+            ..TocChapter::default()
+        }
+    }
+
+    fn into_entry(self, uid: String, default_end: Duration) -> gst::TocEntry {
+        let end = self.end.unwrap_or(default_end);
+
+        let mut entry = gst::TocEntry::new(gst::TocEntryType::Chapter, &uid);
+        entry
+            .get_mut()
+            .unwrap()
+            .set_start_stop_times(self.start.as_i64(), end.as_i64());
+
+        let mut tag_list = gst::TagList::new();
+        {
+            let tag_list = tag_list.get_mut().unwrap();
+            tag_list.add::<gst::tags::Title>(&self.title.as_str(), gst::TagMergeMode::Replace);
+            if let Some(artist) = self.artist.as_ref() {
+                tag_list.add::<gst::tags::Artist>(&artist.as_str(), gst::TagMergeMode::Replace);
+            }
+            if let Some(performer) = self.performer.as_ref() {
+                tag_list
+                    .add::<gst::tags::Performer>(&performer.as_str(), gst::TagMergeMode::Replace);
+            }
+        }
+        entry.get_mut().unwrap().set_tags(tag_list);
+
+        for sub_entry in chapter_entries(self.children, &format!("{}.", uid), end) {
+            entry.get_mut().unwrap().append_sub_entry(sub_entry);
+        }
+
+        entry
+    }
+}
+
+// Chapters which don't specify their end stop where the next sibling starts,
+// the last one stops at `end`.
+fn chapter_entries(
+    mut chapters: Vec<TocChapter>,
+    uid_prefix: &str,
+    end: Duration,
+) -> Vec<gst::TocEntry> {
+    chapters.sort_by_key(|chapter| chapter.start);
+
+    let next_starts: Vec<Duration> = chapters
+        .iter()
+        .skip(1)
+        .map(|chapter| chapter.start)
+        .chain(std::iter::once(end))
+        .collect();
+
+    chapters
+        .into_iter()
+        .zip(next_starts)
+        .enumerate()
+        .map(|(idx, (chapter, next_start))| {
+            chapter.into_entry(format!("{}{:02}", uid_prefix, idx + 1), next_start)
+        })
+        .collect()
+}
+
+/// Builds a `gst::Toc` from the chapters parsed by a `Reader`.
+///
+/// Handles the entries uids, the nesting and the tags.
+#[derive(Debug, Default)]
+pub struct TocBuilder {
+    chapters: Vec<TocChapter>,
+}
+
+impl TocBuilder {
+    pub fn new() -> Self {
+        TocBuilder::default()
+    }
+
+    pub fn add_chapter(&mut self, chapter: TocChapter) {
+        self.chapters.push(chapter);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chapters.is_empty()
+    }
+
+    /// Builds the toc, the last chapter ends at `duration` unless specified otherwise.
+    ///
+    /// Returns `None` if no chapters were added.
+    pub fn build(self, duration: Duration) -> Option<gst::Toc> {
+        if self.chapters.is_empty() {
+            return None;
+        }
+
+        let mut edition = gst::TocEntry::new(gst::TocEntryType::Edition, "");
+        for entry in chapter_entries(self.chapters, "", duration) {
+            edition.get_mut().unwrap().append_sub_entry(entry);
+        }
+
+        let mut toc = gst::Toc::new(gst::TocScope::Global);
+        toc.get_mut().unwrap().append_entry(edition);
+
+        Some(toc)
+    }
+}
+
+/// Merges the entries of a toc with scope `Current` into a toc with scope `Global`.
+///
+/// The entries of `current` are relative to the stream being played, so their times are kept.
+/// The entries from `toc` with the same uid as an entry from `current` are replaced.
+pub fn merge_current_toc(toc: Option<&gst::Toc>, current: &gst::Toc) -> gst::Toc {
+    let current_entries = current.get_entries();
+
+    let mut merged = gst::Toc::new(gst::TocScope::Global);
+    {
+        let merged = merged.get_mut().unwrap();
+        if let Some(toc) = toc {
+            for entry in toc.get_entries() {
+                if !current_entries
+                    .iter()
+                    .any(|current_entry| current_entry.get_uid() == entry.get_uid())
+                {
+                    merged.append_entry(entry.copy());
+                }
+            }
+        }
+
+        for entry in current_entries {
+            merged.append_entry(entry.copy());
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::TocVisitor;
+
+    fn title(entry: &gst::TocEntry) -> Option<String> {
+        entry.get_tags().and_then(|tags| {
+            tags.get::<gst::tags::Title>()
+                .and_then(|tag| tag.get().map(ToString::to_string))
+        })
+    }
+
+    #[test]
+    fn empty() {
+        gst::init().unwrap();
+
+        let toc_builder = TocBuilder::new();
+        assert!(toc_builder.is_empty());
+        assert!(toc_builder.build(Duration::from_secs(10)).is_none());
+    }
+
+    #[test]
+    fn nested() {
+        gst::init().unwrap();
+
+        let mut toc_builder = TocBuilder::new();
+        // Added out of order on purpose
+        toc_builder.add_chapter(TocChapter::new(Duration::from_secs(60), "outro"));
+
+        let mut part = TocChapter::new(Duration::from_secs(10), "part");
+        part.artist = Some("artist".to_owned());
+        part.children
+            .push(TocChapter::new(Duration::from_secs(10), "part 1"));
+        let mut part_2 = TocChapter::new(Duration::from_secs(30), "part 2");
+        part_2.end = Some(Duration::from_secs(40));
+        part.children.push(part_2);
+        toc_builder.add_chapter(part);
+
+        toc_builder.add_chapter(TocChapter::new(Duration::default(), "intro"));
+
+        let toc = toc_builder.build(Duration::from_secs(90)).unwrap();
+
+        let mut toc_visitor = TocVisitor::new(&toc);
+        assert!(toc_visitor.enter_chapters());
+
+        let mut expected = vec![
+            ("01", "intro", 0, 10),
+            ("02", "part", 10, 60),
+            ("02.01", "part 1", 10, 30),
+            ("02.02", "part 2", 30, 40),
+            ("03", "outro", 60, 90),
+        ]
+        .into_iter();
+
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            let (uid, expected_title, start, end) = expected.next().unwrap();
+            assert_eq!(uid, chapter.get_uid());
+            assert_eq!(Some(expected_title.to_owned()), title(&chapter));
+            assert_eq!(
+                Some((
+                    Duration::from_secs(start).as_i64(),
+                    Duration::from_secs(end).as_i64()
+                )),
+                chapter.get_start_stop_times(),
+            );
+
+            if uid == "02" {
+                let artist = chapter.get_tags().and_then(|tags| {
+                    tags.get::<gst::tags::Artist>()
+                        .and_then(|tag| tag.get().map(ToString::to_string))
+                });
+                assert_eq!(Some("artist".to_owned()), artist);
+            }
+        }
+        assert!(expected.next().is_none());
+    }
+
+    #[test]
+    fn current_scope() {
+        gst::init().unwrap();
+
+        let mut toc_builder = TocBuilder::new();
+        toc_builder.add_chapter(TocChapter::new(Duration::default(), "intro"));
+        let toc = toc_builder.build(Duration::from_secs(10)).unwrap();
+
+        let current = |title: &str| {
+            let mut edition = gst::TocEntry::new(gst::TocEntryType::Edition, "current");
+            edition.get_mut().unwrap().append_sub_entry(
+                TocChapter::new(Duration::default(), title)
+                    .into_entry("01".to_owned(), Duration::from_secs(10)),
+            );
+
+            let mut current = gst::Toc::new(gst::TocScope::Current);
+            current.get_mut().unwrap().append_entry(edition);
+            current
+        };
+
+        let merged = merge_current_toc(None, &current("first"));
+        assert_eq!(gst::TocScope::Global, merged.get_scope());
+        assert_eq!(1, merged.get_entries().len());
+
+        let merged = merge_current_toc(Some(&toc), &current("first"));
+        assert_eq!(2, merged.get_entries().len());
+
+        // Same uid: the entry is replaced
+        let merged = merge_current_toc(Some(&merged), &current("second"));
+        let entries = merged.get_entries();
+        assert_eq!(2, entries.len());
+        assert_eq!("current", entries[1].get_uid());
+        assert_eq!(
+            Some("second".to_owned()),
+            title(&entries[1].get_sub_entries()[0])
+        );
+    }
+}
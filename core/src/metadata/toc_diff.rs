@@ -0,0 +1,109 @@
+use super::TocVisitor;
+
+// (boundaries, title) for each chapter in order
+fn chapters(toc: &gst::Toc) -> Vec<(Option<(i64, i64)>, Option<String>)> {
+    let mut chapters = Vec::new();
+
+    let mut toc_visitor = TocVisitor::new(toc);
+    if !toc_visitor.enter_chapters() {
+        return chapters;
+    }
+
+    while let Some(chapter) = toc_visitor.next_chapter() {
+        let title = chapter.get_tags().and_then(|tags| {
+            tags.get::<gst::tags::Title>()
+                .and_then(|tag| tag.get().map(ToString::to_string))
+        });
+        chapters.push((chapter.get_start_stop_times(), title));
+    }
+
+    chapters
+}
+
+/// Differences between the chapters of two tocs.
+///
+/// Chapters are compared in order, regardless of their nesting.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TocDiff {
+    pub chapter_count: usize,
+    pub other_chapter_count: usize,
+    /// Chapters at the same rank with a different title.
+    pub changed_titles: usize,
+    /// Chapters at the same rank with different boundaries.
+    pub moved_chapters: usize,
+}
+
+impl TocDiff {
+    pub fn new(toc: &gst::Toc, other: &gst::Toc) -> Self {
+        let chapters = chapters(toc);
+        let other_chapters = chapters(other);
+
+        let mut diff = TocDiff {
+            chapter_count: chapters.len(),
+            other_chapter_count: other_chapters.len(),
+            ..TocDiff::default()
+        };
+
+        for ((times, title), (other_times, other_title)) in chapters.iter().zip(&other_chapters) {
+            if title != other_title {
+                diff.changed_titles += 1;
+            }
+            if times != other_times {
+                diff.moved_chapters += 1;
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chapter_count == self.other_chapter_count
+            && self.changed_titles == 0
+            && self.moved_chapters == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_toc(chapters: &[(u64, u64, &str)]) -> gst::Toc {
+        let mut toc = gst::Toc::new(gst::TocScope::Global);
+        {
+            let mut edition = gst::TocEntry::new(gst::TocEntryType::Edition, "edition");
+            for (idx, (start, end, title)) in chapters.iter().enumerate() {
+                let mut chapter =
+                    gst::TocEntry::new(gst::TocEntryType::Chapter, &(idx + 1).to_string());
+                chapter
+                    .get_mut()
+                    .unwrap()
+                    .set_start_stop_times(*start as i64, *end as i64);
+                let mut tags = gst::TagList::new();
+                tags.get_mut()
+                    .unwrap()
+                    .add::<gst::tags::Title>(title, gst::TagMergeMode::Replace);
+                chapter.get_mut().unwrap().set_tags(tags);
+                edition.get_mut().unwrap().append_sub_entry(chapter);
+            }
+            toc.get_mut().unwrap().append_entry(edition);
+        }
+
+        toc
+    }
+
+    #[test]
+    fn diff() {
+        gst::init().unwrap();
+
+        let toc = new_toc(&[(0, 10, "intro"), (10, 20, "part 1"), (20, 30, "part 2")]);
+        assert!(TocDiff::new(&toc, &toc).is_empty());
+
+        let other = new_toc(&[(0, 10, "intro"), (10, 25, "part one")]);
+        let diff = TocDiff::new(&toc, &other);
+        assert!(!diff.is_empty());
+        assert_eq!(3, diff.chapter_count);
+        assert_eq!(2, diff.other_chapter_count);
+        assert_eq!(1, diff.changed_titles);
+        assert_eq!(1, diff.moved_chapters);
+    }
+}
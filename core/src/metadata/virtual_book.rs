@@ -0,0 +1,305 @@
+use std::{
+    cmp::Ordering,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{get_default_chapter_title, Duration, MediaPath, TocBuilder, TocChapter};
+
+const AUDIO_EXTENSIONS: [&str; 10] = [
+    "aac", "flac", "m4a", "m4b", "mka", "mp3", "oga", "ogg", "opus", "wav",
+];
+
+fn is_audio_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map_or(false, |extension| {
+                AUDIO_EXTENSIONS
+                    .iter()
+                    .any(|audio_extension| extension.eq_ignore_ascii_case(audio_extension))
+            })
+}
+
+// Compares the numbers in the file names by value so that "2" comes before "10"
+fn natural_cmp(name: &str, other: &str) -> Ordering {
+    let mut chars = name.chars().peekable();
+    let mut other_chars = other.chars().peekable();
+
+    loop {
+        match (chars.peek().copied(), other_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c), Some(other_c)) if c.is_ascii_digit() && other_c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = chars.peek().copied().filter(char::is_ascii_digit) {
+                    number.push(c);
+                    chars.next();
+                }
+                let mut other_number = String::new();
+                while let Some(c) = other_chars.peek().copied().filter(char::is_ascii_digit) {
+                    other_number.push(c);
+                    other_chars.next();
+                }
+
+                let number = number.trim_start_matches('0');
+                let other_number = other_number.trim_start_matches('0');
+                let ordering = number
+                    .len()
+                    .cmp(&other_number.len())
+                    .then_with(|| number.cmp(other_number));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(c), Some(other_c)) => {
+                let ordering = c.to_lowercase().cmp(other_c.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                chars.next();
+                other_chars.next();
+            }
+        }
+    }
+}
+
+fn title(entry: &gst::TocEntry) -> Option<String> {
+    entry.get_tags().and_then(|tags| {
+        tags.get::<gst::tags::Title>()
+            .and_then(|tag| tag.get().map(ToString::to_string))
+    })
+}
+
+fn artist(entry: &gst::TocEntry) -> Option<String> {
+    entry.get_tags().and_then(|tags| {
+        tags.get::<gst::tags::Artist>()
+            .and_then(|tag| tag.get().map(ToString::to_string))
+    })
+}
+
+// The chapters from `entries` shifted by `offset`, editions are transparent
+fn shifted_chapters(entries: Vec<gst::TocEntry>, offset: Duration) -> Vec<TocChapter> {
+    let mut chapters = Vec::new();
+
+    for entry in entries {
+        match entry.get_entry_type() {
+            gst::TocEntryType::Edition => {
+                chapters.extend(shifted_chapters(entry.get_sub_entries(), offset))
+            }
+            gst::TocEntryType::Chapter => {
+                let (start, end) = match entry.get_start_stop_times() {
+                    Some(times) => times,
+                    None => continue,
+                };
+
+                let mut chapter = TocChapter::new(
+                    offset + Duration::from_nanos(start as u64),
+                    title(&entry).unwrap_or_else(get_default_chapter_title),
+                );
+                chapter.end = Some(offset + Duration::from_nanos(end as u64));
+                chapter.artist = artist(&entry);
+                chapter.children = shifted_chapters(entry.get_sub_entries(), offset);
+                chapters.push(chapter);
+            }
+            _ => (),
+        }
+    }
+
+    chapters
+}
+
+/// A media file in a `VirtualBook`.
+#[derive(Debug)]
+pub struct BookPart {
+    pub path: MediaPath,
+    /// Position of the beginning of the file in the book.
+    pub start: Duration,
+    pub duration: Duration,
+    toc: Option<gst::Toc>,
+}
+
+/// The audio files from a folder, played as a single book.
+///
+/// Positions in the book are expressed from the beginning of the first part.
+#[derive(Debug)]
+pub struct VirtualBook {
+    dir: MediaPath,
+    parts: Vec<BookPart>,
+    duration: Duration,
+}
+
+impl VirtualBook {
+    pub fn new(dir: MediaPath) -> Self {
+        VirtualBook {
+            dir,
+            parts: Vec::new(),
+            duration: Duration::default(),
+        }
+    }
+
+    /// Lists the audio files from `dir` in the order of their names.
+    pub fn audio_files(dir: &Path) -> io::Result<Vec<MediaPath>> {
+        let mut paths = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| is_audio_file(path))
+            .collect::<Vec<PathBuf>>();
+        paths.sort_by(|path, other| {
+            natural_cmp(
+                &path.file_name().unwrap_or_default().to_string_lossy(),
+                &other.file_name().unwrap_or_default().to_string_lossy(),
+            )
+        });
+
+        Ok(paths.into_iter().map(MediaPath::from).collect())
+    }
+
+    /// Appends the media at `path` to the book.
+    pub fn add_part(&mut self, path: MediaPath, duration: Duration, toc: Option<gst::Toc>) {
+        self.parts.push(BookPart {
+            path,
+            start: self.duration,
+            duration,
+            toc,
+        });
+        self.duration += duration;
+    }
+
+    /// The folder the book was built from, which identifies the book.
+    pub fn dir(&self) -> &MediaPath {
+        &self.dir
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn parts(&self) -> &[BookPart] {
+        &self.parts
+    }
+
+    pub fn part(&self, idx: usize) -> Option<&BookPart> {
+        self.parts.get(idx)
+    }
+
+    pub fn part_idx(&self, path: &Path) -> Option<usize> {
+        self.parts
+            .iter()
+            .position(|part| part.path.as_path() == path)
+    }
+
+    /// Translates `position` in the book into the index of the part
+    /// and the position in that part.
+    ///
+    /// Positions past the end of the book belong to the last part.
+    pub fn locate(&self, position: Duration) -> Option<(usize, Duration)> {
+        let idx = self.parts.iter().rposition(|part| part.start <= position)?;
+
+        Some((idx, position - self.parts[idx].start))
+    }
+
+    /// Builds the toc of the whole book.
+    ///
+    /// Each part is a top-level chapter with the chapters of the file, if any.
+    pub fn toc(&self) -> Option<gst::Toc> {
+        let mut toc_builder = TocBuilder::new();
+
+        for part in &self.parts {
+            let mut chapter = TocChapter::new(part.start, part.path.stem_lossy());
+            chapter.end = Some(part.start + part.duration);
+
+            let children = part.toc.as_ref().map_or_else(Vec::new, |toc| {
+                shifted_chapters(toc.get_entries(), part.start)
+            });
+            // A single chapter would only repeat the part
+            if children.len() > 1 {
+                chapter.children = children;
+            }
+
+            toc_builder.add_chapter(chapter);
+        }
+
+        toc_builder.build(self.duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::TocVisitor;
+
+    #[test]
+    fn natural_order() {
+        let mut names = vec!["Part 10.mp3", "part 2.mp3", "Part 1.mp3", "Part 02b.mp3"];
+        names.sort_by(|name, other| natural_cmp(name, other));
+        assert_eq!(
+            vec!["Part 1.mp3", "part 2.mp3", "Part 02b.mp3", "Part 10.mp3"],
+            names
+        );
+    }
+
+    #[test]
+    fn book() {
+        gst::init().unwrap();
+
+        let mut part_toc = TocBuilder::new();
+        part_toc.add_chapter(TocChapter::new(Duration::default(), "chapter 1"));
+        part_toc.add_chapter(TocChapter::new(Duration::from_secs(20), "chapter 2"));
+        let part_toc = part_toc.build(Duration::from_secs(30));
+
+        let mut book = VirtualBook::new(MediaPath::new("/book"));
+        book.add_part(
+            MediaPath::new("/book/01 intro.mp3"),
+            Duration::from_secs(10),
+            None,
+        );
+        book.add_part(
+            MediaPath::new("/book/02 chapters.mp3"),
+            Duration::from_secs(30),
+            part_toc,
+        );
+        assert_eq!(Duration::from_secs(40), book.duration());
+
+        assert_eq!(
+            Some((0, Duration::from_secs(5))),
+            book.locate(Duration::from_secs(5))
+        );
+        assert_eq!(
+            Some((1, Duration::default())),
+            book.locate(Duration::from_secs(10))
+        );
+        assert_eq!(
+            Some((1, Duration::from_secs(40))),
+            book.locate(Duration::from_secs(50))
+        );
+        assert_eq!(Some(1), book.part_idx(Path::new("/book/02 chapters.mp3")));
+        assert_eq!(Duration::from_secs(10), book.part(1).unwrap().start);
+
+        let toc = book.toc().unwrap();
+        let mut toc_visitor = TocVisitor::new(&toc);
+        assert!(toc_visitor.enter_chapters());
+
+        let mut expected = vec![
+            ("01", 0, 10),
+            ("02", 10, 40),
+            ("02.01", 10, 30),
+            ("02.02", 30, 40),
+        ]
+        .into_iter();
+
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            let (uid, start, end) = expected.next().unwrap();
+            assert_eq!(uid, chapter.get_uid());
+            assert_eq!(
+                Some((
+                    Duration::from_secs(start).as_i64(),
+                    Duration::from_secs(end).as_i64()
+                )),
+                chapter.get_start_stop_times(),
+            );
+        }
+        assert!(expected.next().is_none());
+    }
+}
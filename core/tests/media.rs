@@ -0,0 +1,94 @@
+//! Opens media generated with GStreamer through the public API.
+
+#![cfg(feature = "pipeline-tests")]
+
+use std::fs;
+
+use media_toc_core::{
+    media::{
+        media_probe,
+        test_media::{TestMedia, MEDIA_DURATION},
+        Backend, PlaybackPipeline, Timestamp,
+    },
+    metadata::{register_formats, Duration, Factory, MediaContent, TocVisitor},
+};
+
+const CHAPTERS: &str = "CHAPTER01=00:00:00.000\nCHAPTER01NAME=first\n\
+                        CHAPTER02=00:00:01.000\nCHAPTER02NAME=second\n";
+
+// Allow the durations to differ by 100ms from the expected values
+fn assert_near(expected: Duration, actual: Duration) {
+    let delta = if actual > expected {
+        actual - expected
+    } else {
+        expected - actual
+    };
+    assert!(
+        delta <= Duration::from_nanos(100_000_000),
+        "expected {}, got {}",
+        expected,
+        actual,
+    );
+}
+
+#[test]
+fn probe() {
+    gst::init().unwrap();
+    let media = TestMedia::audio("probe");
+
+    let (duration, toc) = media_probe::probe(media.path()).unwrap();
+    assert_near(MEDIA_DURATION, duration);
+    assert!(toc.is_none());
+}
+
+// A single test because the pipeline's bus watch is attached to the default `MainContext`
+#[test]
+fn playback_with_toc_file() {
+    gst::init().unwrap();
+    register_formats();
+    let media = TestMedia::audio_video("playback_with_toc_file").with_toc(CHAPTERS);
+
+    let main_context = glib::MainContext::default();
+    let mut pipeline = main_context
+        .block_on(PlaybackPipeline::try_new(
+            media.path(),
+            &None,
+            None,
+            false,
+            None,
+            Backend::default(),
+        ))
+        .unwrap();
+    assert!(pipeline.missing_plugins.is_empty());
+    assert_eq!(MediaContent::AudioVideo, pipeline.info.content);
+    assert_near(MEDIA_DURATION, pipeline.info.duration);
+
+    let toc_path = media.toc_path().unwrap();
+    let content = fs::read(toc_path).unwrap();
+    let format = Factory::detect(toc_path, &content).unwrap();
+    let toc = Factory::get_reader(format)
+        .unwrap()
+        .read(&pipeline.info, &mut content.as_slice())
+        .unwrap()
+        .unwrap();
+
+    let mut toc_visitor = TocVisitor::new(&toc);
+    assert!(toc_visitor.enter_chapters());
+    let mut chapter_starts = Vec::new();
+    while let Some(chapter) = toc_visitor.next_chapter() {
+        chapter_starts.push(chapter.get_start_stop_times().unwrap().0);
+    }
+    assert_eq!(vec![0, Duration::from_secs(1).as_i64()], chapter_starts);
+
+    let second_chapter = Timestamp::from(Duration::from_secs(1));
+    main_context
+        .block_on(pipeline.seek(second_chapter, gst::SeekFlags::ACCURATE))
+        .unwrap();
+    let position = pipeline.current_ts().unwrap();
+    assert_near(
+        Duration::from_secs(1),
+        Duration::from_nanos(position.as_u64()),
+    );
+
+    pipeline.stop().unwrap();
+}
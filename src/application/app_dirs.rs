@@ -0,0 +1,217 @@
+use directories::ProjectDirs;
+use lazy_static::lazy_static;
+use log::debug;
+
+use std::{
+    env,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{APP_NAME, SLD, TLD};
+
+pub const CONFIG_DIR_ARG: &str = "config-dir";
+pub const CACHE_DIR_ARG: &str = "cache-dir";
+pub const PORTABLE_ARG: &str = "portable";
+
+const CONFIG_DIR_ENV: &str = "MEDIA_TOC_PLAYER_CONFIG_DIR";
+const CACHE_DIR_ENV: &str = "MEDIA_TOC_PLAYER_CACHE_DIR";
+// A file with this name next to the executable enables the portable mode
+const PORTABLE_MARKER: &str = "portable";
+
+lazy_static! {
+    pub static ref APP_DIRS: AppDirs = AppDirs::new();
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Overrides {
+    config_dir: Option<PathBuf>,
+    cache_dir: Option<PathBuf>,
+    is_portable: bool,
+}
+
+impl Overrides {
+    // The command line is scanned here because the directories are needed
+    // in order to initialize the locale, before `clap` can be used.
+    // The command line takes precedence over the environment.
+    fn new<Args, Env>(args: Args, env_var: Env) -> Self
+    where
+        Args: IntoIterator<Item = OsString>,
+        Env: Fn(&str) -> Option<OsString>,
+    {
+        let config_arg = format!("--{}", CONFIG_DIR_ARG);
+        let cache_arg = format!("--{}", CACHE_DIR_ARG);
+        let portable_arg = format!("--{}", PORTABLE_ARG);
+
+        let mut overrides = Overrides::default();
+        let mut args = args.into_iter().skip(1);
+        while let Some(arg) = args.next() {
+            let arg = match arg.to_str() {
+                Some(arg) => arg,
+                None => continue,
+            };
+
+            let (name, value) = match arg.find('=') {
+                Some(idx) => (&arg[..idx], Some(OsString::from(&arg[idx + 1..]))),
+                None => (arg, None),
+            };
+            if name == "--" {
+                break;
+            } else if name == config_arg {
+                overrides.config_dir = value.or_else(|| args.next()).map(PathBuf::from);
+            } else if name == cache_arg {
+                overrides.cache_dir = value.or_else(|| args.next()).map(PathBuf::from);
+            } else if name == portable_arg {
+                overrides.is_portable = true;
+            }
+        }
+
+        let env_dir = |var| {
+            env_var(var)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+        };
+        if overrides.config_dir.is_none() {
+            overrides.config_dir = env_dir(CONFIG_DIR_ENV);
+        }
+        if overrides.cache_dir.is_none() {
+            overrides.cache_dir = env_dir(CACHE_DIR_ENV);
+        }
+
+        overrides
+    }
+}
+
+/// Where the configuration, the history and the cache are stored.
+///
+/// Defaults to the platform's conventions. In portable mode, everything
+/// is stored next to the executable. The directories can also be set
+/// from the command line or from the environment.
+#[derive(Debug)]
+pub struct AppDirs {
+    pub config: PathBuf,
+    /// Holds the history.
+    pub data: PathBuf,
+    pub cache: PathBuf,
+    pub is_portable: bool,
+}
+
+impl AppDirs {
+    fn new() -> Self {
+        let exe_dir = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        let overrides = Overrides::new(env::args_os(), env::var_os);
+        let is_portable = overrides.is_portable
+            || exe_dir
+                .as_ref()
+                .map_or(false, |exe_dir| exe_dir.join(PORTABLE_MARKER).is_file());
+
+        let app_dirs = AppDirs::resolve(overrides, is_portable, exe_dir.as_deref());
+        debug!("{:?}", app_dirs);
+
+        app_dirs
+    }
+
+    fn resolve(overrides: Overrides, is_portable: bool, exe_dir: Option<&Path>) -> Self {
+        let (config, data, cache) = match exe_dir.filter(|_| is_portable) {
+            Some(exe_dir) => (
+                exe_dir.join("config"),
+                exe_dir.join("data"),
+                exe_dir.join("cache"),
+            ),
+            None => {
+                let project_dirs = ProjectDirs::from(TLD, SLD, &APP_NAME)
+                    .expect("Couldn't find project dirs for this platform");
+                (
+                    project_dirs.config_dir().to_path_buf(),
+                    project_dirs.data_dir().to_path_buf(),
+                    project_dirs.cache_dir().to_path_buf(),
+                )
+            }
+        };
+
+        // The history is kept along with the configuration when its location is forced
+        let (config, data) = match overrides.config_dir {
+            Some(config_dir) => (config_dir.clone(), config_dir),
+            None => (config, data),
+        };
+
+        AppDirs {
+            config,
+            data,
+            cache: overrides.cache_dir.unwrap_or(cache),
+            is_portable,
+        }
+    }
+}
+
+/// Replaces the file at `path` with `content`.
+///
+/// The content is written to a temporary file first so that
+/// the previous file is kept if anything goes wrong.
+pub fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        std::iter::once("media-toc-player")
+            .chain(args.iter().copied())
+            .map(OsString::from)
+            .collect()
+    }
+
+    fn env(var: &str) -> Option<OsString> {
+        match var {
+            CONFIG_DIR_ENV => Some("/env/config".into()),
+            CACHE_DIR_ENV => Some("/env/cache".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn overrides() {
+        assert_eq!(Overrides::default(), Overrides::new(args(&[]), |_| None));
+
+        let overrides = Overrides::new(args(&[]), env);
+        assert_eq!(Some(PathBuf::from("/env/config")), overrides.config_dir);
+        assert_eq!(Some(PathBuf::from("/env/cache")), overrides.cache_dir);
+
+        let overrides = Overrides::new(
+            args(&[
+                "--config-dir",
+                "/cli/config",
+                "--cache-dir=/cli/cache",
+                "media.ogg",
+            ]),
+            env,
+        );
+        assert_eq!(Some(PathBuf::from("/cli/config")), overrides.config_dir);
+        assert_eq!(Some(PathBuf::from("/cli/cache")), overrides.cache_dir);
+        assert!(!overrides.is_portable);
+
+        let overrides = Overrides::new(args(&["--", "--portable"]), |_| None);
+        assert!(!overrides.is_portable);
+    }
+
+    #[test]
+    fn portable() {
+        let exe_dir = Path::new("/opt/media-toc-player");
+        let overrides =
+            Overrides::new(args(&["--portable", "--cache-dir", "/tmp/cache"]), |_| None);
+        assert!(overrides.is_portable);
+
+        let app_dirs = AppDirs::resolve(overrides, true, Some(exe_dir));
+        assert_eq!(exe_dir.join("config"), app_dirs.config);
+        assert_eq!(exe_dir.join("data"), app_dirs.data);
+        assert_eq!(PathBuf::from("/tmp/cache"), app_dirs.cache);
+    }
+}
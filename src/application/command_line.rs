@@ -1,11 +1,28 @@
 use clap::{App, Arg};
 use gettextrs::gettext;
 
+#[cfg(feature = "remote")]
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 
+use crate::media::Backend;
+
+use super::app_dirs::{CACHE_DIR_ARG, CONFIG_DIR_ARG, PORTABLE_ARG};
+
 pub struct CommandLineArguments {
     pub input_file: Option<PathBuf>,
     pub disable_gl: bool,
+    pub backend: Backend,
+    #[cfg(feature = "remote")]
+    pub remote: Option<RemoteArguments>,
+}
+
+#[cfg(feature = "remote")]
+pub struct RemoteArguments {
+    /// Loopback unless the user explicitly opts in for a wider access.
+    pub address: IpAddr,
+    pub port: u16,
+    pub token: String,
 }
 
 pub fn get_command_line() -> CommandLineArguments {
@@ -16,7 +33,7 @@ pub fn get_command_line() -> CommandLineArguments {
     let disable_gl_arg = "DISABLE_GL";
     let input_arg = gettext("MEDIA");
 
-    let matches = App::new(env!("CARGO_PKG_NAME"))
+    let app = App::new(env!("CARGO_PKG_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(&about_msg[..])
@@ -28,17 +45,119 @@ pub fn get_command_line() -> CommandLineArguments {
                 .long("disable-gl")
                 .help(&gettext("Disable video rendering hardware acceleration")),
         )
+        // The directories are resolved by `APP_DIRS`, these are declared for the help & checks
+        .arg(
+            Arg::with_name(CONFIG_DIR_ARG)
+                .long(CONFIG_DIR_ARG)
+                .takes_value(true)
+                .value_name("DIR")
+                .help(&gettext(
+                    "Store the configuration and the history in this directory",
+                )),
+        )
+        .arg(
+            Arg::with_name(CACHE_DIR_ARG)
+                .long(CACHE_DIR_ARG)
+                .takes_value(true)
+                .value_name("DIR")
+                .help(&gettext("Store the cache in this directory")),
+        )
+        .arg(
+            Arg::with_name(PORTABLE_ARG)
+                .long(PORTABLE_ARG)
+                .help(&gettext(
+                    "Store the configuration, the history and the cache next to the executable",
+                )),
+        )
         .arg(
             Arg::with_name(&input_arg[..])
                 .help(&gettext("Path to the input media file"))
                 .last(false),
+        );
+
+    #[cfg(feature = "remote")]
+    let remote_port_arg = "REMOTE_PORT";
+    #[cfg(feature = "remote")]
+    let remote_address_arg = "REMOTE_ADDRESS";
+    #[cfg(feature = "remote")]
+    let remote_token_arg = "REMOTE_TOKEN";
+    #[cfg(feature = "remote")]
+    let app = app
+        .arg(
+            Arg::with_name(remote_port_arg)
+                .long("remote-port")
+                .takes_value(true)
+                .value_name("PORT")
+                .requires(remote_token_arg)
+                .help(&gettext("Serve the remote control API on this port")),
+        )
+        .arg(
+            Arg::with_name(remote_address_arg)
+                .long("remote-address")
+                .takes_value(true)
+                .value_name("ADDRESS")
+                .requires(remote_port_arg)
+                .validator(|address| {
+                    address
+                        .parse::<IpAddr>()
+                        .map(drop)
+                        .map_err(|err| err.to_string())
+                })
+                .help(&gettext(
+                    "Serve the remote control API on this address instead of the local host",
+                )),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name(remote_token_arg)
+                .long("remote-token")
+                .takes_value(true)
+                .value_name("TOKEN")
+                .requires(remote_port_arg)
+                .validator(|token| {
+                    if token.is_empty() {
+                        Err(gettext("The token can't be empty"))
+                    } else {
+                        Ok(())
+                    }
+                })
+                .help(&gettext("Token the remote control clients must present")),
+        );
+
+    #[cfg(feature = "player")]
+    let backend_arg = "BACKEND";
+    #[cfg(feature = "player")]
+    let app = app.arg(
+        Arg::with_name(backend_arg)
+            .long("backend")
+            .takes_value(true)
+            .possible_values(Backend::names())
+            .help(&gettext("Playback backend, used to debug regressions")),
+    );
+
+    let matches = app.get_matches();
+
+    #[cfg(feature = "player")]
+    let backend = matches
+        .value_of(backend_arg)
+        .map_or_else(Backend::default, |backend| backend.parse().unwrap());
+    #[cfg(not(feature = "player"))]
+    let backend = Backend::default();
 
     CommandLineArguments {
-        input_file: matches
-            .value_of(input_arg.as_str())
-            .map(|input_file| input_file.into()),
+        input_file: matches.value_of_os(input_arg.as_str()).map(PathBuf::from),
         disable_gl: matches.is_present(disable_gl_arg),
+        backend,
+        #[cfg(feature = "remote")]
+        remote: matches
+            .value_of(remote_port_arg)
+            .and_then(|port| port.parse().ok())
+            .map(|port| RemoteArguments {
+                address: matches
+                    .value_of(remote_address_arg)
+                    .and_then(|address| address.parse().ok())
+                    .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+                port,
+                token: matches.value_of(remote_token_arg).unwrap().to_owned(),
+            }),
     }
 }
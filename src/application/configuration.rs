@@ -1,36 +1,120 @@
-use directories::ProjectDirs;
 use gettextrs::gettext;
 use lazy_static::lazy_static;
 use log::{debug, error};
 use serde::{Deserialize, Serialize};
 
 use std::{
+    collections::HashMap,
     fs::{create_dir_all, File},
-    io::Write,
     ops::{Deref, DerefMut},
     path::PathBuf,
     sync::RwLock,
 };
 
-use super::{APP_NAME, SLD, TLD};
+use crate::{media::VideoDecoding, metadata::TimestampDisplay};
+
+use super::{app_dirs::write_atomically, stored_path, APP_DIRS};
 
 const CONFIG_FILENAME: &str = "config.ron";
+const DEFAULT_SUBTITLES_FONT: &str = "Sans 18";
 
 lazy_static! {
     pub static ref CONFIG: RwLock<GlobalConfig> = RwLock::new(GlobalConfig::new());
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
 pub struct UI {
     pub width: i32,
     pub height: i32,
+    pub is_maximized: bool,
+    pub is_fullscreen: bool,
+    pub paned_pos: i32,
+    pub perspective: Option<String>,
     pub is_chapters_list_hidden: bool,
+    /// Show nested chapters in sequential order rather than as a tree.
+    pub is_chapter_tree_flat: bool,
+    pub is_notification_enabled: bool,
+    pub is_background_play_enabled: bool,
+    pub is_ms_hidden: bool,
+    pub is_hour_padded: bool,
+    pub mini_width: i32,
+    pub mini_height: i32,
+    /// UI language code, e.g. `fr`, the system's language if `None`.
+    pub language: Option<String>,
+    /// Expose the player actions on the session bus.
+    pub is_dbus_service_enabled: bool,
+    pub chapter_auto_scroll: ChapterAutoScroll,
+    /// Seconds before the end of the current chapter from which a countdown is displayed,
+    /// no countdown if `None`.
+    pub chapter_countdown: Option<u64>,
+}
+
+/// How the chapter tree follows the playing chapter.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ChapterAutoScroll {
+    /// Scrolls so that the playing chapter is at the middle of the tree.
+    Center,
+    /// Scrolls so that the playing chapter is at the top of the tree.
+    Top,
+    Disabled,
+}
+
+impl Default for ChapterAutoScroll {
+    fn default() -> Self {
+        ChapterAutoScroll::Center
+    }
+}
+
+impl UI {
+    pub fn timestamp_display(&self) -> TimestampDisplay {
+        TimestampDisplay {
+            with_ms: !self.is_ms_hidden,
+            with_hours: self.is_hour_padded,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
 pub struct Media {
     pub is_gl_disabled: bool,
+    #[serde(with = "stored_path::option")]
     pub last_path: Option<PathBuf>,
+    /// `gst-launch` like description of the audio sink, `autoaudiosink` if `None`.
+    pub audio_sink: Option<String>,
+    /// Pause playback when the audio output is unplugged, e.g. headphones.
+    pub is_paused_on_output_loss: bool,
+    /// Ignore the tocs which only apply to the current stream, when the media has no global toc.
+    pub is_current_toc_ignored: bool,
+    pub video_decoding: VideoDecoding,
+    /// Maximum height of the rendered video, the original resolution if `None`.
+    pub max_video_height: Option<u32>,
+    /// Toc file selected by the user for a media with several candidates.
+    #[serde(with = "stored_path::entries")]
+    pub toc_choices: HashMap<PathBuf, PathBuf>,
+    /// Audio delay in nanoseconds for the media which need one.
+    #[serde(with = "stored_path::keys")]
+    pub audio_offsets: HashMap<PathBuf, i64>,
+    pub subtitles: Subtitles,
+}
+
+/// Subtitles rendering options.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Subtitles {
+    /// Pango font description, e.g. `Sans Bold 18`.
+    pub font_desc: String,
+    pub is_outline_drawn: bool,
+}
+
+impl Default for Subtitles {
+    fn default() -> Self {
+        Subtitles {
+            font_desc: DEFAULT_SUBTITLES_FONT.to_owned(),
+            is_outline_drawn: true,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
@@ -47,10 +131,8 @@ pub struct GlobalConfig {
 
 impl GlobalConfig {
     fn new() -> GlobalConfig {
-        let project_dirs = ProjectDirs::from(TLD, SLD, &APP_NAME)
-            .expect("Couldn't find project dirs for this platform");
-        let config_dir = project_dirs.config_dir();
-        create_dir_all(&config_dir).unwrap();
+        let config_dir = &APP_DIRS.config;
+        create_dir_all(config_dir).unwrap();
         let path = config_dir.join(CONFIG_FILENAME);
 
         let last = match File::open(&path) {
@@ -90,41 +172,32 @@ impl GlobalConfig {
             return;
         }
 
-        match File::create(&self.path) {
-            Ok(mut config_file) => {
-                match ron::ser::to_string_pretty(&self.current, ron::ser::PrettyConfig::default()) {
-                    Ok(config_str) => match config_file.write_all(config_str.as_bytes()) {
-                        Ok(()) => {
-                            self.last = self.current.clone();
-                            debug!("saved config: {:?}", self.current);
-                        }
-                        Err(err) => {
-                            error!(
-                                "{}",
-                                &gettext("couldn't write configuration: {}").replacen(
-                                    "{}",
-                                    &format!("{:?}", err),
-                                    1
-                                ),
-                            );
-                        }
-                    },
-                    Err(err) => {
-                        error!(
+        // Serialize first so that the previous configuration is kept on failure
+        let config_str =
+            match ron::ser::to_string_pretty(&self.current, ron::ser::PrettyConfig::default()) {
+                Ok(config_str) => config_str,
+                Err(err) => {
+                    error!(
+                        "{}",
+                        &gettext("couldn't serialize configuration: {}").replacen(
                             "{}",
-                            &gettext("couldn't serialize configuration: {}").replacen(
-                                "{}",
-                                &format!("{:?}", err),
-                                1
-                            ),
-                        );
-                    }
+                            &format!("{:?}", err),
+                            1
+                        ),
+                    );
+                    return;
                 }
+            };
+
+        match write_atomically(&self.path, config_str.as_bytes()) {
+            Ok(()) => {
+                self.last = self.current.clone();
+                debug!("saved config: {:?}", self.current);
             }
             Err(err) => {
                 error!(
                     "{}",
-                    &gettext("couldn't create configuration file: {}").replacen(
+                    &gettext("couldn't write configuration: {}").replacen(
                         "{}",
                         &format!("{:?}", err),
                         1
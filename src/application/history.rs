@@ -0,0 +1,352 @@
+use gettextrs::gettext;
+use lazy_static::lazy_static;
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fs::{create_dir_all, File},
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::SystemTime,
+};
+
+use super::{app_dirs::write_atomically, stored_path, SeekHistory, APP_DIRS};
+
+const HISTORY_FILENAME: &str = "history.ron";
+const MAX_ENTRIES: usize = 100;
+// Position jumps longer than this are considered as seeks, not listening
+const MAX_LISTENED_STEP: u64 = 1_000_000_000;
+
+lazy_static! {
+    pub static ref HISTORY: RwLock<GlobalHistory> = RwLock::new(GlobalHistory::new());
+}
+
+/// Timestamps and durations are expressed in nanoseconds,
+/// dates in seconds since the Unix epoch.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct HistoryEntry {
+    #[serde(with = "stored_path")]
+    pub path: PathBuf,
+    pub first_played: u64,
+    pub last_played: u64,
+    pub listened: u64,
+    pub position: u64,
+    pub is_completed: bool,
+    pub seeks: SeekHistory,
+}
+
+impl HistoryEntry {
+    fn new(path: &Path, now: u64) -> Self {
+        HistoryEntry {
+            path: path.to_owned(),
+            first_played: now,
+            last_played: now,
+            ..HistoryEntry::default()
+        }
+    }
+
+    pub fn update_position(&mut self, position: u64) {
+        if position > self.position && position - self.position <= MAX_LISTENED_STEP {
+            self.listened += position - self.position;
+        }
+        self.position = position;
+    }
+
+    pub fn set_completed(&mut self) {
+        self.is_completed = true;
+    }
+
+    /// Position from which to continue playback.
+    pub fn resume_position(&self) -> u64 {
+        if self.is_completed {
+            0
+        } else {
+            self.position
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct History {
+    // Most recent first
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Records the beginning of a playback and returns the corresponding entry.
+    pub fn started(&mut self, path: &Path, now: u64) -> &mut HistoryEntry {
+        let mut entry = match self.entries.iter().position(|entry| entry.path == path) {
+            Some(idx) => self.entries.remove(idx),
+            None => HistoryEntry::new(path, now),
+        };
+        entry.last_played = now;
+        entry.is_completed = false;
+
+        self.entries.insert(0, entry);
+        self.entries.truncate(MAX_ENTRIES);
+
+        &mut self.entries[0]
+    }
+
+    /// The entry for the media being played if any.
+    pub fn current_mut(&mut self) -> Option<&mut HistoryEntry> {
+        self.entries.first_mut()
+    }
+
+    pub fn entry(&self, path: &Path) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'_ HistoryEntry> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|err| err.to_string())
+    }
+
+    pub fn from_json(json: &str) -> Result<History, String> {
+        serde_json::from_str(json).map_err(|err| err.to_string())
+    }
+
+    /// Merges the entries from `other`, e.g. a history exported on another machine.
+    ///
+    /// When both histories contain the same media, the most recently played entry wins.
+    /// Returns the number of entries which were added or updated.
+    pub fn merge(&mut self, other: History) -> usize {
+        let mut updated = 0;
+        for other_entry in other.entries {
+            match self
+                .entries
+                .iter_mut()
+                .find(|entry| entry.path == other_entry.path)
+            {
+                Some(entry) => {
+                    if other_entry.last_played > entry.last_played {
+                        let first_played = entry.first_played.min(other_entry.first_played);
+                        *entry = other_entry;
+                        entry.first_played = first_played;
+                        updated += 1;
+                    }
+                }
+                None => {
+                    self.entries.push(other_entry);
+                    updated += 1;
+                }
+            }
+        }
+
+        // Keep the first entry in place: it might be the one being played.
+        if self.entries.len() > 1 {
+            self.entries[1..].sort_by(|a, b| b.last_played.cmp(&a.last_played));
+        }
+        self.entries.truncate(MAX_ENTRIES);
+
+        updated
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+pub struct GlobalHistory {
+    path: PathBuf,
+    last: History,
+    current: History,
+}
+
+impl GlobalHistory {
+    fn new() -> GlobalHistory {
+        let data_dir = &APP_DIRS.data;
+        create_dir_all(data_dir).unwrap();
+        let path = data_dir.join(HISTORY_FILENAME);
+
+        let last = File::open(&path)
+            .ok()
+            .and_then(|history_file| {
+                ron::de::from_reader(history_file)
+                    .map_err(|err| {
+                        error!(
+                            "{}",
+                            &gettext("couldn't load history: {}").replacen(
+                                "{}",
+                                &format!("{:?}", err),
+                                1
+                            ),
+                        );
+                    })
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        GlobalHistory {
+            path,
+            current: last.clone(),
+            last,
+        }
+    }
+
+    pub fn save(&mut self) {
+        if self.last == self.current {
+            // unchanged => don't save
+            return;
+        }
+
+        let res = ron::ser::to_string_pretty(&self.current, ron::ser::PrettyConfig::default())
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|history_str| {
+                write_atomically(&self.path, history_str.as_bytes())
+                    .map_err(|err| format!("{:?}", err))
+            });
+
+        match res {
+            Ok(()) => {
+                self.last = self.current.clone();
+                debug!("saved history");
+            }
+            Err(err) => error!(
+                "{}",
+                &gettext("couldn't save history: {}").replacen("{}", &err, 1),
+            ),
+        }
+    }
+}
+
+impl Deref for GlobalHistory {
+    type Target = History;
+
+    fn deref(&self) -> &Self::Target {
+        &self.current
+    }
+}
+
+impl DerefMut for GlobalHistory {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u64 = 1_000_000_000;
+
+    #[test]
+    fn started() {
+        let mut history = History::default();
+
+        history.started(Path::new("a"), 1);
+        history.started(Path::new("b"), 2);
+        assert_eq!(
+            vec![Path::new("b"), Path::new("a")],
+            history
+                .iter()
+                .map(|entry| entry.path.as_path())
+                .collect::<Vec<_>>(),
+        );
+
+        // playing again moves the entry first and keeps its stats
+        history.current_mut().unwrap().update_position(SEC / 2);
+        history.started(Path::new("a"), 3);
+        history.started(Path::new("b"), 4);
+        let entry = history.entry(Path::new("b")).unwrap();
+        assert_eq!(2, entry.first_played);
+        assert_eq!(4, entry.last_played);
+        assert_eq!(SEC / 2, entry.listened);
+        assert_eq!(2, history.iter().count());
+
+        for idx in 0..MAX_ENTRIES {
+            history.started(Path::new(&idx.to_string()), 5);
+        }
+        assert_eq!(MAX_ENTRIES, history.iter().count());
+        assert!(history.entry(Path::new("a")).is_none());
+    }
+
+    #[test]
+    fn listened_and_resume() {
+        let mut history = History::default();
+        let entry = history.started(Path::new("a"), 1);
+
+        entry.update_position(SEC / 2);
+        entry.update_position(SEC);
+        assert_eq!(SEC, entry.listened);
+
+        // seeks don't count
+        entry.update_position(10 * SEC);
+        entry.update_position(2 * SEC);
+        assert_eq!(SEC, entry.listened);
+        assert_eq!(2 * SEC, entry.resume_position());
+
+        entry.set_completed();
+        assert_eq!(0, entry.resume_position());
+
+        // completion is reset when playing again
+        let entry = history.started(Path::new("a"), 2);
+        assert!(!entry.is_completed);
+    }
+
+    #[test]
+    fn merge() {
+        let mut history = History::default();
+        history.started(Path::new("a"), 1).update_position(SEC);
+        history.started(Path::new("b"), 2).update_position(SEC);
+        history.started(Path::new("c"), 10).update_position(SEC);
+
+        let mut other = History::default();
+        other.started(Path::new("d"), 3).update_position(2 * SEC);
+        // older than the local entry
+        other.started(Path::new("c"), 5).update_position(2 * SEC);
+        // more recent than the local entry
+        other.started(Path::new("a"), 0);
+        other.started(Path::new("a"), 8).update_position(2 * SEC);
+
+        let other = History::from_json(&other.to_json().unwrap()).unwrap();
+        assert_eq!(2, history.merge(other));
+
+        assert_eq!(
+            vec![
+                Path::new("c"),
+                Path::new("a"),
+                Path::new("d"),
+                Path::new("b")
+            ],
+            history
+                .iter()
+                .map(|entry| entry.path.as_path())
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(SEC, history.entry(Path::new("c")).unwrap().position);
+        let entry = history.entry(Path::new("a")).unwrap();
+        assert_eq!(2 * SEC, entry.position);
+        assert_eq!(0, entry.first_played);
+        assert_eq!(8, entry.last_played);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_path() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = Path::new(OsStr::from_bytes(b"/media/caf\xe9.mkv"));
+        let mut history = History::default();
+        history.started(Path::new("/media/a.mkv"), 1);
+        history.started(path, 2).update_position(SEC);
+
+        let history_str =
+            ron::ser::to_string_pretty(&history, ron::ser::PrettyConfig::default()).unwrap();
+        let restored: History = ron::de::from_str(&history_str).unwrap();
+        assert_eq!(history, restored);
+        assert_eq!(SEC, restored.entry(path).unwrap().resume_position());
+    }
+}
@@ -2,14 +2,23 @@ use directories::ProjectDirs;
 use gettextrs::{TextDomain, TextDomainError};
 use log::{error, info, warn};
 
-use super::{APP_NAME, SLD, TLD};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
-pub fn init_locale() {
+use super::{APP_NAME, CONFIG, SLD, TLD};
+
+// Translatable strings are written in English
+const SOURCE_LANGUAGE: &str = "en";
+
+/// Directories searched for translations before the system data dirs.
+///
+/// Translations are looked up in a `locale` subdirectory.
+fn app_dirs() -> Vec<PathBuf> {
     // Search translations under `target` first
     // in order to reflect latest changes during development
-    let text_domain = TextDomain::new(&*APP_NAME)
-        .codeset("UTF-8")
-        .prepend("target");
+    let mut dirs = vec![PathBuf::from("target")];
 
     // Add user's data dir in the search path
     let project_dirs = ProjectDirs::from(TLD, SLD, &APP_NAME)
@@ -18,13 +27,24 @@ pub fn init_locale() {
 
     // FIXME: figure out macOS conventions
     #[cfg(all(target_family = "unix", not(target_os = "macos")))]
-    let text_domain = match _app_data_dir.parent() {
-        Some(data_dir) => text_domain.prepend(data_dir),
-        None => text_domain,
-    };
+    dirs.extend(_app_data_dir.parent().map(Path::to_path_buf));
 
     #[cfg(target_os = "windows")]
-    let text_domain = text_domain.prepend(_app_data_dir);
+    dirs.push(_app_data_dir.to_path_buf());
+
+    dirs
+}
+
+pub fn init_locale() {
+    // The language selected in the preferences overrides the system's
+    if let Some(language) = CONFIG.read().unwrap().ui.language.as_ref() {
+        env::set_var("LANGUAGE", language);
+    }
+
+    let text_domain = app_dirs().into_iter().fold(
+        TextDomain::new(&*APP_NAME).codeset("UTF-8"),
+        TextDomain::prepend,
+    );
 
     match text_domain.init() {
         Ok(locale) => info!("Translation found, `setlocale` returned {:?}", locale),
@@ -34,3 +54,35 @@ pub fn init_locale() {
         Err(TextDomainError::InvalidLocale(locale)) => error!("Invalid locale {}", locale),
     }
 }
+
+/// Lists the languages the UI is translated to, including the source language.
+pub fn available_languages() -> Vec<String> {
+    let mo_file = format!("{}.mo", *APP_NAME);
+
+    let system_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share/:/usr/share/".to_owned());
+
+    let mut languages = vec![SOURCE_LANGUAGE.to_owned()];
+    for dir in app_dirs().into_iter().chain(env::split_paths(&system_dirs)) {
+        let entries = match fs::read_dir(dir.join("locale")) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        languages.extend(entries.filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if !path.join("LC_MESSAGES").join(&mo_file).is_file() {
+                return None;
+            }
+
+            path.file_name()?.to_str().map(ToOwned::to_owned)
+        }));
+    }
+
+    languages.sort();
+    languages.dedup();
+
+    languages
+}
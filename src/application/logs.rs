@@ -0,0 +1,156 @@
+use lazy_static::lazy_static;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use std::{collections::VecDeque, fmt, sync::Mutex};
+
+use super::unix_now;
+
+const MAX_ENTRIES: usize = 500;
+
+lazy_static! {
+    /// Recent warnings & errors, so that users can report them
+    /// without running the application from a terminal.
+    pub static ref LOGS: Mutex<LogJournal> = Mutex::new(LogJournal::new(MAX_ENTRIES));
+}
+
+/// Dates are expressed in seconds since the Unix epoch.
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub date: u64,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let date = glib::DateTime::from_unix_local(self.date as i64)
+            .format("%F %X")
+            .map_or_else(String::new, |date| date.to_string());
+
+        write!(
+            f,
+            "{} {:5} {}: {}",
+            date, self.level, self.target, self.message
+        )
+    }
+}
+
+pub struct LogJournal {
+    capacity: usize,
+    entries: VecDeque<LogEntry>,
+}
+
+impl LogJournal {
+    fn new(capacity: usize) -> Self {
+        LogJournal {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, level: Level, target: &str, message: String) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(LogEntry {
+            date: unix_now(),
+            level,
+            target: target.to_owned(),
+            message,
+        });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'_ LogEntry> {
+        self.entries.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Forwards the records to `env_logger` and keeps the warnings & errors in `LOGS`.
+struct Logger {
+    env_logger: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn || self.env_logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= Level::Warn {
+            LOGS.lock()
+                .unwrap()
+                .push(record.level(), record.target(), record.args().to_string());
+        }
+
+        self.env_logger.log(record);
+    }
+
+    fn flush(&self) {
+        self.env_logger.flush();
+    }
+}
+
+/// Initializes the logger, `RUST_LOG` is honoured as with `env_logger::init()`.
+pub fn init_logger() {
+    let env_logger = env_logger::Builder::from_default_env().build();
+    let max_level = env_logger.filter().max(LevelFilter::Warn);
+
+    if log::set_boxed_logger(Box::new(Logger { env_logger })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Keeps GStreamer's warnings & errors in `LOGS`.
+///
+/// GStreamer must be initialized. Which messages are emitted depends on
+/// the debug threshold, see `gst::debug_set_default_threshold`.
+pub fn capture_gst_logs() {
+    gst::debug_add_log_function(
+        |category, level, _file, _function, _line, _object, message| {
+            let level = match level {
+                gst::DebugLevel::Error => Level::Error,
+                gst::DebugLevel::Warning => Level::Warn,
+                _ => return,
+            };
+
+            if let Some(message) = message.get() {
+                LOGS.lock().unwrap().push(
+                    level,
+                    &format!("gst::{}", category.get_name()),
+                    message.to_string_lossy().into_owned(),
+                );
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity() {
+        let mut journal = LogJournal::new(2);
+        assert!(journal.is_empty());
+
+        journal.push(Level::Warn, "first", "1".to_owned());
+        journal.push(Level::Error, "second", "2".to_owned());
+        journal.push(Level::Warn, "third", "3".to_owned());
+
+        let targets: Vec<&str> = journal.iter().map(|entry| entry.target.as_str()).collect();
+        assert_eq!(vec!["second", "third"], targets);
+
+        journal.clear();
+        assert!(journal.is_empty());
+    }
+}
@@ -0,0 +1,395 @@
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::{
+    media::Waveform,
+    metadata::{Duration, MediaInfo, MediaPath},
+};
+
+use super::APP_DIRS;
+
+const CACHE_DIRNAME: &str = "media_info";
+const WAVEFORM_CACHE_DIRNAME: &str = "waveforms";
+// Entries are small, but there's one per media ever opened
+const MAX_CACHED_MEDIA_INFO: usize = 500;
+// A peak file is a few hundred KiB for an hour of audio
+const MAX_CACHED_WAVEFORMS: usize = 50;
+
+/// Identifies a version of a media file.
+///
+/// The modification date is expressed in nanoseconds since the Unix epoch.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct MediaKey {
+    path: PathBuf,
+    modified: u64,
+    size: u64,
+}
+
+impl MediaKey {
+    fn new(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()?
+            .as_nanos() as u64;
+
+        Some(MediaKey {
+            path: path.to_owned(),
+            modified,
+            size: metadata.len(),
+        })
+    }
+
+    // The hash is not guaranteed to be stable across builds,
+    // in which case the entry is just probed again.
+    fn file_name(path: &Path) -> String {
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        format!("{:016x}.ron", hasher.finish())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+enum StreamType {
+    Audio,
+    Video,
+    Text,
+}
+
+impl From<StreamType> for gst::StreamType {
+    fn from(type_: StreamType) -> Self {
+        match type_ {
+            StreamType::Audio => gst::StreamType::AUDIO,
+            StreamType::Video => gst::StreamType::VIDEO,
+            StreamType::Text => gst::StreamType::TEXT,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedStream {
+    id: String,
+    type_: StreamType,
+    caps: gst::Caps,
+    tags: gst::TagList,
+}
+
+/// The parts of a `MediaInfo` which are known once the media is prerolled.
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedMediaInfo {
+    key: MediaKey,
+    duration: u64,
+    tags: gst::TagList,
+    toc: Option<gst::Toc>,
+    streams: Vec<CachedStream>,
+    selected_ids: Vec<String>,
+}
+
+impl CachedMediaInfo {
+    fn new(key: MediaKey, info: &MediaInfo) -> Self {
+        let mut streams = Vec::new();
+        let mut selected_ids = Vec::new();
+
+        let collections = [
+            (
+                StreamType::Audio,
+                &info.streams.audio,
+                info.streams.selected_audio(),
+            ),
+            (
+                StreamType::Video,
+                &info.streams.video,
+                info.streams.selected_video(),
+            ),
+            (
+                StreamType::Text,
+                &info.streams.text,
+                info.streams.selected_text(),
+            ),
+        ];
+        for (type_, collection, selected) in collections.iter() {
+            streams.extend(collection.sorted().map(|stream| CachedStream {
+                id: stream.id.to_string(),
+                type_: *type_,
+                caps: stream.caps.clone(),
+                tags: without_images(&stream.tags),
+            }));
+            if let Some(selected) = selected {
+                selected_ids.push(selected.id.to_string());
+            }
+        }
+
+        CachedMediaInfo {
+            key,
+            duration: info.duration.as_u64(),
+            tags: without_images(&info.tags),
+            toc: info.toc.clone(),
+            streams,
+            selected_ids,
+        }
+    }
+
+    fn into_media_info(self) -> MediaInfo {
+        let mut info = MediaInfo::new(&MediaPath::new(self.key.path));
+        info.duration = Duration::from_nanos(self.duration);
+        info.add_tags(&self.tags);
+        info.toc = self.toc;
+
+        for stream in self.streams {
+            let gst_stream = gst::Stream::new(
+                Some(&stream.id),
+                Some(&stream.caps),
+                stream.type_.into(),
+                gst::StreamFlags::empty(),
+            );
+            gst_stream.set_tags(Some(&stream.tags));
+            info.add_stream(&gst_stream);
+        }
+
+        let selected_ids: Vec<Arc<str>> = self
+            .selected_ids
+            .iter()
+            .map(|id| id.as_str().into())
+            .collect();
+        if let Err(err) = info.streams.select_streams(&selected_ids) {
+            debug!("{}", err);
+        }
+
+        info
+    }
+}
+
+// Images are large and they are available once the media is open anyway.
+fn without_images(tags: &gst::TagList) -> gst::TagList {
+    let mut tags = tags.copy();
+    {
+        let tags = tags.get_mut().unwrap();
+        tags.remove::<gst::tags::Image>();
+        tags.remove::<gst::tags::PreviewImage>();
+    }
+
+    tags
+}
+
+// Removes the least recently stored entries in excess of `max_entries`.
+fn prune(dir: &Path, max_entries: usize) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|metadata| metadata.modified());
+            Some((modified.ok()?, entry.path()))
+        })
+        .collect();
+    if files.len() <= max_entries {
+        return;
+    }
+
+    files.sort_by_key(|(modified, _)| *modified);
+    let excess = files.len() - max_entries;
+    for (_, path) in files.into_iter().take(excess) {
+        if let Err(err) = fs::remove_file(&path) {
+            debug!("couldn't remove cache entry {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// Keeps the `MediaInfo` of the recently opened media in the user's cache.
+///
+/// An entry is valid as long as the media file keeps the same modification date & size.
+/// The entries of the media opened least recently are evicted first.
+pub struct MediaCache {}
+
+impl MediaCache {
+    fn dir() -> PathBuf {
+        APP_DIRS.cache.join(CACHE_DIRNAME)
+    }
+
+    /// Returns the `MediaInfo` cached for the media at `path` if it didn't change since.
+    pub fn load(path: &MediaPath) -> Option<MediaInfo> {
+        let key = MediaKey::new(path)?;
+        let cache_file = File::open(Self::dir().join(MediaKey::file_name(path))).ok()?;
+
+        let cached: CachedMediaInfo = ron::de::from_reader(cache_file)
+            .map_err(|err| debug!("couldn't load cached media info: {:?}", err))
+            .ok()?;
+        if cached.key != key {
+            debug!("cached media info outdated for {}", path.display());
+            return None;
+        }
+
+        Some(cached.into_media_info())
+    }
+
+    pub fn store(info: &MediaInfo) {
+        let key = match MediaKey::new(&info.path) {
+            Some(key) => key,
+            None => return,
+        };
+
+        let dir = Self::dir();
+        let cache_path = dir.join(MediaKey::file_name(&info.path));
+        let res = ron::ser::to_string(&CachedMediaInfo::new(key, info))
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|cached_str| {
+                fs::create_dir_all(&dir)
+                    .and_then(|_| fs::write(&cache_path, cached_str))
+                    .map_err(|err| format!("{:?}", err))
+            });
+
+        match res {
+            Ok(()) => prune(&dir, MAX_CACHED_MEDIA_INFO),
+            Err(err) => warn!("couldn't cache media info: {}", err),
+        }
+    }
+}
+
+/// The peak file of a media.
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedWaveform {
+    key: MediaKey,
+    waveform: Waveform,
+}
+
+/// Keeps the `Waveform` of the media in the user's cache.
+///
+/// Generating a `Waveform` requires decoding the whole audio stream.
+/// Entries are invalidated like `MediaCache` entries.
+/// The oldest peak files are evicted first.
+pub struct WaveformCache {}
+
+impl WaveformCache {
+    fn dir() -> PathBuf {
+        APP_DIRS.cache.join(WAVEFORM_CACHE_DIRNAME)
+    }
+
+    /// Returns the `Waveform` cached for the media at `path` if it didn't change since.
+    pub fn load(path: &MediaPath) -> Option<Waveform> {
+        let key = MediaKey::new(path)?;
+        let cache_file = File::open(Self::dir().join(MediaKey::file_name(path))).ok()?;
+
+        let cached: CachedWaveform = ron::de::from_reader(cache_file)
+            .map_err(|err| debug!("couldn't load cached waveform: {:?}", err))
+            .ok()?;
+        if cached.key != key {
+            debug!("cached waveform outdated for {}", path.display());
+            return None;
+        }
+
+        Some(cached.waveform)
+    }
+
+    pub fn store(path: &MediaPath, waveform: &Waveform) {
+        let key = match MediaKey::new(path) {
+            Some(key) => key,
+            None => return,
+        };
+
+        let dir = Self::dir();
+        let cache_path = dir.join(MediaKey::file_name(path));
+        let cached = CachedWaveform {
+            key,
+            waveform: waveform.clone(),
+        };
+        let res = ron::ser::to_string(&cached)
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|cached_str| {
+                fs::create_dir_all(&dir)
+                    .and_then(|_| fs::write(&cache_path, cached_str))
+                    .map_err(|err| format!("{:?}", err))
+            });
+
+        match res {
+            Ok(()) => prune(&dir, MAX_CACHED_WAVEFORMS),
+            Err(err) => warn!("couldn't cache waveform: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        gst::init().unwrap();
+
+        let mut info = MediaInfo::new(&MediaPath::new("/media/test.mkv"));
+        info.duration = Duration::from_secs(42);
+
+        let mut tags = gst::TagList::new();
+        tags.get_mut()
+            .unwrap()
+            .add::<gst::tags::Title>(&"test title", gst::TagMergeMode::Replace);
+        info.add_tags(&tags);
+
+        for (id, caps_name, type_) in &[
+            ("audio-0", "audio/x-vorbis", gst::StreamType::AUDIO),
+            ("audio-1", "audio/x-opus", gst::StreamType::AUDIO),
+            ("video-0", "video/x-vp8", gst::StreamType::VIDEO),
+        ] {
+            let caps = gst::Caps::new_simple(caps_name, &[]);
+            info.add_stream(&gst::Stream::new(
+                Some(id),
+                Some(&caps),
+                *type_,
+                gst::StreamFlags::empty(),
+            ));
+        }
+        let selected_ids: Vec<Arc<str>> = vec!["audio-1".into(), "video-0".into()];
+        info.streams.select_streams(&selected_ids).unwrap();
+
+        let key = MediaKey {
+            path: info.path.to_path_buf(),
+            modified: 1,
+            size: 2,
+        };
+        let cached_str = ron::ser::to_string(&CachedMediaInfo::new(key.clone(), &info)).unwrap();
+        let cached: CachedMediaInfo = ron::de::from_str(&cached_str).unwrap();
+        assert_eq!(key, cached.key);
+
+        let restored = cached.into_media_info();
+        assert_eq!(info.path, restored.path);
+        assert_eq!(info.duration, restored.duration);
+        assert_eq!(Some("test title"), restored.media_title());
+        assert_eq!(2, restored.streams.audio.sorted().count());
+        assert_eq!(
+            "audio-1",
+            restored.streams.selected_audio().unwrap().id.as_ref()
+        );
+        assert!(restored.streams.is_video_selected());
+        assert_eq!(Some("opus"), restored.streams.audio_codec());
+    }
+
+    #[test]
+    fn prune_excess() {
+        let dir = std::env::temp_dir().join(format!("media-toc-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for idx in 0..5 {
+            fs::write(dir.join(format!("{}.ron", idx)), "").unwrap();
+        }
+
+        prune(&dir, 5);
+        assert_eq!(5, fs::read_dir(&dir).unwrap().count());
+
+        prune(&dir, 3);
+        assert_eq!(3, fs::read_dir(&dir).unwrap().count());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
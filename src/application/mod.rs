@@ -15,11 +15,30 @@ lazy_static! {
     pub static ref APP_PATH: String = format!("/{}/{}/{}", TLD, SLD, *APP_NAME);
 }
 
+mod app_dirs;
+pub use self::app_dirs::APP_DIRS;
+
 mod command_line;
+#[cfg(feature = "remote")]
+pub use self::command_line::RemoteArguments;
 pub use self::command_line::{get_command_line, CommandLineArguments};
 
 mod configuration;
-pub use self::configuration::CONFIG;
+pub use self::configuration::{ChapterAutoScroll, CONFIG};
+
+mod history;
+pub use self::history::{unix_now, History, HISTORY};
+
+mod media_cache;
+pub use self::media_cache::{MediaCache, WaveformCache};
+
+mod logs;
+pub use self::logs::{capture_gst_logs, init_logger, LOGS};
+
+mod seek_history;
+pub use self::seek_history::SeekHistory;
+
+mod stored_path;
 
 mod locale;
-pub use self::locale::init_locale;
+pub use self::locale::{available_languages, init_locale};
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+
+const MAX_ENTRIES: usize = 50;
+// Seeks shorter than this are not worth going back to
+const MIN_JUMP: u64 = 10_000_000_000; // 10 s
+
+/// Positions from which the user jumped to another part of a media,
+/// so as to go back and forth between them like in a browser.
+///
+/// Positions are expressed in nanoseconds.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SeekHistory {
+    back: Vec<u64>,
+    forward: Vec<u64>,
+}
+
+impl SeekHistory {
+    /// Records a jump from `origin` to `target`.
+    ///
+    /// A jump which `is_coalesced` with the previous one, e.g. while browsing
+    /// the chapters, doesn't add an entry: going back returns to the position
+    /// before the first jump.
+    ///
+    /// Returns `true` if the history was changed.
+    pub fn jumped(&mut self, origin: u64, target: u64, is_coalesced: bool) -> bool {
+        let distance = if target > origin {
+            target - origin
+        } else {
+            origin - target
+        };
+        if distance < MIN_JUMP {
+            return false;
+        }
+
+        self.forward.clear();
+        if !is_coalesced || self.back.is_empty() {
+            self.back.push(origin);
+            if self.back.len() > MAX_ENTRIES {
+                self.back.remove(0);
+            }
+        }
+
+        true
+    }
+
+    /// Returns the position to go back to from `current`.
+    pub fn back(&mut self, current: u64) -> Option<u64> {
+        let position = self.back.pop()?;
+        self.forward.push(current);
+
+        Some(position)
+    }
+
+    /// Returns the position to go forward to from `current`.
+    pub fn forward(&mut self, current: u64) -> Option<u64> {
+        let position = self.forward.pop()?;
+        self.back.push(current);
+
+        Some(position)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u64 = 1_000_000_000;
+
+    #[test]
+    fn back_and_forward() {
+        let mut history = SeekHistory::default();
+        assert!(!history.can_go_back());
+        assert!(history.back(0).is_none());
+
+        // too short to be recorded
+        assert!(!history.jumped(0, 5 * SEC, false));
+        assert!(!history.can_go_back());
+
+        assert!(history.jumped(5 * SEC, 100 * SEC, false));
+        assert!(history.jumped(110 * SEC, 200 * SEC, false));
+
+        assert_eq!(Some(110 * SEC), history.back(205 * SEC));
+        assert_eq!(Some(5 * SEC), history.back(110 * SEC));
+        assert!(history.back(5 * SEC).is_none());
+
+        assert_eq!(Some(110 * SEC), history.forward(5 * SEC));
+        assert_eq!(Some(205 * SEC), history.forward(110 * SEC));
+        assert!(!history.can_go_forward());
+
+        // a new jump discards the forward entries
+        history.back(205 * SEC);
+        assert!(history.can_go_forward());
+        history.jumped(110 * SEC, 300 * SEC, false);
+        assert!(!history.can_go_forward());
+    }
+
+    #[test]
+    fn coalesced() {
+        let mut history = SeekHistory::default();
+
+        // the first jump is always recorded
+        assert!(history.jumped(0, 100 * SEC, true));
+        assert!(history.jumped(100 * SEC, 200 * SEC, true));
+        assert!(history.jumped(200 * SEC, 300 * SEC, true));
+
+        assert_eq!(Some(0), history.back(300 * SEC));
+        assert!(!history.can_go_back());
+    }
+
+    #[test]
+    fn max_entries() {
+        let mut history = SeekHistory::default();
+        for idx in 0..=MAX_ENTRIES as u64 {
+            history.jumped(idx * 100 * SEC, (idx * 100 + 50) * SEC, false);
+        }
+
+        let mut count = 0;
+        let mut last = None;
+        while let Some(position) = history.back(0) {
+            count += 1;
+            last = Some(position);
+        }
+        assert_eq!(MAX_ENTRIES, count);
+        // the oldest entry was dropped
+        assert_eq!(Some(100 * SEC), last);
+    }
+}
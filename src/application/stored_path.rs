@@ -0,0 +1,173 @@
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::metadata::MediaPath;
+
+const URI_PREFIX: &str = "file://";
+
+/// Converts `path` to a string from which the same path can be restored.
+///
+/// Valid UTF-8 paths are kept as is so that the files remain readable,
+/// the others are stored as a `file://` URI.
+fn to_string(path: &Path) -> Result<String, String> {
+    match path.to_str() {
+        Some(path_str) if !path_str.starts_with(URI_PREFIX) => Ok(path_str.to_owned()),
+        _ => MediaPath::new(path).uri(),
+    }
+}
+
+fn from_string(path_str: String) -> Result<PathBuf, String> {
+    if path_str.starts_with(URI_PREFIX) {
+        glib::filename_from_uri(&path_str)
+            .map(|(path, _hostname)| path)
+            .map_err(|err| err.to_string())
+    } else {
+        Ok(PathBuf::from(path_str))
+    }
+}
+
+struct Stored<'a>(&'a Path);
+
+impl Serialize for Stored<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        to_string(self.0)
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct Loaded(PathBuf);
+
+impl<'de> Deserialize<'de> for Loaded {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        from_string(String::deserialize(deserializer)?)
+            .map(Loaded)
+            .map_err(de::Error::custom)
+    }
+}
+
+// serde's `Path` implementation fails to serialize paths which are not valid UTF-8.
+// Use these with `#[serde(with = "stored_path")]` for the paths chosen by the user.
+
+pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    Stored(path).serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    Loaded::deserialize(deserializer).map(|loaded| loaded.0)
+}
+
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        path: &Option<PathBuf>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        path.as_deref().map(Stored).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<PathBuf>, D::Error> {
+        Option::<Loaded>::deserialize(deserializer).map(|loaded| loaded.map(|loaded| loaded.0))
+    }
+}
+
+/// For maps with paths as keys.
+pub mod keys {
+    use super::*;
+
+    pub fn serialize<S, V>(map: &HashMap<PathBuf, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        V: Serialize,
+    {
+        serializer.collect_map(map.iter().map(|(path, value)| (Stored(path), value)))
+    }
+
+    pub fn deserialize<'de, D, V>(deserializer: D) -> Result<HashMap<PathBuf, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: Deserialize<'de>,
+    {
+        HashMap::<Loaded, V>::deserialize(deserializer).map(|map| {
+            map.into_iter()
+                .map(|(loaded, value)| (loaded.0, value))
+                .collect()
+        })
+    }
+}
+
+/// For maps with paths as keys and values.
+pub mod entries {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        map: &HashMap<PathBuf, PathBuf>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(key, value)| (Stored(key), Stored(value))))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<PathBuf, PathBuf>, D::Error> {
+        HashMap::<Loaded, Loaded>::deserialize(deserializer).map(|map| {
+            map.into_iter()
+                .map(|(key, value)| (key.0, value.0))
+                .collect()
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    #[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+    #[serde(default)]
+    struct Paths {
+        #[serde(with = "super")]
+        path: PathBuf,
+        #[serde(with = "super::option")]
+        last_path: Option<PathBuf>,
+        #[serde(with = "super::keys")]
+        offsets: HashMap<PathBuf, i64>,
+        #[serde(with = "super::entries")]
+        choices: HashMap<PathBuf, PathBuf>,
+    }
+
+    #[test]
+    fn non_utf8_path() {
+        let non_utf8 = PathBuf::from(OsStr::from_bytes(b"/media/caf\xe9.mkv"));
+        let utf8 = PathBuf::from("/media/café.mkv");
+
+        let mut paths = Paths {
+            path: non_utf8.clone(),
+            last_path: non_utf8.parent().map(Path::to_path_buf),
+            ..Paths::default()
+        };
+        paths.offsets.insert(non_utf8.clone(), 25);
+        paths.offsets.insert(utf8.clone(), -25);
+        paths
+            .choices
+            .insert(non_utf8.clone(), non_utf8.with_extension("cue"));
+        paths.choices.insert(utf8.clone(), non_utf8);
+
+        let paths_str = ron::ser::to_string(&paths).unwrap();
+        // Valid UTF-8 paths are kept readable
+        assert!(paths_str.contains("\"/media/café.mkv\""));
+        assert_eq!(paths, ron::de::from_str(&paths_str).unwrap());
+
+        // Paths stored before the URI encoding are still valid
+        let legacy: Paths = ron::de::from_str("(path: \"/media/café.mkv\")").unwrap();
+        assert_eq!(utf8, legacy.path);
+    }
+}
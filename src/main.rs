@@ -2,15 +2,22 @@ use gettextrs::gettext;
 use log::error;
 
 mod application;
-use application::{get_command_line, init_locale};
-mod media;
-mod metadata;
+use application::{get_command_line, init_locale, init_logger};
+use media_toc_core::{media, metadata};
+use metadata::register_formats;
+#[cfg(feature = "remote")]
+mod remote;
 mod ui;
 
 fn main() {
-    env_logger::init();
+    // Must be set before GStreamer spawns any thread
+    #[cfg(feature = "player")]
+    media::init_player_env();
+
+    init_logger();
 
     init_locale();
+    register_formats();
 
     // Character encoding is broken unless gtk (glib) is initialized
     let is_gtk_ok = gtk::init().is_ok();
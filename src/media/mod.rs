@@ -1,8 +0,0 @@
-pub mod playback_pipeline;
-pub use self::playback_pipeline::{
-    MediaMessage, MissingPlugins, OpenError, PlaybackPipeline, SeekError, SelectStreamsError,
-    StateChangeError,
-};
-
-pub mod timestamp;
-pub use self::timestamp::Timestamp;
@@ -1,7 +0,0 @@
-use std::io::Read;
-
-use super::MediaInfo;
-
-pub trait Reader {
-    fn read(&self, info: &MediaInfo, source: &mut dyn Read) -> Result<Option<gst::Toc>, String>;
-}
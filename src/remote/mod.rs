@@ -0,0 +1,354 @@
+//! Remote control over a tiny JSON API.
+//!
+//! - `GET /state` returns the current [`RemoteState`].
+//! - `POST /command` accepts a [`RemoteCommand`].
+//! - A WebSocket connection receives the [`RemoteState`] periodically
+//!   and accepts [`RemoteCommand`]s as text messages.
+//!
+//! Requests must hold the shared token, either in an `Authorization: Bearer <token>`
+//! header or in a `token` query parameter, e.g. for WebSockets opened from a browser.
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{IpAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+const WS_STATE_PERIOD: Duration = Duration::from_millis(250);
+const MAX_REQUEST_HEADER_LEN: usize = 4096;
+// Commands are small JSON objects
+const MAX_REQUEST_BODY_LEN: usize = 4096;
+// Each connection is handled in its own thread
+const MAX_CONNECTIONS: usize = 8;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RemoteChapter {
+    pub title: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Timestamps are expressed in nanoseconds.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RemoteState {
+    pub file_name: Option<String>,
+    pub is_playing: bool,
+    pub position: u64,
+    pub duration: u64,
+    pub chapters: Vec<RemoteChapter>,
+    pub chapter: Option<usize>,
+    /// `file://` URI of the current media's cover art.
+    pub cover_art_uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    PlayPause,
+    Seek {
+        position: u64,
+    },
+    NextChapter,
+    PreviousChapter,
+    /// `index` is the 0-based position of the chapter in the list.
+    SeekToChapter {
+        index: usize,
+    },
+}
+
+/// Handles the commands received by the remote.
+type CommandHandler = Arc<dyn Fn(RemoteCommand) + Send + Sync>;
+
+// Keeps track of the connections being handled
+struct ConnectionGuard(Arc<AtomicUsize>);
+
+impl ConnectionGuard {
+    fn try_new(count: &Arc<AtomicUsize>) -> Option<Self> {
+        if count.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            count.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+
+        Some(ConnectionGuard(Arc::clone(count)))
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub struct Remote {
+    state: Arc<Mutex<RemoteState>>,
+}
+
+impl Remote {
+    /// Starts serving on `address`:`port`, received commands are passed to `on_command`.
+    ///
+    /// Only the requests holding `token` are served.
+    /// Connections are handled in their own threads.
+    pub fn spawn<F>(address: IpAddr, port: u16, token: String, on_command: F) -> io::Result<Self>
+    where
+        F: Fn(RemoteCommand) + Send + Sync + 'static,
+    {
+        let on_command: CommandHandler = Arc::new(on_command);
+        let listener = TcpListener::bind((address, port))?;
+        info!("remote control listening on {}:{}", address, port);
+
+        let state = Arc::new(Mutex::new(RemoteState::default()));
+        let token: Arc<str> = token.into();
+        let connection_count = Arc::new(AtomicUsize::new(0));
+
+        let state_clone = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let guard = match ConnectionGuard::try_new(&connection_count) {
+                            Some(guard) => guard,
+                            None => {
+                                debug!("remote: too many connections");
+                                continue;
+                            }
+                        };
+
+                        let state = Arc::clone(&state_clone);
+                        let token = Arc::clone(&token);
+                        let on_command = Arc::clone(&on_command);
+                        thread::spawn(move || {
+                            let _guard = guard;
+                            if let Err(err) = handle_connection(stream, &token, &state, &on_command)
+                            {
+                                debug!("remote connection: {}", err);
+                            }
+                        });
+                    }
+                    Err(err) => warn!("remote connection failed: {}", err),
+                }
+            }
+        });
+
+        Ok(Remote { state })
+    }
+
+    pub fn update<F: FnOnce(&mut RemoteState)>(&self, func: F) {
+        func(&mut self.state.lock().unwrap());
+    }
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    token: &str,
+    state: &Mutex<RemoteState>,
+    on_command: &CommandHandler,
+) -> io::Result<()> {
+    // Don't consume the request so that the WebSocket handshake can process it
+    let mut header = [0u8; MAX_REQUEST_HEADER_LEN];
+    let len = stream.peek(&mut header)?;
+    let header = String::from_utf8_lossy(&header[..len]);
+
+    if !is_authorized(&header, token) {
+        return write_response(&mut stream, "401 Unauthorized", "");
+    }
+
+    if header.to_lowercase().contains("upgrade: websocket") {
+        handle_websocket(stream, state, on_command)
+    } else {
+        handle_http(stream, state, on_command)
+    }
+}
+
+// Looks for the token in the `Authorization` header, then in the query
+fn is_authorized(header: &str, token: &str) -> bool {
+    let mut lines = header.lines();
+
+    let target = lines
+        .next()
+        .and_then(|request_line| request_line.split_whitespace().nth(1))
+        .unwrap_or_default();
+    let query_token = target.splitn(2, '?').nth(1).and_then(|query| {
+        query
+            .split('&')
+            .find_map(|param| param.strip_prefix("token="))
+    });
+    if query_token.map_or(false, |query_token| is_same_token(query_token, token)) {
+        return true;
+    }
+
+    lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| {
+            let mut fields = line.splitn(2, ':');
+            match (fields.next(), fields.next()) {
+                (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case("authorization") => {
+                    Some(value.trim())
+                }
+                _ => None,
+            }
+        })
+        .any(|value| {
+            value
+                .strip_prefix("Bearer ")
+                .map_or(false, |bearer| is_same_token(bearer.trim(), token))
+        })
+}
+
+// Compares all the bytes so that the time taken doesn't tell how much of the token matched
+fn is_same_token(candidate: &str, token: &str) -> bool {
+    candidate.len() == token.len()
+        && candidate
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body,
+    )
+}
+
+fn handle_http(
+    mut stream: TcpStream,
+    state: &Mutex<RemoteState>,
+    on_command: &CommandHandler,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut content_len = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        let mut fields = line.splitn(2, ':');
+        if let (Some(name), Some(value)) = (fields.next(), fields.next()) {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_len = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut request = request_line.split_whitespace();
+    let method = request.next();
+    let path = request.next().and_then(|target| target.split('?').next());
+    let (status, body) = match (method, path) {
+        (Some("GET"), Some("/state")) => ("200 OK", to_json(&state.lock().unwrap())),
+        (Some("POST"), Some("/command")) if content_len > MAX_REQUEST_BODY_LEN => {
+            ("413 Payload Too Large", String::new())
+        }
+        (Some("POST"), Some("/command")) => {
+            let mut body = vec![0u8; content_len];
+            reader.read_exact(&mut body)?;
+            match serde_json::from_slice(&body) {
+                Ok(command) => {
+                    on_command(command);
+                    ("204 No Content", String::new())
+                }
+                Err(err) => ("400 Bad Request", err.to_string()),
+            }
+        }
+        _ => ("404 Not Found", String::new()),
+    };
+
+    write_response(&mut stream, status, &body)
+}
+
+fn handle_websocket(
+    stream: TcpStream,
+    state: &Mutex<RemoteState>,
+    on_command: &CommandHandler,
+) -> io::Result<()> {
+    use tungstenite::{Error, Message};
+
+    let mut websocket = tungstenite::accept(stream)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+    // Wake up periodically to push the state
+    websocket
+        .get_ref()
+        .set_read_timeout(Some(WS_STATE_PERIOD))?;
+
+    loop {
+        match websocket.read_message() {
+            Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                Ok(command) => on_command(command),
+                Err(err) => debug!("remote: invalid command {}: {}", text, err),
+            },
+            Ok(Message::Close(_)) | Err(Error::ConnectionClosed) => return Ok(()),
+            Ok(_) => (),
+            Err(Error::Io(ref err))
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::TimedOut => {}
+            Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err.to_string())),
+        }
+
+        let state = to_json(&state.lock().unwrap());
+        websocket
+            .write_message(Message::Text(state))
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+    }
+}
+
+fn to_json(state: &RemoteState) -> String {
+    serde_json::to_string(state).expect("couldn't serialize remote state")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authorization() {
+        let token = "s3cr3t";
+
+        assert!(is_authorized(
+            "GET /state HTTP/1.1\r\nAuthorization: Bearer s3cr3t\r\n\r\n",
+            token
+        ));
+        assert!(is_authorized(
+            "GET /?token=s3cr3t HTTP/1.1\r\nUpgrade: websocket\r\n\r\n",
+            token
+        ));
+        assert!(is_authorized(
+            "GET /state?x=1&token=s3cr3t HTTP/1.1\r\n\r\n",
+            token
+        ));
+
+        assert!(!is_authorized("GET /state HTTP/1.1\r\n\r\n", token));
+        assert!(!is_authorized(
+            "GET /state HTTP/1.1\r\nAuthorization: Bearer s3cr3\r\n\r\n",
+            token
+        ));
+        assert!(!is_authorized(
+            "GET /state?token=S3CR3T HTTP/1.1\r\n\r\n",
+            token
+        ));
+        // Headers in the body are ignored
+        assert!(!is_authorized(
+            "POST /command HTTP/1.1\r\n\r\nAuthorization: Bearer s3cr3t",
+            token
+        ));
+    }
+}
@@ -0,0 +1,516 @@
+use std::fmt;
+
+use crate::{media::Timestamp, metadata::Duration};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChapterTimestamps {
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl ChapterTimestamps {
+    pub fn new_from_u64(start: u64, end: u64) -> Self {
+        ChapterTimestamps {
+            start: Timestamp::new(start),
+            end: Timestamp::new(end),
+        }
+    }
+
+    pub fn contains(&self, ts: Timestamp) -> bool {
+        ts >= self.start && ts < self.end
+    }
+
+    /// Returns the time left in the chapter if `ts` is less than `threshold` before its end.
+    pub fn countdown(&self, ts: Timestamp, threshold: Duration) -> Option<Duration> {
+        if !self.contains(ts) {
+            return None;
+        }
+
+        Some(self.end - ts).filter(|remaining| *remaining <= threshold)
+    }
+}
+
+impl fmt::Display for ChapterTimestamps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "start {}, end {}",
+            self.start.for_humans().to_string(),
+            self.end.for_humans().to_string(),
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChapterEntry {
+    ts: ChapterTimestamps,
+    title: String,
+    artist: Option<String>,
+}
+
+impl ChapterEntry {
+    pub fn new(ts: ChapterTimestamps, title: &str, artist: Option<&str>) -> Self {
+        ChapterEntry {
+            ts,
+            title: title.to_owned(),
+            artist: artist.map(ToOwned::to_owned),
+        }
+    }
+
+    pub fn start(&self) -> Timestamp {
+        self.ts.start
+    }
+
+    pub fn timestamps(&self) -> ChapterTimestamps {
+        self.ts
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Artist or performer of this chapter, if different from the media's.
+    pub fn artist(&self) -> Option<&str> {
+        self.artist.as_deref()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChapterIdxStart {
+    pub idx: usize,
+    pub start: Timestamp,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PositionStatus {
+    ChapterChanged {
+        prev_chapter: Option<ChapterIdxStart>,
+    },
+    ChapterNotChanged,
+}
+
+impl From<Option<ChapterIdxStart>> for PositionStatus {
+    fn from(prev_chapter: Option<ChapterIdxStart>) -> Self {
+        PositionStatus::ChapterChanged { prev_chapter }
+    }
+}
+
+/// A discontinuity between a chapter and the one before it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContinuityIssue {
+    /// Chapter `idx` starts `duration` after the end of the previous chapter.
+    Gap { idx: usize, duration: Duration },
+    /// Chapter `idx` starts `duration` before the end of the previous chapter.
+    Overlap { idx: usize, duration: Duration },
+}
+
+impl ContinuityIssue {
+    pub fn idx(&self) -> usize {
+        match *self {
+            ContinuityIssue::Gap { idx, .. } => idx,
+            ContinuityIssue::Overlap { idx, .. } => idx,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ChapterTree {
+    chapters: Vec<ChapterEntry>,
+    iter: Option<usize>,
+    selected: Option<usize>,
+}
+
+impl ChapterTree {
+    pub fn clear(&mut self) {
+        self.selected = None;
+        self.iter = None;
+        self.chapters.clear();
+    }
+
+    pub fn unselect(&mut self) {
+        self.selected = None;
+    }
+
+    pub fn add(&mut self, ts: ChapterTimestamps, title: &str, artist: Option<&str>) -> usize {
+        self.chapters.push(ChapterEntry::new(ts, title, artist));
+        self.chapters.len() - 1
+    }
+
+    pub fn rename(&mut self, idx: usize, title: &str) {
+        if let Some(chapter) = self.chapters.get_mut(idx) {
+            chapter.title = title.to_owned();
+        }
+    }
+
+    pub fn rewind(&mut self) {
+        self.iter = if self.chapters.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+
+        self.selected = self.chapters.first().and_then(|first_chapter| {
+            if first_chapter.start() == Timestamp::default() {
+                Some(0)
+            } else {
+                None
+            }
+        });
+    }
+
+    pub fn chapter(&self, idx: usize) -> Option<&ChapterEntry> {
+        self.chapters.get(idx)
+    }
+
+    pub fn len(&self) -> usize {
+        self.chapters.len()
+    }
+
+    pub fn first(&self) -> Option<&ChapterEntry> {
+        self.chapters.first()
+    }
+
+    pub fn last(&self) -> Option<&ChapterEntry> {
+        self.chapters.last()
+    }
+
+    pub fn selected_idx(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn selected_chapter(&self) -> Option<&ChapterEntry> {
+        self.selected.map(|idx| &self.chapters[idx])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'_ ChapterEntry> {
+        self.chapters.iter()
+    }
+
+    pub fn continuity_issues(&self) -> Vec<ContinuityIssue> {
+        self.chapters
+            .windows(2)
+            .enumerate()
+            .filter_map(|(prev_idx, pair)| {
+                let prev_end = pair[0].timestamps().end.as_u64();
+                let start = pair[1].start().as_u64();
+                let idx = prev_idx + 1;
+                if start > prev_end {
+                    Some(ContinuityIssue::Gap {
+                        idx,
+                        duration: Duration::from_nanos(start - prev_end),
+                    })
+                } else if start < prev_end {
+                    Some(ContinuityIssue::Overlap {
+                        idx,
+                        duration: Duration::from_nanos(prev_end - start),
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn pick_next(&self) -> Option<&ChapterEntry> {
+        match self.selected {
+            // FIXME: with hierarchical tocs, this might be a case where
+            // we should check whether the parent node contains something
+            Some(selected) => self.chapters.get(selected + 1),
+            None => self.chapters.first(),
+        }
+    }
+
+    pub fn pick_previous(&self) -> Option<&ChapterEntry> {
+        match self.selected {
+            // FIXME: with hierarchical tocs, this might be a case where
+            // we should check whether the parent node contains something
+            Some(selected) if selected > 0 => self.chapters.get(selected - 1),
+            Some(_) => None,
+            None => self.chapters.last(),
+        }
+    }
+
+    pub fn select_by_ts(&mut self, ts: Timestamp) -> PositionStatus {
+        let prev_sel_chapter = match self.selected_chapter().map(ChapterEntry::timestamps) {
+            Some(sel_ts) => {
+                if sel_ts.contains(ts) {
+                    // regular case: current timestamp in current chapter => don't change anything
+                    // this check is here to save time in the most frequent case
+                    return PositionStatus::ChapterNotChanged;
+                }
+
+                Some(ChapterIdxStart {
+                    idx: self.selected.take().unwrap(),
+                    start: sel_ts.start,
+                })
+            }
+            None => None,
+        };
+
+        if let Some(mut idx) = self.iter {
+            // not in selected chapter or selected chapter not defined yet
+            // => search for a chapter matching current ts
+            let mut searching_forward = true;
+            loop {
+                let iter_ts = self.chapters[idx].timestamps();
+                if iter_ts.contains(ts) {
+                    // current timestamp is in current chapter
+                    self.iter = Some(idx);
+                    self.selected = Some(idx);
+                    // ChapterChanged
+                    return prev_sel_chapter.into();
+                } else if ts >= iter_ts.end && searching_forward {
+                    // current timestamp is after iter and we were already searching forward
+                    if idx + 1 < self.chapters.len() {
+                        idx += 1;
+                    } else {
+                        // No more chapter => keep track of last iter:
+                        // in case of a seek back, we'll start from here
+                        break;
+                    }
+                } else if ts < iter_ts.start {
+                    // current timestamp before iter
+                    searching_forward = false;
+                    if idx > 0 {
+                        idx -= 1;
+                    } else {
+                        // before first chapter
+                        self.iter = Some(0);
+                        // ChapterChanged
+                        return prev_sel_chapter.into();
+                    }
+                } else {
+                    // in a gap between two chapters
+                    break;
+                }
+            }
+
+            self.iter = Some(idx);
+        }
+
+        // Couldn't find a chapter to select
+        // consider that the chapter changed only if a chapter was selected before
+        match prev_sel_chapter {
+            Some(prev_sel_chapter) => Some(prev_sel_chapter).into(),
+            None => PositionStatus::ChapterNotChanged,
+        }
+    }
+
+    pub fn select_at_end(&mut self, end_ts: Timestamp) -> PositionStatus {
+        let position_status = self.select_by_ts(end_ts);
+        if self.selected.is_some() {
+            return position_status;
+        }
+
+        // The end of a chapter is excluded from its range,
+        // but the end of the media belongs to the last chapter
+        let last_idx = match self.chapters.len() {
+            0 => return position_status,
+            len => len - 1,
+        };
+
+        let last_ts = self.chapters[last_idx].timestamps();
+        if end_ts < last_ts.start || end_ts > last_ts.end {
+            return position_status;
+        }
+
+        self.iter = Some(last_idx);
+        self.selected = Some(last_idx);
+
+        match position_status {
+            PositionStatus::ChapterChanged { prev_chapter } => prev_chapter.into(),
+            PositionStatus::ChapterNotChanged => None.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(secs: u64) -> Timestamp {
+        Timestamp::new(secs * 1_000_000_000)
+    }
+
+    fn new_tree(bounds: &[(u64, u64)]) -> ChapterTree {
+        let mut tree = ChapterTree::default();
+        for (idx, (start, end)) in bounds.iter().enumerate() {
+            tree.add(
+                ChapterTimestamps {
+                    start: ts(*start),
+                    end: ts(*end),
+                },
+                &format!("{:02}", idx),
+                None,
+            );
+        }
+        tree.rewind();
+        tree
+    }
+
+    fn prev(idx: usize, start: u64) -> PositionStatus {
+        Some(ChapterIdxStart {
+            idx,
+            start: ts(start),
+        })
+        .into()
+    }
+
+    fn changed_from_none() -> PositionStatus {
+        PositionStatus::ChapterChanged { prev_chapter: None }
+    }
+
+    #[test]
+    fn countdown() {
+        let chapter = ChapterTimestamps {
+            start: ts(10),
+            end: ts(20),
+        };
+        let threshold = Duration::from_secs(5);
+
+        assert!(chapter.countdown(ts(5), threshold).is_none());
+        assert!(chapter.countdown(ts(14), threshold).is_none());
+        assert_eq!(
+            Some(Duration::from_secs(5)),
+            chapter.countdown(ts(15), threshold)
+        );
+        assert_eq!(
+            Some(Duration::from_secs(1)),
+            chapter.countdown(ts(19), threshold)
+        );
+        assert!(chapter.countdown(ts(20), threshold).is_none());
+    }
+
+    #[test]
+    fn empty() {
+        let mut tree = new_tree(&[]);
+        assert!(tree.iter().next().is_none());
+        assert!(tree.selected_chapter().is_none());
+        assert_eq!(PositionStatus::ChapterNotChanged, tree.select_by_ts(ts(1)));
+        assert_eq!(PositionStatus::ChapterNotChanged, tree.select_at_end(ts(1)));
+        assert!(tree.pick_next().is_none());
+        assert!(tree.pick_previous().is_none());
+    }
+
+    #[test]
+    fn rewind() {
+        let tree = new_tree(&[(0, 1), (1, 2)]);
+        assert_eq!(Some(0), tree.selected_idx());
+
+        // first chapter doesn't start at the beginning of the media
+        let tree = new_tree(&[(1, 2), (2, 3)]);
+        assert!(tree.selected_idx().is_none());
+    }
+
+    #[test]
+    fn select_by_ts_forward_backward() {
+        let mut tree = new_tree(&[(0, 2), (2, 4), (4, 6)]);
+
+        assert_eq!(PositionStatus::ChapterNotChanged, tree.select_by_ts(ts(1)));
+        assert_eq!(Some(0), tree.selected_idx());
+
+        assert_eq!(prev(0, 0), tree.select_by_ts(ts(2)));
+        assert_eq!(Some(1), tree.selected_idx());
+
+        // seek forward over a chapter
+        assert_eq!(prev(1, 2), tree.select_by_ts(ts(5)));
+        assert_eq!(Some(2), tree.selected_idx());
+
+        // past the last chapter
+        assert_eq!(prev(2, 4), tree.select_by_ts(ts(7)));
+        assert!(tree.selected_idx().is_none());
+        assert_eq!(PositionStatus::ChapterNotChanged, tree.select_by_ts(ts(8)));
+
+        // seek back
+        assert_eq!(changed_from_none(), tree.select_by_ts(ts(1)));
+        assert_eq!(Some(0), tree.selected_idx());
+        assert_eq!("00", tree.selected_chapter().unwrap().title());
+    }
+
+    #[test]
+    fn select_by_ts_gaps() {
+        let mut tree = new_tree(&[(1, 2), (3, 4)]);
+        assert!(tree.selected_idx().is_none());
+
+        // before first chapter
+        assert_eq!(changed_from_none(), tree.select_by_ts(ts(0)));
+        assert!(tree.selected_idx().is_none());
+
+        assert_eq!(changed_from_none(), tree.select_by_ts(ts(1)));
+        assert_eq!(Some(0), tree.selected_idx());
+
+        // in the gap
+        assert_eq!(prev(0, 1), tree.select_by_ts(ts(2)));
+        assert!(tree.selected_idx().is_none());
+        assert_eq!(
+            PositionStatus::ChapterNotChanged,
+            tree.select_by_ts(Timestamp::new(2_500_000_000)),
+        );
+
+        assert_eq!(changed_from_none(), tree.select_by_ts(ts(3)));
+        assert_eq!(Some(1), tree.selected_idx());
+    }
+
+    #[test]
+    fn select_at_end() {
+        let mut tree = new_tree(&[(0, 2), (2, 4)]);
+
+        assert_eq!(prev(0, 0), tree.select_at_end(ts(4)));
+        assert_eq!(Some(1), tree.selected_idx());
+
+        // already selected
+        assert_eq!(prev(1, 2), tree.select_at_end(ts(4)));
+        assert_eq!(Some(1), tree.selected_idx());
+
+        // last chapter doesn't reach the end
+        let mut tree = new_tree(&[(0, 2), (2, 3)]);
+        assert_eq!(prev(0, 0), tree.select_at_end(ts(4)));
+        assert!(tree.selected_idx().is_none());
+    }
+
+    #[test]
+    fn pick_next_previous() {
+        let mut tree = new_tree(&[(0, 2), (2, 4), (4, 6)]);
+
+        assert_eq!(ts(2), tree.pick_next().unwrap().start());
+        assert!(tree.pick_previous().is_none());
+
+        tree.select_by_ts(ts(3));
+        assert_eq!(ts(4), tree.pick_next().unwrap().start());
+        assert_eq!(ts(0), tree.pick_previous().unwrap().start());
+
+        tree.select_by_ts(ts(5));
+        assert!(tree.pick_next().is_none());
+        assert_eq!(ts(2), tree.pick_previous().unwrap().start());
+
+        tree.unselect();
+        assert_eq!(ts(0), tree.pick_next().unwrap().start());
+        assert_eq!(ts(4), tree.pick_previous().unwrap().start());
+
+        assert_eq!(ts(0), tree.first().unwrap().start());
+        assert_eq!(ts(4), tree.last().unwrap().start());
+        assert!(ChapterTree::default().last().is_none());
+    }
+
+    #[test]
+    fn continuity_issues() {
+        let tree = new_tree(&[(0, 2), (2, 4), (4, 6)]);
+        assert!(tree.continuity_issues().is_empty());
+
+        let tree = new_tree(&[(0, 2), (3, 4), (4, 6), (5, 7)]);
+        assert_eq!(
+            vec![
+                ContinuityIssue::Gap {
+                    idx: 1,
+                    duration: Duration::from_secs(1),
+                },
+                ContinuityIssue::Overlap {
+                    idx: 3,
+                    duration: Duration::from_secs(1),
+                },
+            ],
+            tree.continuity_issues(),
+        );
+        assert_eq!(3, tree.continuity_issues()[1].idx());
+    }
+}
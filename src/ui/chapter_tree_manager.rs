@@ -4,510 +4,564 @@ use gettextrs::gettext;
 
 use gtk::prelude::*;
 
-use std::{borrow::Cow, fmt};
-
 use crate::{
+    application::CONFIG,
     media::Timestamp,
-    metadata::{get_default_chapter_title, TocVisitor},
+    metadata::{get_default_chapter_title, Duration, TocBuilder, TocChapter},
 };
 
+use super::{ChapterEntry, ChapterTimestamps, ChapterTree, ContinuityIssue, PositionStatus};
+
 const START_COL: u32 = 0;
 const END_COL: u32 = 1;
 const TITLE_COL: u32 = 2;
 const START_STR_COL: u32 = 3;
 const END_STR_COL: u32 = 4;
+const ISSUE_ICON_COL: u32 = 5;
+const ARTIST_COL: u32 = 6;
+const TITLE_MARKUP_COL: u32 = 7;
+const IDX_COL: u32 = 8;
 
-#[derive(Clone, Copy, Debug)]
-pub struct ChapterTimestamps {
-    pub start: Timestamp,
-    pub end: Timestamp,
-}
-
-impl ChapterTimestamps {
-    pub fn new_from_u64(start: u64, end: u64) -> Self {
-        ChapterTimestamps {
-            start: Timestamp::new(start),
-            end: Timestamp::new(end),
-        }
-    }
-}
-
-impl fmt::Display for ChapterTimestamps {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "start {}, end {}",
-            self.start.for_humans().to_string(),
-            self.end.for_humans().to_string(),
-        )
-    }
-}
-
-pub struct ChapterIterStart {
-    pub iter: gtk::TreeIter,
-    pub start: Timestamp,
-}
-
-pub enum PositionStatus {
-    ChapterChanged {
-        prev_chapter: Option<ChapterIterStart>,
-    },
-    ChapterNotChanged,
-}
-
-impl From<Option<ChapterIterStart>> for PositionStatus {
-    fn from(prev_chapter: Option<ChapterIterStart>) -> Self {
-        PositionStatus::ChapterChanged { prev_chapter }
-    }
-}
+const ISSUE_ICON_NAME: &str = "dialog-warning-symbolic";
+// Shows the nesting level of the chapters in the flat view
+const FLAT_INDENT: &str = "    ";
 
 bitflags! {
     struct ColumnOptions: u32 {
         const NONE = 0b0000_0000;
         const CAN_EXPAND = 0b0000_0001;
+        const MARKUP = 0b0000_0010;
     }
 }
 
-#[derive(Clone)]
-pub struct ChapterEntry<'entry> {
-    store: &'entry gtk::TreeStore,
-    iter: Cow<'entry, gtk::TreeIter>,
+// A row in the chapters list: either a chapter or a group of nested chapters
+struct ChapterRow {
+    depth: usize,
+    // Index in the `ChapterTree` of the chapter or of the first chapter in the group
+    idx: usize,
+    is_group: bool,
+    ts: Option<ChapterTimestamps>,
+    title: String,
+    title_markup: String,
+    artist: Option<String>,
 }
 
-impl<'entry> ChapterEntry<'entry> {
-    fn new(store: &'entry gtk::TreeStore, iter: &'entry gtk::TreeIter) -> ChapterEntry<'entry> {
-        ChapterEntry {
-            store,
-            iter: Cow::Borrowed(iter),
-        }
-    }
+pub struct ChapterTreeManager {
+    store: gtk::TreeStore,
+    tree: ChapterTree,
+    // Only the nested chapters without children are part of the `tree`
+    rows: Vec<ChapterRow>,
+    // The path of each chapter in the `tree` as currently displayed
+    paths: Vec<gtk::TreePath>,
+    is_flat: bool,
+    continuity_issues: Vec<ContinuityIssue>,
+    treeview: Option<gtk::TreeView>,
+    title_renderer: gtk::CellRendererText,
+    // Built along with the other columns, when the first chapters are displayed
+    artist_col: Option<gtk::TreeViewColumn>,
+}
 
-    fn new_owned(store: &'entry gtk::TreeStore, iter: gtk::TreeIter) -> ChapterEntry<'entry> {
-        ChapterEntry {
+impl ChapterTreeManager {
+    pub fn new(store: gtk::TreeStore) -> Self {
+        ChapterTreeManager {
             store,
-            iter: Cow::Owned(iter),
+            tree: ChapterTree::default(),
+            rows: Vec::new(),
+            paths: Vec::new(),
+            is_flat: CONFIG.read().unwrap().ui.is_chapter_tree_flat,
+            continuity_issues: Vec::new(),
+            treeview: None,
+            title_renderer: gtk::CellRendererText::new(),
+            artist_col: None,
         }
     }
 
-    pub fn iter(&self) -> &gtk::TreeIter {
-        self.iter.as_ref()
-    }
+    pub fn init_treeview(&mut self, treeview: &gtk::TreeView, search_entry: &gtk::SearchEntry) {
+        treeview.set_model(Some(&self.store));
+        self.treeview = Some(treeview.clone());
 
-    pub fn start(&self) -> Timestamp {
-        self.store
-            .get_value(&self.iter, START_COL as i32)
-            .get_some::<u64>()
-            .unwrap()
-            .into()
+        // Type-ahead search on the chapter titles
+        treeview.set_enable_search(true);
+        treeview.set_search_column(TITLE_COL as i32);
+        treeview.set_search_entry(Some(search_entry));
     }
 
-    pub fn end(&self) -> Timestamp {
-        self.store
-            .get_value(&self.iter, END_COL as i32)
-            .get_some::<u64>()
-            .unwrap()
-            .into()
-    }
+    // Building the columns is deferred since many media have no chapters.
+    fn init_columns(&mut self) {
+        let treeview = match self.treeview.as_ref() {
+            Some(treeview) if self.artist_col.is_none() => treeview,
+            _ => return,
+        };
 
-    pub fn timestamps(&self) -> ChapterTimestamps {
-        ChapterTimestamps {
-            start: self.start(),
-            end: self.end(),
-        }
+        let issue_col = gtk::TreeViewColumn::new();
+        let issue_renderer = gtk::CellRendererPixbuf::new();
+        issue_col.pack_start(&issue_renderer, false);
+        issue_col.add_attribute(&issue_renderer, "icon-name", ISSUE_ICON_COL as i32);
+        treeview.append_column(&issue_col);
+
+        let title_col = Self::add_column(
+            treeview,
+            &gettext("Title"),
+            TITLE_MARKUP_COL,
+            ColumnOptions::CAN_EXPAND | ColumnOptions::MARKUP,
+            &self.title_renderer,
+        );
+        // Nested chapters expanders go with the titles
+        treeview.set_expander_column(Some(&title_col));
+        // Only visible when a chapter defines an artist
+        let artist_col = Self::add_column(
+            treeview,
+            &gettext("Artist"),
+            ARTIST_COL,
+            ColumnOptions::CAN_EXPAND,
+            &gtk::CellRendererText::new(),
+        );
+        artist_col.set_visible(self.rows.iter().any(|row| row.artist.is_some()));
+        Self::add_column(
+            treeview,
+            &gettext("Start"),
+            START_STR_COL,
+            ColumnOptions::NONE,
+            &gtk::CellRendererText::new(),
+        );
+        Self::add_column(
+            treeview,
+            &gettext("End"),
+            END_STR_COL,
+            ColumnOptions::NONE,
+            &gtk::CellRendererText::new(),
+        );
+
+        self.artist_col = Some(artist_col);
     }
-}
 
-struct ChapterTree {
-    store: gtk::TreeStore,
-    iter: Option<gtk::TreeIter>,
-    selected: Option<gtk::TreeIter>,
-}
+    fn add_column(
+        treeview: &gtk::TreeView,
+        title: &str,
+        col_id: u32,
+        options: ColumnOptions,
+        renderer: &gtk::CellRendererText,
+    ) -> gtk::TreeViewColumn {
+        let col = gtk::TreeViewColumn::new();
+        col.set_title(title);
 
-impl ChapterTree {
-    fn new(store: gtk::TreeStore) -> Self {
-        ChapterTree {
-            store,
-            iter: None,
-            selected: None,
+        col.pack_start(renderer, true);
+        let attribute = if options.contains(ColumnOptions::MARKUP) {
+            "markup"
+        } else {
+            "text"
+        };
+        col.add_attribute(renderer, attribute, col_id as i32);
+        if options.contains(ColumnOptions::CAN_EXPAND) {
+            col.set_min_width(70);
+            col.set_expand(true);
+        } else {
+            // align right
+            renderer.set_property_xalign(1f32);
         }
-    }
+        treeview.append_column(&col);
 
-    fn store(&self) -> &gtk::TreeStore {
-        &self.store
+        col
     }
 
-    fn clear(&mut self) {
-        self.selected = None;
-        self.iter = None;
-        self.store.clear();
+    pub fn title_renderer(&self) -> &gtk::CellRendererText {
+        &self.title_renderer
     }
 
-    fn unselect(&mut self) {
-        self.selected = None;
+    /// Allows the user to edit the titles of the chapters.
+    pub fn set_titles_editable(&self, is_editable: bool) {
+        self.title_renderer.set_property_editable(is_editable);
     }
 
-    fn rewind(&mut self) {
-        self.iter = self.store.get_iter_first();
-        self.selected = match &self.iter_chapter() {
-            Some(first_chapter) => {
-                if first_chapter.start() == Timestamp::default() {
-                    self.iter.clone()
-                } else {
-                    None
-                }
-            }
-            None => None,
+    /// Renames the chapter at `tree_path`.
+    ///
+    /// Returns `false` if the title is unchanged or if there is no chapter
+    /// at `tree_path`, e.g. for a group of nested chapters.
+    pub fn rename(&mut self, tree_path: &gtk::TreePath, title: &str) -> bool {
+        let indices = tree_path.get_indices();
+        let idx = match self
+            .paths
+            .iter()
+            .position(|path| path.get_indices() == indices)
+        {
+            Some(idx) => idx,
+            None => return false,
         };
-    }
+        let iter = match self.store.get_iter(tree_path) {
+            Some(iter) => iter,
+            None => return false,
+        };
+        let row = match self
+            .rows
+            .iter_mut()
+            .find(|row| !row.is_group && row.idx == idx)
+        {
+            Some(row) if row.title != title => row,
+            _ => return false,
+        };
+
+        self.tree.rename(idx, title);
+
+        row.title = title.to_owned();
+        row.title_markup = glib::markup_escape_text(title).to_string();
+        let title_markup = if self.is_flat {
+            format!("{}{}", FLAT_INDENT.repeat(row.depth), row.title_markup)
+        } else {
+            row.title_markup.clone()
+        };
+        self.store.set(
+            &iter,
+            &[TITLE_COL, TITLE_MARKUP_COL],
+            &[&row.title, &title_markup],
+        );
 
-    fn chapter_from_path(&self, tree_path: &gtk::TreePath) -> Option<ChapterEntry<'_>> {
-        self.store
-            .get_iter(tree_path)
-            .map(|iter| ChapterEntry::new_owned(&self.store, iter))
+        true
     }
 
-    fn selected_chapter(&self) -> Option<ChapterEntry<'_>> {
-        self.selected
-            .as_ref()
-            .map(|selected| ChapterEntry::new(&self.store, selected))
+    /// Path of the chapter at `idx` as currently displayed.
+    pub fn path(&self, idx: usize) -> Option<gtk::TreePath> {
+        self.paths.get(idx).cloned()
     }
 
-    fn selected_timestamps(&self) -> Option<ChapterTimestamps> {
-        self.selected_chapter().map(|chapter| chapter.timestamps())
+    pub fn selected(&self) -> Option<&ChapterEntry> {
+        self.tree.selected_chapter()
     }
 
-    fn selected_path(&self) -> Option<gtk::TreePath> {
-        self.selected
-            .as_ref()
-            .and_then(|sel_iter| self.store.get_path(sel_iter))
+    pub fn selected_idx(&self) -> Option<usize> {
+        self.tree.selected_idx()
     }
 
-    fn iter_chapter(&self) -> Option<ChapterEntry<'_>> {
-        self.iter
-            .as_ref()
-            .map(|iter| ChapterEntry::new(&self.store, iter))
+    pub fn selected_path(&self) -> Option<gtk::TreePath> {
+        self.tree.selected_idx().and_then(|idx| self.path(idx))
     }
 
-    fn iter_timestamps(&self) -> Option<ChapterTimestamps> {
-        self.iter_chapter().map(|chapter| chapter.timestamps())
+    /// The chapter at `tree_path` or the first chapter of the group at `tree_path`.
+    pub fn chapter_from_path(&self, tree_path: &gtk::TreePath) -> Option<&ChapterEntry> {
+        let iter = self.store.get_iter(tree_path)?;
+        let idx = self
+            .store
+            .get_value(&iter, IDX_COL as i32)
+            .get_some::<u32>()
+            .ok()?;
+        self.tree.chapter(idx as usize)
     }
 
-    fn new_iter(&self) -> Iter<'_> {
-        Iter::new(&self.store)
+    pub fn unselect(&mut self) {
+        self.tree.unselect();
     }
 
-    fn next(&mut self) -> Option<ChapterEntry<'_>> {
-        match self.iter.take() {
-            Some(iter) => {
-                if self.store.iter_next(&iter) {
-                    self.iter = Some(iter);
-                    let store = &self.store;
-                    self.iter
-                        .as_ref()
-                        .map(|iter| ChapterEntry::new(store, iter))
-                } else {
-                    None
-                }
-            }
-            None => None,
+    pub fn clear(&mut self) {
+        self.tree.clear();
+        self.rows.clear();
+        self.paths.clear();
+        self.store.clear();
+        self.continuity_issues.clear();
+        if let Some(artist_col) = self.artist_col.as_ref() {
+            artist_col.set_visible(false);
         }
     }
 
-    fn pick_next(&self) -> Option<ChapterEntry<'_>> {
-        match self.selected.as_ref() {
-            Some(selected) => {
-                let iter = selected.clone();
-                if self.store.iter_next(&iter) {
-                    Some(ChapterEntry::new_owned(&self.store, iter))
-                } else {
-                    // FIXME: with hierarchical tocs, this might be a case where
-                    // we should check whether the parent node contains something
-                    None
-                }
-            }
-            None => self
-                .store
-                .get_iter_first()
-                .map(|first_iter| ChapterEntry::new_owned(&self.store, first_iter)),
-        }
+    /// Whether some chapters contain nested chapters.
+    pub fn is_nested(&self) -> bool {
+        self.rows.iter().any(|row| row.is_group)
     }
 
-    fn previous(&mut self) -> Option<ChapterEntry<'_>> {
-        match self.iter.take() {
-            Some(iter) => {
-                if self.store.iter_previous(&iter) {
-                    self.iter = Some(iter);
-                    let store = &self.store;
-                    self.iter
-                        .as_ref()
-                        .map(|iter| ChapterEntry::new(store, iter))
-                } else {
-                    None
-                }
-            }
-            None => None,
-        }
+    pub fn is_flat(&self) -> bool {
+        self.is_flat
     }
 
-    fn pick_previous(&self) -> Option<ChapterEntry<'_>> {
-        match self.selected.as_ref() {
-            Some(selected) => {
-                let prev_iter = selected.clone();
-                if self.store.iter_previous(&prev_iter) {
-                    Some(ChapterEntry::new_owned(&self.store, prev_iter))
-                } else {
-                    // FIXME: with hierarchical tocs, this might be a case where
-                    // we should check whether the parent node contains something
-                    None
+    /// Shows the nested chapters in sequential order or as a tree.
+    ///
+    /// The selected chapter is kept, but the path to it might change.
+    pub fn set_flat(&mut self, is_flat: bool) {
+        if self.is_flat == is_flat {
+            return;
+        }
+
+        self.is_flat = is_flat;
+        self.fill_store();
+        self.flag_continuity_issues();
+    }
+
+    fn add_entries(&mut self, entries: Vec<gst::TocEntry>, depth: usize) {
+        for entry in entries {
+            match entry.get_entry_type() {
+                gst::TocEntryType::Edition => self.add_entries(entry.get_sub_entries(), depth),
+                gst::TocEntryType::Chapter => {
+                    let tags = entry.get_tags();
+                    let title = tags.as_ref().and_then(|tags| {
+                        tags.get::<gst::tags::Title>()
+                            .and_then(|tag| tag.get().map(ToString::to_string))
+                    });
+                    let artist = tags.as_ref().and_then(|tags| {
+                        tags.get::<gst::tags::Artist>()
+                            .and_then(|tag| tag.get().map(ToString::to_string))
+                            .or_else(|| {
+                                tags.get::<gst::tags::Performer>()
+                                    .and_then(|tag| tag.get().map(ToString::to_string))
+                            })
+                    });
+                    let ts = entry.get_start_stop_times().map(|(start, end)| {
+                        ChapterTimestamps::new_from_u64(start as u64, end as u64)
+                    });
+
+                    let sub_entries = entry.get_sub_entries();
+                    if sub_entries.is_empty() {
+                        if let Some(ts) = ts {
+                            self.add_unchecked(ts, title, artist, depth);
+                        }
+                    } else {
+                        self.add_row(self.tree.len(), true, ts, title, artist, depth);
+                        self.add_entries(sub_entries, depth + 1);
+                    }
                 }
+                _ => (),
             }
-            None => self.store.get_iter_first().map(|iter| {
-                let mut last_iter = iter.clone();
-                while self.store.iter_next(&iter) {
-                    last_iter = iter.clone();
-                }
-                ChapterEntry::new_owned(&self.store, last_iter)
-            }),
         }
     }
 
-    fn add_unchecked(&self, ts: ChapterTimestamps, title: &str) -> gtk::TreeIter {
-        self.store.insert_with_values(
-            None,
-            None,
-            &[START_COL, END_COL, TITLE_COL, START_STR_COL, END_STR_COL],
-            &[
-                &ts.start.as_u64(),
-                &ts.end.as_u64(),
-                &title,
-                &ts.start.for_humans().to_string(),
-                &ts.end.for_humans().to_string(),
-            ],
-        )
-    }
-
-    fn select_by_ts(&mut self, ts: Timestamp) -> PositionStatus {
-        let prev_sel_chapter = match self.selected_timestamps() {
-            Some(sel_ts) => {
-                if ts >= sel_ts.start && ts < sel_ts.end {
-                    // regular case: current timestamp in current chapter => don't change anything
-                    // this check is here to save time in the most frequent case
-                    return PositionStatus::ChapterNotChanged;
-                }
-
-                assert!(self.selected.is_some());
-                Some(ChapterIterStart {
-                    iter: self.selected.take().unwrap(),
-                    start: sel_ts.start,
-                })
+    fn add_unchecked(
+        &mut self,
+        ts: ChapterTimestamps,
+        title: Option<String>,
+        artist: Option<String>,
+        depth: usize,
+    ) {
+        let idx = self.tree.add(
+            ts,
+            title.as_deref().unwrap_or(&get_default_chapter_title()),
+            artist.as_deref(),
+        );
+        self.add_row(idx, false, Some(ts), title, artist, depth);
+    }
+
+    fn add_row(
+        &mut self,
+        idx: usize,
+        is_group: bool,
+        ts: Option<ChapterTimestamps>,
+        title: Option<String>,
+        artist: Option<String>,
+        depth: usize,
+    ) {
+        // Titles are user provided, they must not be interpreted as markup
+        let (title, title_markup) = match title {
+            Some(title) => {
+                let title_markup = glib::markup_escape_text(&title).to_string();
+                (title, title_markup)
+            }
+            None => {
+                let title = get_default_chapter_title();
+                let title_markup = format!("<i>{}</i>", glib::markup_escape_text(&title));
+                (title, title_markup)
             }
-            None => None,
         };
 
-        if self.iter.is_some() {
-            // not in selected_iter or selected_iter not defined yet
-            // => search for a chapter matching current ts
-            let mut searching_forward = true;
-            loop {
-                let iter_ts = self.iter_timestamps().expect("couldn't get start & end");
-                if ts >= iter_ts.start && ts < iter_ts.end {
-                    // current timestamp is in current chapter
-                    self.selected = self.iter.clone();
-                    // ChapterChanged
-                    return prev_sel_chapter.into();
-                } else if ts >= iter_ts.end && searching_forward {
-                    // current timestamp is after iter and we were already searching forward
-                    let cur_iter = self.iter.clone();
-                    self.next();
-                    if self.iter.is_none() {
-                        // No more chapter => keep track of last iter:
-                        // in case of a seek back, we'll start from here
-                        self.iter = cur_iter;
-                        break;
-                    }
-                } else if ts < iter_ts.start {
-                    // current timestamp before iter
-                    searching_forward = false;
-                    self.previous();
-                    if self.iter.is_none() {
-                        // before first chapter
-                        self.iter = self.store.get_iter_first();
-                        // ChapterChanged
-                        return prev_sel_chapter.into();
-                    }
-                } else {
-                    // in a gap between two chapters
-                    break;
-                }
+        if artist.is_some() {
+            if let Some(artist_col) = self.artist_col.as_ref() {
+                artist_col.set_visible(true);
             }
         }
 
-        // Couldn't find a chapter to select
-        // consider that the chapter changed only if a chapter was selected before
-        match prev_sel_chapter {
-            Some(prev_sel_chapter) => Some(prev_sel_chapter).into(),
-            None => PositionStatus::ChapterNotChanged,
-        }
+        self.rows.push(ChapterRow {
+            depth,
+            idx,
+            is_group,
+            ts,
+            title,
+            title_markup,
+            artist,
+        });
     }
-}
 
-pub struct ChapterTreeManager {
-    tree: ChapterTree,
-}
+    // Keep the store in sync with the chapter rows
+    fn fill_store(&mut self) {
+        self.store.clear();
+        self.paths.clear();
 
-impl ChapterTreeManager {
-    pub fn new(store: gtk::TreeStore) -> Self {
-        ChapterTreeManager {
-            tree: ChapterTree::new(store),
+        if self.rows.is_empty() {
+            return;
         }
-    }
-
-    pub fn init_treeview(&mut self, treeview: &gtk::TreeView) {
-        treeview.set_model(Some(self.tree.store()));
-        self.add_column(
-            treeview,
-            &gettext("Title"),
-            TITLE_COL,
-            ColumnOptions::CAN_EXPAND,
-        );
-        self.add_column(
-            treeview,
-            &gettext("Start"),
-            START_STR_COL,
-            ColumnOptions::NONE,
-        );
-        self.add_column(treeview, &gettext("End"), END_STR_COL, ColumnOptions::NONE);
-    }
-
-    fn add_column(
-        &self,
-        treeview: &gtk::TreeView,
-        title: &str,
-        col_id: u32,
-        options: ColumnOptions,
-    ) -> gtk::CellRendererText {
-        let col = gtk::TreeViewColumn::new();
-        col.set_title(title);
-
-        let renderer = gtk::CellRendererText::new();
-
-        col.pack_start(&renderer, true);
-        col.add_attribute(&renderer, "text", col_id as i32);
-        if options.contains(ColumnOptions::CAN_EXPAND) {
-            col.set_min_width(70);
-            col.set_expand(true);
-        } else {
-            // align right
-            renderer.set_property_xalign(1f32);
+        self.init_columns();
+
+        let timestamp_display = CONFIG.read().unwrap().ui.timestamp_display();
+        // The last group at each depth, parent to the rows below it
+        let mut groups: Vec<gtk::TreeIter> = Vec::new();
+        for row in &self.rows {
+            groups.truncate(row.depth);
+            let (parent, title_markup) = if self.is_flat {
+                let indent = FLAT_INDENT.repeat(row.depth);
+                (None, format!("{}{}", indent, row.title_markup))
+            } else {
+                (groups.last().cloned(), row.title_markup.clone())
+            };
+
+            let (start, end, start_str, end_str) = match row.ts {
+                Some(ts) => (
+                    ts.start.as_u64(),
+                    ts.end.as_u64(),
+                    ts.start.for_humans().display(timestamp_display),
+                    ts.end.for_humans().display(timestamp_display),
+                ),
+                None => (0, 0, String::new(), String::new()),
+            };
+
+            let iter = self.store.insert_with_values(
+                parent.as_ref(),
+                None,
+                &[
+                    START_COL,
+                    END_COL,
+                    TITLE_COL,
+                    START_STR_COL,
+                    END_STR_COL,
+                    ARTIST_COL,
+                    TITLE_MARKUP_COL,
+                    IDX_COL,
+                ],
+                &[
+                    &start,
+                    &end,
+                    &row.title,
+                    &start_str,
+                    &end_str,
+                    &row.artist,
+                    &title_markup,
+                    &(row.idx as u32),
+                ],
+            );
+
+            if row.is_group {
+                groups.push(iter);
+            } else {
+                self.paths.push(self.store.get_path(&iter).unwrap());
+            }
         }
-        treeview.append_column(&col);
 
-        renderer
+        if let Some(treeview) = self.treeview.as_ref() {
+            treeview.expand_all();
+        }
     }
 
-    pub fn selected(&self) -> Option<ChapterEntry<'_>> {
-        self.tree.selected_chapter()
-    }
+    pub fn replace_with(&mut self, toc: &Option<gst::Toc>) {
+        self.clear();
 
-    pub fn selected_path(&self) -> Option<gtk::TreePath> {
-        self.tree.selected_path()
-    }
+        if let Some(ref toc) = *toc {
+            self.add_entries(toc.get_entries(), 0);
+        }
 
-    pub fn chapter_from_path(&self, tree_path: &gtk::TreePath) -> Option<ChapterEntry<'_>> {
-        self.tree.chapter_from_path(tree_path)
+        self.fill_store();
+        self.tree.rewind();
+        self.flag_continuity_issues();
     }
 
-    pub fn unselect(&mut self) {
-        self.tree.unselect();
-    }
+    /// Builds a toc from the chapters as currently displayed, including the nested chapters.
+    pub fn toc(&self, duration: Duration) -> Option<gst::Toc> {
+        let mut toc_builder = TocBuilder::new();
+        for chapter in self.chapters() {
+            toc_builder.add_chapter(chapter);
+        }
 
-    pub fn clear(&mut self) {
-        self.tree.clear();
+        toc_builder.build(duration)
     }
 
-    pub fn replace_with(&mut self, toc: &Option<gst::Toc>) {
-        self.clear();
+    /// Returns the top level chapters as currently displayed, with their nested chapters.
+    pub fn chapters(&self) -> Vec<TocChapter> {
+        fn close_group(groups: &mut Vec<TocChapter>, chapters: &mut Vec<TocChapter>) {
+            let group = groups.pop().unwrap();
+            match groups.last_mut() {
+                Some(parent) => parent.children.push(group),
+                None => chapters.push(group),
+            }
+        }
 
-        if let Some(ref toc) = *toc {
-            let mut toc_visitor = TocVisitor::new(toc);
-            if !toc_visitor.enter_chapters() {
-                return;
+        let mut chapters = Vec::new();
+        // The groups being filled at each depth
+        let mut groups: Vec<TocChapter> = Vec::new();
+        for row in &self.rows {
+            while groups.len() > row.depth {
+                close_group(&mut groups, &mut chapters);
             }
 
-            // FIXME: handle hierarchical Tocs
-            while let Some(chapter) = toc_visitor.next_chapter() {
-                assert_eq!(gst::TocEntryType::Chapter, chapter.get_entry_type());
+            // Groups without timestamps start with their first chapter
+            let (start, end) = match row.ts {
+                Some(ts) => (ts.start, Some(ts.end)),
+                None => match self.tree.chapter(row.idx) {
+                    Some(chapter) => (chapter.start(), None),
+                    None => continue,
+                },
+            };
+
+            let mut chapter =
+                TocChapter::new(Duration::from_nanos(start.as_u64()), row.title.as_str());
+            chapter.end = end.map(|end| Duration::from_nanos(end.as_u64()));
+            chapter.artist = row.artist.clone();
+
+            if row.is_group {
+                groups.push(chapter);
+            } else {
+                match groups.last_mut() {
+                    Some(group) => group.children.push(chapter),
+                    None => chapters.push(chapter),
+                }
+            }
+        }
+        while !groups.is_empty() {
+            close_group(&mut groups, &mut chapters);
+        }
 
-                if let Some((start, end)) = chapter.get_start_stop_times() {
-                    let ts = ChapterTimestamps::new_from_u64(start as u64, end as u64);
+        chapters
+    }
 
-                    let title = chapter
-                        .get_tags()
-                        .and_then(|tags| {
-                            tags.get::<gst::tags::Title>()
-                                .and_then(|tag| tag.get().map(ToString::to_string))
-                        })
-                        .unwrap_or_else(get_default_chapter_title);
+    fn flag_continuity_issues(&mut self) {
+        self.continuity_issues = self.tree.continuity_issues();
 
-                    self.tree.add_unchecked(ts, &title);
+        // Flag both chapters on each side of the discontinuity
+        for issue in &self.continuity_issues {
+            for idx in &[issue.idx() - 1, issue.idx()] {
+                if let Some(iter) = self.path(*idx).and_then(|path| self.store.get_iter(&path)) {
+                    self.store
+                        .set_value(&iter, ISSUE_ICON_COL, &ISSUE_ICON_NAME.to_value());
                 }
             }
         }
-
-        self.tree.rewind();
     }
 
-    pub fn iter(&self) -> Iter<'_> {
-        self.tree.new_iter()
+    pub fn continuity_issues(&self) -> &[ContinuityIssue] {
+        &self.continuity_issues
     }
 
-    // Update chapter according to the given ts
-    pub fn update_ts(&mut self, ts: Timestamp) -> PositionStatus {
-        self.tree.select_by_ts(ts)
+    pub fn chapter(&self, idx: usize) -> Option<&ChapterEntry> {
+        self.tree.chapter(idx)
     }
 
-    pub fn pick_next(&self) -> Option<ChapterEntry<'_>> {
-        self.tree.pick_next()
+    pub fn iter(&self) -> impl Iterator<Item = &'_ ChapterEntry> {
+        self.tree.iter()
     }
 
-    pub fn pick_previous(&self) -> Option<ChapterEntry<'_>> {
-        self.tree.pick_previous()
+    pub fn first(&self) -> Option<&ChapterEntry> {
+        self.tree.first()
     }
-}
 
-pub struct Iter<'store> {
-    store: &'store gtk::TreeStore,
-    iter: Option<gtk::TreeIter>,
-    is_first: bool,
-}
+    pub fn last(&self) -> Option<&ChapterEntry> {
+        self.tree.last()
+    }
 
-impl<'store> Iter<'store> {
-    fn new(store: &'store gtk::TreeStore) -> Self {
-        Iter {
-            store,
-            iter: None,
-            is_first: true,
-        }
+    // Update chapter according to the given ts
+    pub fn update_ts(&mut self, ts: Timestamp) -> PositionStatus {
+        self.tree.select_by_ts(ts)
     }
-}
 
-impl<'store> Iterator for Iter<'store> {
-    type Item = ChapterEntry<'store>;
+    // Update chapter according to the given end of media ts
+    pub fn update_ts_at_end(&mut self, end_ts: Timestamp) -> PositionStatus {
+        self.tree.select_at_end(end_ts)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if !self.is_first {
-            if let Some(iter) = self.iter.as_mut() {
-                if !self.store.iter_next(iter) {
-                    self.iter = None;
-                }
-            }
-        } else {
-            self.iter = self.store.get_iter_first();
-            self.is_first = false;
-        }
+    pub fn pick_next(&self) -> Option<&ChapterEntry> {
+        self.tree.pick_next()
+    }
 
-        self.iter
-            .clone()
-            .map(|iter| ChapterEntry::new_owned(&self.store, iter))
+    pub fn pick_previous(&self) -> Option<&ChapterEntry> {
+        self.tree.pick_previous()
     }
 }
@@ -0,0 +1,192 @@
+//! Exposes the player actions on the session bus.
+//!
+//! Unlike MPRIS, the service allows opening files and seeking to arbitrary
+//! positions, so that scripts and automation tools can drive the player, e.g.:
+//!
+//! ```text
+//! gdbus call --session --dest org.fengalin.MediaTocPlayer \
+//!     --object-path /org/fengalin/MediaTocPlayer \
+//!     --method org.fengalin.MediaTocPlayer.NextChapter
+//! ```
+//!
+//! The service is opt-in, see the preferences.
+
+use glib::ToVariant;
+use log::debug;
+
+use std::{cell::RefCell, path::PathBuf, sync::Mutex};
+
+use crate::media::Timestamp;
+
+use super::ui_event::UIEventSyncSender;
+
+const BUS_NAME: &str = "org.fengalin.MediaTocPlayer";
+const OBJECT_PATH: &str = "/org/fengalin/MediaTocPlayer";
+const INTERFACE: &str = "org.fengalin.MediaTocPlayer";
+const FILE_NOT_FOUND_ERROR: &str = "org.freedesktop.DBus.Error.FileNotFound";
+const INVALID_ARGS_ERROR: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.fengalin.MediaTocPlayer">
+    <method name="OpenFile">
+      <arg direction="in" name="Path" type="s"/>
+    </method>
+    <method name="PlayPause"/>
+    <method name="Seek">
+      <arg direction="in" name="Position" type="t"/>
+    </method>
+    <method name="NextChapter"/>
+    <method name="PreviousChapter"/>
+    <method name="SeekToChapter">
+      <arg direction="in" name="Index" type="u"/>
+    </method>
+    <signal name="ChapterChanged">
+      <arg name="Index" type="i"/>
+      <arg name="Title" type="s"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+struct DBusHandler {
+    // Method calls are handled on the main context, but the handlers must be `Sync`
+    ui_event: Mutex<UIEventSyncSender>,
+}
+
+impl DBusHandler {
+    fn method_call(
+        &self,
+        method: &str,
+        parameters: &glib::Variant,
+        invocation: gio::DBusMethodInvocation,
+    ) {
+        let ui_event = self.ui_event.lock().unwrap();
+        match method {
+            "OpenFile" => {
+                let path = match parameters.get_child_value(0).get_str() {
+                    Some(path) => PathBuf::from(path),
+                    None => {
+                        invocation.return_dbus_error(INVALID_ARGS_ERROR, "expected a path");
+                        return;
+                    }
+                };
+                if !path.exists() {
+                    invocation.return_dbus_error(
+                        FILE_NOT_FOUND_ERROR,
+                        &format!("{} not found", path.display()),
+                    );
+                    return;
+                }
+
+                ui_event.send(move |ui_event| ui_event.open_media(path));
+            }
+            "PlayPause" => ui_event.send(|ui_event| ui_event.play_pause()),
+            "Seek" => {
+                // Positions are expressed in nanoseconds
+                if let Some(position) = parameters.get_child_value(0).get::<u64>() {
+                    ui_event.send(move |ui_event| {
+                        ui_event.seek(Timestamp::new(position), gst::SeekFlags::ACCURATE)
+                    });
+                }
+            }
+            "NextChapter" => ui_event.send(|ui_event| ui_event.next_chapter()),
+            "PreviousChapter" => ui_event.send(|ui_event| ui_event.previous_chapter()),
+            "SeekToChapter" => {
+                if let Some(idx) = parameters.get_child_value(0).get::<u32>() {
+                    ui_event.send(move |ui_event| ui_event.seek_to_chapter(idx as usize));
+                }
+            }
+            _ => (),
+        }
+
+        invocation.return_value(None);
+    }
+}
+
+/// The current chapter as reported to the clients.
+#[derive(Debug, PartialEq)]
+struct Chapter {
+    idx: usize,
+    title: String,
+}
+
+pub struct DBusService {
+    connection: gio::DBusConnection,
+    current: RefCell<Option<Chapter>>,
+}
+
+impl DBusService {
+    /// Registers the service on the session bus, if available.
+    pub fn start(ui_event: UIEventSyncSender) -> Option<Self> {
+        let connection = gio::bus_get_sync(gio::BusType::Session, gio::NONE_CANCELLABLE)
+            .map_err(|err| debug!("DBusService: no session bus: {}", err))
+            .ok()?;
+
+        let node_info = gio::DBusNodeInfo::new_for_xml(INTROSPECTION).unwrap();
+        let handler = DBusHandler {
+            ui_event: Mutex::new(ui_event),
+        };
+        let res = connection.register_object(
+            OBJECT_PATH,
+            &node_info.lookup_interface(INTERFACE).unwrap(),
+            move |_, _, _, _, method, parameters, invocation| {
+                handler.method_call(method, &parameters, invocation)
+            },
+            |_, _, _, _, _| false.to_variant(),
+            |_, _, _, _, _, _| false,
+        );
+        if let Err(err) = res {
+            debug!("DBusService: couldn't register {}: {}", INTERFACE, err);
+            return None;
+        }
+
+        let _ = gio::bus_own_name_on_connection(
+            &connection,
+            BUS_NAME,
+            gio::BusNameOwnerFlags::NONE,
+            |_, _| (),
+            |_, name| debug!("DBusService: couldn't own {}", name),
+        );
+
+        Some(DBusService {
+            connection,
+            current: RefCell::new(None),
+        })
+    }
+
+    /// Playback entered the chapter at `idx`, `None` if not in a chapter.
+    ///
+    /// Emits `ChapterChanged` with index -1 when leaving the chapters.
+    pub fn set_current_chapter(&self, chapter: Option<(usize, &str)>) {
+        let chapter = chapter.map(|(idx, title)| Chapter {
+            idx,
+            title: title.to_owned(),
+        });
+
+        let parameters = {
+            let mut current = self.current.borrow_mut();
+            if *current == chapter {
+                return;
+            }
+
+            let parameters = match chapter.as_ref() {
+                Some(chapter) => (chapter.idx as i32, chapter.title.as_str()).to_variant(),
+                None => (-1i32, "").to_variant(),
+            };
+            *current = chapter;
+
+            parameters
+        };
+
+        if let Err(err) = self.connection.emit_signal(
+            None,
+            OBJECT_PATH,
+            INTERFACE,
+            "ChapterChanged",
+            Some(&parameters),
+        ) {
+            debug!("DBusService: couldn't emit ChapterChanged: {}", err);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+
+use std::{cell::RefCell, fs, path::PathBuf, rc::Rc};
+
+use crate::{
+    application::{History, CONFIG, HISTORY},
+    metadata::Timestamp4Humans,
+};
+
+use super::UIEventSender;
+
+const FILE_NAME_COL: u32 = 0;
+const LAST_PLAYED_COL: u32 = 1;
+const LISTENED_COL: u32 = 2;
+const PROGRESS_COL: u32 = 3;
+
+const EXPORT_RESPONSE: gtk::ResponseType = gtk::ResponseType::Other(1);
+const IMPORT_RESPONSE: gtk::ResponseType = gtk::ResponseType::Other(2);
+const HISTORY_EXPORT_NAME: &str = "media-toc-player-history.json";
+
+pub fn show(window: &gtk::ApplicationWindow, ui_event: &UIEventSender) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&gettext("History")),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (&gettext("Export…"), EXPORT_RESPONSE),
+            (&gettext("Import…"), IMPORT_RESPONSE),
+            (&gettext("Clear"), gtk::ResponseType::Reject),
+            (&gettext("Close"), gtk::ResponseType::Close),
+        ],
+    );
+    dialog.set_default_size(600, 400);
+
+    let store = gtk::ListStore::new(&[
+        String::static_type(),
+        String::static_type(),
+        String::static_type(),
+        String::static_type(),
+    ]);
+
+    // Paths are kept as is since they might not be valid UTF-8
+    let paths: Rc<RefCell<Vec<PathBuf>>> = Default::default();
+    fill(&store, &mut paths.borrow_mut());
+
+    let treeview = gtk::TreeView::with_model(&store);
+    treeview.set_activate_on_single_click(false);
+    treeview.set_tooltip_text(Some(&gettext(
+        "Double-click to continue where you left off",
+    )));
+    for (title, col_id) in &[
+        (gettext("File"), FILE_NAME_COL),
+        (gettext("Last played"), LAST_PLAYED_COL),
+        (gettext("Listened"), LISTENED_COL),
+        (gettext("Position"), PROGRESS_COL),
+    ] {
+        let col = gtk::TreeViewColumn::new();
+        col.set_title(title);
+        let renderer = gtk::CellRendererText::new();
+        col.pack_start(&renderer, true);
+        col.add_attribute(&renderer, "text", *col_id as i32);
+        if *col_id == FILE_NAME_COL {
+            col.set_expand(true);
+        }
+        treeview.append_column(&col);
+    }
+
+    treeview.connect_row_activated(
+        clone!(@strong ui_event, @strong paths, @weak dialog => move |_, tree_path, _| {
+            let path = tree_path
+                .get_indices()
+                .first()
+                .and_then(|idx| paths.borrow().get(*idx as usize).cloned());
+            if let Some(path) = path {
+                ui_event.resume_media(path);
+            }
+            dialog.close();
+        }),
+    );
+
+    let scrolled_window = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+    scrolled_window.set_vexpand(true);
+    scrolled_window.add(&treeview);
+    dialog.get_content_area().add(&scrolled_window);
+
+    dialog.connect_response(clone!(@strong ui_event => move |dialog, response| {
+        match response {
+            EXPORT_RESPONSE => {
+                export(dialog, &ui_event);
+                return;
+            }
+            IMPORT_RESPONSE => {
+                if import(dialog, &ui_event) {
+                    fill(&store, &mut paths.borrow_mut());
+                }
+                return;
+            }
+            gtk::ResponseType::Reject => HISTORY.write().unwrap().clear(),
+            _ => (),
+        }
+        dialog.close();
+    }));
+
+    dialog.show_all();
+}
+
+fn fill(store: &gtk::ListStore, paths: &mut Vec<PathBuf>) {
+    store.clear();
+    paths.clear();
+
+    let timestamp_display = CONFIG.read().unwrap().ui.timestamp_display();
+    for entry in HISTORY.read().unwrap().iter() {
+        let file_name = entry
+            .path
+            .file_name()
+            .map_or_else(String::new, |file_name| {
+                file_name.to_string_lossy().to_string()
+            });
+        let last_played = glib::DateTime::from_unix_local(entry.last_played as i64)
+            .format("%x %X")
+            .map_or_else(String::new, |date| date.to_string());
+        let progress = if entry.is_completed {
+            gettext("Completed")
+        } else {
+            Timestamp4Humans::from_nano(entry.position).display(timestamp_display)
+        };
+
+        store.insert_with_values(
+            None,
+            &[FILE_NAME_COL, LAST_PLAYED_COL, LISTENED_COL, PROGRESS_COL],
+            &[
+                &file_name,
+                &last_played,
+                &Timestamp4Humans::from_nano(entry.listened).display(timestamp_display),
+                &progress,
+            ],
+        );
+        paths.push(entry.path.clone());
+    }
+}
+
+fn choose_file(dialog: &gtk::Dialog, action: gtk::FileChooserAction) -> Option<PathBuf> {
+    let (title, accept_label) = match action {
+        gtk::FileChooserAction::Save => (gettext("Export history"), gettext("Export")),
+        _ => (gettext("Import history"), gettext("Import")),
+    };
+
+    let file_dlg = gtk::FileChooserNativeBuilder::new()
+        .title(&title)
+        .transient_for(dialog)
+        .modal(true)
+        .action(action)
+        .do_overwrite_confirmation(true)
+        .accept_label(&accept_label)
+        .cancel_label(&gettext("Cancel"))
+        .build();
+    if action == gtk::FileChooserAction::Save {
+        file_dlg.set_current_name(HISTORY_EXPORT_NAME);
+    }
+
+    let path = if file_dlg.run() == gtk::ResponseType::Accept {
+        file_dlg.get_filename()
+    } else {
+        None
+    };
+    file_dlg.destroy();
+
+    path
+}
+
+/// Exports the resume points to a JSON file, e.g. to sync them with another machine.
+fn export(dialog: &gtk::Dialog, ui_event: &UIEventSender) {
+    let path = match choose_file(dialog, gtk::FileChooserAction::Save) {
+        Some(path) => path,
+        None => return,
+    };
+
+    let res = HISTORY
+        .read()
+        .unwrap()
+        .to_json()
+        .and_then(|json| fs::write(&path, json).map_err(|err| err.to_string()));
+    match res {
+        Ok(()) => ui_event.show_info(gettext("History exported to {}").replacen(
+            "{}",
+            &path.display().to_string(),
+            1,
+        )),
+        Err(err) => {
+            ui_event.show_error(gettext("Couldn't export the history: {}").replacen("{}", &err, 1))
+        }
+    }
+}
+
+/// Merges the resume points from a JSON file, keeping the most recent ones.
+///
+/// Returns `true` if the history was updated.
+fn import(dialog: &gtk::Dialog, ui_event: &UIEventSender) -> bool {
+    let path = match choose_file(dialog, gtk::FileChooserAction::Open) {
+        Some(path) => path,
+        None => return false,
+    };
+
+    let res = fs::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|json| History::from_json(&json));
+    match res {
+        Ok(other) => {
+            let mut history = HISTORY.write().unwrap();
+            let updated = history.merge(other);
+            history.save();
+            ui_event.show_info(gettext("{} history entries imported").replacen(
+                "{}",
+                &updated.to_string(),
+                1,
+            ));
+
+            updated > 0
+        }
+        Err(err) => {
+            ui_event.show_error(gettext("Couldn't import the history: {}").replacen("{}", &err, 1));
+
+            false
+        }
+    }
+}
@@ -1,8 +1,47 @@
+use gdk_pixbuf::prelude::*;
+
 use std::{
     cell::{Cell, RefCell},
     rc::Rc,
 };
 
+// Embedded art can be much larger than what we will ever display
+const MAX_DIMENSION: u32 = 1024;
+
+// Keeps the aspect ratio
+fn bounded_dimensions(width: i32, height: i32) -> (i32, i32) {
+    let max_dimension = MAX_DIMENSION as i32;
+    if width <= max_dimension && height <= max_dimension {
+        return (width, height);
+    }
+
+    let scaled = |dimension: i32, reference: i32| {
+        (i64::from(dimension) * i64::from(max_dimension) / i64::from(reference)).max(1) as i32
+    };
+
+    if width > height {
+        (max_dimension, scaled(height, width))
+    } else {
+        (scaled(width, height), max_dimension)
+    }
+}
+
+/// Decodes `input` into a `Pixbuf` which fits in a `size` x `size` square.
+pub fn scaled_pixbuf(input: &[u8], size: i32) -> Option<gdk_pixbuf::Pixbuf> {
+    let loader = gdk_pixbuf::PixbufLoader::new();
+    loader.write(input).and_then(|_| loader.close()).ok()?;
+    let pixbuf = loader.get_pixbuf()?;
+
+    // Keep the aspect ratio
+    let (width, height) = (pixbuf.get_width(), pixbuf.get_height());
+    let (width, height) = if width > height {
+        (size, (height * size / width).max(1))
+    } else {
+        ((width * size / height).max(1), size)
+    };
+    pixbuf.scale_simple(width, height, gdk_pixbuf::InterpType::Bilinear)
+}
+
 // This is from https://github.com/gtk-rs/examples/blob/master/src/bin/cairo_threads.rs
 // Helper struct that allows passing the pixels to the Cairo image surface and once the
 // image surface is destroyed the pixels will be stored in the return_location.
@@ -46,50 +85,124 @@ pub struct Image {
 
 impl Image {
     pub fn from_unknown(input: &[u8]) -> Result<Self, String> {
-        let image = image::load_from_memory(input)
-            .map_err(|err| format!("Error loading image: {:?}", err))?;
-
-        match image.as_rgb8().as_ref() {
-            Some(rgb_image) => {
-                // Align to Cairo's needs: 4 bytes per pixel
-                // When converting to RGB8, image crate uses 3 bytes in different order
-                let width = rgb_image.width();
-                let height = rgb_image.height();
-
-                if width > i32::max_value() as u32 {
-                    return Err(format!("Image width {} is too large", width));
-                }
-                if height > i32::max_value() as u32 {
-                    return Err(format!("Image height {} is too large", height));
-                }
-
-                let stride = cairo::Format::Rgb24
-                    .stride_for_width(width)
-                    .map_err(|status| {
-                        format!("Couldn't compute stride for width {}: {:?}", width, status)
-                    })?;
-
-                let width = width as i32;
-                let height = height as i32;
-
-                let mut pixels = Vec::with_capacity(height as usize * stride as usize);
-
-                for pixel in rgb_image.chunks(3) {
-                    pixels.push(pixel[2]);
-                    pixels.push(pixel[1]);
-                    pixels.push(pixel[0]);
-                    pixels.push(0);
-                }
-
-                Ok(Image {
-                    pixels: Cell::new(Some(pixels.into())),
-                    width,
-                    height,
-                    stride,
+        match image::load_from_memory(input) {
+            Ok(image) => Self::from_dynamic_image(image),
+            // gdk-pixbuf might have loaders for other formats (e.g. WebP, AVIF)
+            Err(image_err) => Self::from_pixbuf_loader(input).map_err(|pixbuf_err| {
+                format!("Error loading image: {:?}, {}", image_err, pixbuf_err)
+            }),
+        }
+    }
+
+    fn from_dynamic_image(image: image::DynamicImage) -> Result<Self, String> {
+        let image = if image.width() > MAX_DIMENSION || image.height() > MAX_DIMENSION {
+            image.thumbnail(MAX_DIMENSION, MAX_DIMENSION)
+        } else {
+            image
+        };
+
+        let rgb_image = image.to_rgb();
+        Self::from_rgb_pixels(
+            rgb_image.width(),
+            rgb_image.height(),
+            rgb_image
+                .pixels()
+                .map(|pixel| (pixel[0], pixel[1], pixel[2])),
+        )
+    }
+
+    fn from_pixbuf_loader(input: &[u8]) -> Result<Self, String> {
+        let loader = gdk_pixbuf::PixbufLoader::new();
+        loader
+            .write(input)
+            .and_then(|_| loader.close())
+            .map_err(|err| format!("Error loading image with gdk-pixbuf: {}", err))?;
+
+        // Only the first frame of animated images is used
+        let pixbuf = loader
+            .get_pixbuf()
+            .ok_or_else(|| "gdk-pixbuf didn't return any image".to_owned())?;
+
+        Self::from_pixbuf(&pixbuf)
+    }
+
+    pub fn from_pixbuf(pixbuf: &gdk_pixbuf::Pixbuf) -> Result<Self, String> {
+        let (width, height) = bounded_dimensions(pixbuf.get_width(), pixbuf.get_height());
+
+        // Scale down if necessary & get rid of the alpha channel
+        let pixbuf = if pixbuf.get_has_alpha() {
+            pixbuf.composite_color_simple(
+                width,
+                height,
+                gdk_pixbuf::InterpType::Bilinear,
+                255,
+                32,
+                0xffff_ffff,
+                0xffff_ffff,
+            )
+        } else if width != pixbuf.get_width() || height != pixbuf.get_height() {
+            pixbuf.scale_simple(width, height, gdk_pixbuf::InterpType::Bilinear)
+        } else {
+            Some(pixbuf.clone())
+        }
+        .ok_or_else(|| "Error scaling image".to_owned())?;
+
+        let bytes = pixbuf
+            .read_pixel_bytes()
+            .ok_or_else(|| "Couldn't read image pixels".to_owned())?;
+        let rowstride = pixbuf.get_rowstride() as usize;
+        let n_channels = pixbuf.get_n_channels() as usize;
+
+        Self::from_rgb_pixels(
+            width as u32,
+            height as u32,
+            (0..height as usize).flat_map(|row| {
+                let bytes = &bytes;
+                (0..width as usize).map(move |col| {
+                    let offset = row * rowstride + col * n_channels;
+                    (bytes[offset], bytes[offset + 1], bytes[offset + 2])
                 })
-            }
-            None => Err("Error converting image to raw RGB".to_owned()),
+            }),
+        )
+    }
+
+    fn from_rgb_pixels(
+        width: u32,
+        height: u32,
+        rgb_pixels: impl Iterator<Item = (u8, u8, u8)>,
+    ) -> Result<Self, String> {
+        if width > i32::max_value() as u32 {
+            return Err(format!("Image width {} is too large", width));
+        }
+        if height > i32::max_value() as u32 {
+            return Err(format!("Image height {} is too large", height));
+        }
+
+        // Align to Cairo's needs: 4 bytes per pixel
+        let stride = cairo::Format::Rgb24
+            .stride_for_width(width)
+            .map_err(|status| {
+                format!("Couldn't compute stride for width {}: {:?}", width, status)
+            })?;
+
+        let width = width as i32;
+        let height = height as i32;
+
+        let mut pixels = Vec::with_capacity(height as usize * stride as usize);
+
+        for (red, green, blue) in rgb_pixels {
+            pixels.push(blue);
+            pixels.push(green);
+            pixels.push(red);
+            pixels.push(0);
         }
+
+        Ok(Image {
+            pixels: Cell::new(Some(pixels.into())),
+            width,
+            height,
+            stride,
+        })
     }
 
     pub fn width(&self) -> i32 {
@@ -1,38 +1,98 @@
+use futures::{channel::oneshot, prelude::*};
+
 use gettextrs::gettext;
 
 use gio::prelude::*;
 use glib::clone;
 use gtk::prelude::*;
 
-use log::{error, info};
+use log::{debug, error, info, warn};
 
 use std::borrow::Borrow;
 
 use super::{UIEventSender, UIFocusContext};
 
+/// The buttons which can be offered when asking a question.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InfoBarResponse {
+    Cancel,
+    /// Close the media, not to be confused with closing the info bar.
+    Close,
+    /// Install the missing plugins.
+    Install,
+    No,
+    Reload,
+    Retry,
+    Yes,
+    YesToAll,
+}
+
+impl InfoBarResponse {
+    fn label(self) -> String {
+        match self {
+            InfoBarResponse::Cancel => gettext("Cancel"),
+            InfoBarResponse::Close => gettext("Close"),
+            InfoBarResponse::Install => gettext("Install"),
+            InfoBarResponse::No => gettext("No"),
+            InfoBarResponse::Reload => gettext("Reload"),
+            InfoBarResponse::Retry => gettext("Retry"),
+            InfoBarResponse::Yes => gettext("Yes"),
+            InfoBarResponse::YesToAll => gettext("Yes to all"),
+        }
+    }
+
+    fn response_type(self) -> gtk::ResponseType {
+        match self {
+            InfoBarResponse::Cancel => gtk::ResponseType::Cancel,
+            // `gtk::ResponseType::Close` is emitted by the close button
+            InfoBarResponse::Close => gtk::ResponseType::Reject,
+            InfoBarResponse::Install => gtk::ResponseType::Other(0),
+            InfoBarResponse::No => gtk::ResponseType::No,
+            InfoBarResponse::Reload => gtk::ResponseType::Ok,
+            InfoBarResponse::Retry => gtk::ResponseType::Accept,
+            InfoBarResponse::Yes => gtk::ResponseType::Yes,
+            InfoBarResponse::YesToAll => gtk::ResponseType::Apply,
+        }
+    }
+
+    /// Closing the info bar stands for `Cancel`.
+    fn from_response_type(response_type: gtk::ResponseType) -> Self {
+        match response_type {
+            gtk::ResponseType::Reject => InfoBarResponse::Close,
+            gtk::ResponseType::Other(0) => InfoBarResponse::Install,
+            gtk::ResponseType::No => InfoBarResponse::No,
+            gtk::ResponseType::Ok => InfoBarResponse::Reload,
+            gtk::ResponseType::Accept => InfoBarResponse::Retry,
+            gtk::ResponseType::Yes => InfoBarResponse::Yes,
+            gtk::ResponseType::Apply => InfoBarResponse::YesToAll,
+            _ => InfoBarResponse::Cancel,
+        }
+    }
+}
+
 pub struct InfoBarController {
     info_bar: gtk::InfoBar,
     revealer: gtk::Revealer,
     label: gtk::Label,
+    details_expander: gtk::Expander,
+    details_label: gtk::Label,
+    // Buttons for the question being asked
+    buttons: Vec<gtk::Widget>,
+    pending_response: Option<oneshot::Sender<InfoBarResponse>>,
     ui_event: UIEventSender,
 }
 
 impl InfoBarController {
     pub fn new(app: &gtk::Application, builder: &gtk::Builder, ui_event: &UIEventSender) -> Self {
         let info_bar: gtk::InfoBar = builder.get_object("info_bar").unwrap();
-        info_bar.add_button(&gettext("No"), gtk::ResponseType::No);
-        info_bar.add_button(&gettext("Yes to all"), gtk::ResponseType::Apply);
-        info_bar.add_button(&gettext("Cancel"), gtk::ResponseType::Cancel);
-        info_bar.set_default_response(gtk::ResponseType::Yes);
-
         let revealer: gtk::Revealer = builder.get_object("info_bar-revealer").unwrap();
 
         let close_info_bar_action = gio::SimpleAction::new("close_info_bar", None);
         app.add_action(&close_info_bar_action);
         app.set_accels_for_action("app.close_info_bar", &["Escape"]);
 
-        info_bar.connect_response(clone!(@strong ui_event => move |_, _| {
-            ui_event.hide_info_bar();
+        info_bar.connect_response(clone!(@strong ui_event => move |_, response| {
+            ui_event.info_bar_responded(response);
             ui_event.restore_context();
         }));
 
@@ -54,6 +114,10 @@ impl InfoBarController {
             info_bar,
             revealer,
             label: builder.get_object("info_bar-lbl").unwrap(),
+            details_expander: builder.get_object("info_bar-details-expander").unwrap(),
+            details_label: builder.get_object("info_bar-details-lbl").unwrap(),
+            buttons: Vec::new(),
+            pending_response: None,
             ui_event,
         }
     }
@@ -62,10 +126,32 @@ impl InfoBarController {
         self.revealer.set_reveal_child(false);
     }
 
+    /// The user clicked a button or closed the info bar.
+    pub fn responded(&mut self, response_type: gtk::ResponseType) {
+        self.hide();
+        if let Some(pending_response) = self.pending_response.take() {
+            let _ = pending_response.send(InfoBarResponse::from_response_type(response_type));
+        }
+    }
+
+    fn clear_buttons(&mut self) {
+        // Dropping a pending response resolves the question with `Cancel`
+        self.pending_response = None;
+
+        for button in self.buttons.drain(..) {
+            unsafe {
+                button.destroy();
+            }
+        }
+    }
+
     pub fn show_message<Msg: Borrow<str>>(&mut self, type_: gtk::MessageType, message: Msg) {
+        self.clear_buttons();
+
         self.info_bar.set_show_close_button(true);
         self.info_bar.set_message_type(type_);
         self.label.set_label(message.borrow());
+        self.details_expander.set_visible(false);
         self.revealer.set_reveal_child(true);
 
         self.ui_event.temporarily_switch_to(UIFocusContext::InfoBar);
@@ -80,4 +166,59 @@ impl InfoBarController {
         info!("{}", message.borrow());
         self.show_message(gtk::MessageType::Info, message);
     }
+
+    pub fn show_warning_with_details<Msg: Borrow<str>, Details: Borrow<str>>(
+        &mut self,
+        message: Msg,
+        details: Details,
+    ) {
+        warn!("{}\n{}", message.borrow(), details.borrow());
+        self.show_message(gtk::MessageType::Warning, message);
+
+        self.details_label.set_label(details.borrow());
+        self.details_expander.set_expanded(false);
+        self.details_expander.set_visible(true);
+    }
+
+    /// Shows the question `message` with the given `responses` as buttons.
+    ///
+    /// See `ask`.
+    pub fn ask_question<Msg: Borrow<str>>(
+        &mut self,
+        message: Msg,
+        responses: &[InfoBarResponse],
+    ) -> impl Future<Output = InfoBarResponse> {
+        self.ask(gtk::MessageType::Question, message, responses)
+    }
+
+    /// Shows `message` of type `type_` with the given `responses` as buttons.
+    ///
+    /// The returned future resolves when the user responds. It resolves with
+    /// `Cancel` if the info bar is closed or if another message replaces this one.
+    pub fn ask<Msg: Borrow<str>>(
+        &mut self,
+        type_: gtk::MessageType,
+        message: Msg,
+        responses: &[InfoBarResponse],
+    ) -> impl Future<Output = InfoBarResponse> {
+        debug!("{}", message.borrow());
+        self.show_message(type_, message);
+
+        for response in responses {
+            if let Some(button) = self
+                .info_bar
+                .add_button(&response.label(), response.response_type())
+            {
+                self.buttons.push(button.upcast());
+            }
+        }
+        if let Some(first) = responses.first() {
+            self.info_bar.set_default_response(first.response_type());
+        }
+
+        let (response_tx, response_rx) = oneshot::channel();
+        self.pending_response = Some(response_tx);
+
+        response_rx.map(|response| response.unwrap_or(InfoBarResponse::Cancel))
+    }
 }
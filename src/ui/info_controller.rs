@@ -1,23 +1,56 @@
-use gettextrs::gettext;
+use gettextrs::{gettext, ngettext};
+use gio::prelude::*;
+use glib::{clone, StaticVariantType};
 use gtk::prelude::*;
 use log::{debug, info, warn};
 
-use std::fs::File;
+use std::{
+    cell::{Cell, RefCell},
+    fs,
+    path::PathBuf,
+    rc::Rc,
+    thread,
+    time::Instant,
+};
 
 use crate::{
-    application::CONFIG,
-    media::{PlaybackPipeline, Timestamp},
+    application::{ChapterAutoScroll, CONFIG},
+    media::{AudioLevel, PlaybackPipeline, Timestamp, Waveform},
     metadata,
-    metadata::{Duration, MediaInfo, Timestamp4Humans},
+    metadata::{
+        Duration, MediaInfo, MediaPath, StreamSelectionDelta, Timestamp4Humans, TocBuilder,
+        TocChapter, TocDiff, TocVisitor,
+    },
 };
 
 use super::{
-    ChapterTreeManager, ControllerState, Image, PositionStatus, UIController, UIEventSender,
+    ChapterEndAction, ChapterEndMode, ChapterEntry, ChapterTimestamps, ChapterTreeManager,
+    ContinuityIssue, ControllerState, Image, PlaybackPolicy, PositionStatus, UIController,
+    UIEventSender, WaveformStrip,
 };
 
 const EMPTY_REPLACEMENT: &str = "-";
+const PLACEHOLDER_ICON: &str = "image-x-generic";
+const PLACEHOLDER_ICON_SIZE: i32 = 256;
 const GO_TO_PREV_CHAPTER_THRESHOLD: Duration = Duration::from_secs(1);
 pub const SEEK_STEP: Duration = Duration::from_nanos(2_500_000_000);
+// Audio levels below this are displayed as silence
+const MIN_AUDIO_LEVEL_DB: f64 = -60f64;
+// Don't auto-scroll the chapter tree while the user is browsing it
+const CHAPTER_BROWSING_TIMEOUT: u64 = 5; // 5 s
+
+/// A chapter played as a pipeline segment, so that it can loop right at its end.
+#[derive(Clone, Copy, Debug)]
+pub struct ChapterSegment {
+    idx: usize,
+    ts: ChapterTimestamps,
+}
+
+impl ChapterSegment {
+    pub fn end(&self) -> Timestamp {
+        self.ts.end
+    }
+}
 
 enum ThumbnailState {
     Blocked,
@@ -69,11 +102,92 @@ impl Drop for Thumbnail {
     }
 }
 
+// The thumbnail scaled to fit the drawing area
+struct ScaledThumbnail {
+    alloc_width: i32,
+    alloc_height: i32,
+    scale_factor: i32,
+    x: f64,
+    y: f64,
+    surface: cairo::ImageSurface,
+}
+
+#[derive(Debug)]
+pub(super) struct TocCandidate {
+    path: PathBuf,
+    format: metadata::Format,
+    toc: gst::Toc,
+}
+
+impl TocCandidate {
+    fn chapter_count(&self) -> usize {
+        let mut toc_visitor = TocVisitor::new(&self.toc);
+        if !toc_visitor.enter_chapters() {
+            return 0;
+        }
+
+        let mut count = 0;
+        while toc_visitor.next_chapter().is_some() {
+            count += 1;
+        }
+
+        count
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum TocReadError {
+    NoToc(String),
+    Read(String),
+}
+
+/// Outcome of the toc files reading performed off the UI thread.
+#[derive(Debug)]
+pub struct TocReading {
+    media_path: MediaPath,
+    // Index of the reloaded candidate or `None` for a new media
+    reload_idx: Option<usize>,
+    results: Vec<Result<TocCandidate, TocReadError>>,
+}
+
+fn read_toc_candidate(info: &MediaInfo, path: PathBuf) -> Result<TocCandidate, TocReadError> {
+    let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+    let content =
+        fs::read(&path).map_err(|_| TocReadError::Read(gettext("Failed to open toc file.")))?;
+
+    let (format, reader) = match metadata::Factory::detect(&path, &content)
+        .and_then(|format| metadata::Factory::get_reader(format).map(|reader| (format, reader)))
+    {
+        Some(format_reader) => format_reader,
+        None => {
+            return Err(TocReadError::NoToc(
+                gettext("Unsupported toc file \"{}\"").replacen("{}", &file_name, 1),
+            ))
+        }
+    };
+
+    match reader.read(info, &mut content.as_slice()) {
+        Ok(Some(toc)) => Ok(TocCandidate { path, format, toc }),
+        Ok(None) => Err(TocReadError::NoToc(
+            gettext("No toc in file \"{}\"").replacen("{}", &file_name, 1),
+        )),
+        Err(err) => {
+            warn!("{}: {}", path.display(), err);
+            Err(TocReadError::Read(
+                gettext("Error opening toc file \"{}\":\n{}")
+                    .replacen("{}", &file_name, 1)
+                    .replacen("{}", &err.to_string(), 1),
+            ))
+        }
+    }
+}
+
 pub struct InfoController {
     ui_event: UIEventSender,
 
     pub(super) info_container: gtk::Grid,
     pub(super) show_chapters_btn: gtk::ToggleButton,
+    pub(super) flat_chapters_btn: gtk::ToggleButton,
 
     pub(super) drawingarea: gtk::DrawingArea,
 
@@ -86,62 +200,91 @@ pub struct InfoController {
     duration_lbl: gtk::Label,
 
     pub(super) timeline_scale: gtk::Scale,
+    waveform_strip: WaveformStrip,
     pub(super) repeat_btn: gtk::ToggleToolButton,
+    pub(super) repeat_count_spinbtn: gtk::SpinButton,
+    pub(super) stop_at_chapter_end_btn: gtk::ToggleToolButton,
+    audio_peak_bar: gtk::LevelBar,
+    audio_rms_bar: gtk::LevelBar,
 
     pub(super) chapter_treeview: gtk::TreeView,
+    pub(super) chapter_search_bar: gtk::SearchBar,
+    pub(super) chapter_search_entry: gtk::SearchEntry,
+    // Last time the user scrolled the chapter tree
+    chapter_browsed_at: Rc<Cell<Option<Instant>>>,
     pub(super) next_chapter_action: gio::SimpleAction,
     pub(super) previous_chapter_action: gio::SimpleAction,
+    pub(super) first_chapter_action: gio::SimpleAction,
+    pub(super) last_chapter_action: gio::SimpleAction,
+    pub(super) seek_to_chapter_action: gio::SimpleAction,
+    pub(super) search_chapters_action: gio::SimpleAction,
+    pub(super) step_forward_action: gio::SimpleAction,
+    pub(super) step_back_action: gio::SimpleAction,
+    pub(super) fast_forward_action: gio::SimpleAction,
+    pub(super) rewind_action: gio::SimpleAction,
+    pub(super) save_toc_action: gio::SimpleAction,
 
     thumbnail: Option<Thumbnail>,
 
     pub(super) chapter_manager: ChapterTreeManager,
 
+    toc_chooser_btn: gtk::MenuButton,
+    toc_chooser_popover: gtk::Popover,
+    toc_chooser_box: gtk::Box,
+    toc_candidates: Vec<TocCandidate>,
+    toc_idx: Option<usize>,
+    toc_monitor: Option<gio::FileMonitor>,
+    // Chapters were renamed since the toc file was read
+    is_toc_dirty: bool,
+    media_path: MediaPath,
+    // The toc from the media, if any
+    media_toc: Option<gst::Toc>,
+    // The media is a part of a book, see `show_book`
+    is_book: bool,
+
     duration: Duration,
-    pub(super) repeat_chapter: bool,
+    is_seekable: bool,
+    playback_policy: PlaybackPolicy,
+    segment: Option<ChapterSegment>,
 }
 
 impl UIController for InfoController {
     fn new_media(&mut self, pipeline: &PlaybackPipeline) {
-        let toc_extensions = metadata::Factory::get_extensions();
+        self.media_path = pipeline.info.path.clone();
+        self.duration = pipeline.info.duration;
+        self.set_seekable(pipeline.is_seekable());
 
         {
-            // check the presence of a toc file
-            let mut toc_candidates =
-                toc_extensions
-                    .into_iter()
-                    .filter_map(|(extension, format)| {
-                        let path = pipeline
-                            .info
-                            .path
-                            .with_file_name(&format!("{}.{}", pipeline.info.name, extension));
-                        if path.is_file() {
-                            Some((path, format))
-                        } else {
-                            None
-                        }
-                    });
+            // toc files are read asynchronously, see `toc_read`
+            let toc_sources = metadata::Factory::get_extensions()
+                .into_iter()
+                .map(|extension| pipeline.info.path.with_extension(extension))
+                .collect();
+            self.spawn_toc_reading(toc_sources, None);
 
-            self.duration = pipeline.info.duration;
             self.timeline_scale
                 .set_range(0f64, pipeline.info.duration.as_f64());
-            self.duration_lbl
-                .set_label(&Timestamp4Humans::from_duration(pipeline.info.duration).to_string());
-
-            let thumbnail = pipeline.info.media_image().and_then(|image| {
-                image.get_buffer().and_then(|image_buffer| {
-                    image_buffer.map_readable().ok().and_then(|image_map| {
-                        Image::from_unknown(image_map.as_slice())
-                            .map_err(|err| warn!("{}", err))
-                            .ok()
+            self.waveform_strip.set_duration(pipeline.info.duration);
+            self.duration_lbl.set_label(
+                &Timestamp4Humans::from_duration(pipeline.info.duration)
+                    .display(CONFIG.read().unwrap().ui.timestamp_display()),
+            );
+
+            let thumbnail = pipeline.info.cover_art.as_ref().and_then(|cover_art| {
+                Image::from_unknown(&cover_art.data)
+                    .or_else(|err| {
+                        debug!("{}", err);
+                        Self::placeholder_thumbnail()
                     })
-                })
+                    .ok()
             });
 
             if let Some(thumbnail) = thumbnail {
+                let scaled_thumbnail = RefCell::new(None);
                 self.thumbnail = Some(Thumbnail::new(
                     &self.drawingarea,
                     move |drawingarea, cairo_ctx| {
-                        Self::draw_thumbnail(&thumbnail, drawingarea, cairo_ctx);
+                        Self::draw_thumbnail(&thumbnail, &scaled_thumbnail, drawingarea, cairo_ctx);
                         Inhibit(true)
                     },
                 ));
@@ -150,64 +293,24 @@ impl UIController for InfoController {
             self.container_lbl
                 .set_label(pipeline.info.container().unwrap_or(EMPTY_REPLACEMENT));
 
-            let extern_toc = toc_candidates
-                .next()
-                .and_then(|(toc_path, format)| match File::open(toc_path.clone()) {
-                    Ok(mut toc_file) => {
-                        match metadata::Factory::get_reader(format)
-                            .read(&pipeline.info, &mut toc_file)
-                        {
-                            Ok(Some(toc)) => Some(toc),
-                            Ok(None) => {
-                                let msg = gettext("No toc in file \"{}\"").replacen(
-                                    "{}",
-                                    toc_path.file_name().unwrap().to_str().unwrap(),
-                                    1,
-                                );
-                                info!("{}", msg);
-                                self.ui_event.show_info(msg);
-                                None
-                            }
-                            Err(err) => {
-                                self.ui_event.show_error(
-                                    gettext("Error opening toc file \"{}\":\n{}")
-                                        .replacen(
-                                            "{}",
-                                            toc_path.file_name().unwrap().to_str().unwrap(),
-                                            1,
-                                        )
-                                        .replacen("{}", &err, 1),
-                                );
-                                None
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        self.ui_event
-                            .show_error(gettext("Failed to open toc file."));
-                        None
-                    }
-                });
-
-            if extern_toc.is_some() {
-                self.chapter_manager.replace_with(&extern_toc);
-            } else {
-                self.chapter_manager.replace_with(&pipeline.info.toc);
-            }
+            // Start with the toc from the media, if any
+            self.media_toc = pipeline.info.toc.clone();
+            self.chapter_manager.replace_with(&pipeline.info.toc);
+            self.report_continuity_issues();
         }
 
         self.update_marks();
 
         self.repeat_btn.set_sensitive(true);
-        if let Some(sel_chapter) = self.chapter_manager.selected() {
+        self.repeat_count_spinbtn.set_sensitive(true);
+        self.stop_at_chapter_end_btn.set_sensitive(true);
+        if let Some(sel_path) = self.chapter_manager.selected_path() {
             // position is in a chapter => select it
-            self.chapter_treeview
-                .get_selection()
-                .select_iter(sel_chapter.iter());
+            self.select_chapter(&sel_path);
         }
+        self.update_chapter_title();
 
-        self.next_chapter_action.set_enabled(true);
-        self.previous_chapter_action.set_enabled(true);
+        self.update_chapter_actions();
 
         self.ui_event.update_focus();
     }
@@ -220,17 +323,42 @@ impl UIController for InfoController {
         self.video_codec_lbl.set_text("");
         self.position_lbl.set_text("00:00.000");
         self.duration_lbl.set_text("00:00.000");
+        self.reset_audio_level();
         let _ = self.thumbnail.take();
         self.chapter_treeview.get_selection().unselect_all();
         self.chapter_manager.clear();
+        self.toc_candidates.clear();
+        self.update_toc_chooser(0);
+        self.unmonitor_toc();
+        self.set_toc_dirty(false);
+        self.media_path = MediaPath::default();
+        self.media_toc = None;
+        self.is_book = false;
+        self.chapter_search_bar.set_search_mode(false);
+        self.chapter_search_entry.set_text("");
         self.next_chapter_action.set_enabled(false);
         self.previous_chapter_action.set_enabled(false);
+        self.first_chapter_action.set_enabled(false);
+        self.last_chapter_action.set_enabled(false);
+        self.seek_to_chapter_action.set_enabled(false);
+        self.search_chapters_action.set_enabled(false);
+        self.flat_chapters_btn.set_sensitive(false);
         self.timeline_scale.clear_marks();
         self.timeline_scale.set_value(0f64);
+        self.waveform_strip.cleanup();
         self.duration = Duration::default();
+        self.is_seekable = false;
+        self.segment = None;
+        self.step_forward_action.set_enabled(false);
+        self.step_back_action.set_enabled(false);
+        self.fast_forward_action.set_enabled(false);
+        self.rewind_action.set_enabled(false);
+        self.timeline_scale.set_sensitive(true);
+        self.timeline_scale.set_tooltip_text(None);
+        self.chapter_treeview.set_tooltip_text(None);
     }
 
-    fn streams_changed(&mut self, info: &MediaInfo) {
+    fn streams_changed(&mut self, info: &MediaInfo, _delta: &StreamSelectionDelta) {
         match info.media_artist() {
             Some(artist) => self.artist_lbl.set_label(artist),
             None => self.artist_lbl.set_label(EMPTY_REPLACEMENT),
@@ -261,6 +389,12 @@ impl UIController for InfoController {
     }
 
     fn grab_focus(&self) {
+        // Setting the cursor scrolls to it, keep the position set by the user or `select_chapter`
+        let scroll_pos = self
+            .chapter_treeview
+            .get_vadjustment()
+            .map(|vadjustment| (vadjustment.get_value(), vadjustment));
+
         self.chapter_treeview.grab_focus();
 
         match self.chapter_manager.selected_path() {
@@ -278,6 +412,10 @@ impl UIController for InfoController {
                 );
             }
         }
+
+        if let Some((value, vadjustment)) = scroll_pos {
+            vadjustment.set_value(value);
+        }
     }
 }
 
@@ -286,13 +424,24 @@ impl InfoController {
         let mut chapter_manager =
             ChapterTreeManager::new(builder.get_object("chapters-tree-store").unwrap());
         let chapter_treeview: gtk::TreeView = builder.get_object("chapter-treeview").unwrap();
-        chapter_manager.init_treeview(&chapter_treeview);
+        let chapter_search_bar: gtk::SearchBar = builder.get_object("chapter_search-bar").unwrap();
+        let chapter_search_entry: gtk::SearchEntry =
+            builder.get_object("chapter_search-entry").unwrap();
+        chapter_search_bar.connect_entry(&chapter_search_entry);
+        chapter_manager.init_treeview(&chapter_treeview, &chapter_search_entry);
+
+        let chapter_browsed_at = Rc::new(Cell::new(None));
+        chapter_treeview.connect_scroll_event(clone!(@strong chapter_browsed_at => move |_, _| {
+            chapter_browsed_at.set(Some(Instant::now()));
+            Inhibit(false)
+        }));
 
         let mut ctrl = InfoController {
             ui_event,
 
             info_container: builder.get_object("info-chapter_list-grid").unwrap(),
             show_chapters_btn: builder.get_object("show_chapters-toggle").unwrap(),
+            flat_chapters_btn: builder.get_object("flat_chapters-toggle").unwrap(),
 
             drawingarea: builder.get_object("thumbnail-drawingarea").unwrap(),
 
@@ -305,18 +454,53 @@ impl InfoController {
             duration_lbl: builder.get_object("duration-lbl").unwrap(),
 
             timeline_scale: builder.get_object("timeline-scale").unwrap(),
+            waveform_strip: WaveformStrip::new(builder.get_object("waveform-drawingarea").unwrap()),
             repeat_btn: builder.get_object("repeat-toolbutton").unwrap(),
+            repeat_count_spinbtn: builder.get_object("repeat_count-spinbutton").unwrap(),
+            stop_at_chapter_end_btn: builder
+                .get_object("stop_at_chapter_end-toolbutton")
+                .unwrap(),
+            audio_peak_bar: builder.get_object("audio_level-peak-bar").unwrap(),
+            audio_rms_bar: builder.get_object("audio_level-rms-bar").unwrap(),
 
             chapter_treeview,
+            chapter_search_bar,
+            chapter_search_entry,
+            chapter_browsed_at,
             next_chapter_action: gio::SimpleAction::new("next_chapter", None),
             previous_chapter_action: gio::SimpleAction::new("previous_chapter", None),
+            first_chapter_action: gio::SimpleAction::new("first_chapter", None),
+            last_chapter_action: gio::SimpleAction::new("last_chapter", None),
+            seek_to_chapter_action: gio::SimpleAction::new(
+                "seek_to_chapter",
+                Some(&i32::static_variant_type()),
+            ),
+            search_chapters_action: gio::SimpleAction::new("search_chapters", None),
+            step_forward_action: gio::SimpleAction::new("step_forward", None),
+            step_back_action: gio::SimpleAction::new("step_back", None),
+            fast_forward_action: gio::SimpleAction::new("fast_forward", None),
+            rewind_action: gio::SimpleAction::new("rewind", None),
+            save_toc_action: gio::SimpleAction::new("save_toc", None),
 
             thumbnail: None,
 
             chapter_manager,
 
+            toc_chooser_btn: builder.get_object("toc_chooser-btn").unwrap(),
+            toc_chooser_popover: builder.get_object("toc_chooser-popover").unwrap(),
+            toc_chooser_box: builder.get_object("toc_chooser-box").unwrap(),
+            toc_candidates: Vec::new(),
+            toc_idx: None,
+            toc_monitor: None,
+            is_toc_dirty: false,
+            media_path: MediaPath::default(),
+            media_toc: None,
+            is_book: false,
+
             duration: Duration::default(),
-            repeat_chapter: false,
+            is_seekable: false,
+            playback_policy: PlaybackPolicy::default(),
+            segment: None,
         };
 
         ctrl.cleanup();
@@ -332,35 +516,647 @@ impl InfoController {
         ctrl
     }
 
-    pub fn draw_thumbnail(
+    // Displayed when the media contains an image we can't decode
+    fn placeholder_thumbnail() -> Result<Image, String> {
+        gtk::IconTheme::get_default()
+            .and_then(|icon_theme| {
+                icon_theme
+                    .load_icon(
+                        PLACEHOLDER_ICON,
+                        PLACEHOLDER_ICON_SIZE,
+                        gtk::IconLookupFlags::FORCE_SIZE,
+                    )
+                    .ok()
+                    .flatten()
+            })
+            .ok_or_else(|| format!("Couldn't load icon {}", PLACEHOLDER_ICON))
+            .and_then(|pixbuf| Image::from_pixbuf(&pixbuf))
+    }
+
+    fn draw_thumbnail(
         image: &Image,
+        scaled_thumbnail: &RefCell<Option<ScaledThumbnail>>,
         drawingarea: &gtk::DrawingArea,
         cairo_ctx: &cairo::Context,
     ) {
         let allocation = drawingarea.get_allocation();
-        let alloc_width_f: f64 = allocation.width.into();
-        let alloc_height_f: f64 = allocation.height.into();
+        let scale_factor = drawingarea.get_scale_factor();
+
+        let mut scaled_thumbnail = scaled_thumbnail.borrow_mut();
+        let is_up_to_date = scaled_thumbnail.as_ref().map_or(false, |scaled| {
+            scaled.alloc_width == allocation.width
+                && scaled.alloc_height == allocation.height
+                && scaled.scale_factor == scale_factor
+        });
+        if !is_up_to_date {
+            // Only rescale when the allocation or the scale factor change
+            *scaled_thumbnail =
+                Self::scale_thumbnail(image, allocation.width, allocation.height, scale_factor);
+        }
+
+        if let Some(scaled) = scaled_thumbnail.as_ref() {
+            cairo_ctx.set_source_surface(&scaled.surface, scaled.x, scaled.y);
+            cairo_ctx.paint();
+        }
+    }
+
+    fn scale_thumbnail(
+        image: &Image,
+        alloc_width: i32,
+        alloc_height: i32,
+        scale_factor: i32,
+    ) -> Option<ScaledThumbnail> {
+        if alloc_width <= 0 || alloc_height <= 0 || image.width() <= 0 || image.height() <= 0 {
+            return None;
+        }
+
+        let alloc_width_f: f64 = alloc_width.into();
+        let alloc_height_f: f64 = alloc_height.into();
 
         let image_width_f: f64 = image.width().into();
         let image_height_f: f64 = image.height().into();
 
-        let alloc_ratio = alloc_width_f / alloc_height_f;
-        let image_ratio = image_width_f / image_height_f;
-        let scale = if image_ratio < alloc_ratio {
-            alloc_height_f / image_height_f
-        } else {
-            alloc_width_f / image_width_f
-        };
-        let x = (alloc_width_f / scale - image_width_f).abs() / 2f64;
-        let y = (alloc_height_f / scale - image_height_f).abs() / 2f64;
+        let scale = (alloc_width_f / image_width_f).min(alloc_height_f / image_height_f);
+        let width_f = (image_width_f * scale).round().max(1f64);
+        let height_f = (image_height_f * scale).round().max(1f64);
+
+        // Render at the device resolution for HiDPI screens
+        let device_scale: f64 = scale_factor.into();
+        let surface = cairo::ImageSurface::create(
+            cairo::Format::Rgb24,
+            (width_f * device_scale) as i32,
+            (height_f * device_scale) as i32,
+        )
+        .map_err(|err| warn!("Couldn't create thumbnail surface: {:?}", err))
+        .ok()?;
+        surface.set_device_scale(device_scale, device_scale);
+
+        let cr = cairo::Context::new(&surface);
+        image.with_surface_external_context(&cr, |cr, image_surface| {
+            let pattern = cairo::SurfacePattern::create(image_surface);
+            pattern.set_filter(cairo::Filter::Best);
 
-        image.with_surface_external_context(cairo_ctx, |cr, surface| {
             cr.scale(scale, scale);
-            cr.set_source_surface(surface, x, y);
+            cr.set_source(&pattern);
             cr.paint();
+        });
+
+        Some(ScaledThumbnail {
+            alloc_width,
+            alloc_height,
+            scale_factor,
+            x: (alloc_width_f - width_f) / 2f64,
+            y: (alloc_height_f - height_f) / 2f64,
+            surface,
         })
     }
 
+    fn spawn_toc_reading(&self, toc_sources: Vec<PathBuf>, reload_idx: Option<usize>) {
+        let media_path = self.media_path.clone();
+        let duration = self.duration;
+
+        // Toc files might be large or on a slow network mount
+        let ui_event = self.ui_event.sync_sender();
+        thread::spawn(move || {
+            let info = MediaInfo {
+                duration,
+                ..MediaInfo::new(&media_path)
+            };
+            let results = toc_sources
+                .into_iter()
+                .filter(|path| path.is_file())
+                .map(|path| read_toc_candidate(&info, path))
+                .collect();
+
+            ui_event.send(move |ui_event| {
+                ui_event.toc_read(TocReading {
+                    media_path,
+                    reload_idx,
+                    results,
+                })
+            });
+        });
+    }
+
+    /// Shows the `info` cached for a media while the media is being opened.
+    pub fn preview_media(&mut self, info: &MediaInfo) {
+        self.title_lbl
+            .set_label(info.media_title().unwrap_or(EMPTY_REPLACEMENT));
+        self.artist_lbl
+            .set_label(info.media_artist().unwrap_or(EMPTY_REPLACEMENT));
+        self.container_lbl
+            .set_label(info.container().unwrap_or(EMPTY_REPLACEMENT));
+        self.audio_codec_lbl
+            .set_label(info.streams.audio_codec().unwrap_or(EMPTY_REPLACEMENT));
+        self.video_codec_lbl
+            .set_label(info.streams.video_codec().unwrap_or(EMPTY_REPLACEMENT));
+        self.duration_lbl.set_label(
+            &Timestamp4Humans::from_duration(info.duration)
+                .display(CONFIG.read().unwrap().ui.timestamp_display()),
+        );
+
+        // Chapters are not actionable until the media is open
+        self.chapter_manager.replace_with(&info.toc);
+    }
+
+    /// Handles the toc files read by `spawn_toc_reading`.
+    ///
+    /// Returns a question for the user if reloaded chapters differ from the media's.
+    pub fn toc_read(&mut self, reading: TocReading) -> Option<String> {
+        if reading.media_path != self.media_path || self.is_book {
+            // Media changed in the meantime or the book's chapters are shown
+            return None;
+        }
+
+        let mut toc_candidates = Vec::new();
+        for result in reading.results {
+            match result {
+                Ok(candidate) => toc_candidates.push(candidate),
+                Err(TocReadError::NoToc(msg)) => {
+                    info!("{}", msg);
+                    self.ui_event.show_info(msg);
+                }
+                Err(TocReadError::Read(msg)) => self.ui_event.show_error(msg),
+            }
+        }
+
+        match reading.reload_idx {
+            None => self.new_toc_candidates(toc_candidates),
+            Some(reload_idx) => {
+                if self.toc_idx != Some(reload_idx) {
+                    return None;
+                }
+
+                // Keep current chapters if the file can't be read,
+                // it might be in the middle of an edition
+                if let Some(candidate) = toc_candidates.pop() {
+                    let msg = gettext("Reloaded chapters from \"{}\"").replacen(
+                        "{}",
+                        &candidate.path.file_name().unwrap().to_string_lossy(),
+                        1,
+                    );
+                    let toc_diff = self
+                        .media_toc
+                        .as_ref()
+                        .map(|media_toc| TocDiff::new(&candidate.toc, media_toc))
+                        .filter(|toc_diff| !toc_diff.is_empty());
+
+                    self.toc_candidates[reload_idx] = candidate;
+                    self.apply_toc(reload_idx);
+                    // refresh the chapter count
+                    self.update_toc_chooser(reload_idx);
+
+                    match toc_diff {
+                        Some(toc_diff) => return Some(Self::toc_diff_question(msg, &toc_diff)),
+                        None => self.ui_event.show_info(msg),
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn toc_diff_question(msg: String, toc_diff: &TocDiff) -> String {
+        let mut changes = Vec::new();
+        if toc_diff.chapter_count != toc_diff.other_chapter_count {
+            changes.push(
+                ngettext(
+                    "{} chapter instead of {}",
+                    "{} chapters instead of {}",
+                    toc_diff.chapter_count as u32,
+                )
+                .replacen("{}", &toc_diff.chapter_count.to_string(), 1)
+                .replacen("{}", &toc_diff.other_chapter_count.to_string(), 1),
+            );
+        }
+        if toc_diff.changed_titles > 0 {
+            changes.push(
+                ngettext(
+                    "{} title changed",
+                    "{} titles changed",
+                    toc_diff.changed_titles as u32,
+                )
+                .replacen("{}", &toc_diff.changed_titles.to_string(), 1),
+            );
+        }
+        if toc_diff.moved_chapters > 0 {
+            changes.push(
+                ngettext(
+                    "{} chapter moved",
+                    "{} chapters moved",
+                    toc_diff.moved_chapters as u32,
+                )
+                .replacen("{}", &toc_diff.moved_chapters.to_string(), 1),
+            );
+        }
+
+        format!(
+            "{} ({}). {}",
+            msg,
+            changes.join(", "),
+            gettext("Keep them instead of the chapters from the media?"),
+        )
+    }
+
+    fn new_toc_candidates(&mut self, toc_candidates: Vec<TocCandidate>) {
+        self.toc_candidates = toc_candidates;
+        if self.toc_candidates.is_empty() {
+            return;
+        }
+
+        // use the toc file selected by the user the last time this media was opened
+        let toc_idx = CONFIG
+            .read()
+            .unwrap()
+            .media
+            .toc_choices
+            .get(self.media_path.as_path())
+            .and_then(|toc_path| {
+                self.toc_candidates
+                    .iter()
+                    .position(|candidate| &candidate.path == toc_path)
+            })
+            .unwrap_or(0);
+
+        self.apply_toc(toc_idx);
+        self.monitor_toc(toc_idx);
+
+        self.update_toc_chooser(toc_idx);
+        if self.toc_chooser_btn.is_visible() {
+            // let the user know that other toc files are available
+            self.toc_chooser_popover.popup();
+        }
+    }
+
+    fn update_toc_chooser(&self, selected_idx: usize) {
+        for child in self.toc_chooser_box.get_children() {
+            self.toc_chooser_box.remove(&child);
+        }
+
+        if self.toc_candidates.len() < 2 {
+            self.toc_chooser_btn.hide();
+            return;
+        }
+
+        let mut group_leader: Option<gtk::RadioButton> = None;
+        for (idx, candidate) in self.toc_candidates.iter().enumerate() {
+            let chapter_count = candidate.chapter_count();
+            let label = ngettext(
+                "{} ({}, {} chapter)",
+                "{} ({}, {} chapters)",
+                chapter_count as u32,
+            )
+            .replacen(
+                "{}",
+                &candidate.path.file_name().unwrap().to_string_lossy(),
+                1,
+            )
+            .replacen("{}", &candidate.format.to_string(), 1)
+            .replacen("{}", &chapter_count.to_string(), 1);
+
+            let radio_btn = match group_leader {
+                Some(ref group_leader) => {
+                    gtk::RadioButton::with_label_from_widget(group_leader, &label)
+                }
+                None => gtk::RadioButton::with_label(&label),
+            };
+            radio_btn.set_active(idx == selected_idx);
+            radio_btn.connect_toggled(
+                clone!(@strong self.ui_event as ui_event => move |radio_btn| {
+                    if radio_btn.get_active() {
+                        ui_event.select_toc(idx);
+                    }
+                }),
+            );
+            self.toc_chooser_box.pack_start(&radio_btn, false, false, 0);
+
+            if group_leader.is_none() {
+                group_leader = Some(radio_btn);
+            }
+        }
+
+        self.toc_chooser_box.show_all();
+        self.toc_chooser_btn.show();
+    }
+
+    pub fn select_toc(&mut self, idx: usize) {
+        let candidate = match self.toc_candidates.get(idx) {
+            Some(candidate) => candidate,
+            None => return,
+        };
+
+        CONFIG
+            .write()
+            .unwrap()
+            .media
+            .toc_choices
+            .insert(self.media_path.to_path_buf(), candidate.path.clone());
+
+        self.apply_toc(idx);
+        self.monitor_toc(idx);
+
+        self.toc_chooser_popover.popdown();
+    }
+
+    pub fn reload_toc(&self) {
+        let toc_idx = match self.toc_idx {
+            Some(toc_idx) => toc_idx,
+            None => return,
+        };
+
+        let candidate = &self.toc_candidates[toc_idx];
+        self.spawn_toc_reading(vec![candidate.path.clone()], Some(toc_idx));
+    }
+
+    pub fn audio_level(&self, level: &AudioLevel) {
+        let bar_value = |db: f64| {
+            ((db - MIN_AUDIO_LEVEL_DB) / -MIN_AUDIO_LEVEL_DB)
+                .max(0f64)
+                .min(1f64)
+        };
+        self.audio_peak_bar.set_value(bar_value(level.peak));
+        self.audio_rms_bar.set_value(bar_value(level.rms));
+    }
+
+    pub fn reset_audio_level(&self) {
+        self.audio_peak_bar.set_value(0f64);
+        self.audio_rms_bar.set_value(0f64);
+    }
+
+    /// Shows the toc received from the media during playback.
+    ///
+    /// The toc from a toc file, if any, takes precedence.
+    pub fn media_toc_updated(&mut self, toc: gst::Toc) {
+        self.media_toc = Some(toc.clone());
+        if self.toc_idx.is_some() || self.is_book {
+            return;
+        }
+
+        self.show_toc(toc);
+    }
+
+    /// Replaces the chapters from the toc file with the toc from the media.
+    pub fn show_media_toc(&mut self) {
+        let media_toc = match self.media_toc.clone() {
+            Some(media_toc) => media_toc,
+            None => return,
+        };
+
+        // Don't reload the toc file behind the user's back
+        self.unmonitor_toc();
+        self.show_toc(media_toc);
+    }
+
+    /// Shows the `waveform` of the current media under the timeline.
+    pub fn show_waveform(&self, waveform: Waveform) {
+        if self.is_book {
+            return;
+        }
+
+        self.waveform_strip.set_waveform(waveform);
+    }
+
+    /// Shows the chapters of the whole book the current media is a part of.
+    ///
+    /// Timestamps are then expressed in the book, starting with `position`.
+    pub fn show_book(&mut self, duration: Duration, toc: Option<gst::Toc>, position: Timestamp) {
+        self.is_book = true;
+        self.duration = duration;
+        // The waveform only covers the current part
+        self.waveform_strip.cleanup();
+        self.unmonitor_toc();
+        self.toc_candidates.clear();
+        self.update_toc_chooser(0);
+
+        self.timeline_scale.set_range(0f64, duration.as_f64());
+        self.duration_lbl.set_label(
+            &Timestamp4Humans::from_duration(duration)
+                .display(CONFIG.read().unwrap().ui.timestamp_display()),
+        );
+        self.update_position(position);
+
+        if let Some(toc) = toc {
+            self.show_toc(toc);
+        }
+    }
+
+    // Replace the chapters with the toc from the candidate at `idx`
+    fn apply_toc(&mut self, idx: usize) {
+        let toc = self.toc_candidates[idx].toc.clone();
+        self.show_toc(toc);
+    }
+
+    // Replace the chapters with `toc` and select the chapter at current position
+    fn show_toc(&mut self, toc: gst::Toc) {
+        self.set_toc_dirty(false);
+
+        let toc = Some(toc);
+        self.chapter_treeview.get_selection().unselect_all();
+        self.chapter_manager.replace_with(&toc);
+        self.report_continuity_issues();
+        self.update_marks();
+        self.update_chapter_actions();
+        self.ui_event.chapters_changed();
+
+        let ts = Timestamp::new(self.timeline_scale.get_value() as u64);
+        self.chapter_manager.update_ts(ts);
+        if let Some(sel_path) = self.chapter_manager.selected_path() {
+            self.select_chapter(&sel_path);
+        }
+        self.update_chapter_title();
+
+        self.ui_event.update_focus();
+    }
+
+    /// Splits the chapters at `boundaries`.
+    ///
+    /// New chapters get the default title. If there were no chapters,
+    /// a first chapter is added at the beginning of the media.
+    pub fn insert_chapters(&mut self, boundaries: Vec<Timestamp>) {
+        let mut chapters = self.chapter_manager.chapters();
+        let mut inserted = 0;
+        if chapters.is_empty() {
+            chapters.push(TocChapter::new(
+                Duration::default(),
+                metadata::get_default_chapter_title(),
+            ));
+            inserted += 1;
+        }
+
+        for boundary in boundaries {
+            if Self::split_chapters(&mut chapters, Duration::from_nanos(boundary.as_u64())) {
+                inserted += 1;
+            }
+        }
+
+        self.ui_event.show_info(
+            ngettext("Inserted {} chapter", "Inserted {} chapters", inserted).replacen(
+                "{}",
+                &inserted.to_string(),
+                1,
+            ),
+        );
+        if inserted == 0 {
+            return;
+        }
+
+        let mut toc_builder = TocBuilder::new();
+        for chapter in chapters {
+            toc_builder.add_chapter(chapter);
+        }
+        let toc = toc_builder.build(self.duration).unwrap();
+
+        // The chapters no longer reflect the toc file
+        self.unmonitor_toc();
+        self.show_toc(toc);
+    }
+
+    // Inserts a chapter starting at `boundary` next to the deepest chapter which contains it.
+    //
+    // Returns `false` if a chapter already starts at `boundary`.
+    fn split_chapters(chapters: &mut Vec<TocChapter>, boundary: Duration) -> bool {
+        chapters.sort_by_key(|chapter| chapter.start);
+        if chapters.iter().any(|chapter| chapter.start == boundary) {
+            return false;
+        }
+
+        let prev_idx = match chapters
+            .iter()
+            .rposition(|chapter| chapter.start < boundary)
+        {
+            Some(prev_idx) => prev_idx,
+            None => {
+                chapters.insert(
+                    0,
+                    TocChapter::new(boundary, metadata::get_default_chapter_title()),
+                );
+                return true;
+            }
+        };
+
+        let prev = &mut chapters[prev_idx];
+        let is_in_prev = prev.end.map_or(true, |end| boundary < end);
+        if is_in_prev && !prev.children.is_empty() {
+            return Self::split_chapters(&mut prev.children, boundary);
+        }
+
+        let mut chapter = TocChapter::new(boundary, metadata::get_default_chapter_title());
+        if is_in_prev {
+            // The new chapter ends where the one it splits used to
+            chapter.end = prev.end.replace(boundary);
+        }
+        chapters.insert(prev_idx + 1, chapter);
+
+        true
+    }
+
+    fn monitor_toc(&mut self, idx: usize) {
+        self.unmonitor_toc();
+        self.toc_idx = Some(idx);
+
+        let toc_file = gio::File::new_for_path(&self.toc_candidates[idx].path);
+        match toc_file.monitor_file(gio::FileMonitorFlags::NONE, gio::NONE_CANCELLABLE) {
+            Ok(toc_monitor) => {
+                toc_monitor.connect_changed(
+                    clone!(@strong self.ui_event as ui_event => move |_, _, _, event| {
+                        if event == gio::FileMonitorEvent::ChangesDoneHint {
+                            ui_event.reload_toc();
+                        }
+                    }),
+                );
+                self.toc_monitor = Some(toc_monitor);
+            }
+            Err(err) => warn!("Couldn't monitor toc file: {}", err),
+        }
+
+        self.chapter_manager
+            .set_titles_editable(self.is_toc_writable());
+    }
+
+    fn unmonitor_toc(&mut self) {
+        self.toc_idx = None;
+        if let Some(toc_monitor) = self.toc_monitor.take() {
+            toc_monitor.cancel();
+        }
+
+        self.chapter_manager.set_titles_editable(false);
+    }
+
+    // Whether the chapters come from a toc file which can be written back
+    fn is_toc_writable(&self) -> bool {
+        let candidate = match self.toc_idx.and_then(|idx| self.toc_candidates.get(idx)) {
+            Some(candidate) => candidate,
+            None => return false,
+        };
+
+        metadata::Factory::get_writer(candidate.format).is_some()
+            && fs::metadata(&candidate.path)
+                .map_or(false, |metadata| !metadata.permissions().readonly())
+    }
+
+    fn set_toc_dirty(&mut self, is_dirty: bool) {
+        self.is_toc_dirty = is_dirty;
+        self.save_toc_action.set_enabled(is_dirty);
+    }
+
+    /// Renames the chapter at `tree_path` after the user edited its title.
+    pub fn rename_chapter(&mut self, tree_path: &gtk::TreePath, title: &str) {
+        // Nested chapters are indented in the flat view
+        let title = title.trim();
+        if title.is_empty() || !self.chapter_manager.rename(tree_path, title) {
+            return;
+        }
+
+        self.set_toc_dirty(true);
+        self.update_chapter_title();
+        self.ui_event.chapters_changed();
+    }
+
+    /// Writes the renamed chapters back to the toc file they were read from.
+    pub fn save_toc(&mut self) {
+        let toc_idx = match self.toc_idx {
+            Some(toc_idx) if self.is_toc_dirty => toc_idx,
+            _ => return,
+        };
+        let candidate = &self.toc_candidates[toc_idx];
+        let writer = match metadata::Factory::get_writer(candidate.format) {
+            Some(writer) => writer,
+            None => return,
+        };
+        let toc = match self.chapter_manager.toc(self.duration) {
+            Some(toc) => toc,
+            None => return,
+        };
+
+        let file_name = candidate
+            .path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let res = fs::File::create(&candidate.path)
+            .map_err(|err| err.to_string())
+            .and_then(|mut file| writer.write(&toc, &mut file));
+
+        match res {
+            Ok(()) => {
+                self.toc_candidates[toc_idx].toc = toc;
+                self.set_toc_dirty(false);
+                // Don't reload the chapters we just wrote
+                self.monitor_toc(toc_idx);
+
+                self.ui_event
+                    .show_info(gettext("Saved chapters to \"{}\"").replacen("{}", &file_name, 1));
+            }
+            Err(err) => self.ui_event.show_error(
+                gettext("Failed to save chapters to \"{}\":\n{}")
+                    .replacen("{}", &file_name, 1)
+                    .replacen("{}", &err, 1),
+            ),
+        }
+    }
+
     fn update_marks(&self) {
         self.timeline_scale.clear_marks();
 
@@ -368,27 +1164,162 @@ impl InfoController {
         self.chapter_manager.iter().for_each(move |chapter| {
             timeline_scale.add_mark(chapter.start().as_f64(), gtk::PositionType::Top, None);
         });
+
+        self.waveform_strip
+            .set_chapter_starts(self.chapter_manager.iter().map(|chapter| chapter.start()));
+    }
+
+    // Chapter navigation only makes sense when there are chapters & the media can be seeked
+    fn update_chapter_actions(&self) {
+        let has_chapters = self.chapter_manager.iter().next().is_some();
+        let can_seek_chapters = has_chapters && self.is_seekable;
+        self.next_chapter_action.set_enabled(can_seek_chapters);
+        self.previous_chapter_action.set_enabled(can_seek_chapters);
+        self.first_chapter_action.set_enabled(can_seek_chapters);
+        self.last_chapter_action.set_enabled(can_seek_chapters);
+        self.seek_to_chapter_action.set_enabled(can_seek_chapters);
+        self.search_chapters_action.set_enabled(has_chapters);
+        self.flat_chapters_btn
+            .set_sensitive(self.chapter_manager.is_nested());
+    }
+
+    /// Disables the seeking controls when the media doesn't support seeking.
+    fn set_seekable(&mut self, is_seekable: bool) {
+        self.is_seekable = is_seekable;
+        self.timeline_scale.set_sensitive(is_seekable);
+        self.step_forward_action.set_enabled(is_seekable);
+        self.step_back_action.set_enabled(is_seekable);
+        self.fast_forward_action.set_enabled(is_seekable);
+        self.rewind_action.set_enabled(is_seekable);
+
+        let reason = if is_seekable {
+            None
+        } else {
+            Some(gettext("This media doesn't support seeking"))
+        };
+        self.timeline_scale.set_tooltip_text(reason.as_deref());
+        self.chapter_treeview.set_tooltip_text(reason.as_deref());
+    }
+
+    fn report_continuity_issues(&self) {
+        let issues = self.chapter_manager.continuity_issues();
+        if issues.is_empty() {
+            return;
+        }
+
+        let title = |idx: usize| {
+            self.chapter_manager
+                .chapter(idx)
+                .map_or_else(String::new, |chapter| chapter.title().to_owned())
+        };
+
+        let details = issues
+            .iter()
+            .map(|issue| match *issue {
+                ContinuityIssue::Gap { idx, duration } => gettext("Gap of {} before \"{}\"")
+                    .replacen(
+                        "{}",
+                        &Timestamp4Humans::from_duration(duration).to_string(),
+                        1,
+                    )
+                    .replacen("{}", &title(idx), 1),
+                ContinuityIssue::Overlap { idx, duration } => {
+                    gettext("Overlap of {} between \"{}\" and the previous chapter")
+                        .replacen(
+                            "{}",
+                            &Timestamp4Humans::from_duration(duration).to_string(),
+                            1,
+                        )
+                        .replacen("{}", &title(idx), 1)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        self.ui_event.show_warning_with_details(
+            ngettext(
+                "Found {} gap or overlap between chapters",
+                "Found {} gaps or overlaps between chapters",
+                issues.len() as u32,
+            )
+            .replacen("{}", &issues.len().to_string(), 1),
+            details,
+        );
     }
 
     fn repeat_at(&self, ts: Timestamp) {
         self.ui_event.seek(ts, gst::SeekFlags::ACCURATE)
     }
 
-    pub fn tick(&mut self, ts: Timestamp, state: ControllerState) {
+    fn update_position(&self, ts: Timestamp) {
         self.timeline_scale.set_value(ts.as_f64());
-        self.position_lbl
-            .set_text(&Timestamp4Humans::from_nano(ts.as_u64()).to_string());
+        self.waveform_strip.update_position(ts);
+
+        let (position, chapter_countdown) = {
+            let config = CONFIG.read().unwrap();
+            (
+                ts.for_humans().display(config.ui.timestamp_display()),
+                config.ui.chapter_countdown,
+            )
+        };
+        let remaining = chapter_countdown.and_then(|secs| {
+            self.chapter_manager.selected().and_then(|chapter| {
+                chapter
+                    .timestamps()
+                    .countdown(ts, Duration::from_secs(secs))
+            })
+        });
+        match remaining {
+            Some(remaining) => {
+                // Round up so that the countdown doesn't display 0 before the chapter ends
+                let second = Duration::from_secs(1).as_u64();
+                let secs = (remaining.as_u64() + second - 1) / second;
+                self.position_lbl.set_text(
+                    &gettext("{} ({}s left)")
+                        .replacen("{}", &position, 1)
+                        .replacen("{}", &secs.to_string(), 1),
+                );
+            }
+            None => self.position_lbl.set_text(&position),
+        }
+    }
+
+    // The position reported by the pipeline might exceed the duration
+    fn clamp_to_duration(&self, ts: Timestamp) -> Timestamp {
+        if self.duration > Duration::default() && ts > self.duration {
+            self.duration.into()
+        } else {
+            ts
+        }
+    }
+
+    pub fn tick(&mut self, ts: Timestamp, state: ControllerState) {
+        if let Some(segment) = self.segment.as_ref() {
+            if ts >= segment.ts.end {
+                // The chapter end is handled on `SegmentDone`
+                return;
+            }
+        }
+
+        let ts = self.clamp_to_duration(ts);
+        self.update_position(ts);
 
         let mut position_status = self.chapter_manager.update_ts(ts);
 
-        if self.repeat_chapter {
-            // repeat is activated
-            if let ControllerState::EosPlaying = state {
-                // postpone chapter selection change until media has synchronized
-                position_status = PositionStatus::ChapterNotChanged;
-                self.repeat_at(Timestamp::default());
-            } else if let PositionStatus::ChapterChanged { prev_chapter } = &position_status {
-                if let Some(prev_chapter) = prev_chapter {
+        if let PositionStatus::ChapterChanged {
+            prev_chapter: Some(prev_chapter),
+        } = &position_status
+        {
+            if state.is_trick() {
+                // The chapter end policy doesn't apply to the fast preview
+                self.update_chapter_selection(position_status);
+                return;
+            }
+
+            // playback went past the end of the previous chapter
+            match self.playback_policy.chapter_ended(Some(prev_chapter.idx)) {
+                ChapterEndAction::Continue => (),
+                ChapterEndAction::Repeat => {
                     // reset position_status because we will be looping on current chapter
                     let prev_start = prev_chapter.start;
                     position_status = PositionStatus::ChapterNotChanged;
@@ -397,36 +1328,185 @@ impl InfoController {
                     self.chapter_manager.unselect();
                     self.repeat_at(prev_start);
                 }
+                ChapterEndAction::Pause => {
+                    if let ControllerState::Playing = state {
+                        self.ui_event.play_pause();
+                    }
+                }
             }
         }
 
+        self.update_chapter_selection(position_status);
+    }
+
+    pub fn position_finalized(&mut self, end_ts: Timestamp) {
+        self.update_position(end_ts);
+
+        let position_status = self.chapter_manager.update_ts_at_end(end_ts);
+        self.update_chapter_selection(position_status);
+
+        // The last chapter (or the media if there are no chapters) ended
+        let last_chapter = self.chapter_manager.selected_idx();
+        if let ChapterEndAction::Repeat = self.playback_policy.chapter_ended(last_chapter) {
+            let start = self
+                .chapter_manager
+                .selected()
+                .map_or_else(Timestamp::default, |chapter| chapter.start());
+            self.repeat_at(start);
+        }
+    }
+
+    fn update_chapter_selection(&self, position_status: PositionStatus) {
         if let PositionStatus::ChapterChanged { prev_chapter } = position_status {
             // let go the mutable reference on `self.chapter_manager`
-            match self.chapter_manager.selected() {
-                Some(sel_chapter) => {
+            match self.chapter_manager.selected_path() {
+                Some(sel_path) => {
                     // timestamp is in a chapter => select it
-                    self.chapter_treeview
-                        .get_selection()
-                        .select_iter(sel_chapter.iter());
+                    self.select_chapter(&sel_path);
                 }
                 None =>
                 // timestamp is not in any chapter
                 {
-                    if let Some(prev_chapter) = prev_chapter {
-                        // but a previous chapter was selected => unselect it
+                    // but a previous chapter was selected => unselect it
+                    if let Some(prev_path) = prev_chapter
+                        .and_then(|prev_chapter| self.chapter_manager.path(prev_chapter.idx))
+                    {
                         self.chapter_treeview
                             .get_selection()
-                            .unselect_iter(&prev_chapter.iter);
+                            .unselect_path(&prev_path);
                     }
                 }
             }
 
+            self.update_chapter_title();
             self.ui_event.update_focus();
         }
     }
 
-    pub fn seek(&mut self, target: Timestamp, state: ControllerState) {
-        self.tick(target, state);
+    /// Selects the chapter at `sel_path` and scrolls to it as configured.
+    fn select_chapter(&self, sel_path: &gtk::TreePath) {
+        self.chapter_treeview.get_selection().select_path(sel_path);
+
+        let row_align = match CONFIG.read().unwrap().ui.chapter_auto_scroll {
+            ChapterAutoScroll::Center => 0.5f32,
+            ChapterAutoScroll::Top => 0f32,
+            ChapterAutoScroll::Disabled => return,
+        };
+
+        let is_browsing = self.chapter_browsed_at.get().map_or(false, |browsed_at| {
+            browsed_at.elapsed().as_secs() < CHAPTER_BROWSING_TIMEOUT
+        });
+        if is_browsing {
+            return;
+        }
+
+        self.chapter_treeview.scroll_to_cell(
+            Some(sel_path),
+            None::<&gtk::TreeViewColumn>,
+            true,
+            row_align,
+            0f32,
+        );
+    }
+
+    fn update_chapter_title(&self) {
+        self.ui_event.update_chapter_title(
+            self.chapter_manager
+                .selected()
+                .map(|chapter| chapter.title().to_owned()),
+        );
+    }
+
+    /// Returns the chapter to play as a segment when seeking to `target`.
+    ///
+    /// Repeated chapters are played as segments so that they loop
+    /// right at their end instead of when the next tick detects it.
+    pub fn segment_at(&self, target: Timestamp) -> Option<ChapterSegment> {
+        if self.is_book {
+            // The pipeline isn't aware of the positions in the book
+            return None;
+        }
+
+        if let ChapterEndMode::Repeat { .. } = self.playback_policy.mode() {
+            self.chapter_manager
+                .iter()
+                .map(ChapterEntry::timestamps)
+                .enumerate()
+                .find(|(_, ts)| ts.contains(target))
+                .map(|(idx, ts)| ChapterSegment { idx, ts })
+        } else {
+            None
+        }
+    }
+
+    /// Playback reached the end of the chapter played as a segment.
+    ///
+    /// Returns the start and the optional stop of the range to play next.
+    pub fn segment_done(&mut self) -> Option<(Timestamp, Option<Timestamp>)> {
+        let segment = self.segment.take()?;
+        match self.playback_policy.chapter_ended(Some(segment.idx)) {
+            ChapterEndAction::Repeat => {
+                self.segment = Some(segment);
+                Some((segment.ts.start, Some(segment.ts.end)))
+            }
+            action => {
+                if let ChapterEndAction::Pause = action {
+                    self.ui_event.play_pause();
+                }
+
+                // The next chapter might have to be repeated too
+                let end = segment.ts.end;
+                self.segment = self.segment_at(end);
+                Some((end, self.segment.as_ref().map(ChapterSegment::end)))
+            }
+        }
+    }
+
+    pub fn seek(&mut self, target: Timestamp, segment: Option<ChapterSegment>) {
+        self.segment = segment;
+
+        let target = self.clamp_to_duration(target);
+        self.update_position(target);
+
+        // The chapter end policy only applies to the chapters played to the end
+        let position_status = self.chapter_manager.update_ts(target);
+        self.update_chapter_selection(position_status);
+    }
+
+    pub fn toggle_repeat(&mut self, must_repeat: bool) {
+        if must_repeat {
+            self.stop_at_chapter_end_btn.set_active(false);
+            self.playback_policy.set_mode(ChapterEndMode::Repeat {
+                count: self.repeat_count(),
+            });
+        } else if let ChapterEndMode::Repeat { .. } = self.playback_policy.mode() {
+            self.playback_policy.set_mode(ChapterEndMode::Continue);
+        }
+    }
+
+    pub fn repeat_count_changed(&mut self) {
+        if let ChapterEndMode::Repeat { .. } = self.playback_policy.mode() {
+            self.playback_policy.set_mode(ChapterEndMode::Repeat {
+                count: self.repeat_count(),
+            });
+        }
+    }
+
+    // 0 stands for endless repetitions
+    fn repeat_count(&self) -> Option<u32> {
+        match self.repeat_count_spinbtn.get_value_as_int() {
+            count if count > 0 => Some(count as u32),
+            _ => None,
+        }
+    }
+
+    pub fn toggle_stop_at_chapter_end(&mut self, must_stop: bool) {
+        if must_stop {
+            self.repeat_btn.set_active(false);
+            self.playback_policy.set_mode(ChapterEndMode::Stop);
+        } else if let ChapterEndMode::Stop = self.playback_policy.mode() {
+            self.playback_policy.set_mode(ChapterEndMode::Continue);
+        }
     }
 
     pub fn toggle_chapter_list(&self, must_show: bool) {
@@ -439,6 +1519,15 @@ impl InfoController {
         }
     }
 
+    pub fn toggle_flat_chapters(&mut self, is_flat: bool) {
+        CONFIG.write().unwrap().ui.is_chapter_tree_flat = is_flat;
+
+        self.chapter_manager.set_flat(is_flat);
+        if let Some(sel_path) = self.chapter_manager.selected_path() {
+            self.select_chapter(&sel_path);
+        }
+    }
+
     pub fn previous_chapter(&self, cur_ts: Timestamp) -> Option<Timestamp> {
         let cur_start = self
             .chapter_manager
@@ -30,20 +30,138 @@ impl UIDispatcher for InfoDispatcher {
             }
         ));
 
+        // Nested chapters as a tree or in sequential order
+        info_ctrl
+            .flat_chapters_btn
+            .set_active(info_ctrl.chapter_manager.is_flat());
+        info_ctrl.flat_chapters_btn.connect_toggled(clone!(
+            @strong ui_event => move |toggle_button| {
+                ui_event.toggle_flat_chapters(toggle_button.get_active());
+            }
+        ));
+
         // Scale seek
         info_ctrl.timeline_scale.connect_change_value(
             clone!(@strong ui_event => move |_, _, value| {
-                ui_event.seek((value as u64).into(), gst::SeekFlags::KEY_UNIT);
+                ui_event.scrub_move((value as u64).into());
                 Inhibit(true)
             }),
         );
 
+        // Preview while the user drags the scale, audio is muted to avoid glitches
+        info_ctrl.timeline_scale.connect_button_press_event(
+            clone!(@strong ui_event => move |_, _| {
+                ui_event.scrub_start();
+                Inhibit(false)
+            }),
+        );
+        info_ctrl.timeline_scale.connect_button_release_event(
+            clone!(@strong ui_event => move |_, _| {
+                ui_event.scrub_end();
+                Inhibit(false)
+            }),
+        );
+
         // TreeView seek
+        // Enter seeks to the chapter, Shift + Enter also starts playing
         info_ctrl.chapter_treeview.connect_row_activated(
             clone!(@strong ui_event => move |_, tree_path, _| {
                 ui_event.chapter_clicked(tree_path.clone());
             }),
         );
+        info_ctrl.chapter_treeview.connect_key_press_event(
+            clone!(@strong ui_event => move |treeview, event_key| {
+                if event_key.get_keyval() == gdk::keys::constants::F2 {
+                    // Rename the chapter, if the titles are editable
+                    if let (Some(tree_path), _) = treeview.get_cursor() {
+                        treeview.set_cursor(
+                            &tree_path,
+                            treeview.get_expander_column().as_ref(),
+                            true,
+                        );
+                    }
+                    return Inhibit(true);
+                }
+
+                if !Self::is_play_chapter_key(event_key) {
+                    return Inhibit(false);
+                }
+
+                if let (Some(tree_path), _) = treeview.get_cursor() {
+                    ui_event.play_chapter(tree_path);
+                }
+                Inhibit(true)
+            }),
+        );
+
+        // Chapter renaming
+        let title_renderer = info_ctrl.chapter_manager.title_renderer();
+        // Letter keys must reach the entry
+        title_renderer.connect_editing_started(clone!(@strong ui_event => move |_, _, _| {
+            ui_event.temporarily_switch_to(UIFocusContext::TextEntry);
+        }));
+        title_renderer.connect_editing_canceled(clone!(@strong ui_event => move |_| {
+            ui_event.restore_context();
+        }));
+        title_renderer.connect_edited(clone!(@strong ui_event => move |_, tree_path, title| {
+            ui_event.restore_context();
+            ui_event.rename_chapter(tree_path, title);
+        }));
+
+        app.add_action(&info_ctrl.save_toc_action);
+        info_ctrl.save_toc_action.connect_activate(clone!(
+            @strong ui_event => move |_, _| {
+                ui_event.save_toc();
+            }
+        ));
+        app.set_accels_for_action("app.save_toc", &["<Ctrl>S"]);
+
+        // Chapter search
+        // The tree view moves its cursor to the first matching chapter
+        app.add_action(&info_ctrl.search_chapters_action);
+        info_ctrl.search_chapters_action.connect_activate(clone!(
+            @strong info_ctrl.chapter_search_bar as search_bar => move |_, _| {
+                search_bar.set_search_mode(true);
+            }
+        ));
+
+        // Letter keys must reach the entry
+        info_ctrl.chapter_search_entry.connect_focus_in_event(
+            clone!(@strong ui_event => move |_, _| {
+                ui_event.temporarily_switch_to(UIFocusContext::TextEntry);
+                Inhibit(false)
+            }),
+        );
+        info_ctrl.chapter_search_entry.connect_focus_out_event(
+            clone!(@strong ui_event => move |_, _| {
+                ui_event.restore_context();
+                Inhibit(false)
+            }),
+        );
+
+        // The tree view activates the matching chapter on Enter
+        info_ctrl.chapter_search_entry.connect_activate(clone!(
+            @strong info_ctrl.chapter_search_bar as search_bar => move |_| {
+                search_bar.set_search_mode(false);
+            }
+        ));
+        info_ctrl
+            .chapter_search_entry
+            .connect_key_press_event(clone!(
+                @strong ui_event,
+                @strong info_ctrl.chapter_treeview as treeview,
+                @strong info_ctrl.chapter_search_bar as search_bar => move |_, event_key| {
+                    if !Self::is_play_chapter_key(event_key) {
+                        return Inhibit(false);
+                    }
+
+                    if let (Some(tree_path), _) = treeview.get_cursor() {
+                        ui_event.play_chapter(tree_path);
+                    }
+                    search_bar.set_search_mode(false);
+                    Inhibit(true)
+                }
+            ));
 
         // Register Toggle repeat current chapter action
         let toggle_repeat_chapter = gio::SimpleAction::new("toggle_repeat_chapter", None);
@@ -59,6 +177,19 @@ impl UIDispatcher for InfoDispatcher {
                 ui_event.toggle_repeat(button.get_active());
             }));
 
+        info_ctrl
+            .repeat_count_spinbtn
+            .connect_value_changed(clone!(@strong ui_event => move |_| {
+                ui_event.repeat_count_changed();
+            }));
+
+        // Pause at the end of current chapter
+        info_ctrl.stop_at_chapter_end_btn.connect_clicked(
+            clone!(@strong ui_event => move |button| {
+                ui_event.toggle_stop_at_chapter_end(button.get_active());
+            }),
+        );
+
         // Register next chapter action
         app.add_action(&info_ctrl.next_chapter_action);
         info_ctrl
@@ -75,26 +206,65 @@ impl UIDispatcher for InfoDispatcher {
             }
         ));
 
+        // Register first chapter action
+        app.add_action(&info_ctrl.first_chapter_action);
+        info_ctrl
+            .first_chapter_action
+            .connect_activate(clone!(@strong ui_event => move |_, _| {
+                ui_event.first_chapter();
+            }));
+
+        // Register last chapter action
+        app.add_action(&info_ctrl.last_chapter_action);
+        info_ctrl
+            .last_chapter_action
+            .connect_activate(clone!(@strong ui_event => move |_, _| {
+                ui_event.last_chapter();
+            }));
+
+        // Register seek to chapter action
+        // The parameter is the 0-based index of the chapter in the list
+        app.add_action(&info_ctrl.seek_to_chapter_action);
+        info_ctrl.seek_to_chapter_action.connect_activate(clone!(
+            @strong ui_event => move |_, param| {
+                let idx = param.and_then(|param| param.get::<i32>());
+                if let Some(idx) = idx.filter(|idx| *idx >= 0) {
+                    ui_event.seek_to_chapter(idx as usize);
+                }
+            }
+        ));
+
         // Register Step forward action
-        let step_forward = gio::SimpleAction::new("step_forward", None);
-        app.add_action(&step_forward);
-        step_forward.connect_activate(clone!(
+        app.add_action(&info_ctrl.step_forward_action);
+        info_ctrl.step_forward_action.connect_activate(clone!(
             @strong ui_event => move |_, _| {
                 ui_event.step_forward();
             }
         ));
-        app.set_accels_for_action("app.step_forward", &["Right"]);
 
         // Register Step back action
-        let step_back = gio::SimpleAction::new("step_back", None);
-        app.add_action(&step_back);
-        step_back.connect_activate(clone!(
+        app.add_action(&info_ctrl.step_back_action);
+        info_ctrl.step_back_action.connect_activate(clone!(
             @strong ui_event => move |_, _| {
                 ui_event.step_back();
             }
         ));
 
-        app.set_accels_for_action("app.step_back", &["Left"]);
+        // Register Fast forward action
+        app.add_action(&info_ctrl.fast_forward_action);
+        info_ctrl.fast_forward_action.connect_activate(clone!(
+            @strong ui_event => move |_, _| {
+                ui_event.fast_forward();
+            }
+        ));
+
+        // Register Rewind action
+        app.add_action(&info_ctrl.rewind_action);
+        info_ctrl.rewind_action.connect_activate(clone!(
+            @strong ui_event => move |_, _| {
+                ui_event.rewind();
+            }
+        ));
     }
 
     fn bind_accels_for(ctx: UIFocusContext, app: &gtk::Application) {
@@ -102,15 +272,72 @@ impl UIDispatcher for InfoDispatcher {
             UIFocusContext::PlaybackPage => {
                 app.set_accels_for_action("app.toggle_show_list", &["l"]);
                 app.set_accels_for_action("app.toggle_repeat_chapter", &["r"]);
+                app.set_accels_for_action("app.first_chapter", &["Home"]);
+                app.set_accels_for_action("app.last_chapter", &["End"]);
+                app.set_accels_for_action("app.search_chapters", &["<Ctrl>F"]);
+                Self::bind_seek_to_chapter_accels(app, true);
+                Self::bind_step_accels(app, true);
             }
             UIFocusContext::StreamsPage => {
                 app.set_accels_for_action("app.toggle_show_list", &["l"]);
                 app.set_accels_for_action("app.toggle_repeat_chapter", &["r"]);
+                app.set_accels_for_action("app.first_chapter", &[]);
+                app.set_accels_for_action("app.last_chapter", &[]);
+                app.set_accels_for_action("app.search_chapters", &[]);
+                Self::bind_seek_to_chapter_accels(app, true);
+                Self::bind_step_accels(app, true);
             }
             UIFocusContext::InfoBar => {
                 app.set_accels_for_action("app.toggle_show_list", &[]);
                 app.set_accels_for_action("app.toggle_repeat_chapter", &[]);
+                app.set_accels_for_action("app.first_chapter", &[]);
+                app.set_accels_for_action("app.last_chapter", &[]);
+                app.set_accels_for_action("app.search_chapters", &[]);
+                Self::bind_seek_to_chapter_accels(app, false);
+                Self::bind_step_accels(app, true);
+            }
+            UIFocusContext::TextEntry => {
+                app.set_accels_for_action("app.toggle_show_list", &[]);
+                app.set_accels_for_action("app.toggle_repeat_chapter", &[]);
+                app.set_accels_for_action("app.first_chapter", &[]);
+                app.set_accels_for_action("app.last_chapter", &[]);
+                app.set_accels_for_action("app.search_chapters", &[]);
+                Self::bind_seek_to_chapter_accels(app, false);
+                // Left & Right move the cursor in the entry
+                Self::bind_step_accels(app, false);
             }
         }
     }
 }
+
+impl InfoDispatcher {
+    fn is_play_chapter_key(event_key: &gdk::EventKey) -> bool {
+        let keyval = event_key.get_keyval();
+        (keyval == gdk::keys::constants::Return || keyval == gdk::keys::constants::KP_Enter)
+            && event_key
+                .get_state()
+                .contains(gdk::ModifierType::SHIFT_MASK)
+    }
+
+    fn bind_step_accels(app: &gtk::Application, is_enabled: bool) {
+        let (forward, back, fast_forward, rewind): (&[&str], &[&str], &[&str], &[&str]) =
+            if is_enabled {
+                (&["Right"], &["Left"], &["<Shift>Right"], &["<Shift>Left"])
+            } else {
+                (&[], &[], &[], &[])
+            };
+        app.set_accels_for_action("app.step_forward", forward);
+        app.set_accels_for_action("app.step_back", back);
+        app.set_accels_for_action("app.fast_forward", fast_forward);
+        app.set_accels_for_action("app.rewind", rewind);
+    }
+
+    // Keys 1 to 9 jump to the 1st to 9th chapters
+    fn bind_seek_to_chapter_accels(app: &gtk::Application, is_enabled: bool) {
+        for nb in 1..=9 {
+            let accel = nb.to_string();
+            let accels: &[&str] = if is_enabled { &[accel.as_str()] } else { &[] };
+            app.set_accels_for_action(&format!("app.seek_to_chapter({})", nb - 1), accels);
+        }
+    }
+}
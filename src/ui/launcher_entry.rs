@@ -0,0 +1,71 @@
+//! Displays the playback progress on the application icon in docks & taskbars.
+//!
+//! This uses the Unity `LauncherEntry` D-Bus API which is also supported by
+//! Plank, Dash to Dock, KDE Plasma's task manager, ...
+
+use glib::ToVariant;
+use log::debug;
+
+const INTERFACE: &str = "com.canonical.Unity.LauncherEntry";
+const UPDATE_SIGNAL: &str = "Update";
+// Avoid flooding the bus: only notify when the progress changes by this step
+const PROGRESS_STEP: f64 = 0.005;
+
+pub struct LauncherEntry {
+    connection: Option<gio::DBusConnection>,
+    app_uri: String,
+    object_path: String,
+    progress: Option<f64>,
+}
+
+impl LauncherEntry {
+    pub fn new(app_id: &str) -> Self {
+        let connection = gio::bus_get_sync(gio::BusType::Session, gio::NONE_CANCELLABLE)
+            .map_err(|err| debug!("LauncherEntry: no session bus: {}", err))
+            .ok();
+
+        LauncherEntry {
+            connection,
+            app_uri: format!("application://{}.desktop", app_id),
+            object_path: format!(
+                "/{}/LauncherEntry",
+                app_id.replace('.', "/").replace('-', "_")
+            ),
+            progress: None,
+        }
+    }
+
+    /// Sets the progress in the range `[0, 1]`, `None` hides the progress bar.
+    pub fn set_progress(&mut self, progress: Option<f64>) {
+        let progress = progress.map(|progress| progress.max(0f64).min(1f64));
+        let is_changed = match (self.progress, progress) {
+            (Some(prev), Some(progress)) => (progress - prev).abs() >= PROGRESS_STEP,
+            (None, None) => false,
+            _ => true,
+        };
+        if !is_changed {
+            return;
+        }
+        self.progress = progress;
+
+        let connection = match self.connection.as_ref() {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        let properties = glib::VariantDict::new(None);
+        properties.insert_value("progress", &progress.unwrap_or(0f64).to_variant());
+        properties.insert_value("progress-visible", &progress.is_some().to_variant());
+        let parameters = (self.app_uri.as_str(), properties.end()).to_variant();
+
+        if let Err(err) = connection.emit_signal(
+            None,
+            &self.object_path,
+            INTERFACE,
+            UPDATE_SIGNAL,
+            Some(&parameters),
+        ) {
+            debug!("LauncherEntry: couldn't update: {}", err);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use gettextrs::gettext;
+use gtk::prelude::*;
+
+use crate::application::LOGS;
+
+const GST_DEBUG_LEVELS: &[(gst::DebugLevel, &str)] = &[
+    (gst::DebugLevel::None, "none"),
+    (gst::DebugLevel::Error, "error"),
+    (gst::DebugLevel::Warning, "warning"),
+    (gst::DebugLevel::Info, "info"),
+    (gst::DebugLevel::Debug, "debug"),
+];
+
+fn logs_text() -> String {
+    let logs = LOGS.lock().unwrap();
+    if logs.is_empty() {
+        return String::new();
+    }
+
+    let mut text = logs
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join("\n");
+    text.push('\n');
+
+    text
+}
+
+pub fn show(window: &gtk::ApplicationWindow) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&gettext("Logs")),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (&gettext("Clear"), gtk::ResponseType::Reject),
+            (&gettext("Copy"), gtk::ResponseType::Apply),
+            (&gettext("Close"), gtk::ResponseType::Close),
+        ],
+    );
+    dialog.set_default_size(700, 400);
+
+    let content_area = dialog.get_content_area();
+    content_area.set_spacing(6);
+
+    let textview = gtk::TextView::new();
+    textview.set_editable(false);
+    textview.set_cursor_visible(false);
+    textview.set_monospace(true);
+    textview.get_buffer().unwrap().set_text(&logs_text());
+
+    let scrolled_window = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+    scrolled_window.set_vexpand(true);
+    scrolled_window.add(&textview);
+    content_area.add(&scrolled_window);
+
+    // GStreamer debug level
+    let gst_level_box = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+    gst_level_box.set_margin_start(6);
+    gst_level_box.set_margin_end(6);
+    gst_level_box.pack_start(
+        &gtk::Label::new(Some(&gettext("GStreamer debug level"))),
+        false,
+        false,
+        0,
+    );
+
+    let gst_level_cbx = gtk::ComboBoxText::new();
+    gst_level_cbx.set_tooltip_text(Some(&gettext(
+        "Warnings and errors are listed above, other messages are printed on the terminal",
+    )));
+    for (_, name) in GST_DEBUG_LEVELS {
+        gst_level_cbx.append(Some(name), name);
+    }
+    let threshold = gst::debug_get_default_threshold();
+    if let Some((_, name)) = GST_DEBUG_LEVELS
+        .iter()
+        .find(|(level, _)| *level == threshold)
+    {
+        gst_level_cbx.set_active_id(Some(name));
+    }
+    gst_level_cbx.connect_changed(|gst_level_cbx| {
+        let level = gst_level_cbx.get_active_id().and_then(|id| {
+            GST_DEBUG_LEVELS
+                .iter()
+                .find(|(_, name)| *name == id.as_str())
+                .map(|(level, _)| *level)
+        });
+        if let Some(level) = level {
+            gst::debug_set_active(level != gst::DebugLevel::None);
+            gst::debug_set_default_threshold(level);
+        }
+    });
+    gst_level_box.pack_start(&gst_level_cbx, false, false, 0);
+    content_area.add(&gst_level_box);
+
+    dialog.connect_response(move |dialog, response| match response {
+        gtk::ResponseType::Apply => {
+            let clipboard = gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+            clipboard.set_text(&logs_text());
+        }
+        gtk::ResponseType::Reject => {
+            LOGS.lock().unwrap().clear();
+            textview.get_buffer().unwrap().set_text("");
+        }
+        _ => dialog.close(),
+    });
+
+    dialog.show_all();
+}
@@ -1,38 +1,116 @@
 use futures::future::{abortable, AbortHandle, LocalBoxFuture};
 use futures::prelude::*;
 
+use gdk::WindowExt;
+
 use gettextrs::{gettext, ngettext};
 
-use glib::clone;
+use gio::prelude::*;
 use gtk::prelude::*;
 
-use log::error;
-
-use std::{borrow::ToOwned, cell::RefCell, path::PathBuf, rc::Rc, sync::Arc};
+use log::{debug, error, warn};
+
+use std::{
+    borrow::ToOwned,
+    cell::RefCell,
+    collections::VecDeque,
+    fs,
+    path::PathBuf,
+    process::Command,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
 
 use crate::{
-    application::{CommandLineArguments, APP_ID, APP_PATH, CONFIG},
+    application::{
+        capture_gst_logs, unix_now, CommandLineArguments, MediaCache, SeekHistory, WaveformCache,
+        APP_DIRS, APP_ID, APP_NAME, APP_PATH, CONFIG, HISTORY,
+    },
     media::{
-        MediaMessage, MissingPlugins, PlaybackPipeline, SeekError, SelectStreamsError, Timestamp,
+        media_probe, silence_detector,
+        snapshot::{self, SnapshotTarget},
+        waveform, AudioLevel, AudioOutputMonitor, Backend, MediaMessage, MissingPlugins,
+        PlaybackOptions, PlaybackPipeline, SeekError, SegmentDone, SelectStreamsError,
+        SubtitleStyle, Timestamp, VideoDecoding, Waveform,
     },
+    metadata::{playlist, Duration, MediaPath, PlaylistFormat, StreamSelectionDelta, VirtualBook},
 };
 
 use super::{
-    spawn, ui_event, InfoController, MainDispatcher, PerspectiveController, StreamsController,
-    UIController, UIEventSender, VideoController,
+    history_dialog, info_controller, logs_dialog, preferences_dialog, silence_dialog, spawn,
+    ui_event::{self, Reply},
+    ControllerState, DBusService, InfoController, LauncherEntry, MainDispatcher, MediaChooser,
+    MediaInfoExporter, MiniPlayerController, Mpris, MprisChapter, PerspectiveController,
+    PlaybackEvent, PlaybackStateMachine, SeekSerializer, StreamsController, Transition,
+    UIController, UIEventSender, VideoController, TRICK_SPEEDS,
 };
 
 const PAUSE_ICON: &str = "media-playback-pause-symbolic";
 const PLAYBACK_ICON: &str = "media-playback-start-symbolic";
+const SCRUB_SEEK_PERIOD: u128 = 150; // 150 ms
+
+// Jumps in a row within this period are recorded as one in the seek history
+const JUMP_COALESCING_PERIOD: u128 = 3_000; // 3 s
+
+const AUDIO_OFFSET_STEP: i64 = 25_000_000; // 25 ms
+const AUDIO_OFFSET_MAX: i64 = 5_000_000_000; // 5 s
+
+/// Timeline dragging in progress.
+#[derive(Debug, Default)]
+struct Scrubbing {
+    origin: Option<Timestamp>,
+    last_seek: Option<Instant>,
+    target: Option<Timestamp>,
+}
+
+impl Scrubbing {
+    /// Registers the new `target` and tells whether it's time to seek.
+    fn must_seek(&mut self, target: Timestamp) -> bool {
+        self.target = Some(target);
+
+        let must_seek = self.last_seek.map_or(true, |last_seek| {
+            last_seek.elapsed().as_millis() >= SCRUB_SEEK_PERIOD
+        });
+        if must_seek {
+            self.last_seek = Some(Instant::now());
+        }
+
+        must_seek
+    }
+}
+
+/// Outcome of the silence detection performed off the UI thread.
+#[derive(Debug)]
+pub struct SilenceDetection {
+    is_cancelled: Arc<AtomicBool>,
+    result: Result<Vec<Timestamp>, String>,
+}
+
+/// Outcome of the snapshot capture performed off the UI thread.
+#[derive(Debug)]
+pub struct SnapshotCapture {
+    is_cancelled: Arc<AtomicBool>,
+    result: Result<Vec<PathBuf>, String>,
+}
+
+/// Outcome of the waveform generation performed off the UI thread.
+#[derive(Debug)]
+pub struct WaveformGeneration {
+    media_path: MediaPath,
+    is_cancelled: Arc<AtomicBool>,
+    result: Result<Waveform, String>,
+}
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum ControllerState {
-    EosPaused,
-    EosPlaying,
-    Paused,
-    PendingSelectMediaDecision,
-    Playing,
-    Stopped,
+/// Outcome of the probing of a book's files performed off the UI thread.
+#[derive(Debug)]
+pub struct BookProbing {
+    dir: MediaPath,
+    result: Result<VirtualBook, String>,
 }
 
 pub struct MainController {
@@ -40,10 +118,19 @@ pub struct MainController {
     pub(super) window_delete_id: Option<glib::signal::SignalHandlerId>,
 
     header_bar: gtk::HeaderBar,
+    paned: gtk::Paned,
     pub(super) open_btn: gtk::Button,
     pub(super) display_page: gtk::Box,
     pub(super) play_pause_btn: gtk::ToolButton,
-    file_dlg: gtk::FileChooserNative,
+    pub(super) close_media_action: gio::SimpleAction,
+    pub(super) detect_silences_action: gio::SimpleAction,
+    pub(super) export_media_info_action: gio::SimpleAction,
+    pub(super) go_back_action: gio::SimpleAction,
+    pub(super) go_forward_action: gio::SimpleAction,
+    pub(super) take_chapter_snapshots_action: gio::SimpleAction,
+    pub(super) take_snapshot_action: gio::SimpleAction,
+    media_chooser: MediaChooser,
+    media_info_exporter: MediaInfoExporter,
 
     pub(super) ui_event: UIEventSender,
 
@@ -51,18 +138,48 @@ pub struct MainController {
     pub(super) video_ctrl: VideoController,
     pub(super) info_ctrl: InfoController,
     pub(super) streams_ctrl: StreamsController,
+    mini_player_ctrl: MiniPlayerController,
 
     pub(super) pipeline: Option<PlaybackPipeline>,
-    pub(super) state: ControllerState,
+    pub(super) state: PlaybackStateMachine,
+    pending_resume: Option<Timestamp>,
+    // The audio device was lost while playing
+    is_playing_on_device_loss: bool,
+    queue: VecDeque<MediaPath>,
+    book: Option<VirtualBook>,
+    book_probing: Option<MediaPath>,
+    silence_detection: Option<Arc<AtomicBool>>,
+    snapshot_capture: Option<Arc<AtomicBool>>,
+    waveform_generation: Option<Arc<AtomicBool>>,
+    scrubbing: Option<Scrubbing>,
+    seek_serializer: SeekSerializer,
+    last_jump: Option<Instant>,
 
     media_msg_abort_handle: Option<AbortHandle>,
+    media_monitor: Option<gio::FileMonitor>,
+
+    launcher_entry: LauncherEntry,
+    mpris: Mpris,
+    dbus_service: Option<DBusService>,
+    audio_output_monitor: Option<AudioOutputMonitor>,
+    // Hardware decoding failed in `VideoDecoding::Auto` mode during this session
+    is_hardware_decoding_broken: bool,
+    backend: Backend,
+    // Plugins missing to decode the current media
+    missing_plugins: MissingPlugins,
 
     pub(super) new_tracker: Option<Box<dyn Fn() -> LocalBoxFuture<'static, ()>>>,
     tracker_abort_handle: Option<AbortHandle>,
+
+    #[cfg(feature = "remote")]
+    remote: Option<crate::remote::Remote>,
 }
 
 impl MainController {
-    pub fn setup(app: &gtk::Application, args: &CommandLineArguments) {
+    pub fn setup(
+        app: &gtk::Application,
+        args: &CommandLineArguments,
+    ) -> Rc<RefCell<MainController>> {
         let builder =
             gtk::Builder::from_resource(&format!("{}/{}", *APP_PATH, "media-toc-player.ui"));
 
@@ -72,26 +189,6 @@ impl MainController {
 
         let (ui_event, ui_event_receiver) = ui_event::new_pair();
 
-        let file_dlg = gtk::FileChooserNativeBuilder::new()
-            .title(&gettext("Open a media file"))
-            .transient_for(&window)
-            .modal(true)
-            .accept_label(&gettext("Open"))
-            .cancel_label(&gettext("Cancel"))
-            .build();
-
-        file_dlg.connect_response(clone!(@strong ui_event => move |file_dlg, response| {
-            file_dlg.hide();
-            if response == gtk::ResponseType::Accept {
-                if let Some(path) = file_dlg.get_filename() {
-                    ui_event.open_media(path);
-                    return;
-                }
-            }
-
-            ui_event.cancel_select_media();
-        }));
-
         let gst_init_res = gst::init();
 
         let main_ctrl_rc = Rc::new(RefCell::new(MainController {
@@ -99,10 +196,19 @@ impl MainController {
             window_delete_id: None,
 
             header_bar: builder.get_object("header-bar").unwrap(),
+            paned: builder.get_object("main-paned").unwrap(),
             open_btn: builder.get_object("open-btn").unwrap(),
             display_page: builder.get_object("video-container").unwrap(),
             play_pause_btn: builder.get_object("play_pause-toolbutton").unwrap(),
-            file_dlg,
+            close_media_action: gio::SimpleAction::new("close_media", None),
+            detect_silences_action: gio::SimpleAction::new("detect_silences", None),
+            export_media_info_action: gio::SimpleAction::new("export_media_info", None),
+            go_back_action: gio::SimpleAction::new("go_back", None),
+            go_forward_action: gio::SimpleAction::new("go_forward", None),
+            take_chapter_snapshots_action: gio::SimpleAction::new("take_chapter_snapshots", None),
+            take_snapshot_action: gio::SimpleAction::new("take_snapshot", None),
+            media_chooser: MediaChooser::new(&window),
+            media_info_exporter: MediaInfoExporter::new(&window, &ui_event),
 
             ui_event: ui_event.clone(),
 
@@ -110,14 +216,38 @@ impl MainController {
             video_ctrl: VideoController::new(&builder, args),
             info_ctrl: InfoController::new(&builder, ui_event.clone()),
             streams_ctrl: StreamsController::new(&builder),
+            mini_player_ctrl: MiniPlayerController::new(&builder, &window),
 
             pipeline: None,
-            state: ControllerState::Stopped,
+            state: PlaybackStateMachine::default(),
+            pending_resume: None,
+            is_playing_on_device_loss: false,
+            queue: VecDeque::new(),
+            book: None,
+            book_probing: None,
+            silence_detection: None,
+            snapshot_capture: None,
+            waveform_generation: None,
+            scrubbing: None,
+            seek_serializer: SeekSerializer::default(),
+            last_jump: None,
 
             media_msg_abort_handle: None,
+            media_monitor: None,
+
+            launcher_entry: LauncherEntry::new(&APP_ID),
+            mpris: Mpris::new(&APP_ID, &APP_NAME, ui_event.sync_sender()),
+            dbus_service: None,
+            audio_output_monitor: None,
+            is_hardware_decoding_broken: false,
+            backend: args.backend,
+            missing_plugins: MissingPlugins::default(),
 
             new_tracker: None,
             tracker_abort_handle: None,
+
+            #[cfg(feature = "remote")]
+            remote: None,
         }));
 
         let mut main_ctrl = main_ctrl_rc.borrow_mut();
@@ -131,15 +261,39 @@ impl MainController {
         );
 
         if gst_init_res.is_ok() {
+            capture_gst_logs();
+
             {
                 let config = CONFIG.read().unwrap();
                 if config.ui.width > 0 && config.ui.height > 0 {
                     main_ctrl.window.resize(config.ui.width, config.ui.height);
                 }
+                if config.ui.paned_pos > 0 {
+                    main_ctrl.paned.set_position(config.ui.paned_pos);
+                }
+                if config.ui.is_maximized {
+                    main_ctrl.window.maximize();
+                }
+                if config.ui.is_fullscreen {
+                    main_ctrl.window.fullscreen();
+                }
 
                 main_ctrl.open_btn.set_sensitive(true);
             }
 
+            main_ctrl.monitor_audio_output();
+
+            if CONFIG.read().unwrap().ui.is_dbus_service_enabled {
+                main_ctrl.dbus_service = DBusService::start(ui_event.sync_sender());
+            }
+
+            #[cfg(feature = "remote")]
+            {
+                if let Some(remote_args) = args.remote.as_ref() {
+                    main_ctrl.spawn_remote(remote_args);
+                }
+            }
+
             ui_event.show_all();
 
             if let Some(input_file) = args.input_file.to_owned() {
@@ -148,6 +302,122 @@ impl MainController {
         } else {
             ui_event.show_all();
         }
+
+        drop(main_ctrl);
+        main_ctrl_rc
+    }
+
+    #[cfg(feature = "remote")]
+    fn spawn_remote(&mut self, args: &crate::application::RemoteArguments) {
+        use crate::remote::{Remote, RemoteCommand};
+
+        // Commands are received on the remote's threads
+        let ui_event = self.ui_event.sync_sender();
+        let on_command = move |command: RemoteCommand| {
+            ui_event.send(move |ui_event| match command {
+                RemoteCommand::PlayPause => ui_event.play_pause(),
+                RemoteCommand::Seek { position } => {
+                    ui_event.seek(Timestamp::new(position), gst::SeekFlags::ACCURATE)
+                }
+                RemoteCommand::NextChapter => ui_event.next_chapter(),
+                RemoteCommand::PreviousChapter => ui_event.previous_chapter(),
+                RemoteCommand::SeekToChapter { index } => ui_event.seek_to_chapter(index),
+            });
+        };
+
+        match Remote::spawn(args.address, args.port, args.token.clone(), on_command) {
+            Ok(remote) => self.remote = Some(remote),
+            Err(err) => {
+                self.ui_event
+                    .show_error(gettext("Couldn't start the remote control: {}").replacen(
+                        "{}",
+                        &err.to_string(),
+                        1,
+                    ))
+            }
+        }
+    }
+
+    #[cfg(feature = "remote")]
+    fn update_remote(&self, position: Option<Timestamp>) {
+        use crate::remote::RemoteState;
+
+        let remote = match self.remote.as_ref() {
+            Some(remote) => remote,
+            None => return,
+        };
+
+        let pipeline = match self.pipeline.as_ref() {
+            Some(pipeline) => pipeline,
+            None => {
+                remote.update(|state| *state = RemoteState::default());
+                return;
+            }
+        };
+
+        remote.update(|state| {
+            state.file_name = Some(pipeline.info.file_name.clone());
+            state.is_playing = self.state.get() == ControllerState::Playing;
+            if let Some(position) = position {
+                state.position = position.as_u64();
+            }
+            state.duration = self.duration().as_u64();
+            state.chapter = self.selected_chapter_idx();
+        });
+    }
+
+    // The chapters are only rebuilt when the toc changes
+    #[cfg(feature = "remote")]
+    fn update_remote_chapters(&self) {
+        use crate::remote::RemoteChapter;
+
+        let remote = match self.remote.as_ref() {
+            Some(remote) => remote,
+            None => return,
+        };
+
+        let chapter_manager = &self.info_ctrl.chapter_manager;
+        remote.update(|state| {
+            state.chapters = chapter_manager
+                .iter()
+                .map(|chapter| RemoteChapter {
+                    title: chapter.title().to_owned(),
+                    start: chapter.start().as_u64(),
+                    end: chapter.timestamps().end.as_u64(),
+                })
+                .collect();
+            state.chapter = self.selected_chapter_idx();
+        });
+    }
+
+    // MPRIS & remote clients can't access the tags, so the cover art is exported to a file
+    fn export_cover_art(&self) {
+        let cover_art = self
+            .pipeline
+            .as_ref()
+            .and_then(|pipeline| pipeline.info.cover_art.as_ref());
+        let cover_art_uri = cover_art.and_then(|cover_art| {
+            let cache_dir = &APP_DIRS.cache;
+            let path = cache_dir
+                .join("cover")
+                .with_extension(cover_art.extension().unwrap_or("img"));
+            fs::create_dir_all(cache_dir)
+                .and_then(|_| fs::write(&path, &cover_art.data))
+                .map_err(|err| error!("Couldn't export the cover art: {}", err))
+                .ok()?;
+
+            glib::filename_to_uri(&path, None)
+                .ok()
+                .map(|uri| uri.to_string())
+        });
+
+        self.mpris.set_art_url(cover_art_uri.clone());
+        #[cfg(feature = "remote")]
+        {
+            if let Some(remote) = self.remote.as_ref() {
+                remote.update(|state| state.cover_art_uri = cover_art_uri);
+            }
+        }
     }
 
     pub fn ui_event(&self) -> &UIEventSender {
@@ -169,23 +439,159 @@ impl MainController {
         dialog.set_website(Some(env!("CARGO_PKG_HOMEPAGE")));
         dialog.set_website_label(Some(&gettext("Learn more about media-toc-player")));
 
+        let mut system_information = format!(
+            "{}\n{}\n{}",
+            gettext("Configuration: {}").replacen("{}", &APP_DIRS.config.to_string_lossy(), 1),
+            gettext("History: {}").replacen("{}", &APP_DIRS.data.to_string_lossy(), 1),
+            gettext("Cache: {}").replacen("{}", &APP_DIRS.cache.to_string_lossy(), 1),
+        );
+        if APP_DIRS.is_portable {
+            system_information.push_str("\n");
+            system_information.push_str(&gettext("Portable mode"));
+        }
+        dialog.set_system_information(Some(&system_information));
+
         dialog.connect_response(|dialog, _| dialog.close());
         dialog.show();
     }
 
+    // Keep track of the window layout for next launch
+    fn save_window_state(&self) {
+        self.mini_player_ctrl.save_geometry();
+
+        let mut config = CONFIG.write().unwrap();
+
+        let window_state = self
+            .window
+            .get_window()
+            .map_or_else(gdk::WindowState::empty, |gdk_window| gdk_window.get_state());
+        config.ui.is_maximized = window_state.contains(gdk::WindowState::MAXIMIZED);
+        config.ui.is_fullscreen = window_state.contains(gdk::WindowState::FULLSCREEN);
+        if let Some((width, height)) = self.mini_player_ctrl.full_size() {
+            // The mini player geometry is saved separately
+            config.ui.width = width;
+            config.ui.height = height;
+        } else if !config.ui.is_maximized && !config.ui.is_fullscreen {
+            // Only keep track of the size for the regular state
+            let size = self.window.get_size();
+            config.ui.width = size.0;
+            config.ui.height = size.1;
+        }
+
+        if !config.ui.is_chapters_list_hidden {
+            config.ui.paned_pos = self.paned.get_position();
+        }
+        config.ui.perspective = self.perspective_ctrl.selected();
+    }
+
+    // Keep playing in the background until the window is shown again
+    pub fn hide(&mut self) {
+        self.save_window_state();
+        self.window.hide();
+    }
+
+    // Playback is over, so there's no reason to stay in the background
+    fn show_if_hidden(&self) {
+        if !self.window.is_visible() {
+            self.window.present();
+        }
+    }
+
+    pub fn preferences(&self) {
+        preferences_dialog::show(&self.window, &self.ui_event);
+    }
+
+    pub fn max_video_height_changed(&self) {
+        if let Some(pipeline) = self.pipeline.as_ref() {
+            pipeline.set_max_video_height(CONFIG.read().unwrap().media.max_video_height);
+        }
+    }
+
+    fn subtitle_style() -> SubtitleStyle {
+        let subtitles = &CONFIG.read().unwrap().media.subtitles;
+        SubtitleStyle {
+            font_desc: Some(subtitles.font_desc.clone()),
+            is_outline_drawn: subtitles.is_outline_drawn,
+        }
+    }
+
+    pub fn subtitle_style_changed(&self) {
+        if let Some(pipeline) = self.pipeline.as_ref() {
+            pipeline.set_subtitle_style(Self::subtitle_style());
+        }
+    }
+
+    pub fn history(&self) {
+        history_dialog::show(&self.window, &self.ui_event);
+    }
+
+    pub fn logs(&self) {
+        logs_dialog::show(&self.window);
+    }
+
+    /// Writes the pipeline graph to the cache dir and opens it.
+    ///
+    /// The graph is rendered as a PNG if Graphviz' `dot` is available.
+    pub fn dump_pipeline(&self) {
+        let pipeline = match self.pipeline.as_ref() {
+            Some(pipeline) => pipeline,
+            None => return,
+        };
+
+        let cache_dir = &APP_DIRS.cache;
+        let dot_path = cache_dir.join("pipeline.dot");
+        let res =
+            fs::create_dir_all(cache_dir).and_then(|_| fs::write(&dot_path, pipeline.dot_graph()));
+        if let Err(err) = res {
+            self.ui_event
+                .show_error(gettext("Couldn't write the pipeline graph: {}").replacen(
+                    "{}",
+                    &err.to_string(),
+                    1,
+                ));
+            return;
+        }
+
+        // Rendering large graphs can take a while
+        let ui_event = self.ui_event.sync_sender();
+        thread::spawn(move || {
+            let png_path = dot_path.with_extension("png");
+            let is_rendered = Command::new("dot")
+                .arg("-Tpng")
+                .arg(&dot_path)
+                .arg("-o")
+                .arg(&png_path)
+                .status()
+                .map_or(false, |status| status.success());
+            let graph_path = if is_rendered { png_path } else { dot_path };
+
+            ui_event.send(move |ui_event| {
+                ui_event.show_info(gettext("Pipeline graph written to {}").replacen(
+                    "{}",
+                    &graph_path.to_string_lossy(),
+                    1,
+                ));
+                if let Ok(uri) = glib::filename_to_uri(&graph_path, None) {
+                    let _ =
+                        gio::AppInfo::launch_default_for_uri(&uri, gio::NONE_APP_LAUNCH_CONTEXT);
+                }
+            });
+        });
+    }
+
     pub fn quit(&mut self) {
         self.abort_tracker();
+        self.cancel_silence_detection();
+        self.cancel_waveform_generation();
 
         if let Some(mut pipeline) = self.pipeline.take() {
             let _ = pipeline.stop();
         }
 
         if let Some(window_delete_id) = self.window_delete_id.take() {
-            let size = self.window.get_size();
-            let mut config = CONFIG.write().unwrap();
-            config.ui.width = size.0;
-            config.ui.height = size.1;
-            config.save();
+            self.save_window_state();
+            CONFIG.write().unwrap().save();
+            HISTORY.write().unwrap().save();
 
             // Restore default delete handler
             glib::signal::signal_handler_disconnect(&self.window, window_delete_id);
@@ -194,61 +600,287 @@ impl MainController {
         self.window.close();
     }
 
+    // Applies `event` to the playback state and reacts to the resulting transition
+    fn transition(&mut self, event: PlaybackEvent) -> Option<Transition> {
+        let transition = self.state.handle(event)?;
+
+        if transition.is_starting() {
+            self.mpris.set_playing(true);
+            self.spawn_tracker();
+        } else if transition.is_halting() {
+            self.info_ctrl.reset_audio_level();
+            self.mpris.set_playing(false);
+            self.abort_tracker();
+
+            #[cfg(feature = "remote")]
+            self.update_remote(None);
+        }
+
+        // Play reverts the fast preview to normal playback
+        let icon = if transition.to == ControllerState::Playing {
+            PAUSE_ICON
+        } else {
+            PLAYBACK_ICON
+        };
+        self.play_pause_btn.set_icon_name(Some(icon));
+        self.mini_player_ctrl.set_play_pause_icon(icon);
+
+        if transition.from.is_trick() && !transition.to.is_trick() && self.scrubbing.is_none() {
+            // The audio was muted for the fast preview
+            if let Some(pipeline) = self.pipeline.as_ref() {
+                pipeline.set_muted(false);
+            }
+        }
+
+        Some(transition)
+    }
+
     pub async fn play_pause(&mut self) {
         use ControllerState::*;
 
-        match self.state {
+        match self.state.get() {
             Paused => {
-                self.play_pause_btn.set_icon_name(Some(PAUSE_ICON));
-                self.state = Playing;
                 self.pipeline.as_mut().unwrap().play().await.unwrap();
-
-                self.spawn_tracker();
+                self.transition(PlaybackEvent::Play);
             }
             Playing => {
                 self.pipeline.as_mut().unwrap().pause().await.unwrap();
-                self.play_pause_btn.set_icon_name(Some(PLAYBACK_ICON));
-                self.abort_tracker();
-                self.state = Paused;
+                self.transition(PlaybackEvent::Pause);
             }
             EosPlaying | EosPaused => {
                 // Restart the stream from the begining
-                self.play_pause_btn.set_icon_name(Some(PAUSE_ICON));
-                self.state = Playing;
-
                 if self
                     .seek(Timestamp::default(), gst::SeekFlags::ACCURATE)
                     .await
                     .is_ok()
                 {
                     self.pipeline.as_mut().unwrap().play().await.unwrap();
-                    self.spawn_tracker();
+                    self.transition(PlaybackEvent::Play);
+                }
+            }
+            FastForwarding { .. } | Rewinding { .. } => {
+                // Back to normal speed from current position
+                if let Some(position) = self.current_ts() {
+                    let _ = self.seek(position, gst::SeekFlags::ACCURATE).await;
+                }
+            }
+            Stopped => self.ui_event.select_media(),
+        }
+    }
+
+    pub async fn fast_forward(&mut self) {
+        self.trick_play(true).await;
+    }
+
+    pub async fn rewind(&mut self) {
+        self.trick_play(false).await;
+    }
+
+    // Repeated requests in the same direction cycle through the `TRICK_SPEEDS`
+    async fn trick_play(&mut self, is_forward: bool) {
+        use ControllerState::*;
+
+        if !self
+            .pipeline
+            .as_ref()
+            .map_or(false, PlaybackPipeline::is_seekable)
+        {
+            return;
+        }
+
+        let state = self.state.get();
+        let speed = match (state, is_forward) {
+            (FastForwarding { speed }, true) | (Rewinding { speed }, false) => {
+                let idx = TRICK_SPEEDS
+                    .iter()
+                    .position(|trick_speed| *trick_speed == speed)
+                    .map_or(0, |idx| (idx + 1) % TRICK_SPEEDS.len());
+                TRICK_SPEEDS[idx]
+            }
+            (Stopped, _) | (EosPlaying, true) | (EosPaused, true) => return,
+            _ => TRICK_SPEEDS[0],
+        };
+        let rate = if is_forward {
+            f64::from(speed)
+        } else {
+            -f64::from(speed)
+        };
+
+        let pipeline = self.pipeline.as_mut().unwrap();
+        let position = match pipeline.current_ts() {
+            Some(position) => position,
+            None => return,
+        };
+
+        pipeline.set_muted(true);
+        match pipeline.seek_trick(position, rate).await {
+            Ok(()) => {
+                if !state.is_running() {
+                    self.pipeline.as_mut().unwrap().play().await.unwrap();
+                }
+
+                let (event, msg) = if is_forward {
+                    (
+                        PlaybackEvent::FastForward(speed),
+                        gettext("Fast forward ×{}"),
+                    )
+                } else {
+                    (PlaybackEvent::Rewind(speed), gettext("Rewind ×{}"))
+                };
+                self.transition(event);
+                self.ui_event
+                    .show_info(msg.replacen("{}", &speed.to_string(), 1));
+            }
+            Err(SeekError::Eos) => {
+                self.pipeline.as_ref().unwrap().set_muted(false);
+                self.ui_event.eos();
+            }
+            Err(SeekError::Rejected) => {
+                self.pipeline.as_ref().unwrap().set_muted(false);
+                if let FastForwarding { .. } | Rewinding { .. } = state {
+                    // Back to normal speed from current position
+                    let _ = self.seek(position, gst::SeekFlags::ACCURATE).await;
                 }
+
+                self.ui_event.show_info(if is_forward {
+                    gettext("This media can't be played fast forward")
+                } else {
+                    gettext("This media can't be rewound")
+                });
+            }
+            Err(SeekError::Unrecoverable) => self.stop(),
+        }
+    }
+
+    pub async fn audio_device_lost(&mut self) {
+        if self.state.get() == ControllerState::Playing {
+            // Best effort: the audio sink is in error
+            let _ = self.pipeline.as_mut().unwrap().pause().await;
+            self.transition(PlaybackEvent::Pause);
+            self.is_playing_on_device_loss = true;
+        }
+    }
+
+    fn monitor_audio_output(&mut self) {
+        let ui_event = self.ui_event.clone();
+        match AudioOutputMonitor::start(move || ui_event.audio_output_lost()) {
+            Ok(audio_output_monitor) => self.audio_output_monitor = Some(audio_output_monitor),
+            Err(err) => warn!("Couldn't monitor audio output: {}", err),
+        }
+    }
+
+    /// Pauses so that the audio doesn't switch to the speakers, the user resumes manually.
+    pub async fn audio_output_lost(&mut self) {
+        if !CONFIG.read().unwrap().media.is_paused_on_output_loss
+            || self.state.get() != ControllerState::Playing
+        {
+            return;
+        }
+
+        if self.pipeline.as_mut().unwrap().pause().await.is_ok() {
+            self.transition(PlaybackEvent::Pause);
+            self.ui_event
+                .show_info(gettext("Playback paused: the audio output was unplugged"));
+        }
+    }
+
+    pub async fn retry_audio_device(&mut self) {
+        let audio_sink = self.audio_sink();
+        let pipeline = match self.pipeline.as_mut() {
+            Some(pipeline) => pipeline,
+            None => return,
+        };
+
+        if pipeline.reset_audio_sink(audio_sink).is_err() {
+            self.ui_event.audio_device_lost(gettext(
+                "Couldn't restore the audio device. Check that an audio device is available.",
+            ));
+            return;
+        }
+
+        // Preroll the new audio sink
+        if let Some(current_ts) = self.current_ts() {
+            let _ = self.seek(current_ts, gst::SeekFlags::ACCURATE).await;
+        }
+
+        if std::mem::take(&mut self.is_playing_on_device_loss)
+            && self.state.get() == ControllerState::Paused
+        {
+            self.play_pause().await;
+        }
+    }
+
+    /// Keeps the UI consistent when the pipeline state is changed by someone else.
+    pub fn external_state_change(&mut self, state: gst::State) {
+        let event = match state {
+            gst::State::Playing => PlaybackEvent::ExternalPlay,
+            gst::State::Paused => PlaybackEvent::ExternalPause,
+            _ => return,
+        };
+
+        if let Some(transition) = self.transition(event) {
+            if transition.to == ControllerState::Paused {
+                // The tracker might have missed the last position
+                self.tick();
             }
-            Stopped => self.select_media().await,
-            PendingSelectMediaDecision => (),
         }
     }
 
     pub async fn seek(&mut self, position: Timestamp, flags: gst::SeekFlags) -> Result<(), ()> {
         use ControllerState::*;
 
-        match self.state {
-            Playing | Paused | EosPaused | EosPlaying => {
-                match self.pipeline.as_mut().unwrap().seek(position, flags).await {
-                    Ok(()) => {
-                        self.info_ctrl.seek(position, self.state);
+        if !self
+            .pipeline
+            .as_ref()
+            .map_or(false, PlaybackPipeline::is_seekable)
+        {
+            return Ok(());
+        }
+
+        match self.state.get() {
+            Playing
+            | Paused
+            | EosPaused
+            | EosPlaying
+            | FastForwarding { .. }
+            | Rewinding { .. } => {
+                let mut media_position = position;
+                let part = self
+                    .book
+                    .as_ref()
+                    .and_then(|book| book.locate(Duration::from_nanos(position.as_u64())));
+                if let Some((idx, part_position)) = part {
+                    if Some(idx) != self.book_part_idx() {
+                        // Async recursion is not allowed, open the part from the handler
+                        self.ui_event.open_book_part(idx, position);
+                        return Ok(());
+                    }
+                    media_position = part_position.into();
+                }
 
-                        match self.state {
-                            EosPlaying => self.state = Playing,
-                            EosPaused => self.state = Paused,
-                            _ => (),
+                let segment = self.info_ctrl.segment_at(position);
+                let pipeline = self.pipeline.as_mut().unwrap();
+                let res = match segment.as_ref() {
+                    Some(segment) => pipeline
+                        .seek_segment(media_position, segment.end(), 1f64, flags)
+                        .await
+                        .map(Some),
+                    None => pipeline.seek(media_position, flags).await.map(|()| None),
+                };
+                match res {
+                    Ok(segment_done) => {
+                        if let Some(segment_done) = segment_done {
+                            self.watch_segment(segment_done);
                         }
+                        self.seeked(position, segment);
+                        self.transition(PlaybackEvent::Seeked);
                     }
                     Err(SeekError::Eos) => {
-                        self.info_ctrl.seek(position, self.state);
+                        self.seeked(position, None);
                         self.ui_event.eos();
                     }
+                    // Playback goes on from the current position
+                    Err(SeekError::Rejected) => return Err(()),
                     Err(SeekError::Unrecoverable) => {
                         self.stop();
                         return Err(());
@@ -261,126 +893,772 @@ impl MainController {
         Ok(())
     }
 
-    pub fn current_ts(&mut self) -> Option<Timestamp> {
-        self.pipeline.as_mut().unwrap().current_ts()
+    // Whatever initiated the seek (UI, MPRIS, remote, ...), the position
+    // and the chapter selection must be reflected right away.
+    fn seeked(&mut self, position: Timestamp, segment: Option<info_controller::ChapterSegment>) {
+        self.info_ctrl.seek(position, segment);
+        self.mini_player_ctrl.tick(position);
+        self.mpris.seeked(position);
+        self.current_chapter_changed();
+
+        #[cfg(feature = "remote")]
+        self.update_remote(Some(position));
     }
 
-    pub fn tick(&mut self) {
-        if let Some(ts) = self.current_ts() {
-            self.info_ctrl.tick(ts, self.state);
-        }
+    fn watch_segment(&self, segment_done: SegmentDone) {
+        let ui_event = self.ui_event.clone();
+        spawn(async move {
+            // Aborted segments are superseded by a seek
+            if segment_done.await.is_ok() {
+                ui_event.segment_done();
+            }
+        });
     }
 
-    pub async fn select_streams(&mut self, stream_ids: &[Arc<str>]) {
-        let res = self
-            .pipeline
-            .as_mut()
-            .unwrap()
-            .select_streams(stream_ids)
-            .await;
+    /// Goes on with the next range once the segment is played.
+    pub fn segment_done(&mut self) {
+        let (start, stop) = match self.info_ctrl.segment_done() {
+            Some(range) => range,
+            None => return,
+        };
 
-        match res {
-            Ok(()) => self.streams_selected(),
-            Err(SelectStreamsError::Unrecoverable) => self.stop(),
-            Err(err) => panic!("{}", err),
+        let segment_done = match self.pipeline.as_ref() {
+            Some(pipeline) => pipeline.chain_segment(start, stop, 1f64),
+            None => return,
+        };
+        if let Some(segment_done) = segment_done {
+            self.watch_segment(segment_done);
         }
     }
 
-    pub fn streams_selected(&mut self) {
-        let info = &self.pipeline.as_ref().unwrap().info;
-        self.info_ctrl.streams_changed(info);
-        self.perspective_ctrl.streams_changed(info);
-        self.video_ctrl.streams_changed(info);
-    }
-
-    pub fn eos(&mut self) {
-        self.play_pause_btn.set_icon_name(Some(PLAYBACK_ICON));
+    pub async fn toggle_repeat(&mut self, must_repeat: bool) {
+        self.info_ctrl.toggle_repeat(must_repeat);
 
-        use ControllerState::*;
-        match self.state {
-            Playing => self.state = EosPlaying,
-            Paused => self.state = EosPaused,
-            _ => (),
+        if must_repeat {
+            // Play current chapter as a segment right away
+            let position = match self.current_ts() {
+                Some(position) => position,
+                None => return,
+            };
+            if self.info_ctrl.segment_at(position).is_some() {
+                let _ = self.seek(position, gst::SeekFlags::ACCURATE).await;
+            }
         }
-
-        self.abort_tracker();
     }
 
-    fn spawn_tracker(&mut self) {
-        if self.tracker_abort_handle.is_some() {
-            return;
-        }
+    fn step<F>(&mut self, step: F)
+    where
+        F: FnOnce(Timestamp) -> Timestamp,
+    {
+        let position = match self.current_ts() {
+            Some(position) => position,
+            None => return,
+        };
 
-        let (abortable_tracker, abort_handle) = abortable(self.new_tracker.as_ref().unwrap()());
-        spawn(abortable_tracker.map(drop));
-        self.tracker_abort_handle = Some(abort_handle);
+        // The seek is scheduled after the steps already queued, so they are coalesced
+        if self.seek_serializer.step(position, step) {
+            self.ui_event.seek_steps();
+        }
     }
 
-    fn abort_tracker(&mut self) {
-        if let Some(abort_handle) = self.tracker_abort_handle.take() {
-            abort_handle.abort();
-        }
+    pub fn step_back(&mut self) {
+        self.step(|ts| ts.saturating_sub(info_controller::SEEK_STEP));
     }
 
-    pub async fn hold(&mut self) {
-        self.ui_event.set_cursor_waiting();
-        self.play_pause_btn.set_icon_name(Some(PLAYBACK_ICON));
+    pub fn step_forward(&mut self) {
+        self.step(|ts| ts + info_controller::SEEK_STEP);
+    }
 
-        if let Some(pipeline) = self.pipeline.as_mut() {
-            pipeline.pause().await.unwrap();
+    /// Seeks to the target of the steps accumulated since the previous step seek.
+    pub async fn seek_steps(&mut self) {
+        let target = match self.seek_serializer.take_target() {
+            Some(target) => target,
+            None => return,
         };
+
+        // The seek must complete so that the position and the state are updated.
+        // Steps requested meanwhile accumulate for the next seek.
+        let _ = self.seek(target, gst::SeekFlags::ACCURATE).await;
     }
 
-    pub async fn select_media(&mut self) {
-        self.abort_tracker();
+    pub fn scrub_start(&mut self) {
+        let origin = self.current_ts();
+        if let Some(pipeline) = self.pipeline.as_ref() {
+            pipeline.set_muted(true);
+            self.scrubbing = Some(Scrubbing {
+                origin,
+                ..Scrubbing::default()
+            });
+        }
+    }
 
-        if let ControllerState::Playing | ControllerState::EosPlaying = self.state {
-            self.hold().await;
+    /// Seeks to `target` for a quick preview.
+    ///
+    /// While the timeline is dragged, seeks are throttled.
+    pub async fn scrub_move(&mut self, target: Timestamp) {
+        let must_seek = self
+            .scrubbing
+            .as_mut()
+            .map_or(true, |scrubbing| scrubbing.must_seek(target));
+        if must_seek {
+            let _ = self.seek(target, gst::SeekFlags::KEY_UNIT).await;
         }
+    }
 
-        self.state = ControllerState::PendingSelectMediaDecision;
+    pub async fn scrub_end(&mut self) {
+        let scrubbing = match self.scrubbing.take() {
+            Some(scrubbing) => scrubbing,
+            None => return,
+        };
 
-        self.ui_event.hide_info_bar();
+        if let Some(target) = scrubbing.target {
+            if self.seek(target, gst::SeekFlags::ACCURATE).await.is_ok() {
+                if let Some(origin) = scrubbing.origin {
+                    self.jumped(origin, target);
+                }
+            }
+        }
 
-        if let Some(ref last_path) = CONFIG.read().unwrap().media.last_path {
-            self.file_dlg.set_current_folder(last_path);
+        if let Some(pipeline) = self.pipeline.as_ref() {
+            pipeline.set_muted(false);
         }
-        self.file_dlg.show();
     }
 
-    pub fn stop(&mut self) {
-        self.abort_tracker();
-
-        if let Some(mut pipeline) = self.pipeline.take() {
-            let _ = pipeline.stop();
+    /// Seeks to `target`, recording the current position in the seek history.
+    ///
+    /// This is intended for large jumps, e.g. to a chapter.
+    pub async fn jump(&mut self, target: Timestamp) -> Result<(), ()> {
+        let origin = self.current_ts();
+        self.seek(target, gst::SeekFlags::ACCURATE).await?;
+        if let Some(origin) = origin {
+            self.jumped(origin, target);
         }
 
-        self.state = ControllerState::Stopped;
+        Ok(())
     }
 
-    pub async fn open_media(&mut self, path: PathBuf) {
-        if let Some(abort_handle) = self.media_msg_abort_handle.take() {
-            abort_handle.abort();
+    fn jumped(&mut self, origin: Timestamp, target: Timestamp) {
+        let is_coalesced = self.last_jump.map_or(false, |last_jump| {
+            last_jump.elapsed().as_millis() < JUMP_COALESCING_PERIOD
+        });
+        let is_recorded = HISTORY
+            .write()
+            .unwrap()
+            .current_mut()
+            .map_or(false, |entry| {
+                entry
+                    .seeks
+                    .jumped(origin.as_u64(), target.as_u64(), is_coalesced)
+            });
+
+        if is_recorded {
+            self.last_jump = Some(Instant::now());
+            self.update_seek_history_actions();
         }
+    }
 
-        self.stop();
+    /// Returns to the position before the last jump.
+    pub async fn go_back(&mut self) {
+        self.navigate_seek_history(SeekHistory::back).await;
+    }
+
+    /// Returns to the position before the last `go_back`.
+    pub async fn go_forward(&mut self) {
+        self.navigate_seek_history(SeekHistory::forward).await;
+    }
+
+    async fn navigate_seek_history<F>(&mut self, navigate: F)
+    where
+        F: FnOnce(&mut SeekHistory, u64) -> Option<u64>,
+    {
+        let current = match self.current_ts() {
+            Some(current) => current,
+            None => return,
+        };
+
+        let target = HISTORY
+            .write()
+            .unwrap()
+            .current_mut()
+            .and_then(|entry| navigate(&mut entry.seeks, current.as_u64()));
+        if let Some(target) = target {
+            self.last_jump = None;
+            self.update_seek_history_actions();
+            let _ = self
+                .seek(Timestamp::new(target), gst::SeekFlags::ACCURATE)
+                .await;
+        }
+    }
+
+    fn update_seek_history_actions(&self) {
+        let (can_go_back, can_go_forward) = match HISTORY.write().unwrap().current_mut() {
+            Some(entry) => (entry.seeks.can_go_back(), entry.seeks.can_go_forward()),
+            None => (false, false),
+        };
+        self.go_back_action.set_enabled(can_go_back);
+        self.go_forward_action.set_enabled(can_go_forward);
+    }
+
+    /// Shifts the audio delay by `steps` times `AUDIO_OFFSET_STEP`.
+    ///
+    /// The delay is remembered for the current media.
+    pub fn shift_audio_offset(&mut self, steps: i32) {
+        let pipeline = match self.pipeline.as_mut() {
+            Some(pipeline) => pipeline,
+            None => return,
+        };
+
+        let offset = (pipeline.audio_offset() + i64::from(steps) * AUDIO_OFFSET_STEP)
+            .max(-AUDIO_OFFSET_MAX)
+            .min(AUDIO_OFFSET_MAX);
+        pipeline.set_audio_offset(offset);
+
+        let path = pipeline.info.path.to_path_buf();
+        let audio_offsets = &mut CONFIG.write().unwrap().media.audio_offsets;
+        if offset == 0 {
+            audio_offsets.remove(&path);
+        } else {
+            audio_offsets.insert(path, offset);
+        }
+
+        self.ui_event
+            .show_info(gettext("Audio delay: {} ms").replacen(
+                "{}",
+                &(offset / 1_000_000).to_string(),
+                1,
+            ));
+    }
+
+    /// Returns the current position, in the book if the media is part of one.
+    pub fn current_ts(&mut self) -> Option<Timestamp> {
+        let offset = self.book_offset();
+        self.pipeline
+            .as_mut()?
+            .current_ts()
+            .map(|position| position + offset)
+    }
+
+    // The index of current media in the book, if any
+    fn book_part_idx(&self) -> Option<usize> {
+        let book = self.book.as_ref()?;
+        book.part_idx(&self.pipeline.as_ref()?.info.path)
+    }
+
+    // The position of current media in the book, if any
+    fn book_offset(&self) -> Duration {
+        self.book_part_idx()
+            .and_then(|idx| self.book.as_ref().unwrap().part(idx))
+            .map_or_else(Duration::default, |part| part.start)
+    }
+
+    // The duration of the book if the media is part of one
+    fn duration(&self) -> Duration {
+        if self.book_part_idx().is_some() {
+            return self.book.as_ref().unwrap().duration();
+        }
+
+        self.pipeline
+            .as_ref()
+            .map_or_else(Duration::default, |pipeline| pipeline.info.duration)
+    }
+
+    pub fn tick(&mut self) {
+        if self.scrubbing.is_some() {
+            // Don't move the timeline under the user's pointer
+            return;
+        }
+
+        let ts = self.current_ts();
+        if let Some(ts) = ts {
+            self.info_ctrl.tick(ts, self.state.get());
+            self.mini_player_ctrl.tick(ts);
+            self.mpris.tick(ts);
+
+            if let Some(entry) = HISTORY.write().unwrap().current_mut() {
+                entry.update_position(ts.as_u64());
+            }
+
+            let pipeline = self.pipeline.as_ref().unwrap();
+            self.streams_ctrl.update_qos(&pipeline.qos_stats());
+
+            let duration = self.duration();
+            if duration > Duration::default() {
+                self.launcher_entry
+                    .set_progress(Some(ts.as_f64() / duration.as_f64()));
+            }
+        }
+
+        #[cfg(feature = "remote")]
+        self.update_remote(ts);
+    }
+
+    pub async fn select_streams(&mut self, stream_ids: &[Arc<str>]) {
+        let res = self
+            .pipeline
+            .as_mut()
+            .unwrap()
+            .select_streams(stream_ids)
+            .await;
+
+        match res {
+            Ok(delta) if delta.is_empty() => (),
+            Ok(delta) => self.streams_selected(&delta),
+            Err(SelectStreamsError::Unrecoverable) => self.stop(),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn streams_selected(&mut self, delta: &StreamSelectionDelta) {
+        let pipeline = self.pipeline.as_ref().unwrap();
+        self.streams_ctrl.update_negotiated_caps(pipeline);
+
+        let info = &pipeline.info;
+        self.info_ctrl.streams_changed(info, delta);
+        self.perspective_ctrl.streams_changed(info, delta);
+        self.video_ctrl.streams_changed(info, delta);
+    }
+
+    pub fn audio_level(&self, level: &AudioLevel) {
+        self.info_ctrl.audio_level(level);
+    }
+
+    pub fn toc_updated(&mut self, toc: gst::Toc) {
+        let pipeline = match self.pipeline.as_mut() {
+            Some(pipeline) => pipeline,
+            None => return,
+        };
+
+        pipeline.info.toc = Some(toc.clone());
+        self.info_ctrl.media_toc_updated(toc);
+    }
+
+    pub fn chapters_changed(&self) {
+        let info = match self.pipeline.as_ref() {
+            Some(pipeline) => &pipeline.info,
+            None => return,
+        };
+
+        let chapter_manager = &self.info_ctrl.chapter_manager;
+        let chapters = chapter_manager
+            .iter()
+            .map(|chapter| MprisChapter {
+                title: chapter.title().to_owned(),
+                start: chapter.start().as_u64(),
+                end: chapter.timestamps().end.as_u64(),
+            })
+            .collect();
+        self.mpris.set_media(
+            info.media_title().unwrap_or(&info.file_name),
+            self.duration().as_u64(),
+            chapters,
+        );
+        #[cfg(feature = "remote")]
+        self.update_remote_chapters();
+        self.current_chapter_changed();
+    }
+
+    fn current_chapter_changed(&self) {
+        let chapter_idx = self.selected_chapter_idx();
+        self.mpris.set_current_chapter(chapter_idx);
+
+        if let Some(dbus_service) = self.dbus_service.as_ref() {
+            let chapter_manager = &self.info_ctrl.chapter_manager;
+            dbus_service.set_current_chapter(chapter_idx.and_then(|idx| {
+                chapter_manager
+                    .chapter(idx)
+                    .map(|chapter| (idx, chapter.title()))
+            }));
+        }
+    }
+
+    fn selected_chapter_idx(&self) -> Option<usize> {
+        self.info_ctrl.chapter_manager.selected_idx()
+    }
+
+    pub fn eos(&mut self) {
+        if let ControllerState::Rewinding { .. } = self.state.get() {
+            // Reached the beginning of the media, go on at normal speed
+            self.ui_event
+                .seek(self.book_offset().into(), gst::SeekFlags::ACCURATE);
+            return;
+        }
+
+        self.transition(PlaybackEvent::Eos);
+
+        // The last tick might have occurred up to a tracker period before the end
+        if let Some(pipeline) = self.pipeline.as_ref() {
+            self.ui_event
+                .position_finalized(Timestamp::from(pipeline.info.duration) + self.book_offset());
+        }
+
+        // A book is completed when its last part ends
+        if self.book.is_none() || self.queue.is_empty() {
+            if let Some(entry) = HISTORY.write().unwrap().current_mut() {
+                entry.set_completed();
+            }
+        }
+
+        if !self.queue.is_empty() {
+            self.ui_event.next_media();
+            return;
+        }
+
+        self.notify(&gettext("End of media"));
+        self.show_if_hidden();
+    }
+
+    // Notify the user when listening in background
+    fn notify(&self, body: &str) {
+        if !CONFIG.read().unwrap().ui.is_notification_enabled || self.window.is_active() {
+            return;
+        }
+
+        let (app, pipeline) = match (self.window.get_application(), self.pipeline.as_ref()) {
+            (Some(app), Some(pipeline)) => (app, pipeline),
+            _ => return,
+        };
+
+        let notification = gio::Notification::new(
+            pipeline
+                .info
+                .media_title()
+                .unwrap_or(&pipeline.info.file_name),
+        );
+        notification.set_body(Some(body));
+
+        if let Some(cover_art) = pipeline.info.cover_art.as_ref() {
+            notification.set_icon(&gio::BytesIcon::new(&cover_art.data));
+        }
+
+        // Use the same id so that a new notification replaces the previous one
+        app.send_notification(Some("playback"), &notification);
+    }
+
+    pub fn toggle_mini_player(&mut self, is_mini: bool) {
+        if is_mini {
+            self.mini_player_ctrl.enter();
+            if let Some(ts) = self.current_ts() {
+                self.mini_player_ctrl.tick(ts);
+            }
+        } else {
+            self.mini_player_ctrl.leave();
+            self.ui_event.update_focus();
+        }
+    }
+
+    pub fn update_chapter_title(&self, chapter_title: Option<String>) {
+        let file_name = match self.pipeline.as_ref() {
+            Some(pipeline) => pipeline.info.file_name.as_str(),
+            None => return,
+        };
+
+        self.mini_player_ctrl
+            .set_chapter_title(chapter_title.as_deref());
+        self.current_chapter_changed();
+        match chapter_title {
+            Some(chapter_title) => {
+                self.header_bar
+                    .set_subtitle(Some(&format!("{} — {}", file_name, chapter_title)));
+                self.notify(&chapter_title);
+            }
+            None => self.header_bar.set_subtitle(Some(file_name)),
+        }
+    }
+
+    fn spawn_tracker(&mut self) {
+        if self.tracker_abort_handle.is_some() {
+            return;
+        }
+
+        let (abortable_tracker, abort_handle) = abortable(self.new_tracker.as_ref().unwrap()());
+        spawn(abortable_tracker.map(drop));
+        self.tracker_abort_handle = Some(abort_handle);
+    }
+
+    fn abort_tracker(&mut self) {
+        if let Some(abort_handle) = self.tracker_abort_handle.take() {
+            abort_handle.abort();
+        }
+    }
+
+    pub async fn select_media(&mut self, reply: Reply<Option<MediaPath>>) {
+        if let ControllerState::Playing | ControllerState::EosPlaying = self.state.get() {
+            self.pipeline.as_mut().unwrap().pause().await.unwrap();
+            self.transition(PlaybackEvent::Pause);
+        }
+
+        self.ui_event.hide_info_bar();
+
+        let last_path = CONFIG.read().unwrap().media.last_path.clone();
+        self.media_chooser.choose(last_path.as_deref(), reply);
+    }
+
+    pub async fn select_book(&mut self, reply: Reply<Option<MediaPath>>) {
+        if let ControllerState::Playing | ControllerState::EosPlaying = self.state.get() {
+            self.pipeline.as_mut().unwrap().pause().await.unwrap();
+            self.transition(PlaybackEvent::Pause);
+        }
+
+        self.ui_event.hide_info_bar();
+
+        let last_path = CONFIG.read().unwrap().media.last_path.clone();
+        self.media_chooser
+            .choose_folder(last_path.as_deref(), reply);
+    }
+
+    pub fn stop(&mut self) {
+        self.transition(PlaybackEvent::Stop);
+        self.cancel_silence_detection();
+        self.cancel_snapshot_capture();
+        self.cancel_waveform_generation();
+        self.close_media_action.set_enabled(false);
+        self.export_media_info_action.set_enabled(false);
+        self.go_back_action.set_enabled(false);
+        self.go_forward_action.set_enabled(false);
+        self.last_jump = None;
+        self.is_playing_on_device_loss = false;
+
+        if let Some(mut pipeline) = self.pipeline.take() {
+            let _ = pipeline.stop();
+        }
+        if let Some(media_monitor) = self.media_monitor.take() {
+            media_monitor.cancel();
+        }
+
+        self.perspective_ctrl.cleanup();
+        self.launcher_entry.set_progress(None);
+        self.mpris.clear();
+        if let Some(dbus_service) = self.dbus_service.as_ref() {
+            dbus_service.set_current_chapter(None);
+        }
+        #[cfg(feature = "remote")]
+        {
+            if let Some(remote) = self.remote.as_ref() {
+                remote.update(|state| *state = crate::remote::RemoteState::default());
+            }
+        }
+    }
+
+    fn audio_sink(&self) -> Option<gst::Element> {
+        let config = CONFIG.read().unwrap();
+        let description = config.media.audio_sink.as_ref()?;
+
+        PlaybackPipeline::audio_sink_from_description(description)
+            .map_err(|err| {
+                self.ui_event.show_error(
+                    gettext("Couldn't use audio sink \"{}\": {}\nUsing the default audio sink.")
+                        .replacen("{}", description, 1)
+                        .replacen("{}", &err, 1),
+                );
+            })
+            .ok()
+    }
+
+    pub async fn open_media(&mut self, path: MediaPath) {
+        if path.is_dir() {
+            self.open_book(path);
+            return;
+        }
+
+        self.clear_queue();
+
+        let path = match PlaylistFormat::from_path(&path) {
+            Some(format) => match playlist::read(&path, format) {
+                Ok(entries) => {
+                    self.queue = entries.into_iter().map(MediaPath::from).collect();
+                    self.queue.pop_front().unwrap()
+                }
+                Err(err) => {
+                    self.ui_event.reset_cursor();
+                    self.ui_event.show_error(err);
+                    return;
+                }
+            },
+            None => path,
+        };
+
+        self.open(path).await;
+    }
+
+    // Open the next entry from the playlist and keep playing
+    pub async fn next_media(&mut self) {
+        let path = match self.queue.pop_front() {
+            Some(path) => path,
+            None => return,
+        };
+
+        self.ui_event.set_cursor_waiting();
+        self.open(path).await;
+        if self.state.get() == ControllerState::Paused {
+            self.play_pause().await;
+        }
+    }
+
+    /// Opens the audio files from `dir` as a single book.
+    ///
+    /// The files are probed first, so that the chapters of the whole book can be shown.
+    pub fn open_book(&mut self, dir: MediaPath) {
+        self.clear_queue();
+        self.pending_resume = None;
+        self.book_probing = Some(dir.clone());
+
+        let ui_event = self.ui_event.sync_sender();
+        thread::spawn(move || {
+            let result = Self::probe_book(&dir);
+            ui_event.send(move |ui_event| ui_event.book_probed(BookProbing { dir, result }));
+        });
+    }
+
+    // This is a blocking function which is expected to run off the UI thread
+    fn probe_book(dir: &MediaPath) -> Result<VirtualBook, String> {
+        let paths = VirtualBook::audio_files(dir).map_err(|err| {
+            gettext("Couldn't read folder \"{}\". {}")
+                .replacen("{}", &dir.file_name_lossy(), 1)
+                .replacen("{}", &err.to_string(), 1)
+        })?;
+        if paths.is_empty() {
+            return Err(gettext("No audio files found in \"{}\"").replacen(
+                "{}",
+                &dir.file_name_lossy(),
+                1,
+            ));
+        }
+
+        let mut book = VirtualBook::new(dir.clone());
+        for path in paths {
+            // Media opened before don't need to be prerolled
+            let (duration, toc) = match MediaCache::load(&path) {
+                Some(info) => (info.duration, info.toc),
+                None => media_probe::probe(&path).map_err(|err| {
+                    gettext("Error opening file. {}")
+                        .replace("{}", &format!("\"{}\": {}", path.file_name_lossy(), err))
+                })?,
+            };
+            book.add_part(path, duration, toc);
+        }
+
+        Ok(book)
+    }
+
+    pub async fn book_probed(&mut self, probing: BookProbing) {
+        if self.book_probing.as_ref() != Some(&probing.dir) {
+            // Another media was opened in the meantime
+            return;
+        }
+        self.book_probing = None;
+
+        match probing.result {
+            Ok(book) => {
+                let position = self.pending_resume.take();
+                let idx = position
+                    .and_then(|position| book.locate(Duration::from_nanos(position.as_u64())))
+                    .map_or(0, |(idx, _)| idx);
+
+                self.book = Some(book);
+                self.open_book_part(idx, position).await;
+            }
+            Err(err) => {
+                self.pending_resume = None;
+                self.ui_event.reset_cursor();
+                self.ui_event.show_error(err);
+            }
+        }
+    }
+
+    /// Opens the part `idx` of current book, resuming at `position` in the book if provided.
+    ///
+    /// The following parts are queued, so that playback goes on with them.
+    pub async fn open_book_part(&mut self, idx: usize, position: Option<Timestamp>) {
+        let book = match self.book.as_ref() {
+            Some(book) => book,
+            None => return,
+        };
+        let path = match book.part(idx) {
+            Some(part) => part.path.clone(),
+            None => return,
+        };
+        self.queue = book.parts()[idx + 1..]
+            .iter()
+            .map(|part| part.path.clone())
+            .collect();
+
+        let was_playing = matches!(
+            self.state.get(),
+            ControllerState::Playing | ControllerState::EosPlaying
+        );
+        self.pending_resume = position;
+        self.open(path).await;
+        if was_playing && self.state.get() == ControllerState::Paused {
+            self.play_pause().await;
+        }
+    }
+
+    // Forgets about the playlist or the book, if any
+    fn clear_queue(&mut self) {
+        self.queue.clear();
+        self.book = None;
+        self.book_probing = None;
+    }
+
+    /// Closes current media and goes back to the landing page.
+    ///
+    /// The media file is released, so it can be moved or deleted.
+    pub fn close_media(&mut self) {
+        self.clear_queue();
+        self.unload();
+        self.show_if_hidden();
+    }
+
+    fn unload(&mut self) {
+        if let Some(abort_handle) = self.media_msg_abort_handle.take() {
+            abort_handle.abort();
+        }
+
+        self.stop();
 
         self.info_ctrl.cleanup();
         self.video_ctrl.cleanup();
         self.streams_ctrl.cleanup();
         self.perspective_ctrl.cleanup();
+        self.mini_player_ctrl.cleanup();
         self.header_bar.set_subtitle(Some(""));
+    }
+
+    async fn open(&mut self, path: MediaPath) {
+        self.unload();
 
         CONFIG.write().unwrap().media.last_path = path.parent().map(ToOwned::to_owned);
 
-        match PlaybackPipeline::try_new(path.as_ref(), &self.video_ctrl.video_sink()).await {
+        // Show what we already know about the media while it prerolls
+        if let Some(info) = MediaCache::load(&path) {
+            self.header_bar.set_subtitle(Some(info.file_name.as_str()));
+            self.info_ctrl.preview_media(&info);
+            self.mini_player_ctrl
+                .set_title(info.media_title().unwrap_or(&info.file_name));
+        }
+
+        let options = {
+            let config = CONFIG.read().unwrap();
+            if self.is_hardware_decoding_broken {
+                VideoDecoding::Software.apply();
+            } else {
+                config.media.video_decoding.apply();
+            }
+
+            PlaybackOptions {
+                audio_sink: self.audio_sink(),
+                is_current_toc_ignored: config.media.is_current_toc_ignored,
+                max_video_height: config.media.max_video_height,
+                backend: self.backend,
+            }
+        };
+        match PlaybackPipeline::try_new(&path, &self.video_ctrl.video_sink(), options).await {
             Ok(mut pipeline) => {
+                pipeline.set_subtitle_style(Self::subtitle_style());
+                self.missing_plugins = MissingPlugins::default();
                 if !pipeline.missing_plugins.is_empty() {
                     self.ui_event
-                        .show_info(gettext("Some streams are not usable. {}").replace(
-                            "{}",
-                            &Self::format_missing_plugins(&pipeline.missing_plugins),
-                        ));
+                        .missing_plugins(pipeline.missing_plugins.clone());
                 }
 
                 self.header_bar
@@ -390,15 +1668,33 @@ impl MainController {
                 self.perspective_ctrl.new_media(&pipeline);
                 self.streams_ctrl.new_media(&pipeline);
                 self.video_ctrl.new_media(&pipeline);
+                self.mini_player_ctrl.new_media(&pipeline);
 
                 let ui_event = self.ui_event.clone();
+                let media_path = path.clone();
                 let mut media_msg_rx = pipeline.media_msg_rx.take().unwrap();
                 let (media_msg_handler, abort_handle) = abortable(async move {
                     while let Some(msg) = media_msg_rx.next().await {
                         match msg {
                             MediaMessage::Eos => ui_event.eos(),
+                            MediaMessage::AudioLevel(level) => ui_event.audio_level(level),
+                            MediaMessage::AudioDeviceLost(err) => ui_event.audio_device_lost(
+                                gettext("The audio device is no longer available. {}")
+                                    .replacen("{}", &err, 1),
+                            ),
+                            MediaMessage::UnexpectedStateChange(state) => {
+                                ui_event.external_state_change(state)
+                            }
+                            MediaMessage::TocUpdated(toc) => ui_event.toc_updated(toc),
+                            MediaMessage::MissingPlugins(plugins) => {
+                                ui_event.missing_plugins(plugins)
+                            }
+                            MediaMessage::HardwareDecoderError(err) => {
+                                ui_event.hardware_decoder_failed(media_path.clone(), err);
+                                break;
+                            }
                             MediaMessage::Error(err) => {
-                                let err = gettext("An unrecoverable error occured. {}")
+                                let err = gettext("An unrecoverable error occurred. {}")
                                     .replace("{}", &err);
                                 error!("{}", err);
                                 ui_event.show_error(err);
@@ -410,22 +1706,84 @@ impl MainController {
                 self.media_msg_abort_handle = Some(abort_handle);
                 spawn(media_msg_handler.map(|_| ()));
 
+                if let Some(offset) = CONFIG
+                    .read()
+                    .unwrap()
+                    .media
+                    .audio_offsets
+                    .get(path.as_path())
+                {
+                    pipeline.set_audio_offset(*offset);
+                }
+
+                self.close_media_action.set_enabled(true);
+                self.detect_silences_action
+                    .set_enabled(pipeline.info.streams.selected_audio().is_some());
+                self.export_media_info_action.set_enabled(true);
+                // Chapters of a book span several files
+                let can_snapshot =
+                    self.book.is_none() && pipeline.info.streams.selected_video().is_some();
+                self.take_snapshot_action.set_enabled(can_snapshot);
+                self.take_chapter_snapshots_action.set_enabled(can_snapshot);
+                let delta = pipeline.info.streams.initial_selection();
                 self.pipeline = Some(pipeline);
 
-                self.streams_selected();
+                self.streams_selected(&delta);
+
+                if let Some(idx) = self.book_part_idx() {
+                    let book = self.book.as_ref().unwrap();
+                    let start = book.part(idx).unwrap().start;
+                    self.info_ctrl
+                        .show_book(book.duration(), book.toc(), start.into());
+                }
 
                 self.ui_event.reset_cursor();
-                self.state = ControllerState::Paused;
+                self.transition(PlaybackEvent::Opened);
+                self.chapters_changed();
+                self.generate_waveform();
+
+                self.monitor_media(&path);
+                // Books are recorded as a whole, so that they can be resumed
+                let history_path = match self.book_part_idx() {
+                    Some(_) => self.book.as_ref().unwrap().dir().as_path(),
+                    None => path.as_path(),
+                };
+                HISTORY.write().unwrap().started(history_path, unix_now());
+                self.update_seek_history_actions();
+                MediaCache::store(&self.pipeline.as_ref().unwrap().info);
+                if let Some(position) = self.pending_resume.take() {
+                    let _ = self.seek(position, gst::SeekFlags::ACCURATE).await;
+                }
+
+                self.export_cover_art();
+                #[cfg(feature = "remote")]
+                self.update_remote(Some(Timestamp::default()));
             }
             Err(error) => {
                 use super::media::playback_pipeline::OpenError;
 
+                if let OpenError::HardwareDecoder(err) = &error {
+                    if self.can_fall_back_to_software_decoding() {
+                        self.ui_event.hardware_decoder_failed(path, err.clone());
+                        return;
+                    }
+                }
+
+                // Discard the cached info preview
+                self.info_ctrl.cleanup();
+                self.mini_player_ctrl.cleanup();
+                self.header_bar.set_subtitle(Some(""));
+
                 self.ui_event.reset_cursor();
+                self.pending_resume = None;
 
                 let error = match error {
-                    OpenError::Generic(error) => error,
+                    OpenError::Generic(error) | OpenError::HardwareDecoder(error) => error,
                     OpenError::MissingPlugins(plugins) => Self::format_missing_plugins(&plugins),
                     OpenError::StateChange => gettext("Failed to switch the media to Paused"),
+                    OpenError::TextOnly => gettext(
+                        "This media only contains text, such as subtitles, which can't be played on its own.",
+                    ),
                     OpenError::GLSinkError => {
                         let mut config = CONFIG.write().expect("Failed to get CONFIG as mut");
                         config.media.is_gl_disabled = true;
@@ -443,6 +1801,350 @@ impl MainController {
         };
     }
 
+    fn can_fall_back_to_software_decoding(&self) -> bool {
+        !self.is_hardware_decoding_broken
+            && CONFIG.read().unwrap().media.video_decoding == VideoDecoding::Auto
+    }
+
+    /// Opens the media at `path` again with software decoding, if allowed.
+    pub async fn hardware_decoder_failed(&mut self, path: MediaPath, err: String) {
+        if !self.can_fall_back_to_software_decoding() {
+            let err = gettext("An unrecoverable error occurred. {}").replace("{}", &err);
+            error!("{}", err);
+            self.ui_event.show_error(err);
+            return;
+        }
+
+        warn!("hardware video decoding failed: {}", err);
+        self.is_hardware_decoding_broken = true;
+        self.ui_event.show_info(gettext(
+            "Hardware video decoding failed, switching to software decoding.",
+        ));
+
+        if self.pipeline.is_some() {
+            self.pending_resume = self.current_ts().filter(|position| position.as_u64() > 0);
+        }
+        self.open(path).await;
+    }
+
+    /// Opens current media again, e.g. after it was modified, and restores the position.
+    pub async fn reload_media(&mut self) {
+        let path = match self.pipeline.as_ref() {
+            Some(pipeline) => pipeline.info.path.clone(),
+            None => return,
+        };
+
+        self.pending_resume = self.current_ts().filter(|position| position.as_u64() > 0);
+        self.open(path).await;
+    }
+
+    // Proposes to reload or close the media if it is modified or removed
+    fn monitor_media(&mut self, path: &MediaPath) {
+        let media_file = gio::File::new_for_path(path.as_path());
+        match media_file.monitor_file(gio::FileMonitorFlags::WATCH_MOVES, gio::NONE_CANCELLABLE) {
+            Ok(media_monitor) => {
+                let ui_event = self.ui_event.clone();
+                media_monitor.connect_changed(move |_, _, _, event| {
+                    use gio::FileMonitorEvent::*;
+                    match event {
+                        ChangesDoneHint | Deleted | MovedOut | Renamed => ui_event.media_changed(),
+                        _ => (),
+                    }
+                });
+                self.media_monitor = Some(media_monitor);
+            }
+            Err(err) => warn!("Couldn't monitor media file: {}", err),
+        }
+    }
+
+    // Continue where the user left off
+    pub async fn resume_media(&mut self, path: MediaPath) {
+        let position = HISTORY
+            .read()
+            .unwrap()
+            .entry(&path)
+            .map(|entry| Timestamp::new(entry.resume_position()))
+            .filter(|position| position.as_u64() > 0);
+
+        if path.is_dir() {
+            // The position is applied once the book is probed
+            self.open_book(path);
+            self.pending_resume = position;
+            return;
+        }
+
+        self.pending_resume = position;
+        self.clear_queue();
+        self.open(path).await;
+    }
+
+    pub fn export_media_info(&self) {
+        if let Some(pipeline) = self.pipeline.as_ref() {
+            self.media_info_exporter.export(&pipeline.info);
+        }
+    }
+
+    pub fn detect_silences(&mut self) {
+        let media_path = match self.pipeline.as_ref() {
+            Some(pipeline) => pipeline.info.path.clone(),
+            None => return,
+        };
+
+        self.cancel_silence_detection();
+        self.detect_silences_action.set_enabled(false);
+        self.ui_event
+            .show_info(gettext("Looking for silences. This might take a while."));
+
+        let is_cancelled = Arc::new(AtomicBool::new(false));
+        self.silence_detection = Some(Arc::clone(&is_cancelled));
+
+        // The whole audio stream is decoded
+        let ui_event = self.ui_event.sync_sender();
+        thread::spawn(move || {
+            let result = silence_detector::detect(&media_path, Arc::clone(&is_cancelled));
+            ui_event.send(move |ui_event| {
+                ui_event.silences_detected(SilenceDetection {
+                    is_cancelled,
+                    result,
+                })
+            });
+        });
+    }
+
+    pub fn silences_detected(&mut self, detection: SilenceDetection) {
+        if detection.is_cancelled.load(Ordering::Relaxed) {
+            // Media changed in the meantime
+            return;
+        }
+
+        self.silence_detection = None;
+        self.detect_silences_action.set_enabled(true);
+
+        match detection.result {
+            Ok(boundaries) if boundaries.is_empty() => {
+                self.ui_event.show_info(gettext("No long silences found"))
+            }
+            Ok(boundaries) => silence_dialog::show(&self.window, &self.ui_event, boundaries),
+            Err(err) => self.ui_event.show_error(err),
+        }
+    }
+
+    fn cancel_silence_detection(&mut self) {
+        if let Some(is_cancelled) = self.silence_detection.take() {
+            is_cancelled.store(true, Ordering::Relaxed);
+        }
+        self.detect_silences_action.set_enabled(false);
+    }
+
+    /// Captures the frame at current position next to the media.
+    pub fn take_snapshot(&mut self) {
+        let pipeline = match self.pipeline.as_mut() {
+            Some(pipeline) => pipeline,
+            None => return,
+        };
+        let ts = match pipeline.current_ts() {
+            Some(ts) => ts,
+            None => return,
+        };
+
+        let media_path = pipeline.info.path.clone();
+        let target = SnapshotTarget {
+            ts,
+            name: format!("{} {}", media_path.stem_lossy(), ts.as_u64() / 1_000_000),
+        };
+        let dir = match media_path.as_path().parent() {
+            Some(dir) => dir.to_owned(),
+            None => return,
+        };
+
+        self.capture_snapshots(media_path, vec![target], dir);
+    }
+
+    /// Captures the frame at the start of each chapter.
+    ///
+    /// The snapshots are stored in a folder named after the media
+    /// and the files are named after the chapters titles.
+    pub fn take_chapter_snapshots(&mut self) {
+        let media_path = match self.pipeline.as_ref() {
+            Some(pipeline) => pipeline.info.path.clone(),
+            None => return,
+        };
+
+        let targets: Vec<SnapshotTarget> = self
+            .info_ctrl
+            .chapter_manager
+            .iter()
+            .enumerate()
+            .map(|(idx, chapter)| {
+                SnapshotTarget::for_chapter(idx, chapter.start(), chapter.title())
+            })
+            .collect();
+        if targets.is_empty() {
+            self.ui_event
+                .show_info(gettext("No chapters to take snapshots of"));
+            return;
+        }
+
+        let dir = media_path
+            .as_path()
+            .with_file_name(gettext("{} snapshots").replacen("{}", &media_path.stem_lossy(), 1));
+
+        self.capture_snapshots(media_path, targets, dir);
+    }
+
+    fn capture_snapshots(
+        &mut self,
+        media_path: MediaPath,
+        targets: Vec<SnapshotTarget>,
+        dir: PathBuf,
+    ) {
+        self.cancel_snapshot_capture();
+        self.take_snapshot_action.set_enabled(false);
+        self.take_chapter_snapshots_action.set_enabled(false);
+
+        let is_cancelled = Arc::new(AtomicBool::new(false));
+        self.snapshot_capture = Some(Arc::clone(&is_cancelled));
+
+        let ui_event = self.ui_event.sync_sender();
+        thread::spawn(move || {
+            let count = targets.len();
+            let progress = |done: usize| {
+                if count > 1 {
+                    let msg = gettext("Taking snapshots: {} / {}")
+                        .replacen("{}", &done.to_string(), 1)
+                        .replacen("{}", &count.to_string(), 1);
+                    ui_event.send(move |ui_event| ui_event.show_info(msg));
+                }
+            };
+            let result = snapshot::capture(
+                &media_path,
+                &targets,
+                &dir,
+                Arc::clone(&is_cancelled),
+                progress,
+            );
+            ui_event.send(move |ui_event| {
+                ui_event.snapshots_taken(SnapshotCapture {
+                    is_cancelled,
+                    result,
+                })
+            });
+        });
+    }
+
+    pub fn snapshots_taken(&mut self, capture: SnapshotCapture) {
+        if capture.is_cancelled.load(Ordering::Relaxed) {
+            // Media changed in the meantime
+            return;
+        }
+
+        self.snapshot_capture = None;
+        self.take_snapshot_action.set_enabled(true);
+        self.take_chapter_snapshots_action.set_enabled(true);
+
+        match capture.result {
+            Ok(snapshots) => {
+                let dir = snapshots
+                    .first()
+                    .and_then(|snapshot| snapshot.parent())
+                    .map_or_else(String::new, |dir| dir.display().to_string());
+                self.ui_event.show_info(
+                    ngettext(
+                        "{} snapshot saved in {}",
+                        "{} snapshots saved in {}",
+                        snapshots.len() as u32,
+                    )
+                    .replacen("{}", &snapshots.len().to_string(), 1)
+                    .replacen("{}", &dir, 1),
+                );
+            }
+            Err(err) => self.ui_event.show_error(err),
+        }
+    }
+
+    fn cancel_snapshot_capture(&mut self) {
+        if let Some(is_cancelled) = self.snapshot_capture.take() {
+            is_cancelled.store(true, Ordering::Relaxed);
+        }
+        self.take_snapshot_action.set_enabled(false);
+        self.take_chapter_snapshots_action.set_enabled(false);
+    }
+
+    /// Shows the waveform of the current media, generating it if it's not cached.
+    fn generate_waveform(&mut self) {
+        self.cancel_waveform_generation();
+
+        let pipeline = match self.pipeline.as_ref() {
+            Some(pipeline) => pipeline,
+            None => return,
+        };
+        if self.book_part_idx().is_some() || pipeline.info.streams.selected_audio().is_none() {
+            return;
+        }
+
+        let media_path = pipeline.info.path.clone();
+        if let Some(waveform) = WaveformCache::load(&media_path) {
+            self.info_ctrl.show_waveform(waveform);
+            return;
+        }
+
+        let is_cancelled = Arc::new(AtomicBool::new(false));
+        self.waveform_generation = Some(Arc::clone(&is_cancelled));
+
+        // The whole audio stream is decoded
+        let duration = pipeline.info.duration;
+        let ui_event = self.ui_event.sync_sender();
+        thread::spawn(move || {
+            let result = waveform::generate(&media_path, duration, Arc::clone(&is_cancelled));
+            ui_event.send(move |ui_event| {
+                ui_event.waveform_generated(WaveformGeneration {
+                    media_path,
+                    is_cancelled,
+                    result,
+                })
+            });
+        });
+    }
+
+    pub fn waveform_generated(&mut self, generation: WaveformGeneration) {
+        if generation.is_cancelled.load(Ordering::Relaxed) {
+            // Media changed in the meantime
+            return;
+        }
+
+        self.waveform_generation = None;
+
+        match generation.result {
+            Ok(waveform) => {
+                WaveformCache::store(&generation.media_path, &waveform);
+                self.info_ctrl.show_waveform(waveform);
+            }
+            // The waveform is only informative
+            Err(err) => debug!("{}", err),
+        }
+    }
+
+    fn cancel_waveform_generation(&mut self) {
+        if let Some(is_cancelled) = self.waveform_generation.take() {
+            is_cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Registers the `plugins` missing to decode some streams of the current media.
+    ///
+    /// If some of them were not already known, returns the message to display
+    /// along with all the plugins missing for the media.
+    pub fn missing_plugins(&mut self, plugins: MissingPlugins) -> Option<(String, MissingPlugins)> {
+        if !self.missing_plugins.extend(plugins) {
+            return None;
+        }
+
+        let msg = gettext("Some streams are not usable. {}")
+            .replace("{}", &Self::format_missing_plugins(&self.missing_plugins));
+
+        Some((msg, self.missing_plugins.clone()))
+    }
+
     fn format_missing_plugins(plugins: &MissingPlugins) -> String {
         ngettext(
             "Missing plugin:\n{}",
@@ -451,14 +2153,4 @@ impl MainController {
         )
         .replacen("{}", &format!("{}", plugins), 1)
     }
-
-    pub fn cancel_select_media(&mut self) {
-        if self.state == ControllerState::PendingSelectMediaDecision {
-            self.state = if self.pipeline.is_some() {
-                ControllerState::Paused
-            } else {
-                ControllerState::Stopped
-            };
-        }
-    }
 }
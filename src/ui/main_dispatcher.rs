@@ -6,19 +6,19 @@ use gdk::{Cursor, CursorType, WindowExt};
 use gettextrs::gettext;
 
 use gio::prelude::*;
-use glib::clone;
+use glib::{clone, StaticVariantType, ToVariant};
 use gtk::prelude::*;
 
-use log::debug;
+use log::{debug, warn};
 
 use std::{cell::RefCell, rc::Rc, time::Duration};
 
-use crate::media::Timestamp;
+use crate::{application::CONFIG, media::Timestamp};
 
 use super::{
-    info_controller, spawn, ui_event::UIEvent, InfoBarController, InfoDispatcher, MainController,
-    PerspectiveDispatcher, PlaybackPipeline, StreamsDispatcher, UIController, UIDispatcher,
-    UIFocusContext, VideoDispatcher,
+    spawn, ui_event::UIEvent, ControllerState, InfoBarController, InfoBarResponse, InfoDispatcher,
+    MainController, PerspectiveDispatcher, PlaybackPipeline, StreamsDispatcher, UIController,
+    UIDispatcher, UIFocusContext, VideoDispatcher,
 };
 
 const TRACKER_PERIOD: u64 = 40; //  40 ms (25 Hz)
@@ -76,6 +76,16 @@ impl MainDispatcher {
         app.set_accels_for_action("app.about", &["<Ctrl>A"]);
         app_section.append(Some(&gettext("About")), Some("app.about"));
 
+        // Logs
+        let logs = gio::SimpleAction::new("logs", None);
+        app.add_action(&logs);
+        logs.connect_activate(
+            clone!(@strong main_ctrl.ui_event as ui_event => move |_, _| {
+                ui_event.logs();
+            }),
+        );
+        app_section.append(Some(&gettext("Logs")), Some("app.logs"));
+
         // Quit
         let quit = gio::SimpleAction::new("quit", None);
         app.add_action(&quit);
@@ -89,7 +99,7 @@ impl MainDispatcher {
 
         main_ctrl.window_delete_id = Some(main_ctrl.window.connect_delete_event(
             clone!(@strong main_ctrl.ui_event as ui_event => move |_, _| {
-                ui_event.quit();
+                ui_event.close();
                 Inhibit(true)
             }),
         ));
@@ -132,8 +142,223 @@ impl MainDispatcher {
             main_section.append(Some(&gettext("Open media file")), Some("app.open"));
             app.set_accels_for_action("app.open", &["<Ctrl>O"]);
 
+            // Register Open book action
+            let open_book = gio::SimpleAction::new("open_book", None);
+            app.add_action(&open_book);
+            open_book
+                .connect_activate(clone!(@strong ui_event => move |_, _| ui_event.select_book()));
+            main_section.append(
+                Some(&gettext("Open folder as a book")),
+                Some("app.open_book"),
+            );
+            app.set_accels_for_action("app.open_book", &["<Ctrl><Shift>O"]);
+
+            // Register Close media action
+            app.add_action(&main_ctrl.close_media_action);
+            main_ctrl.close_media_action.set_enabled(false);
+            main_ctrl
+                .close_media_action
+                .connect_activate(clone!(@strong ui_event => move |_, _| {
+                    ui_event.close_media();
+                }));
+            main_section.append(Some(&gettext("Close")), Some("app.close_media"));
+            app.set_accels_for_action("app.close_media", &["<Ctrl>W"]);
+
             main_ctrl.open_btn.set_sensitive(true);
 
+            // Open media files dropped on the window
+            main_ctrl.window.drag_dest_set(
+                gtk::DestDefaults::ALL,
+                &[gtk::TargetEntry::new(
+                    "text/uri-list",
+                    gtk::TargetFlags::OTHER_APP,
+                    0,
+                )],
+                gdk::DragAction::COPY,
+            );
+            main_ctrl.window.connect_drag_data_received(
+                clone!(@strong ui_event => move |_, _, _, _, selection_data, _, _| {
+                    let path = selection_data
+                        .get_uris()
+                        .first()
+                        .and_then(|uri| gio::File::new_for_uri(uri).get_path());
+                    if let Some(path) = path {
+                        ui_event.open_media(path);
+                    }
+                }),
+            );
+
+            // Register Preferences action
+            let preferences = gio::SimpleAction::new("preferences", None);
+            app.add_action(&preferences);
+            preferences.connect_activate(clone!(@strong ui_event => move |_, _| {
+                ui_event.preferences();
+            }));
+            main_section.append(Some(&gettext("Preferences")), Some("app.preferences"));
+            app.set_accels_for_action("app.preferences", &["<Ctrl>comma"]);
+
+            // Register History action
+            let history = gio::SimpleAction::new("history", None);
+            app.add_action(&history);
+            history.connect_activate(clone!(@strong ui_event => move |_, _| {
+                ui_event.history();
+            }));
+            main_section.append(Some(&gettext("History")), Some("app.history"));
+            app.set_accels_for_action("app.history", &["<Ctrl>H"]);
+
+            // Register Detect silences action
+            app.add_action(&main_ctrl.detect_silences_action);
+            main_ctrl.detect_silences_action.set_enabled(false);
+            main_ctrl.detect_silences_action.connect_activate(
+                clone!(@strong ui_event => move |_, _| {
+                    ui_event.detect_silences();
+                }),
+            );
+            main_section.append(
+                Some(&gettext("Detect chapters from silences")),
+                Some("app.detect_silences"),
+            );
+
+            // Register Take snapshot actions
+            app.add_action(&main_ctrl.take_snapshot_action);
+            main_ctrl.take_snapshot_action.set_enabled(false);
+            main_ctrl.take_snapshot_action.connect_activate(
+                clone!(@strong ui_event => move |_, _| {
+                    ui_event.take_snapshot();
+                }),
+            );
+            main_section.append(Some(&gettext("Take snapshot")), Some("app.take_snapshot"));
+            app.set_accels_for_action("app.take_snapshot", &["<Ctrl><Shift>S"]);
+
+            app.add_action(&main_ctrl.take_chapter_snapshots_action);
+            main_ctrl.take_chapter_snapshots_action.set_enabled(false);
+            main_ctrl.take_chapter_snapshots_action.connect_activate(
+                clone!(@strong ui_event => move |_, _| {
+                    ui_event.take_chapter_snapshots();
+                }),
+            );
+            main_section.append(
+                Some(&gettext("Take a snapshot of each chapter")),
+                Some("app.take_chapter_snapshots"),
+            );
+
+            // Register Export media info action
+            app.add_action(&main_ctrl.export_media_info_action);
+            main_ctrl.export_media_info_action.set_enabled(false);
+            main_ctrl.export_media_info_action.connect_activate(
+                clone!(@strong ui_event => move |_, _| {
+                    ui_event.export_media_info();
+                }),
+            );
+            main_section.append(
+                Some(&gettext("Export media info")),
+                Some("app.export_media_info"),
+            );
+
+            // Register Go back and Go forward actions
+            // Not in the menu: these navigate the seek history like in a browser
+            app.add_action(&main_ctrl.go_back_action);
+            main_ctrl.go_back_action.set_enabled(false);
+            main_ctrl
+                .go_back_action
+                .connect_activate(clone!(@strong ui_event => move |_, _| {
+                    ui_event.go_back();
+                }));
+            app.set_accels_for_action("app.go_back", &["<Alt>Left"]);
+
+            app.add_action(&main_ctrl.go_forward_action);
+            main_ctrl.go_forward_action.set_enabled(false);
+            main_ctrl
+                .go_forward_action
+                .connect_activate(clone!(@strong ui_event => move |_, _| {
+                    ui_event.go_forward();
+                }));
+            app.set_accels_for_action("app.go_forward", &["<Alt>Right"]);
+
+            // Register Dump pipeline action
+            // Not in the menu: this is intended for debugging
+            let dump_pipeline = gio::SimpleAction::new("dump_pipeline", None);
+            app.add_action(&dump_pipeline);
+            dump_pipeline.connect_activate(clone!(@strong ui_event => move |_, _| {
+                ui_event.dump_pipeline();
+            }));
+            app.set_accels_for_action("app.dump_pipeline", &["<Ctrl><Shift>D"]);
+
+            // Register Shift audio offset action
+            // The parameter is the number of steps, negative values play the audio earlier
+            let shift_audio_offset =
+                gio::SimpleAction::new("shift_audio_offset", Some(&i32::static_variant_type()));
+            app.add_action(&shift_audio_offset);
+            shift_audio_offset.connect_activate(clone!(@strong ui_event => move |_, param| {
+                if let Some(steps) = param.and_then(|param| param.get::<i32>()) {
+                    ui_event.shift_audio_offset(steps);
+                }
+            }));
+
+            // Register Toggle mini player action
+            let toggle_mini_player =
+                gio::SimpleAction::new_stateful("toggle_mini_player", None, &false.to_variant());
+            app.add_action(&toggle_mini_player);
+            toggle_mini_player.connect_change_state(
+                clone!(@strong ui_event => move |action, state| {
+                    if let Some(is_mini) = state.and_then(|state| state.get::<bool>()) {
+                        action.set_state(&is_mini.to_variant());
+                        ui_event.toggle_mini_player(is_mini);
+                    }
+                }),
+            );
+            main_section.append(
+                Some(&gettext("Mini player")),
+                Some("app.toggle_mini_player"),
+            );
+            app.set_accels_for_action("app.toggle_mini_player", &["<Ctrl>M"]);
+
+            // Register Toggle notifications action
+            let toggle_notifications = gio::SimpleAction::new_stateful(
+                "toggle_notifications",
+                None,
+                &CONFIG
+                    .read()
+                    .unwrap()
+                    .ui
+                    .is_notification_enabled
+                    .to_variant(),
+            );
+            app.add_action(&toggle_notifications);
+            toggle_notifications.connect_change_state(|action, state| {
+                if let Some(is_enabled) = state.and_then(|state| state.get::<bool>()) {
+                    CONFIG.write().unwrap().ui.is_notification_enabled = is_enabled;
+                    action.set_state(&is_enabled.to_variant());
+                }
+            });
+            main_section.append(
+                Some(&gettext("Notify when in background")),
+                Some("app.toggle_notifications"),
+            );
+
+            // Register Toggle background play action
+            let toggle_background_play = gio::SimpleAction::new_stateful(
+                "toggle_background_play",
+                None,
+                &CONFIG
+                    .read()
+                    .unwrap()
+                    .ui
+                    .is_background_play_enabled
+                    .to_variant(),
+            );
+            app.add_action(&toggle_background_play);
+            toggle_background_play.connect_change_state(|action, state| {
+                if let Some(is_enabled) = state.and_then(|state| state.get::<bool>()) {
+                    CONFIG.write().unwrap().ui.is_background_play_enabled = is_enabled;
+                    action.set_state(&is_enabled.to_variant());
+                }
+            });
+            main_section.append(
+                Some(&gettext("Keep playing when closed")),
+                Some("app.toggle_background_play"),
+            );
+
             // Register Play/Pause action
             let play_pause = gio::SimpleAction::new("play_pause", None);
             app.add_action(&play_pause);
@@ -163,7 +388,25 @@ impl MainDispatcher {
 
         match event {
             About => self.main_ctrl.borrow().about(),
-            CancelSelectMedia => self.main_ctrl.borrow_mut().cancel_select_media(),
+            AudioDeviceLost(msg) => {
+                self.main_ctrl.borrow_mut().audio_device_lost().await;
+
+                warn!("{}", msg);
+                let response = self.info_bar_ctrl.ask(
+                    gtk::MessageType::Warning,
+                    msg,
+                    &[InfoBarResponse::Retry],
+                );
+                let ui_event = self.main_ctrl.borrow().ui_event.clone();
+                spawn(async move {
+                    if response.await == InfoBarResponse::Retry {
+                        ui_event.retry_audio_device();
+                    }
+                });
+            }
+            AudioLevel(level) => self.main_ctrl.borrow().audio_level(&level),
+            AudioOutputLost => self.main_ctrl.borrow_mut().audio_output_lost().await,
+            BookProbed(probing) => self.main_ctrl.borrow_mut().book_probed(probing).await,
             ChapterClicked(tree_path) => {
                 let mut main_ctrl = self.main_ctrl.borrow_mut();
                 let seek_ts = main_ctrl
@@ -173,11 +416,142 @@ impl MainDispatcher {
                     .map(|chapter| chapter.start());
 
                 if let Some(seek_ts) = seek_ts {
-                    let _ = main_ctrl.seek(seek_ts, gst::SeekFlags::ACCURATE).await;
+                    let _ = main_ctrl.jump(seek_ts).await;
+                }
+            }
+            ChaptersChanged => self.main_ctrl.borrow().chapters_changed(),
+            Close => {
+                let mut main_ctrl = self.main_ctrl.borrow_mut();
+                if CONFIG.read().unwrap().ui.is_background_play_enabled
+                    && main_ctrl.state.get() == ControllerState::Playing
+                {
+                    main_ctrl.hide();
+                } else {
+                    main_ctrl.quit();
+                    return Err(());
+                }
+            }
+            CloseMedia => self.main_ctrl.borrow_mut().close_media(),
+            CycleVideoStream => {
+                let mut main_ctrl = self.main_ctrl.borrow_mut();
+                if let super::StreamToggledStatus::Changed =
+                    main_ctrl.streams_ctrl.cycle_video_stream()
+                {
+                    let streams = main_ctrl.streams_ctrl.selected_streams();
+                    main_ctrl.select_streams(&streams).await;
                 }
             }
+            DetectSilences => self.main_ctrl.borrow_mut().detect_silences(),
+            DumpPipeline => self.main_ctrl.borrow().dump_pipeline(),
             Eos => self.main_ctrl.borrow_mut().eos(),
+            ExportMediaInfo => self.main_ctrl.borrow().export_media_info(),
+            ExternalStateChange(state) => self.main_ctrl.borrow_mut().external_state_change(state),
+            FastForward => self.main_ctrl.borrow_mut().fast_forward().await,
+            FirstChapter => {
+                let mut main_ctrl = self.main_ctrl.borrow_mut();
+                let seek_ts = main_ctrl
+                    .info_ctrl
+                    .chapter_manager
+                    .first()
+                    .map(|chapter| chapter.start());
+
+                if let Some(seek_ts) = seek_ts {
+                    let _ = main_ctrl.jump(seek_ts).await;
+                }
+            }
+            GoBack => self.main_ctrl.borrow_mut().go_back().await,
+            GoForward => self.main_ctrl.borrow_mut().go_forward().await,
+            HardwareDecoderFailed { path, err } => {
+                self.main_ctrl
+                    .borrow_mut()
+                    .hardware_decoder_failed(path, err)
+                    .await
+            }
             HideInfoBar => self.info_bar_ctrl.hide(),
+            History => self.main_ctrl.borrow().history(),
+            InfoBarResponded(response_type) => self.info_bar_ctrl.responded(response_type),
+            InsertChapters(boundaries) => self
+                .main_ctrl
+                .borrow_mut()
+                .info_ctrl
+                .insert_chapters(boundaries),
+            LastChapter => {
+                let mut main_ctrl = self.main_ctrl.borrow_mut();
+                let seek_ts = main_ctrl
+                    .info_ctrl
+                    .chapter_manager
+                    .last()
+                    .map(|chapter| chapter.start());
+
+                if let Some(seek_ts) = seek_ts {
+                    let _ = main_ctrl.jump(seek_ts).await;
+                }
+            }
+            Logs => self.main_ctrl.borrow().logs(),
+            MaxVideoHeightChanged => self.main_ctrl.borrow().max_video_height_changed(),
+            MediaChanged => {
+                let is_removed = match self.main_ctrl.borrow().pipeline.as_ref() {
+                    Some(pipeline) => !pipeline.info.path.exists(),
+                    None => return Ok(()),
+                };
+
+                let response = if is_removed {
+                    self.info_bar_ctrl.ask(
+                        gtk::MessageType::Warning,
+                        gettext("The media file was moved or removed."),
+                        &[InfoBarResponse::Close],
+                    )
+                } else {
+                    self.info_bar_ctrl.ask(
+                        gtk::MessageType::Warning,
+                        gettext("The media file was modified."),
+                        &[InfoBarResponse::Reload, InfoBarResponse::Close],
+                    )
+                };
+                let ui_event = self.main_ctrl.borrow().ui_event.clone();
+                spawn(async move {
+                    match response.await {
+                        InfoBarResponse::Close => ui_event.close_media(),
+                        InfoBarResponse::Reload => ui_event.reload_media(),
+                        _ => (),
+                    }
+                });
+            }
+            MissingPlugins(plugins) => {
+                let missing_plugins = self.main_ctrl.borrow_mut().missing_plugins(plugins);
+                let (msg, plugins) = match missing_plugins {
+                    Some(missing_plugins) => missing_plugins,
+                    None => return Ok(()),
+                };
+
+                if !plugins.can_install() {
+                    self.info_bar_ctrl.show_info(msg);
+                    return Ok(());
+                }
+
+                let response = self.info_bar_ctrl.ask(
+                    gtk::MessageType::Info,
+                    msg,
+                    &[InfoBarResponse::Install],
+                );
+                let ui_event = self.main_ctrl.borrow().ui_event.clone();
+                spawn(async move {
+                    if response.await != InfoBarResponse::Install {
+                        return;
+                    }
+
+                    let sync_sender = ui_event.sync_sender();
+                    let res = plugins.install(move |is_installed| {
+                        sync_sender.send(move |ui_event| ui_event.plugins_installed(is_installed));
+                    });
+                    if let Err(err) = res {
+                        ui_event.show_error(
+                            gettext("Couldn't start the plugins installer: {}")
+                                .replacen("{}", &err, 1),
+                        );
+                    }
+                });
+            }
             NextChapter => {
                 let mut main_ctrl = self.main_ctrl.borrow_mut();
                 let seek_ts = main_ctrl
@@ -187,11 +561,47 @@ impl MainDispatcher {
                     .map(|next_chapter| next_chapter.start());
 
                 if let Some(seek_ts) = seek_ts {
-                    let _ = main_ctrl.seek(seek_ts, gst::SeekFlags::ACCURATE).await;
+                    let _ = main_ctrl.jump(seek_ts).await;
                 }
             }
+            NextMedia => self.main_ctrl.borrow_mut().next_media().await,
+            OpenBook(dir) => self.main_ctrl.borrow_mut().open_book(dir),
+            OpenBookPart { idx, position } => {
+                self.main_ctrl
+                    .borrow_mut()
+                    .open_book_part(idx, Some(position))
+                    .await
+            }
             OpenMedia(path) => self.main_ctrl.borrow_mut().open_media(path).await,
+            PlayChapter(tree_path) => {
+                let mut main_ctrl = self.main_ctrl.borrow_mut();
+                let seek_ts = main_ctrl
+                    .info_ctrl
+                    .chapter_manager
+                    .chapter_from_path(&tree_path)
+                    .map(|chapter| chapter.start());
+
+                if let Some(seek_ts) = seek_ts {
+                    if main_ctrl.jump(seek_ts).await.is_ok()
+                        && main_ctrl.state.get() == ControllerState::Paused
+                    {
+                        main_ctrl.play_pause().await;
+                    }
+                }
+            }
             PlayPause => self.main_ctrl.borrow_mut().play_pause().await,
+            PluginsInstalled(is_installed) => {
+                if is_installed {
+                    // The missing plugins are only used when the media is opened
+                    self.info_bar_ctrl.hide();
+                    self.main_ctrl.borrow().ui_event.reload_media();
+                } else {
+                    self.info_bar_ctrl
+                        .show_error(gettext("The missing plugins couldn't be installed"));
+                }
+            }
+            PositionFinalized(ts) => self.main_ctrl.borrow_mut().info_ctrl.position_finalized(ts),
+            Preferences => self.main_ctrl.borrow().preferences(),
             PreviousChapter => {
                 let mut main_ctrl = self.main_ctrl.borrow_mut();
                 let seek_ts = main_ctrl
@@ -199,63 +609,131 @@ impl MainDispatcher {
                     .and_then(|cur_ts| main_ctrl.info_ctrl.previous_chapter(cur_ts));
 
                 let _ = main_ctrl
-                    .seek(
-                        seek_ts.unwrap_or_else(Timestamp::default),
-                        gst::SeekFlags::ACCURATE,
-                    )
+                    .jump(seek_ts.unwrap_or_else(Timestamp::default))
                     .await;
             }
             Quit => {
                 self.main_ctrl.borrow_mut().quit();
                 return Err(());
             }
+            ReloadMedia => self.main_ctrl.borrow_mut().reload_media().await,
+            ReloadToc => self.main_ctrl.borrow().info_ctrl.reload_toc(),
+            RenameChapter { tree_path, title } => self
+                .main_ctrl
+                .borrow_mut()
+                .info_ctrl
+                .rename_chapter(&tree_path, &title),
+            RepeatCountChanged => self.main_ctrl.borrow_mut().info_ctrl.repeat_count_changed(),
             ResetCursor => self.reset_cursor(),
             RestoreContext => self.restore_context(),
+            ResumeMedia(path) => self.main_ctrl.borrow_mut().resume_media(path).await,
+            RetryAudioDevice => self.main_ctrl.borrow_mut().retry_audio_device().await,
+            Rewind => self.main_ctrl.borrow_mut().rewind().await,
             ShowAll => self.show_all(),
+            SaveToc => self.main_ctrl.borrow_mut().info_ctrl.save_toc(),
+            ScrubEnd => self.main_ctrl.borrow_mut().scrub_end().await,
+            ScrubMove(target) => self.main_ctrl.borrow_mut().scrub_move(target).await,
+            ScrubStart => self.main_ctrl.borrow_mut().scrub_start(),
             Seek { target, flags } => {
                 let _ = self.main_ctrl.borrow_mut().seek(target, flags).await;
             }
-            SelectMedia => self.main_ctrl.borrow_mut().select_media().await,
-            SetCursorWaiting => self.set_cursor_waiting(),
-            ShowError(msg) => self.info_bar_ctrl.show_error(msg),
-            ShowInfo(msg) => self.info_bar_ctrl.show_info(msg),
-            StepBack => {
+            SeekSteps => self.main_ctrl.borrow_mut().seek_steps().await,
+            SeekToChapter(idx) => {
                 let mut main_ctrl = self.main_ctrl.borrow_mut();
-                if let Some(current_ts) = main_ctrl.current_ts() {
-                    let seek_ts = current_ts.saturating_sub(info_controller::SEEK_STEP);
-                    let _ = main_ctrl.seek(seek_ts, gst::SeekFlags::ACCURATE).await;
+                let seek_ts = main_ctrl
+                    .info_ctrl
+                    .chapter_manager
+                    .chapter(idx)
+                    .map(|chapter| chapter.start());
+
+                if let Some(seek_ts) = seek_ts {
+                    let _ = main_ctrl.jump(seek_ts).await;
                 }
             }
-            StepForward => {
-                let mut main_ctrl = self.main_ctrl.borrow_mut();
-                if let Some(current_ts) = main_ctrl.current_ts() {
-                    let seek_ts = current_ts + info_controller::SEEK_STEP;
-                    let _ = main_ctrl.seek(seek_ts, gst::SeekFlags::ACCURATE).await;
-                }
+            SegmentDone => self.main_ctrl.borrow_mut().segment_done(),
+            SelectBook(reply) => self.main_ctrl.borrow_mut().select_book(reply).await,
+            SelectMedia(reply) => self.main_ctrl.borrow_mut().select_media(reply).await,
+            SelectToc(idx) => self.main_ctrl.borrow_mut().info_ctrl.select_toc(idx),
+            SetCursorWaiting => self.set_cursor_waiting(),
+            ShiftAudioOffset(steps) => self.main_ctrl.borrow_mut().shift_audio_offset(steps),
+            ShowError(msg) => self.info_bar_ctrl.show_error(msg),
+            ShowInfo(msg) => self.info_bar_ctrl.show_info(msg),
+            ShowMediaToc => self.main_ctrl.borrow_mut().info_ctrl.show_media_toc(),
+            ShowWarningWithDetails { msg, details } => {
+                self.info_bar_ctrl.show_warning_with_details(msg, details)
             }
-            StreamClicked(type_) => {
+            SilencesDetected(detection) => self.main_ctrl.borrow_mut().silences_detected(detection),
+            SnapshotsTaken(capture) => self.main_ctrl.borrow_mut().snapshots_taken(capture),
+            StepBack => self.main_ctrl.borrow_mut().step_back(),
+            StepForward => self.main_ctrl.borrow_mut().step_forward(),
+            StreamToggled(tree_path) => {
                 let mut main_ctrl = self.main_ctrl.borrow_mut();
-                if let super::StreamClickedStatus::Changed =
-                    main_ctrl.streams_ctrl.stream_clicked(type_)
+                if let super::StreamToggledStatus::Changed =
+                    main_ctrl.streams_ctrl.stream_toggled(&tree_path)
                 {
                     let streams = main_ctrl.streams_ctrl.selected_streams();
                     main_ctrl.select_streams(&streams).await;
                 }
             }
+            SubtitleStyleChanged => self.main_ctrl.borrow().subtitle_style_changed(),
+            SwitchPerspective(name) => self
+                .main_ctrl
+                .borrow_mut()
+                .perspective_ctrl
+                .switch_to(&name),
             SwitchTo(focus_ctx) => self.switch_to(focus_ctx),
+            TakeChapterSnapshots => self.main_ctrl.borrow_mut().take_chapter_snapshots(),
+            TakeSnapshot => self.main_ctrl.borrow_mut().take_snapshot(),
             TemporarilySwitchTo(focus_ctx) => {
                 self.save_context();
                 self.bind_accels_for(focus_ctx);
             }
+            TocRead(reading) => {
+                let question = self.main_ctrl.borrow_mut().info_ctrl.toc_read(reading);
+                if let Some(question) = question {
+                    let response = self
+                        .info_bar_ctrl
+                        .ask_question(question, &[InfoBarResponse::Yes, InfoBarResponse::No]);
+                    let ui_event = self.main_ctrl.borrow().ui_event.clone();
+                    spawn(async move {
+                        if response.await == InfoBarResponse::No {
+                            ui_event.show_media_toc();
+                        }
+                    });
+                }
+            }
+            TocUpdated(toc) => self.main_ctrl.borrow_mut().toc_updated(toc),
             ToggleChapterList(must_show) => self
                 .main_ctrl
                 .borrow()
                 .info_ctrl
                 .toggle_chapter_list(must_show),
+            ToggleFlatChapters(is_flat) => self
+                .main_ctrl
+                .borrow_mut()
+                .info_ctrl
+                .toggle_flat_chapters(is_flat),
+            ToggleMiniPlayer(is_mini) => self.main_ctrl.borrow_mut().toggle_mini_player(is_mini),
             ToggleRepeat(must_repeat) => {
-                self.main_ctrl.borrow_mut().info_ctrl.repeat_chapter = must_repeat
+                self.main_ctrl.borrow_mut().toggle_repeat(must_repeat).await
+            }
+            ToggleStopAtChapterEnd(must_stop) => self
+                .main_ctrl
+                .borrow_mut()
+                .info_ctrl
+                .toggle_stop_at_chapter_end(must_stop),
+            UpdateChapterTitle(chapter_title) => {
+                self.main_ctrl.borrow().update_chapter_title(chapter_title)
             }
             UpdateFocus => self.update_focus(),
+            WaveformGenerated(generation) => {
+                self.main_ctrl.borrow_mut().waveform_generated(generation)
+            }
+            WindowResized(width) => self
+                .main_ctrl
+                .borrow_mut()
+                .perspective_ctrl
+                .update_layout(width),
         }
 
         Ok(())
@@ -291,6 +769,7 @@ impl MainDispatcher {
                 self.app
                     .set_accels_for_action("app.previous_chapter", &["Up", "AudioPrev"]);
                 self.app.set_accels_for_action("app.close_info_bar", &[]);
+                self.bind_shift_audio_offset_accels(true);
             }
             UIFocusContext::StreamsPage => {
                 self.app
@@ -300,6 +779,7 @@ impl MainDispatcher {
                 self.app
                     .set_accels_for_action("app.previous_chapter", &["AudioPrev"]);
                 self.app.set_accels_for_action("app.close_info_bar", &[]);
+                self.bind_shift_audio_offset_accels(true);
             }
             UIFocusContext::InfoBar => {
                 self.app
@@ -308,6 +788,17 @@ impl MainDispatcher {
                 self.app.set_accels_for_action("app.previous_chapter", &[]);
                 self.app
                     .set_accels_for_action("app.close_info_bar", &["Escape"]);
+                self.bind_shift_audio_offset_accels(false);
+            }
+            UIFocusContext::TextEntry => {
+                self.app
+                    .set_accels_for_action("app.play_pause", &["AudioPlay"]);
+                self.app
+                    .set_accels_for_action("app.next_chapter", &["AudioNext"]);
+                self.app
+                    .set_accels_for_action("app.previous_chapter", &["AudioPrev"]);
+                self.app.set_accels_for_action("app.close_info_bar", &[]);
+                self.bind_shift_audio_offset_accels(false);
             }
         }
 
@@ -317,6 +808,19 @@ impl MainDispatcher {
         StreamsDispatcher::bind_accels_for(ctx, &self.app);
     }
 
+    // Keys o and O shorten and lengthen the audio delay
+    fn bind_shift_audio_offset_accels(&self, is_enabled: bool) {
+        let (earlier, later): (&[&str], &[&str]) = if is_enabled {
+            (&["o"], &["<Shift>o"])
+        } else {
+            (&[], &[])
+        };
+        self.app
+            .set_accels_for_action("app.shift_audio_offset(-1)", earlier);
+        self.app
+            .set_accels_for_action("app.shift_audio_offset(1)", later);
+    }
+
     fn update_focus(&self) {
         let main_ctrl = self.main_ctrl.borrow();
         match self.focus {
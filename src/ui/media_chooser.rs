@@ -0,0 +1,100 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use crate::metadata::MediaPath;
+
+use super::ui_event::Reply;
+
+type PendingReply = Rc<RefCell<Option<Reply<Option<MediaPath>>>>>;
+
+/// Asks the user for a media file or a folder.
+///
+/// Controllers only deal with the `Reply`, the dialog itself
+/// is the only part depending on the toolkit's file chooser API.
+pub struct MediaChooser {
+    dlg: gtk::FileChooserNative,
+    folder_dlg: gtk::FileChooserNative,
+    pending: PendingReply,
+}
+
+impl MediaChooser {
+    pub fn new(window: &gtk::ApplicationWindow) -> Self {
+        let pending: PendingReply = Default::default();
+
+        let dlg = Self::new_dialog(
+            window,
+            &gettext("Open a media file"),
+            gtk::FileChooserAction::Open,
+            &pending,
+        );
+        let folder_dlg = Self::new_dialog(
+            window,
+            &gettext("Open a folder as a book"),
+            gtk::FileChooserAction::SelectFolder,
+            &pending,
+        );
+
+        MediaChooser {
+            dlg,
+            folder_dlg,
+            pending,
+        }
+    }
+
+    fn new_dialog(
+        window: &gtk::ApplicationWindow,
+        title: &str,
+        action: gtk::FileChooserAction,
+        pending: &PendingReply,
+    ) -> gtk::FileChooserNative {
+        let dlg = gtk::FileChooserNativeBuilder::new()
+            .title(title)
+            .action(action)
+            .transient_for(window)
+            .modal(true)
+            .accept_label(&gettext("Open"))
+            .cancel_label(&gettext("Cancel"))
+            .build();
+
+        dlg.connect_response(clone!(@strong pending => move |dlg, response| {
+            dlg.hide();
+
+            let path = if response == gtk::ResponseType::Accept {
+                dlg.get_filename().map(MediaPath::from)
+            } else {
+                None
+            };
+            if let Some(reply) = pending.borrow_mut().take() {
+                reply.reply(path);
+            }
+        }));
+
+        dlg
+    }
+
+    /// Shows the dialog, starting from `folder` if provided.
+    ///
+    /// The selected media, if any, is sent to `reply`.
+    /// A previous selection, if any, is abandoned.
+    pub fn choose(&self, folder: Option<&Path>, reply: Reply<Option<MediaPath>>) {
+        *self.pending.borrow_mut() = Some(reply);
+
+        if let Some(folder) = folder {
+            self.dlg.set_current_folder(folder);
+        }
+        self.dlg.show();
+    }
+
+    /// Same as `choose`, but for a folder.
+    pub fn choose_folder(&self, folder: Option<&Path>, reply: Reply<Option<MediaPath>>) {
+        *self.pending.borrow_mut() = Some(reply);
+
+        if let Some(folder) = folder {
+            self.folder_dlg.set_current_folder(folder);
+        }
+        self.folder_dlg.show();
+    }
+}
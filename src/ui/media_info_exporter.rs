@@ -0,0 +1,74 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+
+use std::{cell::RefCell, fs, rc::Rc};
+
+use crate::metadata::{MediaInfo, MediaReport, ReportFormat};
+
+use super::UIEventSender;
+
+/// Saves a `MediaReport` to the file chosen by the user.
+///
+/// The report is rendered as JSON if the file has a `json` extension,
+/// as plain text otherwise.
+pub struct MediaInfoExporter {
+    dlg: gtk::FileChooserNative,
+    pending: Rc<RefCell<Option<MediaReport>>>,
+}
+
+impl MediaInfoExporter {
+    pub fn new(window: &gtk::ApplicationWindow, ui_event: &UIEventSender) -> Self {
+        let dlg = gtk::FileChooserNativeBuilder::new()
+            .title(&gettext("Export media info"))
+            .transient_for(window)
+            .modal(true)
+            .action(gtk::FileChooserAction::Save)
+            .do_overwrite_confirmation(true)
+            .accept_label(&gettext("Export"))
+            .cancel_label(&gettext("Cancel"))
+            .build();
+
+        let pending: Rc<RefCell<Option<MediaReport>>> = Default::default();
+        dlg.connect_response(
+            clone!(@strong pending, @strong ui_event => move |dlg, response| {
+                dlg.hide();
+
+                let report = match pending.borrow_mut().take() {
+                    Some(report) => report,
+                    None => return,
+                };
+                if response != gtk::ResponseType::Accept {
+                    return;
+                }
+                let path = match dlg.get_filename() {
+                    Some(path) => path,
+                    None => return,
+                };
+
+                match fs::write(&path, report.render(ReportFormat::from_path(&path))) {
+                    Ok(()) => ui_event.show_info(
+                        gettext("Media info exported to {}")
+                            .replacen("{}", &path.display().to_string(), 1),
+                    ),
+                    Err(err) => ui_event.show_error(
+                        gettext("Couldn't export the media info: {}")
+                            .replacen("{}", &err.to_string(), 1),
+                    ),
+                }
+            }),
+        );
+
+        MediaInfoExporter { dlg, pending }
+    }
+
+    pub fn export(&self, info: &MediaInfo) {
+        *self.pending.borrow_mut() = Some(MediaReport::new(info));
+
+        if let Some(folder) = info.path.parent() {
+            self.dlg.set_current_folder(folder);
+        }
+        self.dlg.set_current_name(&format!("{}.json", info.name));
+        self.dlg.show();
+    }
+}
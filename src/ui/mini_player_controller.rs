@@ -0,0 +1,167 @@
+use gdk::WindowExt;
+use gtk::prelude::*;
+
+use crate::{
+    application::CONFIG,
+    media::{PlaybackPipeline, Timestamp},
+};
+
+use super::{image, UIController};
+
+const FULL_PAGE: &str = "full";
+const MINI_PAGE: &str = "mini";
+const COVER_ICON: &str = "audio-x-generic-symbolic";
+const COVER_SIZE: i32 = 48;
+const DEFAULT_WIDTH: i32 = 420;
+
+/// A small always on top bar with the essential playback controls.
+pub struct MiniPlayerController {
+    window: gtk::ApplicationWindow,
+    stack: gtk::Stack,
+    cover_image: gtk::Image,
+    title_lbl: gtk::Label,
+    chapter_lbl: gtk::Label,
+    position_lbl: gtk::Label,
+    play_pause_image: gtk::Image,
+
+    // Size of the regular window, restored when leaving the mini player
+    full_size: Option<(i32, i32)>,
+}
+
+impl MiniPlayerController {
+    pub fn new(builder: &gtk::Builder, window: &gtk::ApplicationWindow) -> Self {
+        let mut ctrl = MiniPlayerController {
+            window: window.clone(),
+            stack: builder.get_object("window-stack").unwrap(),
+            cover_image: builder.get_object("mini_player-cover-image").unwrap(),
+            title_lbl: builder.get_object("mini_player-title-lbl").unwrap(),
+            chapter_lbl: builder.get_object("mini_player-chapter-lbl").unwrap(),
+            position_lbl: builder.get_object("mini_player-position-lbl").unwrap(),
+            play_pause_image: builder.get_object("mini_player-play_pause-image").unwrap(),
+            full_size: None,
+        };
+
+        ctrl.cleanup();
+
+        ctrl
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.full_size.is_some()
+    }
+
+    pub fn enter(&mut self) {
+        if self.is_active() {
+            return;
+        }
+
+        let window_state = self
+            .window
+            .get_window()
+            .map_or_else(gdk::WindowState::empty, |gdk_window| gdk_window.get_state());
+        if window_state.contains(gdk::WindowState::FULLSCREEN) {
+            self.window.unfullscreen();
+        }
+        if window_state.contains(gdk::WindowState::MAXIMIZED) {
+            self.window.unmaximize();
+        }
+
+        self.full_size = Some(self.window.get_size());
+
+        self.stack.set_visible_child_name(MINI_PAGE);
+        self.window.set_keep_above(true);
+
+        let (width, height) = {
+            let config = CONFIG.read().unwrap();
+            (config.ui.mini_width, config.ui.mini_height)
+        };
+        if width > 0 && height > 0 {
+            self.window.resize(width, height);
+        } else {
+            // Shrink to the content
+            self.window.resize(DEFAULT_WIDTH, 1);
+        }
+    }
+
+    pub fn leave(&mut self) {
+        let (width, height) = match self.full_size.take() {
+            Some(full_size) => full_size,
+            None => return,
+        };
+
+        self.save_geometry();
+
+        self.window.set_keep_above(false);
+        self.stack.set_visible_child_name(FULL_PAGE);
+        self.window.resize(width, height);
+    }
+
+    /// Size of the regular window, if the mini player is active.
+    pub fn full_size(&self) -> Option<(i32, i32)> {
+        self.full_size
+    }
+
+    pub fn save_geometry(&self) {
+        if !self.is_active() {
+            return;
+        }
+
+        let (width, height) = self.window.get_size();
+        let mut config = CONFIG.write().unwrap();
+        config.ui.mini_width = width;
+        config.ui.mini_height = height;
+    }
+
+    pub fn set_title(&self, title: &str) {
+        self.title_lbl.set_text(title);
+    }
+
+    pub fn set_chapter_title(&self, chapter_title: Option<&str>) {
+        self.chapter_lbl.set_text(chapter_title.unwrap_or(""));
+    }
+
+    pub fn set_play_pause_icon(&self, icon_name: &str) {
+        self.play_pause_image
+            .set_from_icon_name(Some(icon_name), gtk::IconSize::Button);
+    }
+
+    pub fn tick(&self, ts: Timestamp) {
+        if !self.is_active() {
+            return;
+        }
+
+        self.position_lbl.set_text(
+            &ts.for_humans()
+                .display(CONFIG.read().unwrap().ui.timestamp_display()),
+        );
+    }
+}
+
+impl UIController for MiniPlayerController {
+    fn new_media(&mut self, pipeline: &PlaybackPipeline) {
+        let info = &pipeline.info;
+        self.set_title(info.media_title().unwrap_or(&info.file_name));
+
+        let cover = info
+            .cover_art
+            .as_ref()
+            .and_then(|cover_art| image::scaled_pixbuf(&cover_art.data, COVER_SIZE));
+        match cover {
+            Some(cover) => self.cover_image.set_from_pixbuf(Some(&cover)),
+            None => {
+                self.cover_image
+                    .set_from_icon_name(Some(COVER_ICON), gtk::IconSize::Dialog);
+                self.cover_image.set_pixel_size(COVER_SIZE);
+            }
+        }
+    }
+
+    fn cleanup(&mut self) {
+        self.title_lbl.set_text("");
+        self.chapter_lbl.set_text("");
+        self.position_lbl.set_text("00:00.000");
+        self.cover_image
+            .set_from_icon_name(Some(COVER_ICON), gtk::IconSize::Dialog);
+        self.cover_image.set_pixel_size(COVER_SIZE);
+    }
+}
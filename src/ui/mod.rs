@@ -1,29 +1,70 @@
+mod chapter_tree;
+use self::chapter_tree::{
+    ChapterEntry, ChapterTimestamps, ChapterTree, ContinuityIssue, PositionStatus,
+};
 mod chapter_tree_manager;
-use self::chapter_tree_manager::{ChapterTreeManager, PositionStatus};
+use self::chapter_tree_manager::ChapterTreeManager;
+
+mod dbus_service;
+use self::dbus_service::DBusService;
+
+mod history_dialog;
 
 mod image;
 use self::image::Image;
 
 mod info_bar_controller;
-use self::info_bar_controller::InfoBarController;
+use self::info_bar_controller::{InfoBarController, InfoBarResponse};
 
 mod info_controller;
-use self::info_controller::InfoController;
+use self::info_controller::{InfoController, TocReading};
 mod info_dispatcher;
 use self::info_dispatcher::InfoDispatcher;
 
+mod launcher_entry;
+use self::launcher_entry::LauncherEntry;
+
+mod logs_dialog;
+
 pub mod main_controller;
-pub use self::main_controller::{ControllerState, MainController};
+pub use self::main_controller::{
+    BookProbing, MainController, SilenceDetection, SnapshotCapture, WaveformGeneration,
+};
 mod main_dispatcher;
 pub use self::main_dispatcher::MainDispatcher;
 
+mod media_chooser;
+use self::media_chooser::MediaChooser;
+mod media_info_exporter;
+use self::media_info_exporter::MediaInfoExporter;
+
+mod mini_player_controller;
+use self::mini_player_controller::MiniPlayerController;
+
+mod mpris;
+use self::mpris::{Mpris, MprisChapter};
+
 mod perspective_controller;
 use self::perspective_controller::PerspectiveController;
 mod perspective_dispatcher;
 use self::perspective_dispatcher::PerspectiveDispatcher;
 
+mod playback_policy;
+use self::playback_policy::{ChapterEndAction, ChapterEndMode, PlaybackPolicy};
+
+mod playback_state;
+pub use self::playback_state::ControllerState;
+use self::playback_state::{PlaybackEvent, PlaybackStateMachine, Transition, TRICK_SPEEDS};
+
+mod preferences_dialog;
+
+mod seek_serializer;
+use self::seek_serializer::SeekSerializer;
+
+mod silence_dialog;
+
 mod streams_controller;
-use self::streams_controller::{StreamClickedStatus, StreamsController};
+use self::streams_controller::{StreamToggledStatus, StreamsController};
 mod streams_dispatcher;
 use self::streams_dispatcher::StreamsDispatcher;
 
@@ -35,14 +76,18 @@ use self::video_controller::VideoController;
 mod video_dispatcher;
 use self::video_dispatcher::VideoDispatcher;
 
+mod waveform_strip;
+use self::waveform_strip::WaveformStrip;
+
 use futures::prelude::*;
 use gio::prelude::*;
+use gtk::prelude::*;
 use log::warn;
 
 use std::{cell::RefCell, rc::Rc};
 
 use crate::{
-    application::{CommandLineArguments, APP_ID},
+    application::{CommandLineArguments, APP_ID, APP_PATH},
     media::{self, PlaybackPipeline},
     metadata,
 };
@@ -62,20 +107,40 @@ fn register_resource(resource: &[u8]) {
         });
 }
 
+// The icons bundled in the resource don't depend on the icon theme in use
+fn register_icons() {
+    if let Some(icon_theme) = gtk::IconTheme::get_default() {
+        icon_theme.add_resource_path(&format!("{}/icons", *APP_PATH));
+    }
+}
+
 pub fn run(args: CommandLineArguments) {
     register_resource(include_bytes!("../../target/resources/ui.gresource"));
 
     let gtk_app = gtk::Application::new(Some(&APP_ID), gio::ApplicationFlags::empty())
         .expect("Failed to initialize GtkApplication");
 
-    gtk_app.connect_activate(move |gtk_app| MainController::setup(gtk_app, &args));
+    gtk_app.connect_startup(|_| register_icons());
+
+    gtk_app.connect_activate(move |gtk_app| match gtk_app.get_windows().first() {
+        // Already running, e.g. playing in the background after the window was closed
+        Some(window) => window.present(),
+        None => {
+            MainController::setup(gtk_app, &args);
+        }
+    });
     gtk_app.run(&[]);
 }
 
 pub trait UIController {
     fn new_media(&mut self, _pipeline: &PlaybackPipeline) {}
     fn cleanup(&mut self);
-    fn streams_changed(&mut self, _info: &metadata::MediaInfo) {}
+    fn streams_changed(
+        &mut self,
+        _info: &metadata::MediaInfo,
+        _delta: &metadata::StreamSelectionDelta,
+    ) {
+    }
     fn grab_focus(&self) {}
 }
 
@@ -92,3 +157,6 @@ pub trait UIDispatcher {
     // bind context specific accels
     fn bind_accels_for(_ctx: UIFocusContext, _app: &gtk::Application) {}
 }
+
+#[cfg(all(test, feature = "ui-tests"))]
+mod tests;
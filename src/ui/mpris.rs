@@ -0,0 +1,522 @@
+//! Exposes the chapters of the media as an MPRIS `TrackList`.
+//!
+//! Desktop widgets (GNOME Shell, KDE Plasma, ...) can then list the chapters
+//! and jump to one of them. The `Player` interface is read-only: it only
+//! reports the current chapter & the playback status.
+//!
+//! See <https://specifications.freedesktop.org/mpris-spec/latest/>.
+
+use glib::{StaticVariantType, ToVariant};
+use log::debug;
+
+use std::{
+    borrow::Cow,
+    sync::{Arc, Mutex},
+};
+
+use crate::media::Timestamp;
+
+use super::ui_event::UIEventSyncSender;
+
+const BUS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const TRACK_LIST_INTERFACE: &str = "org.mpris.MediaPlayer2.TrackList";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.mpris.MediaPlayer2">
+    <method name="Raise"/>
+    <method name="Quit"/>
+    <property name="CanQuit" type="b" access="read"/>
+    <property name="CanRaise" type="b" access="read"/>
+    <property name="HasTrackList" type="b" access="read"/>
+    <property name="Identity" type="s" access="read"/>
+    <property name="DesktopEntry" type="s" access="read"/>
+    <property name="SupportedUriSchemes" type="as" access="read"/>
+    <property name="SupportedMimeTypes" type="as" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.Player">
+    <method name="Next"/>
+    <method name="Previous"/>
+    <method name="Pause"/>
+    <method name="PlayPause"/>
+    <method name="Stop"/>
+    <method name="Play"/>
+    <method name="Seek">
+      <arg direction="in" name="Offset" type="x"/>
+    </method>
+    <method name="SetPosition">
+      <arg direction="in" name="TrackId" type="o"/>
+      <arg direction="in" name="Position" type="x"/>
+    </method>
+    <method name="OpenUri">
+      <arg direction="in" name="Uri" type="s"/>
+    </method>
+    <signal name="Seeked">
+      <arg name="Position" type="x"/>
+    </signal>
+    <property name="PlaybackStatus" type="s" access="read"/>
+    <property name="Rate" type="d" access="read"/>
+    <property name="Metadata" type="a{sv}" access="read"/>
+    <property name="Position" type="x" access="read"/>
+    <property name="MinimumRate" type="d" access="read"/>
+    <property name="MaximumRate" type="d" access="read"/>
+    <property name="CanGoNext" type="b" access="read"/>
+    <property name="CanGoPrevious" type="b" access="read"/>
+    <property name="CanPlay" type="b" access="read"/>
+    <property name="CanPause" type="b" access="read"/>
+    <property name="CanSeek" type="b" access="read"/>
+    <property name="CanControl" type="b" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.TrackList">
+    <method name="GetTracksMetadata">
+      <arg direction="in" name="TrackIds" type="ao"/>
+      <arg direction="out" name="Metadata" type="aa{sv}"/>
+    </method>
+    <method name="AddTrack">
+      <arg direction="in" name="Uri" type="s"/>
+      <arg direction="in" name="AfterTrack" type="o"/>
+      <arg direction="in" name="SetAsCurrent" type="b"/>
+    </method>
+    <method name="RemoveTrack">
+      <arg direction="in" name="TrackId" type="o"/>
+    </method>
+    <method name="GoTo">
+      <arg direction="in" name="TrackId" type="o"/>
+    </method>
+    <signal name="TrackListReplaced">
+      <arg name="Tracks" type="ao"/>
+      <arg name="CurrentTrack" type="o"/>
+    </signal>
+    <property name="Tracks" type="ao" access="read"/>
+    <property name="CanEditTracks" type="b" access="read"/>
+  </interface>
+</node>
+"#;
+
+// glib doesn't provide `ToVariant` for D-Bus object paths
+struct ObjectPath(String);
+
+impl StaticVariantType for ObjectPath {
+    fn static_variant_type() -> Cow<'static, glib::VariantTy> {
+        Cow::Borrowed(glib::VariantTy::new("o").unwrap())
+    }
+}
+
+impl ToVariant for ObjectPath {
+    fn to_variant(&self) -> glib::Variant {
+        // Object paths are serialized as nul terminated strings
+        let mut bytes = self.0.as_bytes().to_vec();
+        bytes.push(0);
+        glib::Variant::from_bytes::<ObjectPath>(&glib::Bytes::from_owned(bytes))
+    }
+}
+
+// A `glib::Variant` would be boxed in a `v` when used as a tuple field or an array item
+struct Metadata(glib::Variant);
+
+impl StaticVariantType for Metadata {
+    fn static_variant_type() -> Cow<'static, glib::VariantTy> {
+        Cow::Borrowed(glib::VariantTy::new("a{sv}").unwrap())
+    }
+}
+
+impl ToVariant for Metadata {
+    fn to_variant(&self) -> glib::Variant {
+        self.0.clone()
+    }
+}
+
+/// A chapter as exposed to the MPRIS clients.
+///
+/// Timestamps are expressed in nanoseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MprisChapter {
+    pub title: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, Default)]
+struct MprisState {
+    desktop_entry: String,
+    track_id_prefix: String,
+    title: Option<String>,
+    art_url: Option<String>,
+    duration: u64,
+    chapters: Vec<MprisChapter>,
+    current: Option<usize>,
+    position: u64,
+    is_playing: bool,
+    has_media: bool,
+}
+
+impl MprisState {
+    fn new(app_id: &str) -> Self {
+        MprisState {
+            desktop_entry: app_id.to_owned(),
+            track_id_prefix: format!("/{}/Chapter/", app_id.replace('.', "/").replace('-', "_")),
+            ..MprisState::default()
+        }
+    }
+
+    fn track_id(&self, idx: Option<usize>) -> ObjectPath {
+        ObjectPath(match idx {
+            Some(idx) => format!("{}{}", self.track_id_prefix, idx),
+            None => NO_TRACK.to_owned(),
+        })
+    }
+
+    fn chapter_idx(&self, track_id: &str) -> Option<usize> {
+        if !track_id.starts_with(&self.track_id_prefix) {
+            return None;
+        }
+
+        let idx = track_id[self.track_id_prefix.len()..]
+            .parse::<usize>()
+            .ok()?;
+        if idx < self.chapters.len() {
+            Some(idx)
+        } else {
+            None
+        }
+    }
+
+    fn tracks(&self) -> Vec<ObjectPath> {
+        (0..self.chapters.len())
+            .map(|idx| self.track_id(Some(idx)))
+            .collect()
+    }
+
+    fn metadata(&self, idx: Option<usize>) -> Metadata {
+        let metadata = glib::VariantDict::new(None);
+        if self.has_media {
+            let (title, length) = match idx.and_then(|idx| self.chapters.get(idx)) {
+                Some(chapter) => (
+                    Some(chapter.title.as_str()),
+                    chapter.end.saturating_sub(chapter.start),
+                ),
+                None => (self.title.as_deref(), self.duration),
+            };
+
+            metadata.insert_value("mpris:trackid", &self.track_id(idx).to_variant());
+            // MPRIS durations are expressed in µs
+            metadata.insert_value("mpris:length", &((length / 1_000) as i64).to_variant());
+            if let Some(title) = title {
+                metadata.insert_value("xesam:title", &title.to_variant());
+            }
+            if let Some(album) = self.title.as_ref() {
+                metadata.insert_value("xesam:album", &album.to_variant());
+            }
+            if let Some(art_url) = self.art_url.as_ref() {
+                metadata.insert_value("mpris:artUrl", &art_url.to_variant());
+            }
+        }
+
+        Metadata(metadata.end())
+    }
+
+    fn player_property(&self, property: &str) -> glib::Variant {
+        match property {
+            "PlaybackStatus" => {
+                if !self.has_media {
+                    "Stopped"
+                } else if self.is_playing {
+                    "Playing"
+                } else {
+                    "Paused"
+                }
+            }
+            .to_variant(),
+            "Rate" | "MinimumRate" | "MaximumRate" => 1f64.to_variant(),
+            "Metadata" => self.metadata(self.current).to_variant(),
+            "Position" => ((self.position / 1_000) as i64).to_variant(),
+            // `CanGoNext`, `CanGoPrevious`, ..., `CanControl`
+            _ => false.to_variant(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.title = None;
+        self.art_url = None;
+        self.duration = 0;
+        self.chapters.clear();
+        self.current = None;
+        self.position = 0;
+        self.is_playing = false;
+        self.has_media = false;
+    }
+}
+
+struct MprisService {
+    state: Arc<Mutex<MprisState>>,
+    // Method calls are handled on the main context, but the handlers must be `Sync`
+    ui_event: Mutex<UIEventSyncSender>,
+}
+
+impl MprisService {
+    fn method_call(
+        &self,
+        interface: &str,
+        method: &str,
+        parameters: &glib::Variant,
+        invocation: gio::DBusMethodInvocation,
+    ) {
+        match (interface, method) {
+            (TRACK_LIST_INTERFACE, "GetTracksMetadata") => {
+                let state = self.state.lock().unwrap();
+                let track_ids = parameters.get_child_value(0);
+                let metadata: Vec<Metadata> = (0..track_ids.n_children())
+                    .filter_map(|idx| {
+                        let track_id = track_ids.get_child_value(idx);
+                        let chapter_idx = state.chapter_idx(track_id.get_str()?)?;
+                        Some(state.metadata(Some(chapter_idx)))
+                    })
+                    .collect();
+
+                invocation.return_value(Some(&(metadata,).to_variant()));
+            }
+            (TRACK_LIST_INTERFACE, "GoTo") => {
+                let chapter_idx = parameters
+                    .get_child_value(0)
+                    .get_str()
+                    .and_then(|track_id| self.state.lock().unwrap().chapter_idx(track_id));
+                if let Some(chapter_idx) = chapter_idx {
+                    self.ui_event
+                        .lock()
+                        .unwrap()
+                        .send(move |ui_event| ui_event.seek_to_chapter(chapter_idx));
+                }
+
+                invocation.return_value(None);
+            }
+            // Chapters can't be edited & the player can't be controlled (`CanControl` is false)
+            _ => invocation.return_value(None),
+        }
+    }
+
+    fn get_property(&self, interface: &str, property: &str) -> glib::Variant {
+        let state = self.state.lock().unwrap();
+        match (interface, property) {
+            (ROOT_INTERFACE, "HasTrackList") => true.to_variant(),
+            (ROOT_INTERFACE, "Identity") => "media-toc player".to_variant(),
+            (ROOT_INTERFACE, "DesktopEntry") => state.desktop_entry.to_variant(),
+            (ROOT_INTERFACE, "SupportedUriSchemes") | (ROOT_INTERFACE, "SupportedMimeTypes") => {
+                Vec::<String>::new().to_variant()
+            }
+            (PLAYER_INTERFACE, _) => state.player_property(property),
+            (TRACK_LIST_INTERFACE, "Tracks") => state.tracks().to_variant(),
+            // `CanQuit`, `CanRaise` & `CanEditTracks`
+            _ => false.to_variant(),
+        }
+    }
+}
+
+pub struct Mpris {
+    connection: Option<gio::DBusConnection>,
+    state: Arc<Mutex<MprisState>>,
+}
+
+impl Mpris {
+    pub fn new(app_id: &str, app_name: &str, ui_event: UIEventSyncSender) -> Self {
+        let state = Arc::new(Mutex::new(MprisState::new(app_id)));
+        let connection = gio::bus_get_sync(gio::BusType::Session, gio::NONE_CANCELLABLE)
+            .map_err(|err| debug!("Mpris: no session bus: {}", err))
+            .ok();
+
+        if let Some(connection) = connection.as_ref() {
+            let service = Arc::new(MprisService {
+                state: Arc::clone(&state),
+                ui_event: Mutex::new(ui_event),
+            });
+            Self::register(connection, &service);
+
+            let _ = gio::bus_own_name_on_connection(
+                connection,
+                &format!("{}.{}", BUS_NAME_PREFIX, app_name),
+                gio::BusNameOwnerFlags::NONE,
+                |_, _| (),
+                |_, name| debug!("Mpris: couldn't own {}", name),
+            );
+        }
+
+        Mpris { connection, state }
+    }
+
+    fn register(connection: &gio::DBusConnection, service: &Arc<MprisService>) {
+        let node_info = gio::DBusNodeInfo::new_for_xml(INTROSPECTION).unwrap();
+        for interface in &[ROOT_INTERFACE, PLAYER_INTERFACE, TRACK_LIST_INTERFACE] {
+            let method_service = Arc::clone(service);
+            let property_service = Arc::clone(service);
+            let res = connection.register_object(
+                OBJECT_PATH,
+                &node_info.lookup_interface(interface).unwrap(),
+                move |_, _, _, interface, method, parameters, invocation| {
+                    method_service.method_call(interface, method, &parameters, invocation)
+                },
+                move |_, _, _, interface, property| {
+                    property_service.get_property(interface, property)
+                },
+                |_, _, _, _, _, _| false,
+            );
+
+            if let Err(err) = res {
+                debug!("Mpris: couldn't register {}: {}", interface, err);
+            }
+        }
+    }
+
+    /// Replaces the track list with the `chapters` of the media.
+    pub fn set_media(&self, title: &str, duration: u64, chapters: Vec<MprisChapter>) {
+        let parameters = {
+            let mut state = self.state.lock().unwrap();
+            state.title = Some(title.to_owned());
+            state.duration = duration;
+            state.chapters = chapters;
+            state.current = None;
+            state.has_media = true;
+
+            (state.tracks(), state.track_id(None)).to_variant()
+        };
+
+        self.emit(TRACK_LIST_INTERFACE, "TrackListReplaced", &parameters);
+        self.player_changed(&["Metadata", "PlaybackStatus"]);
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().clear();
+
+        self.emit(
+            TRACK_LIST_INTERFACE,
+            "TrackListReplaced",
+            &(Vec::<ObjectPath>::new(), ObjectPath(NO_TRACK.to_owned())).to_variant(),
+        );
+        self.player_changed(&["Metadata", "PlaybackStatus"]);
+    }
+
+    /// Sets the `file://` URL of the media's cover art.
+    pub fn set_art_url(&self, art_url: Option<String>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.art_url == art_url {
+                return;
+            }
+            state.art_url = art_url;
+        }
+
+        self.player_changed(&["Metadata"]);
+    }
+
+    /// Playback entered the chapter at `chapter_idx`, `None` if not in a chapter.
+    pub fn set_current_chapter(&self, chapter_idx: Option<usize>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.current == chapter_idx {
+                return;
+            }
+            state.current = chapter_idx;
+        }
+
+        self.player_changed(&["Metadata"]);
+    }
+
+    pub fn set_playing(&self, is_playing: bool) {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.is_playing == is_playing {
+                return;
+            }
+            state.is_playing = is_playing;
+        }
+
+        self.player_changed(&["PlaybackStatus"]);
+    }
+
+    /// Clients query the position, so no signal is emitted.
+    pub fn tick(&self, ts: Timestamp) {
+        self.state.lock().unwrap().position = ts.as_u64();
+    }
+
+    /// The position changed in a way which can't be deduced from the playback rate.
+    pub fn seeked(&self, ts: Timestamp) {
+        self.tick(ts);
+        self.emit(
+            PLAYER_INTERFACE,
+            "Seeked",
+            &((ts.as_u64() / 1_000) as i64,).to_variant(),
+        );
+    }
+
+    fn player_changed(&self, properties: &[&str]) {
+        let changed = glib::VariantDict::new(None);
+        {
+            let state = self.state.lock().unwrap();
+            for property in properties {
+                changed.insert_value(property, &state.player_property(property));
+            }
+        }
+
+        let parameters = (
+            PLAYER_INTERFACE,
+            Metadata(changed.end()),
+            Vec::<String>::new(),
+        )
+            .to_variant();
+        self.emit(PROPERTIES_INTERFACE, "PropertiesChanged", &parameters);
+    }
+
+    fn emit(&self, interface: &str, signal: &str, parameters: &glib::Variant) {
+        let connection = match self.connection.as_ref() {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        if let Err(err) =
+            connection.emit_signal(None, OBJECT_PATH, interface, signal, Some(parameters))
+        {
+            debug!("Mpris: couldn't emit {}: {}", signal, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_ids() {
+        let mut state = MprisState::new("org.fengalin.media-toc-player");
+        state.chapters = vec![
+            MprisChapter {
+                title: "intro".to_owned(),
+                start: 0,
+                end: 60_000_000_000,
+            },
+            MprisChapter {
+                title: "outro".to_owned(),
+                start: 60_000_000_000,
+                end: 90_000_000_000,
+            },
+        ];
+
+        let tracks = state.tracks();
+        assert_eq!(2, tracks.len());
+        assert_eq!("/org/fengalin/media_toc_player/Chapter/1", tracks[1].0);
+        assert_eq!(Some(1), state.chapter_idx(&tracks[1].0));
+
+        assert_eq!(NO_TRACK, state.track_id(None).0);
+        assert_eq!(None, state.chapter_idx(NO_TRACK));
+        assert_eq!(
+            None,
+            state.chapter_idx("/org/fengalin/media_toc_player/Chapter/2")
+        );
+        assert_eq!(
+            None,
+            state.chapter_idx("/org/fengalin/media_toc_player/Chapter/x")
+        );
+    }
+}
@@ -1,35 +1,159 @@
 use gtk::prelude::*;
 
-use crate::media::PlaybackPipeline;
+use std::collections::HashMap;
 
-use super::UIController;
+use crate::{media::PlaybackPipeline, metadata::CoverArt};
+
+use super::{image, UIController};
+
+// Displayed when no media is open
+const LANDING_PAGE: &str = "landing";
+const DISPLAY_PAGE: &str = "display";
+const STREAMS_PAGE: &str = "streams";
+// Below this window width, the streams are only displayed in their own perspective
+const SPLIT_VIEW_MIN_WIDTH: i32 = 1400;
+const LANDING_ICON: &str = "emblem-documents-symbolic";
+const LANDING_ICON_SIZE: i32 = 96;
+const LANDING_COVER_SIZE: i32 = 192;
 
 pub struct PerspectiveController {
+    pub(super) window: gtk::ApplicationWindow,
     pub(super) menu_btn: gtk::MenuButton,
+    menu_btn_image: gtk::Image,
     pub(super) popover: gtk::PopoverMenu,
     pub(super) stack: gtk::Stack,
+    // Shows the cover art of the last media on the landing page
+    landing_image: gtk::Image,
+    // Icon name for each perspective, by stack page name
+    pub(super) icon_names: HashMap<String, String>,
+    // The perspective chosen by the user, displayed when a media is open
+    selected: Option<String>,
+    // Hosts the streams page next to the chapters in the split view
+    info_container: gtk::Grid,
+    streams_page: gtk::Widget,
+    is_split: bool,
 }
 
 impl PerspectiveController {
     pub fn new(builder: &gtk::Builder) -> Self {
         let mut ctrl = PerspectiveController {
+            window: builder.get_object("application-window").unwrap(),
             menu_btn: builder.get_object("perspective-menu-btn").unwrap(),
+            menu_btn_image: builder
+                .get_object("perspective-menu-btn-selected-image")
+                .unwrap(),
             popover: builder.get_object("perspective-popovermenu").unwrap(),
             stack: builder.get_object("perspective-stack").unwrap(),
+            landing_image: builder.get_object("landing-image").unwrap(),
+            icon_names: HashMap::new(),
+            selected: None,
+            info_container: builder.get_object("info-chapter_list-grid").unwrap(),
+            streams_page: builder.get_object("streams-grid").unwrap(),
+            is_split: false,
         };
 
         ctrl.cleanup();
 
         ctrl
     }
+
+    pub fn selected(&self) -> Option<String> {
+        self.selected.clone()
+    }
+
+    /// Selects the perspective displayed in the stack page `name`.
+    pub fn switch_to(&mut self, name: &str) {
+        let icon_name = match self.icon_names.get(name) {
+            Some(icon_name) => icon_name,
+            None => return,
+        };
+
+        self.menu_btn_image
+            .set_property_icon_name(Some(icon_name.as_str()));
+        self.selected = Some(name.to_owned());
+        // Keep the landing page until a media is open
+        if !self.is_landing_page_visible() {
+            self.show_selected();
+        }
+        // popdown is available from GTK 3.22
+        // current package used on travis is GTK 3.18
+        self.popover.hide();
+    }
+
+    fn show_selected(&self) {
+        let selected = match self.selected.as_deref() {
+            // The streams are already visible in the split view
+            Some(STREAMS_PAGE) if self.is_split => DISPLAY_PAGE,
+            Some(selected) => selected,
+            None => return,
+        };
+
+        self.stack.set_visible_child_name(selected);
+    }
+
+    /// Shows the streams next to the chapters when the window is wide enough.
+    ///
+    /// The perspective switcher is hidden in the split view and the streams
+    /// are shown or hidden along with the chapters list.
+    pub fn update_layout(&mut self, window_width: i32) {
+        let must_split = window_width >= SPLIT_VIEW_MIN_WIDTH;
+        if must_split == self.is_split {
+            return;
+        }
+        self.is_split = must_split;
+
+        let is_landing_page_visible = self.is_landing_page_visible();
+        if must_split {
+            self.stack.remove(&self.streams_page);
+            self.info_container.attach(&self.streams_page, 1, 0, 1, 3);
+            self.menu_btn.hide();
+        } else {
+            self.info_container.remove(&self.streams_page);
+            self.stack.add_named(&self.streams_page, STREAMS_PAGE);
+            // Keep the pages in the same order as the perspective buttons
+            self.stack.set_child_position(&self.streams_page, 1);
+            self.menu_btn.show();
+        }
+
+        if !is_landing_page_visible {
+            self.show_selected();
+        }
+    }
+
+    fn set_landing_cover(&self, cover_art: Option<&CoverArt>) {
+        let cover = cover_art
+            .and_then(|cover_art| image::scaled_pixbuf(&cover_art.data, LANDING_COVER_SIZE));
+
+        let style_ctx = self.landing_image.get_style_context();
+        match cover {
+            Some(cover) => {
+                self.landing_image.set_from_pixbuf(Some(&cover));
+                style_ctx.remove_class("dim-label");
+            }
+            None => {
+                self.landing_image
+                    .set_from_icon_name(Some(LANDING_ICON), gtk::IconSize::Dialog);
+                self.landing_image.set_pixel_size(LANDING_ICON_SIZE);
+                style_ctx.add_class("dim-label");
+            }
+        }
+    }
+
+    fn is_landing_page_visible(&self) -> bool {
+        self.stack.get_visible_child_name().as_deref() == Some(LANDING_PAGE)
+    }
 }
 
 impl UIController for PerspectiveController {
-    fn new_media(&mut self, _pipeline: &PlaybackPipeline) {
+    fn new_media(&mut self, pipeline: &PlaybackPipeline) {
         self.menu_btn.set_sensitive(true);
+        // Displayed on the landing page once the media is stopped
+        self.set_landing_cover(pipeline.info.cover_art.as_ref());
+        self.show_selected();
     }
 
     fn cleanup(&mut self) {
         self.menu_btn.set_sensitive(false);
+        self.stack.set_visible_child_name(LANDING_PAGE);
     }
 }
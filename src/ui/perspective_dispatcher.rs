@@ -1,9 +1,11 @@
 use gio::prelude::*;
-use glib::Cast;
+use glib::{clone, Cast};
 use gtk::prelude::*;
 
 use std::{cell::RefCell, rc::Rc};
 
+use crate::application::CONFIG;
+
 use super::{MainController, PerspectiveController, UIDispatcher, UIEventSender};
 
 macro_rules! gtk_downcast(
@@ -41,20 +43,21 @@ impl UIDispatcher for PerspectiveDispatcher {
         perspective_ctrl: &mut PerspectiveController,
         _main_ctrl_rc: &Rc<RefCell<MainController>>,
         app: &gtk::Application,
-        _ui_event: &UIEventSender,
+        ui_event: &UIEventSender,
     ) {
-        let menu_btn_box = gtk_downcast!(
-            perspective_ctrl
-                .menu_btn
-                .get_child()
-                .expect("PerspectiveController no box for menu button"),
-            gtk::Box,
-            "menu button"
+        // Wide windows show the streams next to the chapters
+        perspective_ctrl.window.connect_configure_event(
+            clone!(@strong ui_event => move |_, event| {
+                let (width, _height) = event.get_size();
+                ui_event.window_resized(width as i32);
+                false
+            }),
         );
-        let menu_btn_image = gtk_downcast!(menu_btn_box, 0, gtk::Image, "menu button");
 
         let popover_box = gtk_downcast!(perspective_ctrl.popover, 0, gtk::Box, "popover");
 
+        let restored_perspective = CONFIG.read().unwrap().ui.perspective.clone();
+
         let stack_children = perspective_ctrl.stack.get_children();
         for (index, perspective_box_child) in popover_box.get_children().into_iter().enumerate() {
             let stack_child = stack_children.get(index).unwrap_or_else(|| {
@@ -92,26 +95,18 @@ impl UIDispatcher for PerspectiveDispatcher {
                 })
                 .to_owned();
 
-            if index == 0 {
-                // set the default perspective
-                menu_btn_image.set_property_icon_name(Some(perspective_icon_name.as_str()));
-                perspective_ctrl
-                    .stack
-                    .set_visible_child_name(&stack_child_name);
+            perspective_ctrl
+                .icon_names
+                .insert(stack_child_name.clone(), perspective_icon_name.to_string());
+            if index == 0 || restored_perspective.as_deref() == Some(stack_child_name.as_str()) {
+                // set the default or the restored perspective
+                perspective_ctrl.switch_to(&stack_child_name);
             }
 
             button.set_sensitive(true);
 
-            let menu_btn_image = menu_btn_image.clone();
-            let stack_clone = perspective_ctrl.stack.clone();
-            let popover_clone = perspective_ctrl.popover.clone();
-            let event = move || {
-                menu_btn_image.set_property_icon_name(Some(perspective_icon_name.as_str()));
-                stack_clone.set_visible_child_name(&stack_child_name);
-                // popdown is available from GTK 3.22
-                // current package used on travis is GTK 3.18
-                popover_clone.hide();
-            };
+            let ui_event = ui_event.clone();
+            let event = move || ui_event.switch_perspective(stack_child_name.clone());
 
             match button.get_action_name() {
                 Some(action_name) => {
@@ -128,7 +123,11 @@ impl UIDispatcher for PerspectiveDispatcher {
                     let action = gio::SimpleAction::new(action_splits[1], None);
                     app.add_action(&action);
                     action.connect_activate(move |_, _| event());
-                    app.set_accels_for_action(&action_name, &[&accel_key]);
+                    // Ctrl+1 for the first perspective, Ctrl+2 for the second...
+                    app.set_accels_for_action(
+                        &action_name,
+                        &[&accel_key, &format!("<Ctrl>{}", index + 1)],
+                    );
                 }
                 None => {
                     button.connect_clicked(move |_| event());
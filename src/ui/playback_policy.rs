@@ -0,0 +1,107 @@
+/// What to do when playback reaches the end of the current chapter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChapterEndMode {
+    /// Go on with next chapter.
+    Continue,
+    /// Play the chapter again, `count` times or endlessly if `None`.
+    Repeat { count: Option<u32> },
+    /// Pause playback, e.g. to review the chapter.
+    Stop,
+}
+
+impl Default for ChapterEndMode {
+    fn default() -> Self {
+        ChapterEndMode::Continue
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChapterEndAction {
+    Continue,
+    Repeat,
+    Pause,
+}
+
+/// Decides what happens at the end of the chapters.
+#[derive(Debug, Default)]
+pub struct PlaybackPolicy {
+    mode: ChapterEndMode,
+    // Chapter being repeated, `None` stands for the whole media
+    repeated_chapter: Option<usize>,
+    // Number of times `repeated_chapter` was repeated
+    repeated: u32,
+}
+
+impl PlaybackPolicy {
+    pub fn mode(&self) -> ChapterEndMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: ChapterEndMode) {
+        self.mode = mode;
+        self.repeated = 0;
+    }
+
+    /// Playback reached the end of the chapter at `chapter_idx`.
+    ///
+    /// `chapter_idx` is `None` for a media without chapters.
+    pub fn chapter_ended(&mut self, chapter_idx: Option<usize>) -> ChapterEndAction {
+        if chapter_idx != self.repeated_chapter {
+            self.repeated_chapter = chapter_idx;
+            self.repeated = 0;
+        }
+
+        match self.mode {
+            ChapterEndMode::Continue => ChapterEndAction::Continue,
+            ChapterEndMode::Stop => ChapterEndAction::Pause,
+            ChapterEndMode::Repeat { count: None } => ChapterEndAction::Repeat,
+            ChapterEndMode::Repeat { count: Some(count) } => {
+                if self.repeated < count {
+                    self.repeated += 1;
+                    ChapterEndAction::Repeat
+                } else {
+                    self.repeated = 0;
+                    ChapterEndAction::Continue
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChapterEndAction::*, *};
+
+    #[test]
+    fn repeat_count() {
+        let mut policy = PlaybackPolicy::default();
+        assert_eq!(Continue, policy.chapter_ended(Some(0)));
+
+        policy.set_mode(ChapterEndMode::Repeat { count: Some(2) });
+        assert_eq!(Repeat, policy.chapter_ended(Some(0)));
+        assert_eq!(Repeat, policy.chapter_ended(Some(0)));
+        assert_eq!(Continue, policy.chapter_ended(Some(0)));
+
+        // The user moved to another chapter in the meantime
+        assert_eq!(Repeat, policy.chapter_ended(Some(1)));
+        assert_eq!(Repeat, policy.chapter_ended(Some(2)));
+        assert_eq!(Repeat, policy.chapter_ended(Some(2)));
+        assert_eq!(Continue, policy.chapter_ended(Some(2)));
+
+        policy.set_mode(ChapterEndMode::Repeat { count: None });
+        for _ in 0..10 {
+            assert_eq!(Repeat, policy.chapter_ended(None));
+        }
+    }
+
+    #[test]
+    fn stop() {
+        let mut policy = PlaybackPolicy::default();
+        policy.set_mode(ChapterEndMode::Stop);
+        assert_eq!(Pause, policy.chapter_ended(Some(0)));
+        assert_eq!(Pause, policy.chapter_ended(Some(1)));
+
+        policy.set_mode(ChapterEndMode::Continue);
+        assert_eq!(Continue, policy.chapter_ended(Some(2)));
+    }
+}
@@ -0,0 +1,247 @@
+/// Speed factors of the fast preview, in the order they are cycled through.
+pub const TRICK_SPEEDS: [u8; 3] = [2, 4, 8];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ControllerState {
+    EosPaused,
+    EosPlaying,
+    /// Fast preview: only the key frames are rendered and the audio is muted.
+    FastForwarding {
+        speed: u8,
+    },
+    Paused,
+    Playing,
+    /// Same as `FastForwarding`, backward.
+    Rewinding {
+        speed: u8,
+    },
+    Stopped,
+}
+
+impl ControllerState {
+    /// The position moves, either at normal speed or in a fast preview.
+    pub fn is_running(self) -> bool {
+        matches!(
+            self,
+            ControllerState::Playing
+                | ControllerState::FastForwarding { .. }
+                | ControllerState::Rewinding { .. }
+        )
+    }
+
+    pub fn is_trick(self) -> bool {
+        matches!(
+            self,
+            ControllerState::FastForwarding { .. } | ControllerState::Rewinding { .. }
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaybackEvent {
+    Eos,
+    /// The pipeline was set to Paused by someone else.
+    ExternalPause,
+    /// The pipeline was set to Playing by someone else.
+    ExternalPlay,
+    /// A fast forward at the given speed started.
+    FastForward(u8),
+    /// A media was opened and is ready to play.
+    Opened,
+    Pause,
+    Play,
+    /// A rewind at the given speed started.
+    Rewind(u8),
+    /// A seek completed successfully.
+    Seeked,
+    Stop,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transition {
+    pub from: ControllerState,
+    pub to: ControllerState,
+}
+
+impl Transition {
+    /// Playback is started: the position must be tracked.
+    pub fn is_starting(&self) -> bool {
+        !self.from.is_running() && self.to.is_running()
+    }
+
+    /// Playback is halted: the position no longer needs to be tracked.
+    pub fn is_halting(&self) -> bool {
+        self.from.is_running() && !self.to.is_running()
+    }
+}
+
+/// Keeps track of the playback state.
+///
+/// `handle` returns the `Transition` triggered by an event if any,
+/// so that the pipeline and the UI can react in one place.
+#[derive(Debug)]
+pub struct PlaybackStateMachine {
+    state: ControllerState,
+}
+
+impl Default for PlaybackStateMachine {
+    fn default() -> Self {
+        PlaybackStateMachine {
+            state: ControllerState::Stopped,
+        }
+    }
+}
+
+impl PlaybackStateMachine {
+    pub fn get(&self) -> ControllerState {
+        self.state
+    }
+
+    pub fn handle(&mut self, event: PlaybackEvent) -> Option<Transition> {
+        use ControllerState::*;
+        use PlaybackEvent::*;
+
+        let to = match (event, self.state) {
+            (Eos, Playing) | (Eos, FastForwarding { .. }) => EosPlaying,
+            (Eos, Paused) => EosPaused,
+            (ExternalPause, state) if state.is_running() => Paused,
+            (ExternalPause, EosPlaying) => Paused,
+            (ExternalPlay, Paused) | (ExternalPlay, EosPaused) => Playing,
+            (FastForward(speed), state) if state.is_running() || state == Paused => {
+                FastForwarding { speed }
+            }
+            (Opened, _) => Paused,
+            (Pause, state) if state.is_running() => Paused,
+            (Pause, EosPlaying) => EosPaused,
+            (Play, Paused) | (Play, EosPaused) | (Play, EosPlaying) => Playing,
+            (Rewind(_), Stopped) => return None,
+            (Rewind(speed), _) => Rewinding { speed },
+            // Seeks are performed at normal speed
+            (Seeked, state) if state.is_trick() => Playing,
+            (Seeked, EosPlaying) => Playing,
+            (Seeked, EosPaused) => Paused,
+            (Stop, Stopped) => return None,
+            (Stop, _) => Stopped,
+            _ => return None,
+        };
+
+        let transition = Transition {
+            from: self.state,
+            to,
+        };
+        self.state = to;
+
+        Some(transition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ControllerState::*, PlaybackEvent::*, *};
+
+    fn state_after(events: &[PlaybackEvent]) -> ControllerState {
+        let mut state_machine = PlaybackStateMachine::default();
+        for event in events {
+            state_machine.handle(*event);
+        }
+
+        state_machine.get()
+    }
+
+    #[test]
+    fn play_pause() {
+        let mut state_machine = PlaybackStateMachine::default();
+        assert_eq!(None, state_machine.handle(Play));
+        assert_eq!(None, state_machine.handle(Pause));
+
+        assert_eq!(
+            Some(Transition {
+                from: Stopped,
+                to: Paused
+            }),
+            state_machine.handle(Opened),
+        );
+
+        let transition = state_machine.handle(Play).unwrap();
+        assert!(transition.is_starting());
+        assert!(!transition.is_halting());
+        assert_eq!(None, state_machine.handle(Play));
+
+        let transition = state_machine.handle(Pause).unwrap();
+        assert!(transition.is_halting());
+        assert_eq!(Paused, state_machine.get());
+
+        let transition = state_machine.handle(Stop).unwrap();
+        assert!(!transition.is_halting());
+        assert_eq!(None, state_machine.handle(Stop));
+    }
+
+    #[test]
+    fn eos() {
+        assert_eq!(EosPlaying, state_after(&[Opened, Play, Eos]));
+        assert_eq!(EosPaused, state_after(&[Opened, Eos]));
+        assert_eq!(Stopped, state_after(&[Eos]));
+
+        // Seeking back from the end resumes the previous state
+        assert_eq!(Playing, state_after(&[Opened, Play, Eos, Seeked]));
+        assert_eq!(Paused, state_after(&[Opened, Eos, Seeked]));
+        assert_eq!(Playing, state_after(&[Opened, Eos, Play]));
+
+        let mut state_machine = PlaybackStateMachine::default();
+        state_machine.handle(Opened);
+        state_machine.handle(Play);
+        assert!(state_machine.handle(Eos).unwrap().is_halting());
+        assert!(state_machine.handle(Seeked).unwrap().is_starting());
+    }
+
+    #[test]
+    fn external_changes() {
+        assert_eq!(Playing, state_after(&[Opened, ExternalPlay]));
+        assert_eq!(Paused, state_after(&[Opened, Play, ExternalPause]));
+        assert_eq!(Paused, state_after(&[Opened, Play, Eos, ExternalPause]));
+        assert_eq!(EosPlaying, state_after(&[Opened, Play, Eos, ExternalPlay]));
+        assert_eq!(Stopped, state_after(&[ExternalPlay]));
+    }
+
+    #[test]
+    fn trick_modes() {
+        let fast_forwarding = FastForwarding { speed: 2 };
+        let rewinding = Rewinding { speed: 4 };
+
+        let mut state_machine = PlaybackStateMachine::default();
+        assert_eq!(None, state_machine.handle(FastForward(2)));
+        assert_eq!(None, state_machine.handle(Rewind(2)));
+
+        state_machine.handle(Opened);
+        assert!(state_machine.handle(FastForward(2)).unwrap().is_starting());
+        assert_eq!(fast_forwarding, state_machine.get());
+
+        // Switching between trick modes keeps tracking the position
+        let transition = state_machine.handle(Rewind(4)).unwrap();
+        assert!(!transition.is_starting());
+        assert!(!transition.is_halting());
+        assert_eq!(rewinding, state_machine.get());
+
+        // Back to normal speed
+        assert_eq!(
+            Playing,
+            state_after(&[Opened, Play, FastForward(2), Seeked])
+        );
+        assert_eq!(Paused, state_after(&[Opened, Rewind(4), Pause]));
+
+        assert_eq!(
+            EosPlaying,
+            state_after(&[Opened, Play, FastForward(8), Eos])
+        );
+        assert_eq!(rewinding, state_after(&[Opened, Play, Eos, Rewind(4)]));
+    }
+
+    #[test]
+    fn pause_at_eos() {
+        assert_eq!(EosPaused, state_after(&[Opened, Play, Eos, Pause]));
+        assert_eq!(
+            Playing,
+            state_after(&[Opened, Play, Eos, Pause, Seeked, Play])
+        );
+    }
+}
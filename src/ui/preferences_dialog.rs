@@ -0,0 +1,291 @@
+use gettextrs::gettext;
+use glib::clone;
+use gtk::prelude::*;
+
+use crate::{
+    application::{available_languages, ChapterAutoScroll, CONFIG},
+    media::VideoDecoding,
+};
+
+use super::UIEventSender;
+
+const DEFAULT_AUDIO_SINK: &str = "autoaudiosink";
+const MAX_VIDEO_HEIGHTS: [u32; 3] = [1080, 720, 480];
+const CHAPTER_COUNTDOWNS: [u64; 3] = [10, 30, 60];
+const SUBTITLES_PREVIEW_TEXT: &str = "The quick brown fox jumps over the lazy dog";
+
+fn subtitles_preview_markup(font_desc: &str) -> String {
+    format!(
+        "<span font_desc=\"{}\">{}</span>",
+        glib::markup_escape_text(font_desc),
+        glib::markup_escape_text(&gettext(SUBTITLES_PREVIEW_TEXT)),
+    )
+}
+
+pub fn show(window: &gtk::ApplicationWindow, ui_event: &UIEventSender) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&gettext("Preferences")),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[(&gettext("Close"), gtk::ResponseType::Close)],
+    );
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(6);
+    grid.set_column_spacing(12);
+    grid.set_margin_start(12);
+    grid.set_margin_end(12);
+    grid.set_margin_top(12);
+    grid.set_margin_bottom(12);
+
+    let audio_sink_lbl = gtk::Label::new(Some(&gettext("Audio sink")));
+    audio_sink_lbl.set_halign(gtk::Align::Start);
+    grid.attach(&audio_sink_lbl, 0, 0, 1, 1);
+
+    let audio_sink_entry = gtk::Entry::new();
+    audio_sink_entry.set_hexpand(true);
+    audio_sink_entry.set_placeholder_text(Some(DEFAULT_AUDIO_SINK));
+    audio_sink_entry.set_tooltip_text(Some(&gettext(
+        "GStreamer pipeline description, e.g. \"audioconvert ! pulsesink device=my_sink\".\n\
+         Applies to the next media.",
+    )));
+    if let Some(audio_sink) = CONFIG.read().unwrap().media.audio_sink.as_ref() {
+        audio_sink_entry.set_text(audio_sink);
+    }
+    grid.attach(&audio_sink_entry, 1, 0, 1, 1);
+
+    let paused_on_output_loss_btn =
+        gtk::CheckButton::with_label(&gettext("Pause when the audio output is unplugged"));
+    paused_on_output_loss_btn.set_active(CONFIG.read().unwrap().media.is_paused_on_output_loss);
+    paused_on_output_loss_btn.connect_toggled(|btn| {
+        CONFIG.write().unwrap().media.is_paused_on_output_loss = btn.get_active();
+    });
+    grid.attach(&paused_on_output_loss_btn, 0, 1, 2, 1);
+
+    let current_toc_ignored_btn = gtk::CheckButton::with_label(&gettext(
+        "Ignore the chapters which only apply to the current stream",
+    ));
+    current_toc_ignored_btn.set_tooltip_text(Some(&gettext(
+        "Only used when the media has no global chapters.\nApplies to the next media.",
+    )));
+    current_toc_ignored_btn.set_active(CONFIG.read().unwrap().media.is_current_toc_ignored);
+    current_toc_ignored_btn.connect_toggled(|btn| {
+        CONFIG.write().unwrap().media.is_current_toc_ignored = btn.get_active();
+    });
+    grid.attach(&current_toc_ignored_btn, 0, 2, 2, 1);
+
+    let video_decoding_lbl = gtk::Label::new(Some(&gettext("Video decoding")));
+    video_decoding_lbl.set_halign(gtk::Align::Start);
+    grid.attach(&video_decoding_lbl, 0, 3, 1, 1);
+
+    let video_decoding_combo = gtk::ComboBoxText::new();
+    video_decoding_combo.append(Some("auto"), &gettext("Automatic"));
+    video_decoding_combo.append(Some("hardware"), &gettext("Hardware"));
+    video_decoding_combo.append(Some("software"), &gettext("Software"));
+    video_decoding_combo.set_tooltip_text(Some(&gettext(
+        "Automatic prefers the hardware decoders and switches to software decoding on failure.\n\
+         Applies to the next media.",
+    )));
+    video_decoding_combo.set_active_id(Some(match CONFIG.read().unwrap().media.video_decoding {
+        VideoDecoding::Auto => "auto",
+        VideoDecoding::Hardware => "hardware",
+        VideoDecoding::Software => "software",
+    }));
+    video_decoding_combo.connect_changed(|combo| {
+        let video_decoding = match combo.get_active_id().as_deref() {
+            Some("hardware") => VideoDecoding::Hardware,
+            Some("software") => VideoDecoding::Software,
+            _ => VideoDecoding::Auto,
+        };
+        CONFIG.write().unwrap().media.video_decoding = video_decoding;
+    });
+    grid.attach(&video_decoding_combo, 1, 3, 1, 1);
+
+    let max_video_height_lbl = gtk::Label::new(Some(&gettext("Maximum video resolution")));
+    max_video_height_lbl.set_halign(gtk::Align::Start);
+    grid.attach(&max_video_height_lbl, 0, 4, 1, 1);
+
+    // The empty id stands for the original resolution
+    let max_video_height_combo = gtk::ComboBoxText::new();
+    max_video_height_combo.append(Some(""), &gettext("Original"));
+    for height in MAX_VIDEO_HEIGHTS.iter() {
+        max_video_height_combo.append(Some(&height.to_string()), &format!("{}p", height));
+    }
+    max_video_height_combo.set_tooltip_text(Some(&gettext(
+        "Downscales the video in order to reduce the load on low-power machines.",
+    )));
+    let max_video_height = CONFIG.read().unwrap().media.max_video_height;
+    if !max_video_height_combo.set_active_id(Some(
+        &max_video_height.map_or_else(String::new, |height| height.to_string()),
+    )) {
+        max_video_height_combo.set_active_id(Some(""));
+    }
+    max_video_height_combo.connect_changed(clone!(@strong ui_event => move |combo| {
+        CONFIG.write().unwrap().media.max_video_height = combo
+            .get_active_id()
+            .and_then(|height| height.parse::<u32>().ok());
+        ui_event.max_video_height_changed();
+    }));
+    grid.attach(&max_video_height_combo, 1, 4, 1, 1);
+
+    let language_lbl = gtk::Label::new(Some(&gettext("Language")));
+    language_lbl.set_halign(gtk::Align::Start);
+    grid.attach(&language_lbl, 0, 5, 1, 1);
+
+    // The empty id stands for the system's language
+    let language_combo = gtk::ComboBoxText::new();
+    language_combo.append(Some(""), &gettext("System default"));
+    for language in available_languages() {
+        language_combo.append(Some(&language), &language);
+    }
+    language_combo.set_tooltip_text(Some(&gettext("Applies on next start.")));
+    let language = CONFIG.read().unwrap().ui.language.clone();
+    if !language_combo.set_active_id(Some(language.as_deref().unwrap_or(""))) {
+        language_combo.set_active_id(Some(""));
+    }
+    language_combo.connect_changed(|combo| {
+        CONFIG.write().unwrap().ui.language = combo
+            .get_active_id()
+            .filter(|language| !language.is_empty())
+            .map(|language| language.to_string());
+    });
+    grid.attach(&language_combo, 1, 5, 1, 1);
+
+    let ms_hidden_btn = gtk::CheckButton::with_label(&gettext("Hide milliseconds"));
+    ms_hidden_btn.set_active(CONFIG.read().unwrap().ui.is_ms_hidden);
+    ms_hidden_btn.connect_toggled(|btn| {
+        CONFIG.write().unwrap().ui.is_ms_hidden = btn.get_active();
+    });
+    grid.attach(&ms_hidden_btn, 0, 6, 2, 1);
+
+    let hour_padded_btn = gtk::CheckButton::with_label(&gettext("Always display hours"));
+    hour_padded_btn.set_active(CONFIG.read().unwrap().ui.is_hour_padded);
+    hour_padded_btn.connect_toggled(|btn| {
+        CONFIG.write().unwrap().ui.is_hour_padded = btn.get_active();
+    });
+    grid.attach(&hour_padded_btn, 0, 7, 2, 1);
+
+    let chapter_auto_scroll_lbl = gtk::Label::new(Some(&gettext("Chapter list scrolling")));
+    chapter_auto_scroll_lbl.set_halign(gtk::Align::Start);
+    grid.attach(&chapter_auto_scroll_lbl, 0, 8, 1, 1);
+
+    let chapter_auto_scroll_combo = gtk::ComboBoxText::new();
+    chapter_auto_scroll_combo.append(Some("center"), &gettext("Center the current chapter"));
+    chapter_auto_scroll_combo.append(Some("top"), &gettext("Current chapter at the top"));
+    chapter_auto_scroll_combo.append(Some("disabled"), &gettext("Don't scroll"));
+    chapter_auto_scroll_combo.set_tooltip_text(Some(&gettext(
+        "Keeps the current chapter visible during playback.\n\
+         The list doesn't scroll for a few seconds after you scrolled it.",
+    )));
+    chapter_auto_scroll_combo.set_active_id(Some(
+        match CONFIG.read().unwrap().ui.chapter_auto_scroll {
+            ChapterAutoScroll::Center => "center",
+            ChapterAutoScroll::Top => "top",
+            ChapterAutoScroll::Disabled => "disabled",
+        },
+    ));
+    chapter_auto_scroll_combo.connect_changed(|combo| {
+        let chapter_auto_scroll = match combo.get_active_id().as_deref() {
+            Some("top") => ChapterAutoScroll::Top,
+            Some("disabled") => ChapterAutoScroll::Disabled,
+            _ => ChapterAutoScroll::Center,
+        };
+        CONFIG.write().unwrap().ui.chapter_auto_scroll = chapter_auto_scroll;
+    });
+    grid.attach(&chapter_auto_scroll_combo, 1, 8, 1, 1);
+
+    let chapter_countdown_lbl = gtk::Label::new(Some(&gettext("Chapter end countdown")));
+    chapter_countdown_lbl.set_halign(gtk::Align::Start);
+    grid.attach(&chapter_countdown_lbl, 0, 9, 1, 1);
+
+    // The empty id stands for no countdown
+    let chapter_countdown_combo = gtk::ComboBoxText::new();
+    chapter_countdown_combo.append(Some(""), &gettext("Disabled"));
+    for secs in CHAPTER_COUNTDOWNS.iter() {
+        chapter_countdown_combo.append(
+            Some(&secs.to_string()),
+            &gettext("Last {} seconds").replacen("{}", &secs.to_string(), 1),
+        );
+    }
+    chapter_countdown_combo.set_tooltip_text(Some(&gettext(
+        "Displays the time left in the current chapter next to the position.",
+    )));
+    let chapter_countdown = CONFIG.read().unwrap().ui.chapter_countdown;
+    if !chapter_countdown_combo.set_active_id(Some(
+        &chapter_countdown.map_or_else(String::new, |secs| secs.to_string()),
+    )) {
+        chapter_countdown_combo.set_active_id(Some(""));
+    }
+    chapter_countdown_combo.connect_changed(|combo| {
+        CONFIG.write().unwrap().ui.chapter_countdown = combo
+            .get_active_id()
+            .and_then(|secs| secs.parse::<u64>().ok());
+    });
+    grid.attach(&chapter_countdown_combo, 1, 9, 1, 1);
+
+    // Subtitles
+    let subtitles_font_lbl = gtk::Label::new(Some(&gettext("Subtitles font")));
+    subtitles_font_lbl.set_halign(gtk::Align::Start);
+    grid.attach(&subtitles_font_lbl, 0, 10, 1, 1);
+
+    let subtitles_preview_lbl = gtk::Label::new(None);
+    subtitles_preview_lbl.set_line_wrap(true);
+    subtitles_preview_lbl.set_size_request(-1, 80);
+
+    let subtitles_font_btn = gtk::FontButton::new();
+    subtitles_font_btn.set_use_font(true);
+    {
+        let font_desc = &CONFIG.read().unwrap().media.subtitles.font_desc;
+        subtitles_font_btn.set_font(font_desc);
+        subtitles_preview_lbl.set_markup(&subtitles_preview_markup(font_desc));
+    }
+    subtitles_font_btn.connect_font_set(
+        clone!(@strong ui_event, @strong subtitles_preview_lbl => move |btn| {
+            if let Some(font_desc) = btn.get_font() {
+                subtitles_preview_lbl.set_markup(&subtitles_preview_markup(&font_desc));
+                CONFIG.write().unwrap().media.subtitles.font_desc = font_desc.to_string();
+                ui_event.subtitle_style_changed();
+            }
+        }),
+    );
+    grid.attach(&subtitles_font_btn, 1, 10, 1, 1);
+
+    let subtitles_outline_btn = gtk::CheckButton::with_label(&gettext("Draw subtitles outline"));
+    subtitles_outline_btn.set_active(CONFIG.read().unwrap().media.subtitles.is_outline_drawn);
+    subtitles_outline_btn.connect_toggled(clone!(@strong ui_event => move |btn| {
+        CONFIG.write().unwrap().media.subtitles.is_outline_drawn = btn.get_active();
+        ui_event.subtitle_style_changed();
+    }));
+    grid.attach(&subtitles_outline_btn, 0, 11, 2, 1);
+
+    grid.attach(&subtitles_preview_lbl, 0, 12, 2, 1);
+
+    let dbus_service_btn =
+        gtk::CheckButton::with_label(&gettext("Expose the player actions on the session bus"));
+    dbus_service_btn.set_tooltip_text(Some(&gettext(
+        "Allows scripts to open files, seek and navigate the chapters.\n\
+         Applies on next start.",
+    )));
+    dbus_service_btn.set_active(CONFIG.read().unwrap().ui.is_dbus_service_enabled);
+    dbus_service_btn.connect_toggled(|btn| {
+        CONFIG.write().unwrap().ui.is_dbus_service_enabled = btn.get_active();
+    });
+    grid.attach(&dbus_service_btn, 0, 13, 2, 1);
+
+    dialog.get_content_area().add(&grid);
+
+    dialog.connect_response(move |dialog, _| {
+        let audio_sink = audio_sink_entry.get_text();
+        let audio_sink = audio_sink.trim();
+        CONFIG.write().unwrap().media.audio_sink =
+            if audio_sink.is_empty() || audio_sink == DEFAULT_AUDIO_SINK {
+                None
+            } else {
+                Some(audio_sink.to_owned())
+            };
+
+        dialog.close();
+    });
+
+    dialog.show_all();
+}
@@ -0,0 +1,66 @@
+use crate::media::Timestamp;
+
+/// Coalesces the seek steps requested while a step seek is pending.
+///
+/// Holding a step key produces key repeats faster than the seeks complete.
+/// The steps are accumulated and a single seek is issued for all of them,
+/// which results in one larger jump instead of a queue of overlapping seeks.
+#[derive(Debug, Default)]
+pub struct SeekSerializer {
+    // Target of the accumulated steps
+    target: Option<Timestamp>,
+}
+
+impl SeekSerializer {
+    /// Applies `step` to the pending target or to `position` if no steps are pending.
+    ///
+    /// Returns `true` if a seek must be scheduled for the steps.
+    pub fn step<F>(&mut self, position: Timestamp, step: F) -> bool
+    where
+        F: FnOnce(Timestamp) -> Timestamp,
+    {
+        let must_schedule = self.target.is_none();
+        self.target = Some(step(self.target.unwrap_or(position)));
+
+        must_schedule
+    }
+
+    /// Takes the target of the steps accumulated since the last seek.
+    pub fn take_target(&mut self) -> Option<Timestamp> {
+        self.target.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Duration;
+
+    const STEP: Duration = Duration::from_secs(2);
+
+    #[test]
+    fn accumulate() {
+        let mut serializer = SeekSerializer::default();
+        assert_eq!(None, serializer.take_target());
+
+        let position = Timestamp::new(10_000_000_000);
+        assert!(serializer.step(position, |ts| ts + STEP));
+        // Steps requested before the seek is issued
+        assert!(!serializer.step(position, |ts| ts + STEP));
+        assert!(!serializer.step(position, |ts| ts + STEP));
+        assert!(!serializer.step(position, |ts| ts.saturating_sub(STEP)));
+        assert_eq!(
+            Some(Timestamp::new(14_000_000_000)),
+            serializer.take_target()
+        );
+        assert_eq!(None, serializer.take_target());
+
+        // Next steps start from the new position
+        let position = Timestamp::new(14_000_000_000);
+        assert!(serializer.step(position, |ts| ts.saturating_sub(STEP)));
+        assert_eq!(
+            Some(Timestamp::new(12_000_000_000)),
+            serializer.take_target()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use gettextrs::{gettext, ngettext};
+use glib::clone;
+use gtk::prelude::*;
+
+use crate::{application::CONFIG, media::Timestamp};
+
+use super::UIEventSender;
+
+const SELECTED_COL: u32 = 0;
+const POSITION_COL: u32 = 1;
+
+/// Lets the user pick the chapter boundaries to insert among the detected silences.
+pub fn show(window: &gtk::ApplicationWindow, ui_event: &UIEventSender, boundaries: Vec<Timestamp>) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&gettext("Detected silences")),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (&gettext("Cancel"), gtk::ResponseType::Cancel),
+            (&gettext("Insert chapters"), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_size(300, 400);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let label = gtk::Label::new(Some(
+        &ngettext(
+            "{} silence could separate chapters.",
+            "{} silences could separate chapters.",
+            boundaries.len() as u32,
+        )
+        .replacen("{}", &boundaries.len().to_string(), 1),
+    ));
+    label.set_margin_top(6);
+    label.set_margin_bottom(6);
+    dialog.get_content_area().add(&label);
+
+    let store = gtk::ListStore::new(&[bool::static_type(), String::static_type()]);
+    let timestamp_display = CONFIG.read().unwrap().ui.timestamp_display();
+    for boundary in &boundaries {
+        store.insert_with_values(
+            None,
+            &[SELECTED_COL, POSITION_COL],
+            &[&true, &boundary.for_humans().display(timestamp_display)],
+        );
+    }
+
+    let treeview = gtk::TreeView::with_model(&store);
+
+    let selected_col = gtk::TreeViewColumn::new();
+    let toggle_renderer = gtk::CellRendererToggle::new();
+    toggle_renderer.connect_toggled(clone!(@weak store => move |_, tree_path| {
+        if let Some(iter) = store.get_iter(&tree_path) {
+            let is_selected = store
+                .get_value(&iter, SELECTED_COL as i32)
+                .get_some::<bool>()
+                .unwrap();
+            store.set_value(&iter, SELECTED_COL, &(!is_selected).to_value());
+        }
+    }));
+    selected_col.pack_start(&toggle_renderer, false);
+    selected_col.add_attribute(&toggle_renderer, "active", SELECTED_COL as i32);
+    treeview.append_column(&selected_col);
+
+    let position_col = gtk::TreeViewColumn::new();
+    position_col.set_title(&gettext("Position"));
+    let text_renderer = gtk::CellRendererText::new();
+    position_col.pack_start(&text_renderer, true);
+    position_col.add_attribute(&text_renderer, "text", POSITION_COL as i32);
+    position_col.set_expand(true);
+    treeview.append_column(&position_col);
+
+    let scrolled_window = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+    scrolled_window.set_vexpand(true);
+    scrolled_window.add(&treeview);
+    dialog.get_content_area().add(&scrolled_window);
+
+    dialog.connect_response(clone!(@strong ui_event => move |dialog, response| {
+        if response == gtk::ResponseType::Accept {
+            let mut selected = Vec::new();
+            let mut idx = 0;
+            if let Some(iter) = store.get_iter_first() {
+                loop {
+                    let is_selected = store
+                        .get_value(&iter, SELECTED_COL as i32)
+                        .get_some::<bool>()
+                        .unwrap();
+                    if is_selected {
+                        selected.push(boundaries[idx]);
+                    }
+
+                    idx += 1;
+                    if !store.iter_next(&iter) {
+                        break;
+                    }
+                }
+            }
+
+            if !selected.is_empty() {
+                ui_event.insert_chapters(selected);
+            }
+        }
+        dialog.close();
+    }));
+
+    dialog.show_all();
+}
@@ -1,201 +1,234 @@
 use gettextrs::gettext;
 
+use gio::prelude::*;
 use gtk::prelude::*;
 
 use std::sync::Arc;
 
-use crate::{media::PlaybackPipeline, metadata};
+use crate::{
+    media::{PlaybackPipeline, QosStats},
+    metadata,
+};
 
-use super::{spawn, UIController};
+use super::UIController;
 
 const ALIGN_LEFT: f32 = 0f32;
 const ALIGN_CENTER: f32 = 0.5f32;
 const ALIGN_RIGHT: f32 = 1f32;
 
-const STREAM_ID_COL: u32 = 0;
-const STREAM_ID_DISPLAY_COL: u32 = 1;
-
-const LANGUAGE_COL: u32 = 2;
-const CODEC_COL: u32 = 3;
-const COMMENT_COL: u32 = 4;
-
-pub enum StreamClickedStatus {
+const ACTIVE_COL: u32 = 0;
+const STREAM_ID_COL: u32 = 1;
+const TYPE_COL: u32 = 2;
+const TYPE_DISPLAY_COL: u32 = 3;
+const STREAM_ID_DISPLAY_COL: u32 = 4;
+const LANGUAGE_COL: u32 = 5;
+const CODEC_COL: u32 = 6;
+const PROPERTIES_COL: u32 = 7;
+const BITRATE_COL: u32 = 8;
+const COMMENT_COL: u32 = 9;
+const CAPS_COL: u32 = 10;
+
+// Types in display order
+const STREAM_TYPES: [gst::StreamType; 3] = [
+    gst::StreamType::VIDEO,
+    gst::StreamType::AUDIO,
+    gst::StreamType::TEXT,
+];
+
+pub enum StreamToggledStatus {
     Changed,
     Unchanged,
 }
 
-pub(super) trait UIStreamImpl {
-    const TYPE: gst::StreamType;
-
-    fn new_media(store: &gtk::ListStore, iter: &gtk::TreeIter, caps_struct: &gst::StructureRef);
-    fn init_treeview(treeview: &gtk::TreeView, store: &gtk::ListStore);
-
-    fn add_text_column(
-        treeview: &gtk::TreeView,
-        title: &str,
-        alignment: f32,
-        col_id: u32,
-        width: Option<i32>,
-    ) {
-        let col = gtk::TreeViewColumn::new();
-        col.set_title(title);
+/// Whether the user can choose to play none of the streams of this type.
+fn can_be_none(type_: gst::StreamType) -> bool {
+    type_ != gst::StreamType::AUDIO
+}
 
-        let renderer = gtk::CellRendererText::new();
-        renderer.set_alignment(alignment, ALIGN_CENTER);
-        col.pack_start(&renderer, true);
-        col.add_attribute(&renderer, "text", col_id as i32);
+fn type_display(type_: gst::StreamType) -> String {
+    match type_ {
+        gst::StreamType::VIDEO => gettext("Video"),
+        gst::StreamType::AUDIO => gettext("Audio"),
+        gst::StreamType::TEXT => gettext("Text"),
+        other => unimplemented!("{:?}", other),
+    }
+}
 
-        if let Some(width) = width {
-            renderer.set_fixed_size(width, -1);
-        }
+fn bitrate(tags: &gst::TagList) -> Option<String> {
+    tags.get_index::<gst::tags::Bitrate>(0)
+        .or_else(|| tags.get_index::<gst::tags::NominalBitrate>(0))
+        .and_then(|value| value.get())
+        .map(|bitrate| format!("{} kb/s", bitrate / 1_000))
+}
 
-        treeview.append_column(&col);
+fn channel_layout(channels: i32, channel_mask: Option<u64>) -> String {
+    // Low frequency effects channels: LFE1 & LFE2
+    const LFE_MASK: u64 = (1 << 3) | (1 << 9);
+
+    let (main, lfe) = match channel_mask {
+        Some(mask) if mask != 0 => (
+            (mask & !LFE_MASK).count_ones(),
+            (mask & LFE_MASK).count_ones(),
+        ),
+        _ => (channels.max(0) as u32, 0),
+    };
+
+    match (main, lfe) {
+        (1, 0) => gettext("mono"),
+        (2, 0) => gettext("stereo"),
+        (main, lfe) => format!("{}.{}", main, lfe),
     }
 }
 
-pub(super) struct UIStream<Impl: UIStreamImpl> {
-    pub(super) treeview: gtk::TreeView,
-    store: gtk::ListStore,
-    selected: Option<Arc<str>>,
-    phantom: std::marker::PhantomData<Impl>,
-}
+fn video_properties(caps_struct: &gst::StructureRef) -> String {
+    let mut properties = Vec::<String>::new();
 
-impl<Impl: UIStreamImpl> UIStream<Impl> {
-    fn new(treeview: gtk::TreeView, store: gtk::ListStore) -> Self {
-        UIStream {
-            treeview,
-            store,
-            selected: None,
-            phantom: std::marker::PhantomData,
+    if let (Ok(Some(width)), Ok(Some(height))) = (
+        caps_struct.get::<i32>("width"),
+        caps_struct.get::<i32>("height"),
+    ) {
+        properties.push(format!("{}x{}", width, height));
+    }
+    if let Ok(Some(framerate)) = caps_struct.get::<gst::Fraction>("framerate") {
+        let (numer, denom) = (*framerate.numer(), *framerate.denom());
+        if numer > 0 && denom > 0 {
+            if denom == 1 {
+                properties.push(format!("{} fps", numer));
+            } else {
+                properties.push(format!("{:.2} fps", f64::from(numer) / f64::from(denom)));
+            }
         }
     }
-
-    fn init_treeview(&self) {
-        Impl::init_treeview(&self.treeview, &self.store);
+    if let Ok(Some(par)) = caps_struct.get::<gst::Fraction>("pixel-aspect-ratio") {
+        properties.push(gettext("pixel ratio {}").replacen(
+            "{}",
+            &format!("{}:{}", *par.numer(), *par.denom()),
+            1,
+        ));
     }
-
-    fn cleanup(&mut self) {
-        self.selected = None;
-        self.treeview
-            .set_cursor(&gtk::TreePath::new(), None::<&gtk::TreeViewColumn>, false);
-        self.store.clear();
+    if let Ok(Some(interlace_mode)) = caps_struct.get::<&str>("interlace-mode") {
+        properties.push(interlace_mode.to_owned());
     }
 
-    fn new_media(&mut self, streams: &metadata::Streams) {
-        let sorted_collection = streams.collection(Impl::TYPE).sorted();
-        for stream in sorted_collection {
-            let iter = self.add_stream(stream);
-            let caps_structure = stream.caps.get_structure(0).unwrap();
-            Impl::new_media(&self.store, &iter, &caps_structure);
-        }
+    properties.join(", ")
+}
 
-        self.selected = self.store.get_iter_first().map(|ref iter| {
-            self.treeview.get_selection().select_iter(iter);
-            self.store
-                .get_value(iter, STREAM_ID_COL as i32)
-                .get::<String>()
-                .unwrap()
-                .unwrap()
-                .into()
-        });
+fn audio_properties(caps_struct: &gst::StructureRef) -> String {
+    let mut properties = Vec::<String>::new();
+
+    if let Ok(Some(rate)) = caps_struct.get::<i32>("rate") {
+        properties.push(format!("{} Hz", rate));
+    }
+    if let Ok(Some(channels)) = caps_struct.get::<i32>("channels") {
+        let channel_mask = caps_struct
+            .get::<gst::Bitmask>("channel-mask")
+            .ok()
+            .flatten()
+            .map(|mask| *mask);
+        properties.push(channel_layout(channels, channel_mask));
+    }
+    if let Ok(Some(format)) = caps_struct.get::<&str>("format") {
+        properties.push(format.to_owned());
     }
 
-    fn add_stream(&self, stream: &metadata::Stream) -> gtk::TreeIter {
-        let id_parts: Vec<&str> = stream.id.split('/').collect();
-        let stream_id_display = if id_parts.len() == 2 {
-            id_parts[1].to_owned()
-        } else {
-            gettext("unknown")
-        };
+    properties.join(", ")
+}
 
-        let iter = self.store.insert_with_values(
-            None,
-            &[STREAM_ID_COL, STREAM_ID_DISPLAY_COL],
-            &[&stream.id.as_ref(), &stream_id_display],
-        );
+fn text_properties(caps_struct: &gst::StructureRef) -> String {
+    caps_struct
+        .get::<&str>("format")
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+        .to_owned()
+}
 
-        let lang = stream
-            .tags
-            .get_index::<gst::tags::LanguageName>(0)
-            .or_else(|| stream.tags.get_index::<gst::tags::LanguageCode>(0))
-            .and_then(|value| value.get())
-            .unwrap_or("-");
-        self.store
-            .set_value(&iter, LANGUAGE_COL, &glib::Value::from(lang));
+/// Lists the streams of all types in a single view.
+///
+/// At most one stream of each type is active since the pipeline renders
+/// a single stream per type: activating a stream deactivates the other
+/// streams of the same type.
+pub struct StreamsController {
+    pub(super) page: gtk::Grid,
 
-        if let Some(comment) = stream
-            .tags
-            .get_index::<gst::tags::Comment>(0)
-            .and_then(|value| value.get())
-        {
-            self.store
-                .set_value(&iter, COMMENT_COL, &glib::Value::from(comment));
-        }
+    pub(super) treeview: gtk::TreeView,
+    store: gtk::ListStore,
+    pub(super) active_renderer: gtk::CellRendererToggle,
 
-        self.store.set_value(
-            &iter,
-            CODEC_COL,
-            &glib::Value::from(&stream.codec_printable),
-        );
+    qos_lbl: gtk::Label,
 
-        iter
-    }
+    pub(super) cycle_video_stream_action: gio::SimpleAction,
+}
 
-    fn stream_clicked(&mut self) -> StreamClickedStatus {
-        if let (Some(cursor_path), _) = self.treeview.get_cursor() {
-            if let Some(iter) = self.store.get_iter(&cursor_path) {
-                let stream = self
-                    .store
-                    .get_value(&iter, STREAM_ID_COL as i32)
-                    .get::<String>()
-                    .unwrap()
-                    .unwrap()
-                    .into();
-                let stream_to_select = match &self.selected {
-                    Some(stream_id) => {
-                        if stream_id != &stream {
-                            // Stream has changed
-                            Some(stream)
-                        } else {
-                            None
-                        }
-                    }
-                    None => Some(stream),
-                };
-
-                if let Some(new_stream) = stream_to_select {
-                    self.selected = Some(new_stream);
-                    return StreamClickedStatus::Changed;
-                }
+impl UIController for StreamsController {
+    fn new_media(&mut self, pipeline: &PlaybackPipeline) {
+        let streams = &pipeline.info.streams;
+        for type_ in STREAM_TYPES.iter() {
+            let selected = match *type_ {
+                gst::StreamType::VIDEO => streams.selected_video(),
+                gst::StreamType::AUDIO => streams.selected_audio(),
+                gst::StreamType::TEXT => streams.selected_text(),
+                _ => None,
+            };
+
+            for stream in streams.collection(*type_).sorted() {
+                let is_active = selected.map_or(false, |selected| selected.id == stream.id);
+                self.add_stream(*type_, stream, is_active);
             }
         }
 
-        StreamClickedStatus::Unchanged
+        // Multi-angle media
+        self.cycle_video_stream_action
+            .set_enabled(streams.video.len() > 1);
+    }
+
+    fn cleanup(&mut self) {
+        self.store.clear();
+        self.qos_lbl.set_text("");
+        self.cycle_video_stream_action.set_enabled(false);
     }
-}
 
-pub(super) struct UIStreamVideoImpl;
-impl UIStreamVideoImpl {
-    const VIDEO_WIDTH_COL: u32 = 5;
-    const VIDEO_HEIGHT_COL: u32 = 6;
+    fn grab_focus(&self) {
+        self.treeview.grab_focus();
+    }
 }
 
-impl UIStreamImpl for UIStreamVideoImpl {
-    const TYPE: gst::StreamType = gst::StreamType::VIDEO;
+impl StreamsController {
+    pub fn new(builder: &gtk::Builder) -> Self {
+        let mut ctrl = StreamsController {
+            page: builder.get_object("streams-grid").unwrap(),
 
-    fn new_media(store: &gtk::ListStore, iter: &gtk::TreeIter, caps_struct: &gst::StructureRef) {
-        if let Ok(Some(width)) = caps_struct.get::<i32>("width") {
-            store.set_value(iter, Self::VIDEO_WIDTH_COL, &glib::Value::from(&width));
-        }
-        if let Ok(Some(height)) = caps_struct.get::<i32>("height") {
-            store.set_value(iter, Self::VIDEO_HEIGHT_COL, &glib::Value::from(&height));
-        }
+            treeview: builder.get_object("streams-treeview").unwrap(),
+            store: builder.get_object("streams-liststore").unwrap(),
+            active_renderer: gtk::CellRendererToggle::new(),
+
+            qos_lbl: builder.get_object("streams-qos-lbl").unwrap(),
+
+            cycle_video_stream_action: gio::SimpleAction::new("cycle_video_stream", None),
+        };
+
+        ctrl.cleanup();
+
+        ctrl
     }
 
-    fn init_treeview(treeview: &gtk::TreeView, store: &gtk::ListStore) {
-        treeview.set_model(Some(store));
+    /// Builds the columns, which is deferred until the streams are first displayed.
+    pub(super) fn init_treeview(
+        treeview: &gtk::TreeView,
+        active_renderer: &gtk::CellRendererToggle,
+    ) {
+        let active_col = gtk::TreeViewColumn::new();
+        active_col.pack_start(active_renderer, false);
+        active_col.add_attribute(active_renderer, "active", ACTIVE_COL as i32);
+        treeview.append_column(&active_col);
 
-        // Video
+        Self::add_text_column(
+            treeview,
+            &gettext("Type"),
+            ALIGN_LEFT,
+            TYPE_DISPLAY_COL,
+            None,
+        );
         Self::add_text_column(
             treeview,
             &gettext("Stream id"),
@@ -213,205 +246,312 @@ impl UIStreamImpl for UIStreamVideoImpl {
         Self::add_text_column(treeview, &gettext("Codec"), ALIGN_LEFT, CODEC_COL, None);
         Self::add_text_column(
             treeview,
-            &gettext("Width"),
-            ALIGN_RIGHT,
-            Self::VIDEO_WIDTH_COL,
+            &gettext("Properties"),
+            ALIGN_LEFT,
+            PROPERTIES_COL,
             None,
         );
         Self::add_text_column(
             treeview,
-            &gettext("Height"),
+            &gettext("Bitrate"),
             ALIGN_RIGHT,
-            Self::VIDEO_HEIGHT_COL,
+            BITRATE_COL,
             None,
         );
         Self::add_text_column(treeview, &gettext("Comment"), ALIGN_LEFT, COMMENT_COL, None);
+
+        treeview.set_tooltip_column(CAPS_COL as i32);
     }
-}
 
-pub(super) struct UIStreamAudioImpl;
-impl UIStreamAudioImpl {
-    const AUDIO_RATE_COL: u32 = 5;
-    const AUDIO_CHANNELS_COL: u32 = 6;
-}
+    fn add_text_column(
+        treeview: &gtk::TreeView,
+        title: &str,
+        alignment: f32,
+        col_id: u32,
+        width: Option<i32>,
+    ) {
+        let col = gtk::TreeViewColumn::new();
+        col.set_title(title);
 
-impl UIStreamImpl for UIStreamAudioImpl {
-    const TYPE: gst::StreamType = gst::StreamType::AUDIO;
+        let renderer = gtk::CellRendererText::new();
+        renderer.set_alignment(alignment, ALIGN_CENTER);
+        col.pack_start(&renderer, true);
+        col.add_attribute(&renderer, "text", col_id as i32);
 
-    fn new_media(store: &gtk::ListStore, iter: &gtk::TreeIter, caps_struct: &gst::StructureRef) {
-        if let Ok(Some(rate)) = caps_struct.get::<i32>("rate") {
-            store.set_value(&iter, Self::AUDIO_RATE_COL, &glib::Value::from(&rate));
-        }
-        if let Ok(Some(channels)) = caps_struct.get::<i32>("channels") {
-            store.set_value(
-                &iter,
-                Self::AUDIO_CHANNELS_COL,
-                &glib::Value::from(&channels),
-            );
+        if let Some(width) = width {
+            renderer.set_fixed_size(width, -1);
         }
+
+        treeview.append_column(&col);
     }
 
-    fn init_treeview(treeview: &gtk::TreeView, store: &gtk::ListStore) {
-        treeview.set_model(Some(store));
+    fn add_stream(&self, type_: gst::StreamType, stream: &metadata::Stream, is_active: bool) {
+        let id_parts: Vec<&str> = stream.id.split('/').collect();
+        let stream_id_display = if id_parts.len() == 2 {
+            id_parts[1].to_owned()
+        } else {
+            gettext("unknown")
+        };
 
-        Self::add_text_column(
-            treeview,
-            &gettext("Stream id"),
-            ALIGN_LEFT,
-            STREAM_ID_DISPLAY_COL,
-            Some(200),
-        );
-        Self::add_text_column(
-            treeview,
-            &gettext("Language"),
-            ALIGN_CENTER,
-            LANGUAGE_COL,
-            None,
-        );
-        Self::add_text_column(treeview, &gettext("Codec"), ALIGN_LEFT, CODEC_COL, None);
-        Self::add_text_column(
-            treeview,
-            &gettext("Rate"),
-            ALIGN_RIGHT,
-            Self::AUDIO_RATE_COL,
-            None,
-        );
-        Self::add_text_column(
-            treeview,
-            &gettext("Channels"),
-            ALIGN_RIGHT,
-            Self::AUDIO_CHANNELS_COL,
+        let lang = stream
+            .tags
+            .get_index::<gst::tags::LanguageName>(0)
+            .or_else(|| stream.tags.get_index::<gst::tags::LanguageCode>(0))
+            .and_then(|value| value.get())
+            .unwrap_or("-");
+
+        let iter = self.store.insert_with_values(
             None,
+            &[
+                ACTIVE_COL,
+                STREAM_ID_COL,
+                TYPE_COL,
+                TYPE_DISPLAY_COL,
+                STREAM_ID_DISPLAY_COL,
+                LANGUAGE_COL,
+                CODEC_COL,
+            ],
+            &[
+                &is_active,
+                &stream.id.as_ref(),
+                &type_.bits(),
+                &type_display(type_),
+                &stream_id_display,
+                &lang,
+                &stream.codec_printable,
+            ],
         );
-        Self::add_text_column(treeview, &gettext("Comment"), ALIGN_LEFT, COMMENT_COL, None);
-    }
-}
 
-pub(super) struct UIStreamTextImpl;
-impl UIStreamTextImpl {
-    const TEXT_FORMAT_COL: u32 = 5;
-}
-
-impl UIStreamImpl for UIStreamTextImpl {
-    const TYPE: gst::StreamType = gst::StreamType::TEXT;
+        let caps_struct = stream.caps.get_structure(0).unwrap();
+        let properties = match type_ {
+            gst::StreamType::VIDEO => video_properties(caps_struct),
+            gst::StreamType::AUDIO => audio_properties(caps_struct),
+            gst::StreamType::TEXT => text_properties(caps_struct),
+            other => unimplemented!("{:?}", other),
+        };
+        self.store
+            .set_value(&iter, PROPERTIES_COL, &glib::Value::from(&properties));
 
-    fn new_media(store: &gtk::ListStore, iter: &gtk::TreeIter, caps_struct: &gst::StructureRef) {
-        if let Ok(Some(format)) = caps_struct.get::<&str>("format") {
-            store.set_value(&iter, Self::TEXT_FORMAT_COL, &glib::Value::from(&format));
+        if let Some(bitrate) = bitrate(&stream.tags) {
+            self.store
+                .set_value(&iter, BITRATE_COL, &glib::Value::from(&bitrate));
         }
-    }
 
-    fn init_treeview(treeview: &gtk::TreeView, store: &gtk::ListStore) {
-        treeview.set_model(Some(store));
+        if let Some(comment) = stream
+            .tags
+            .get_index::<gst::tags::Comment>(0)
+            .and_then(|value| value.get())
+        {
+            self.store
+                .set_value(&iter, COMMENT_COL, &glib::Value::from(comment));
+        }
 
-        Self::add_text_column(
-            treeview,
-            &gettext("Stream id"),
-            ALIGN_LEFT,
-            STREAM_ID_DISPLAY_COL,
-            Some(200),
-        );
-        Self::add_text_column(
-            treeview,
-            &gettext("Language"),
-            ALIGN_CENTER,
-            LANGUAGE_COL,
-            None,
+        self.set_caps(
+            &iter,
+            &gettext("Stream caps:\n{}").replacen("{}", &stream.caps.to_string(), 1),
         );
-        Self::add_text_column(treeview, &gettext("Codec"), ALIGN_LEFT, CODEC_COL, None);
-        Self::add_text_column(
-            treeview,
-            &gettext("Format"),
-            ALIGN_LEFT,
-            Self::TEXT_FORMAT_COL,
-            None,
+    }
+
+    fn set_caps(&self, iter: &gtk::TreeIter, caps: &str) {
+        // The tooltip column is interpreted as markup
+        self.store.set_value(
+            iter,
+            CAPS_COL,
+            &glib::Value::from(glib::markup_escape_text(caps).as_str()),
         );
-        Self::add_text_column(treeview, &gettext("Comment"), ALIGN_LEFT, COMMENT_COL, None);
     }
-}
 
-pub struct StreamsController {
-    pub(super) page: gtk::Grid,
+    fn stream_type(&self, iter: &gtk::TreeIter) -> gst::StreamType {
+        gst::StreamType::from_bits_truncate(
+            self.store
+                .get_value(iter, TYPE_COL as i32)
+                .get_some::<u32>()
+                .unwrap(),
+        )
+    }
 
-    pub(super) video: UIStream<UIStreamVideoImpl>,
-    pub(super) audio: UIStream<UIStreamAudioImpl>,
-    pub(super) text: UIStream<UIStreamTextImpl>,
-}
+    fn is_active(&self, iter: &gtk::TreeIter) -> bool {
+        self.store
+            .get_value(iter, ACTIVE_COL as i32)
+            .get_some::<bool>()
+            .unwrap()
+    }
 
-impl UIController for StreamsController {
-    fn new_media(&mut self, pipeline: &PlaybackPipeline) {
-        self.video.new_media(&pipeline.info.streams);
-        self.audio.new_media(&pipeline.info.streams);
-        self.text.new_media(&pipeline.info.streams);
+    fn stream_id(&self, iter: &gtk::TreeIter) -> Arc<str> {
+        self.store
+            .get_value(iter, STREAM_ID_COL as i32)
+            .get::<String>()
+            .unwrap()
+            .unwrap()
+            .into()
     }
 
-    fn cleanup(&mut self) {
-        self.video.cleanup();
-        self.audio.cleanup();
-        self.text.cleanup();
+    fn for_each_row(&self, mut func: impl FnMut(&gtk::TreeIter)) {
+        if let Some(iter) = self.store.get_iter_first() {
+            loop {
+                func(&iter);
+                if !self.store.iter_next(&iter) {
+                    break;
+                }
+            }
+        }
     }
 
-    fn grab_focus(&self) {
-        // grab focus asynchronoulsy because it triggers the `cursor_changed` signal
-        // which needs to check if the stream has changed
-        let audio_treeview = self.audio.treeview.clone();
-        spawn(async move {
-            audio_treeview.grab_focus();
+    fn active_row(&self, type_: gst::StreamType) -> Option<gtk::TreeIter> {
+        let mut active_row = None;
+        self.for_each_row(|iter| {
+            if active_row.is_none() && self.stream_type(iter) == type_ && self.is_active(iter) {
+                active_row = Some(iter.clone());
+            }
         });
+
+        active_row
     }
-}
 
-impl StreamsController {
-    pub fn new(builder: &gtk::Builder) -> Self {
-        let mut ctrl = StreamsController {
-            page: builder.get_object("streams-grid").unwrap(),
+    pub(super) fn stream_toggled(&mut self, tree_path: &gtk::TreePath) -> StreamToggledStatus {
+        let iter = match self.store.get_iter(tree_path) {
+            Some(iter) => iter,
+            None => return StreamToggledStatus::Unchanged,
+        };
 
-            video: UIStream::new(
-                builder.get_object("video_streams-treeview").unwrap(),
-                builder.get_object("video_streams-liststore").unwrap(),
-            ),
+        let type_ = self.stream_type(&iter);
+        let is_active = self.is_active(&iter);
+        if is_active {
+            if !can_be_none(type_) {
+                return StreamToggledStatus::Unchanged;
+            }
+        } else if let Some(active_row) = self.active_row(type_) {
+            self.store
+                .set_value(&active_row, ACTIVE_COL, &false.to_value());
+        }
 
-            audio: UIStream::new(
-                builder.get_object("audio_streams-treeview").unwrap(),
-                builder.get_object("audio_streams-liststore").unwrap(),
-            ),
+        self.store
+            .set_value(&iter, ACTIVE_COL, &(!is_active).to_value());
+
+        StreamToggledStatus::Changed
+    }
 
-            text: UIStream::new(
-                builder.get_object("text_streams-treeview").unwrap(),
-                builder.get_object("text_streams-liststore").unwrap(),
-            ),
+    /// Activates the video stream following the active one, in display order.
+    pub(super) fn cycle_video_stream(&mut self) -> StreamToggledStatus {
+        let mut video_rows = Vec::new();
+        self.for_each_row(|iter| {
+            if self.stream_type(iter) == gst::StreamType::VIDEO {
+                video_rows.push(iter.clone());
+            }
+        });
+
+        let active_idx = video_rows.iter().position(|iter| self.is_active(iter));
+        let next_idx = match active_idx {
+            Some(active_idx) => (active_idx + 1) % video_rows.len(),
+            None if !video_rows.is_empty() => 0,
+            None => return StreamToggledStatus::Unchanged,
         };
+        if active_idx == Some(next_idx) {
+            return StreamToggledStatus::Unchanged;
+        }
 
-        ctrl.cleanup();
+        if let Some(active_idx) = active_idx {
+            self.store
+                .set_value(&video_rows[active_idx], ACTIVE_COL, &false.to_value());
+        }
+        self.store
+            .set_value(&video_rows[next_idx], ACTIVE_COL, &true.to_value());
 
-        ctrl.video.init_treeview();
-        ctrl.audio.init_treeview();
-        ctrl.text.init_treeview();
+        StreamToggledStatus::Changed
+    }
 
-        ctrl
+    /// Replaces the caps of the active streams with the negotiated caps.
+    pub fn update_negotiated_caps(&self, pipeline: &PlaybackPipeline) {
+        for type_ in STREAM_TYPES.iter() {
+            if let (Some(iter), Some(caps)) =
+                (self.active_row(*type_), pipeline.negotiated_caps(*type_))
+            {
+                self.set_caps(
+                    &iter,
+                    &gettext("Negotiated caps:\n{}").replacen("{}", &caps.to_string(), 1),
+                );
+            }
+        }
     }
 
-    pub(super) fn stream_clicked(&mut self, type_: gst::StreamType) -> StreamClickedStatus {
-        match type_ {
-            gst::StreamType::VIDEO => self.video.stream_clicked(),
-            gst::StreamType::AUDIO => self.audio.stream_clicked(),
-            gst::StreamType::TEXT => self.text.stream_clicked(),
-            other => unimplemented!("{:?}", other),
+    pub fn update_qos(&self, stats: &QosStats) {
+        if stats.is_empty() {
+            return;
         }
+
+        let jitter = stats
+            .average_jitter()
+            .map_or(0f64, |jitter| jitter.as_f64() / 1_000_000f64);
+        self.qos_lbl.set_text(
+            &gettext("Dropped frames: {} of {}, average jitter: {} ms")
+                .replacen("{}", &stats.dropped().to_string(), 1)
+                .replacen("{}", &stats.processed().to_string(), 1)
+                .replacen("{}", &format!("{:.1}", jitter), 1),
+        );
     }
 
+    /// Returns the ids of the active streams.
     pub fn selected_streams(&self) -> Vec<Arc<str>> {
         let mut streams: Vec<Arc<str>> = Vec::new();
-        if let Some(stream) = self.video.selected.as_ref() {
-            streams.push(Arc::clone(stream));
-        }
-        if let Some(stream) = self.audio.selected.as_ref() {
-            streams.push(Arc::clone(stream));
-        }
-        if let Some(stream) = self.text.selected.as_ref() {
-            streams.push(Arc::clone(stream));
-        }
+        self.for_each_row(|iter| {
+            if self.is_active(iter) {
+                streams.push(self.stream_id(iter));
+            }
+        });
 
         streams
     }
 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_layouts() {
+        assert_eq!("mono", channel_layout(1, None));
+        assert_eq!("stereo", channel_layout(2, None));
+        assert_eq!("stereo", channel_layout(2, Some(0x3)));
+        assert_eq!("6.0", channel_layout(6, None));
+        // FL FR FC LFE RL RR
+        assert_eq!("5.1", channel_layout(6, Some(0x3f)));
+        // FL FR FC LFE RL RR SL SR
+        assert_eq!("7.1", channel_layout(8, Some(0xc3f)));
+        // FL FR LFE
+        assert_eq!("2.1", channel_layout(3, Some(0xb)));
+    }
+
+    #[test]
+    fn properties() {
+        gst::init().unwrap();
+
+        let caps = gst::Caps::new_simple(
+            "video/x-raw",
+            &[
+                ("width", &1920i32),
+                ("height", &1080i32),
+                ("framerate", &gst::Fraction::new(30_000, 1_001)),
+                ("interlace-mode", &"progressive"),
+            ],
+        );
+        assert_eq!(
+            "1920x1080, 29.97 fps, progressive",
+            video_properties(caps.get_structure(0).unwrap()),
+        );
+
+        let caps = gst::Caps::new_simple(
+            "audio/x-raw",
+            &[
+                ("rate", &48_000i32),
+                ("channels", &2i32),
+                ("format", &"S16LE"),
+            ],
+        );
+        assert_eq!(
+            "48000 Hz, stereo, S16LE",
+            audio_properties(caps.get_structure(0).unwrap()),
+        );
+
+        let caps = gst::Caps::new_simple("text/x-raw", &[]);
+        assert_eq!("", text_properties(caps.get_structure(0).unwrap()));
+    }
+}
@@ -1,7 +1,11 @@
+use gio::prelude::*;
 use glib::clone;
 use gtk::prelude::*;
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
 use super::{MainController, StreamsController, UIDispatcher, UIEventSender, UIFocusContext};
 
@@ -12,25 +16,48 @@ impl UIDispatcher for StreamsDispatcher {
     fn setup(
         streams_ctrl: &mut StreamsController,
         _main_ctrl_rc: &Rc<RefCell<MainController>>,
-        _app: &gtk::Application,
+        app: &gtk::Application,
         ui_event: &UIEventSender,
     ) {
-        streams_ctrl.video.treeview.connect_cursor_changed(
-            clone!(@strong ui_event => move |_| ui_event.stream_clicked(gst::StreamType::VIDEO)),
+        let is_treeview_initialized = Cell::new(false);
+        streams_ctrl.treeview.connect_map(
+            clone!(@strong streams_ctrl.active_renderer as active_renderer => move |treeview| {
+                if !is_treeview_initialized.replace(true) {
+                    StreamsController::init_treeview(treeview, &active_renderer);
+                }
+            }),
         );
 
-        streams_ctrl.audio.treeview.connect_cursor_changed(
-            clone!(@strong ui_event => move |_| ui_event.stream_clicked(gst::StreamType::AUDIO)),
+        streams_ctrl.active_renderer.connect_toggled(
+            clone!(@strong ui_event => move |_, tree_path| ui_event.stream_toggled(tree_path)),
         );
 
-        streams_ctrl.text.treeview.connect_cursor_changed(
-            clone!(@strong ui_event => move |_| ui_event.stream_clicked(gst::StreamType::TEXT)),
+        // Register Cycle video stream action
+        app.add_action(&streams_ctrl.cycle_video_stream_action);
+        streams_ctrl.cycle_video_stream_action.connect_activate(
+            clone!(@strong ui_event => move |_, _| {
+                ui_event.cycle_video_stream();
+            }),
         );
 
         streams_ctrl
             .page
-            .connect_map(clone!(@strong ui_event => move |_| {
-                ui_event.switch_to(UIFocusContext::StreamsPage);
+            .connect_map(clone!(@strong ui_event => move |page| {
+                // In the split view, the streams are displayed along with the video
+                if page.get_parent().map_or(false, |parent| parent.is::<gtk::Stack>()) {
+                    ui_event.switch_to(UIFocusContext::StreamsPage);
+                }
             }));
     }
+
+    fn bind_accels_for(ctx: UIFocusContext, app: &gtk::Application) {
+        match ctx {
+            UIFocusContext::PlaybackPage | UIFocusContext::StreamsPage => {
+                app.set_accels_for_action("app.cycle_video_stream", &["v"]);
+            }
+            UIFocusContext::InfoBar | UIFocusContext::TextEntry => {
+                app.set_accels_for_action("app.cycle_video_stream", &[]);
+            }
+        }
+    }
 }
@@ -0,0 +1,229 @@
+//! Drives the controllers through `UIEvent`s and checks the widgets' state.
+//!
+//! These tests need a display, e.g.:
+//!
+//! ```sh
+//! $ xvfb-run cargo test --features ui-tests
+//! ```
+//!
+//! GTK's broadway backend can also be used: run `broadwayd` and set `GDK_BACKEND=broadway`.
+//!
+//! GTK can only be used from the thread it was initialized on,
+//! so the flows are run in sequence from a single test.
+
+use gio::prelude::*;
+use gtk::prelude::*;
+
+use std::{
+    cell::RefCell,
+    env,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    application::{CommandLineArguments, CONFIG},
+    media::{
+        test_media::{TestMedia, MEDIA_DURATION},
+        Backend, Timestamp,
+    },
+};
+
+use super::{register_resource, ControllerState, MainController, UIEventSender};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+// Positions are compared with this tolerance in nanoseconds
+const TOLERANCE: f64 = 100_000_000f64;
+const CHAPTERS: &str = "CHAPTER01=00:00:00.000\nCHAPTER01NAME=first\n\
+                        CHAPTER02=00:00:01.000\nCHAPTER02NAME=second\n";
+
+struct Harness {
+    _app: gtk::Application,
+    main_ctrl: Rc<RefCell<MainController>>,
+}
+
+impl Harness {
+    fn new() -> Self {
+        // Keep the user's configuration, history & cache out of the tests
+        let home = env::temp_dir().join(format!("media-toc-player-ui-{}", std::process::id()));
+        env::set_var("XDG_CONFIG_HOME", home.join("config"));
+        env::set_var("XDG_CACHE_HOME", home.join("cache"));
+        env::set_var("XDG_DATA_HOME", home.join("data"));
+
+        gtk::init().expect("ui-tests need a display, see `src/ui/tests.rs`");
+        register_resource(include_bytes!("../../target/resources/ui.gresource"));
+
+        // Don't depend on an audio device
+        CONFIG.write().unwrap().media.audio_sink = Some("fakesink sync=true".to_owned());
+
+        let app = gtk::Application::new(
+            Some("org.fengalin.media-toc-player.tests"),
+            gio::ApplicationFlags::NON_UNIQUE,
+        )
+        .unwrap();
+        app.register(gio::NONE_CANCELLABLE).unwrap();
+
+        let args = CommandLineArguments {
+            input_file: None,
+            disable_gl: true,
+            backend: Backend::default(),
+            #[cfg(feature = "remote")]
+            remote: None,
+        };
+        let main_ctrl = MainController::setup(&app, &args);
+
+        Harness {
+            _app: app,
+            main_ctrl,
+        }
+    }
+
+    fn ui_event(&self) -> UIEventSender {
+        self.main_ctrl.borrow().ui_event.clone()
+    }
+
+    /// Iterates the main context until `predicate` holds.
+    fn wait_until<P>(&self, what: &str, predicate: P)
+    where
+        P: Fn(&MainController) -> bool,
+    {
+        let main_context = glib::MainContext::default();
+        let start = Instant::now();
+        loop {
+            // The controller is borrowed while an async handler is pending
+            if let Ok(main_ctrl) = self.main_ctrl.try_borrow() {
+                if predicate(&main_ctrl) {
+                    return;
+                }
+            }
+
+            if start.elapsed() > TIMEOUT {
+                panic!("timeout waiting for {}", what);
+            }
+
+            if !main_context.iteration(false) {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+fn selected_chapter(main_ctrl: &MainController) -> Option<usize> {
+    main_ctrl.info_ctrl.chapter_manager.selected_idx()
+}
+
+fn is_at(main_ctrl: &MainController, ts: Timestamp) -> bool {
+    (main_ctrl.info_ctrl.timeline_scale.get_value() - ts.as_f64()).abs() <= TOLERANCE
+}
+
+fn open_media(harness: &Harness, media: &TestMedia) {
+    harness.ui_event().open_media(media.path().clone());
+    harness.wait_until("the media to open", |main_ctrl| {
+        main_ctrl.pipeline.is_some() && main_ctrl.state.get() == ControllerState::Paused
+    });
+    // toc files are read asynchronously
+    harness.wait_until("the chapters", |main_ctrl| {
+        main_ctrl.info_ctrl.chapter_manager.iter().count() == 2
+    });
+
+    let main_ctrl = harness.main_ctrl.borrow();
+    let duration = main_ctrl.pipeline.as_ref().unwrap().info.duration;
+    assert!((duration.as_f64() - MEDIA_DURATION.as_f64()).abs() <= TOLERANCE);
+
+    assert!(main_ctrl.close_media_action.get_enabled());
+    assert!(main_ctrl.export_media_info_action.get_enabled());
+    assert!(main_ctrl.info_ctrl.next_chapter_action.get_enabled());
+    assert!(main_ctrl.info_ctrl.timeline_scale.is_sensitive());
+    // video & one of the audio streams
+    assert_eq!(2, main_ctrl.streams_ctrl.selected_streams().len());
+    assert_eq!(Some(0), selected_chapter(&main_ctrl));
+}
+
+fn seek(harness: &Harness) {
+    let target = Timestamp::new(1_500_000_000);
+    harness.ui_event().seek(target, gst::SeekFlags::ACCURATE);
+    harness.wait_until("the seek", |main_ctrl| is_at(main_ctrl, target));
+
+    assert_eq!(Some(1), selected_chapter(&harness.main_ctrl.borrow()));
+}
+
+fn chapter_navigation(harness: &Harness) {
+    harness.ui_event().seek_to_chapter(0);
+    harness.wait_until("the first chapter", |main_ctrl| {
+        selected_chapter(main_ctrl) == Some(0) && is_at(main_ctrl, Timestamp::default())
+    });
+
+    harness.ui_event().next_chapter();
+    harness.wait_until("the next chapter", |main_ctrl| {
+        selected_chapter(main_ctrl) == Some(1) && is_at(main_ctrl, Timestamp::new(1_000_000_000))
+    });
+
+    harness.ui_event().previous_chapter();
+    harness.wait_until("the previous chapter", |main_ctrl| {
+        selected_chapter(main_ctrl) == Some(0)
+    });
+}
+
+fn play_pause(harness: &Harness) {
+    harness.ui_event().seek_to_chapter(0);
+    harness.wait_until("the first chapter", |main_ctrl| {
+        is_at(main_ctrl, Timestamp::default())
+    });
+
+    harness.ui_event().play_pause();
+    harness.wait_until("playback", |main_ctrl| {
+        main_ctrl.state.get() == ControllerState::Playing
+    });
+    assert_eq!(
+        Some("media-playback-pause-symbolic"),
+        harness
+            .main_ctrl
+            .borrow()
+            .play_pause_btn
+            .get_icon_name()
+            .as_deref(),
+    );
+
+    // The timeline follows the playback
+    harness.wait_until("a tick", |main_ctrl| {
+        main_ctrl.info_ctrl.timeline_scale.get_value() > 0f64
+    });
+
+    harness.ui_event().play_pause();
+    harness.wait_until("the pause", |main_ctrl| {
+        main_ctrl.state.get() == ControllerState::Paused
+    });
+    assert_eq!(
+        Some("media-playback-start-symbolic"),
+        harness
+            .main_ctrl
+            .borrow()
+            .play_pause_btn
+            .get_icon_name()
+            .as_deref(),
+    );
+}
+
+fn close_media(harness: &Harness) {
+    harness.ui_event().close_media();
+    harness.wait_until("the media to close", |main_ctrl| {
+        main_ctrl.pipeline.is_none() && main_ctrl.state.get() == ControllerState::Stopped
+    });
+
+    let main_ctrl = harness.main_ctrl.borrow();
+    assert!(!main_ctrl.close_media_action.get_enabled());
+    assert_eq!(0, main_ctrl.info_ctrl.chapter_manager.iter().count());
+}
+
+#[test]
+fn controller_flows() {
+    let harness = Harness::new();
+    let media = TestMedia::audio_video("controller_flows").with_toc(CHAPTERS);
+
+    open_media(&harness, &media);
+    seek(&harness);
+    chapter_navigation(&harness);
+    play_pause(&harness);
+    close_media(&harness);
+}
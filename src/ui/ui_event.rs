@@ -1,47 +1,151 @@
-use futures::channel::mpsc as async_mpsc;
+use futures::{
+    channel::{mpsc as async_mpsc, oneshot},
+    prelude::*,
+};
 
-use std::{borrow::Cow, cell::RefCell, path::PathBuf};
+use std::{borrow::Cow, cell::RefCell, fmt};
 
-use crate::media::Timestamp;
+use crate::{
+    media::{AudioLevel, MissingPlugins, Timestamp},
+    metadata::MediaPath,
+};
+
+use super::{
+    spawn, BookProbing, SilenceDetection, SnapshotCapture, TocReading, WaveformGeneration,
+};
+
+/// Sends back the reply to an event, see `UIEventSender::send_with_reply`.
+pub struct Reply<T>(oneshot::Sender<T>);
+
+impl<T> Reply<T> {
+    pub fn reply(self, value: T) {
+        // The requester might not be waiting anymore
+        let _ = self.0.send(value);
+    }
+}
+
+impl<T> fmt::Debug for Reply<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Reply")
+    }
+}
 
 #[derive(Clone, Copy, Debug)]
 pub enum UIFocusContext {
     InfoBar,
     PlaybackPage,
     StreamsPage,
+    /// Text is being typed, e.g. in the chapter search entry.
+    TextEntry,
 }
 
 #[derive(Debug)]
 pub enum UIEvent {
     About,
-    CancelSelectMedia,
+    AudioDeviceLost(Cow<'static, str>),
+    AudioLevel(AudioLevel),
+    AudioOutputLost,
+    BookProbed(BookProbing),
     ChapterClicked(gtk::TreePath),
+    ChaptersChanged,
+    Close,
+    CloseMedia,
+    CycleVideoStream,
+    DetectSilences,
+    DumpPipeline,
     Eos,
+    ExportMediaInfo,
+    ExternalStateChange(gst::State),
+    FastForward,
+    FirstChapter,
+    GoBack,
+    GoForward,
+    HardwareDecoderFailed {
+        path: MediaPath,
+        err: String,
+    },
     HideInfoBar,
+    History,
+    InfoBarResponded(gtk::ResponseType),
+    InsertChapters(Vec<Timestamp>),
+    LastChapter,
+    Logs,
+    MaxVideoHeightChanged,
+    MediaChanged,
+    MissingPlugins(MissingPlugins),
     NextChapter,
-    OpenMedia(PathBuf),
+    NextMedia,
+    OpenBook(MediaPath),
+    OpenBookPart {
+        idx: usize,
+        position: Timestamp,
+    },
+    OpenMedia(MediaPath),
+    PlayChapter(gtk::TreePath),
     PlayPause,
+    PluginsInstalled(bool),
+    PositionFinalized(Timestamp),
+    Preferences,
     PreviousChapter,
     Quit,
+    ReloadMedia,
+    ReloadToc,
+    RenameChapter {
+        tree_path: gtk::TreePath,
+        title: String,
+    },
+    RepeatCountChanged,
     ResetCursor,
     RestoreContext,
+    ResumeMedia(MediaPath),
+    RetryAudioDevice,
+    Rewind,
+    SaveToc,
+    ScrubEnd,
+    ScrubMove(Timestamp),
+    ScrubStart,
     Seek {
         target: Timestamp,
         flags: gst::SeekFlags,
     },
-    SelectMedia,
+    SeekSteps,
+    SeekToChapter(usize),
+    SegmentDone,
+    SelectBook(Reply<Option<MediaPath>>),
+    SelectMedia(Reply<Option<MediaPath>>),
+    SelectToc(usize),
     ShowAll,
     SetCursorWaiting,
+    ShiftAudioOffset(i32),
     ShowError(Cow<'static, str>),
     ShowInfo(Cow<'static, str>),
+    ShowMediaToc,
+    ShowWarningWithDetails {
+        msg: Cow<'static, str>,
+        details: Cow<'static, str>,
+    },
+    SilencesDetected(SilenceDetection),
+    SnapshotsTaken(SnapshotCapture),
     StepBack,
     StepForward,
-    StreamClicked(gst::StreamType),
+    StreamToggled(gtk::TreePath),
+    SubtitleStyleChanged,
+    SwitchPerspective(String),
     SwitchTo(UIFocusContext),
+    TakeChapterSnapshots,
+    TakeSnapshot,
     TemporarilySwitchTo(UIFocusContext),
+    TocRead(TocReading),
+    TocUpdated(gst::Toc),
     ToggleChapterList(bool),
+    ToggleFlatChapters(bool),
+    ToggleMiniPlayer(bool),
     ToggleRepeat(bool),
+    ToggleStopAtChapterEnd(bool),
+    UpdateChapterTitle(Option<String>),
     UpdateFocus,
+    WaveformGenerated(WaveformGeneration),
+    WindowResized(i32),
 }
 
 #[derive(Clone)]
@@ -53,39 +157,197 @@ impl UIEventSender {
         let _ = self.0.borrow_mut().unbounded_send(event);
     }
 
+    /// Sends the event built by `event` and returns the reply from its handler.
+    ///
+    /// The reply is `None` if the handler dropped the `Reply` without replying.
+    pub fn send_with_reply<T, F>(&self, event: F) -> impl Future<Output = Option<T>>
+    where
+        F: FnOnce(Reply<T>) -> UIEvent,
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(event(Reply(reply_tx)));
+
+        reply_rx.map(Result::ok)
+    }
+
+    /// Returns a facade which can be moved to other threads.
+    ///
+    /// Must be called from the main context's thread.
+    pub fn sync_sender(&self) -> UIEventSyncSender {
+        let (sync_tx, sync_rx) = glib::MainContext::channel::<SyncEvent>(glib::PRIORITY_DEFAULT);
+        let ui_event = self.clone();
+        sync_rx.attach(None, move |sync_event| {
+            sync_event(&ui_event);
+            glib::Continue(true)
+        });
+
+        UIEventSyncSender(sync_tx)
+    }
+
     pub fn about(&self) {
         self.send(UIEvent::About);
     }
 
-    pub fn cancel_select_media(&self) {
-        self.send(UIEvent::CancelSelectMedia);
+    pub fn audio_device_lost<Msg>(&self, msg: Msg)
+    where
+        Msg: Into<Cow<'static, str>>,
+    {
+        self.send(UIEvent::AudioDeviceLost(msg.into()));
+    }
+
+    pub fn audio_level(&self, level: AudioLevel) {
+        self.send(UIEvent::AudioLevel(level));
+    }
+
+    pub fn audio_output_lost(&self) {
+        self.send(UIEvent::AudioOutputLost);
+    }
+
+    pub fn book_probed(&self, probing: BookProbing) {
+        self.send(UIEvent::BookProbed(probing));
     }
 
     pub fn chapter_clicked(&self, tree_path: gtk::TreePath) {
         self.send(UIEvent::ChapterClicked(tree_path));
     }
 
+    pub fn chapters_changed(&self) {
+        self.send(UIEvent::ChaptersChanged);
+    }
+
+    pub fn close(&self) {
+        self.send(UIEvent::Close);
+    }
+
+    pub fn close_media(&self) {
+        self.send(UIEvent::CloseMedia);
+    }
+
+    pub fn cycle_video_stream(&self) {
+        self.send(UIEvent::CycleVideoStream);
+    }
+
+    pub fn detect_silences(&self) {
+        self.send(UIEvent::DetectSilences);
+    }
+
+    pub fn dump_pipeline(&self) {
+        self.send(UIEvent::DumpPipeline);
+    }
+
     pub fn eos(&self) {
         self.send(UIEvent::Eos);
     }
 
+    pub fn export_media_info(&self) {
+        self.send(UIEvent::ExportMediaInfo);
+    }
+
+    pub fn external_state_change(&self, state: gst::State) {
+        self.send(UIEvent::ExternalStateChange(state));
+    }
+
+    pub fn fast_forward(&self) {
+        self.send(UIEvent::FastForward);
+    }
+
+    pub fn first_chapter(&self) {
+        self.send(UIEvent::FirstChapter);
+    }
+
+    pub fn go_back(&self) {
+        self.send(UIEvent::GoBack);
+    }
+
+    pub fn go_forward(&self) {
+        self.send(UIEvent::GoForward);
+    }
+
+    pub fn hardware_decoder_failed(&self, path: MediaPath, err: String) {
+        self.send(UIEvent::HardwareDecoderFailed { path, err });
+    }
+
     pub fn hide_info_bar(&self) {
         self.send(UIEvent::HideInfoBar);
     }
 
+    pub fn history(&self) {
+        self.send(UIEvent::History);
+    }
+
+    pub fn info_bar_responded(&self, response_type: gtk::ResponseType) {
+        self.send(UIEvent::InfoBarResponded(response_type));
+    }
+
+    pub fn insert_chapters(&self, boundaries: Vec<Timestamp>) {
+        self.send(UIEvent::InsertChapters(boundaries));
+    }
+
+    pub fn last_chapter(&self) {
+        self.send(UIEvent::LastChapter);
+    }
+
+    pub fn logs(&self) {
+        self.send(UIEvent::Logs);
+    }
+
+    pub fn max_video_height_changed(&self) {
+        self.send(UIEvent::MaxVideoHeightChanged);
+    }
+
+    pub fn media_changed(&self) {
+        self.send(UIEvent::MediaChanged);
+    }
+
+    pub fn missing_plugins(&self, plugins: MissingPlugins) {
+        self.send(UIEvent::MissingPlugins(plugins));
+    }
+
     pub fn next_chapter(&self) {
         self.send(UIEvent::NextChapter);
     }
 
-    pub fn open_media(&self, path: PathBuf) {
+    pub fn next_media(&self) {
+        self.send(UIEvent::NextMedia);
+    }
+
+    pub fn open_book<P: Into<MediaPath>>(&self, dir: P) {
         self.set_cursor_waiting();
-        self.send(UIEvent::OpenMedia(path));
+        self.send(UIEvent::OpenBook(dir.into()));
+    }
+
+    // Opens another part of current book and seeks to `position` in the book
+    pub fn open_book_part(&self, idx: usize, position: Timestamp) {
+        self.set_cursor_waiting();
+        self.send(UIEvent::OpenBookPart { idx, position });
+    }
+
+    pub fn open_media<P: Into<MediaPath>>(&self, path: P) {
+        self.set_cursor_waiting();
+        self.send(UIEvent::OpenMedia(path.into()));
+    }
+
+    // Seeks to the chapter and starts playing
+    pub fn play_chapter(&self, tree_path: gtk::TreePath) {
+        self.send(UIEvent::PlayChapter(tree_path));
     }
 
     pub fn play_pause(&self) {
         self.send(UIEvent::PlayPause);
     }
 
+    pub fn plugins_installed(&self, is_installed: bool) {
+        self.send(UIEvent::PluginsInstalled(is_installed));
+    }
+
+    pub fn position_finalized(&self, ts: Timestamp) {
+        self.send(UIEvent::PositionFinalized(ts));
+    }
+
+    pub fn preferences(&self) {
+        self.send(UIEvent::Preferences);
+    }
+
     pub fn previous_chapter(&self) {
         self.send(UIEvent::PreviousChapter);
     }
@@ -94,6 +356,25 @@ impl UIEventSender {
         self.send(UIEvent::Quit);
     }
 
+    pub fn reload_media(&self) {
+        self.send(UIEvent::ReloadMedia);
+    }
+
+    pub fn reload_toc(&self) {
+        self.send(UIEvent::ReloadToc);
+    }
+
+    pub fn rename_chapter(&self, tree_path: gtk::TreePath, title: &str) {
+        self.send(UIEvent::RenameChapter {
+            tree_path,
+            title: title.to_owned(),
+        });
+    }
+
+    pub fn repeat_count_changed(&self) {
+        self.send(UIEvent::RepeatCountChanged);
+    }
+
     pub fn reset_cursor(&self) {
         self.send(UIEvent::ResetCursor);
     }
@@ -102,8 +383,69 @@ impl UIEventSender {
         self.send(UIEvent::RestoreContext);
     }
 
+    pub fn resume_media<P: Into<MediaPath>>(&self, path: P) {
+        self.set_cursor_waiting();
+        self.send(UIEvent::ResumeMedia(path.into()));
+    }
+
+    pub fn retry_audio_device(&self) {
+        self.send(UIEvent::RetryAudioDevice);
+    }
+
+    pub fn rewind(&self) {
+        self.send(UIEvent::Rewind);
+    }
+
+    pub fn save_toc(&self) {
+        self.send(UIEvent::SaveToc);
+    }
+
+    pub fn seek_steps(&self) {
+        self.send(UIEvent::SeekSteps);
+    }
+
+    pub fn seek_to_chapter(&self, idx: usize) {
+        self.send(UIEvent::SeekToChapter(idx));
+    }
+
+    pub fn segment_done(&self) {
+        self.send(UIEvent::SegmentDone);
+    }
+
+    /// Asks the user for a folder to open as a book.
+    pub fn select_book(&self) {
+        let ui_event = self.clone();
+        spawn(async move {
+            if let Some(Some(dir)) = ui_event.send_with_reply(UIEvent::SelectBook).await {
+                ui_event.open_book(dir);
+            }
+        });
+    }
+
+    /// Asks the user for a media to open.
     pub fn select_media(&self) {
-        self.send(UIEvent::SelectMedia);
+        let ui_event = self.clone();
+        spawn(async move {
+            if let Some(Some(path)) = ui_event.send_with_reply(UIEvent::SelectMedia).await {
+                ui_event.open_media(path);
+            }
+        });
+    }
+
+    pub fn select_toc(&self, idx: usize) {
+        self.send(UIEvent::SelectToc(idx));
+    }
+
+    pub fn scrub_end(&self) {
+        self.send(UIEvent::ScrubEnd);
+    }
+
+    pub fn scrub_move(&self, target: Timestamp) {
+        self.send(UIEvent::ScrubMove(target));
+    }
+
+    pub fn scrub_start(&self) {
+        self.send(UIEvent::ScrubStart);
     }
 
     pub fn seek(&self, target: Timestamp, flags: gst::SeekFlags) {
@@ -114,6 +456,10 @@ impl UIEventSender {
         self.send(UIEvent::SetCursorWaiting);
     }
 
+    pub fn shift_audio_offset(&self, steps: i32) {
+        self.send(UIEvent::ShiftAudioOffset(steps));
+    }
+
     pub fn show_all(&self) {
         self.send(UIEvent::ShowAll);
     }
@@ -132,6 +478,29 @@ impl UIEventSender {
         self.send(UIEvent::ShowInfo(msg.into()));
     }
 
+    pub fn show_media_toc(&self) {
+        self.send(UIEvent::ShowMediaToc);
+    }
+
+    pub fn show_warning_with_details<Msg, Details>(&self, msg: Msg, details: Details)
+    where
+        Msg: Into<Cow<'static, str>>,
+        Details: Into<Cow<'static, str>>,
+    {
+        self.send(UIEvent::ShowWarningWithDetails {
+            msg: msg.into(),
+            details: details.into(),
+        });
+    }
+
+    pub fn silences_detected(&self, detection: SilenceDetection) {
+        self.send(UIEvent::SilencesDetected(detection));
+    }
+
+    pub fn snapshots_taken(&self, capture: SnapshotCapture) {
+        self.send(UIEvent::SnapshotsTaken(capture));
+    }
+
     pub fn step_back(&self) {
         self.send(UIEvent::StepBack);
     }
@@ -140,30 +509,96 @@ impl UIEventSender {
         self.send(UIEvent::StepForward);
     }
 
-    pub fn stream_clicked(&self, type_: gst::StreamType) {
-        self.send(UIEvent::StreamClicked(type_));
+    pub fn stream_toggled(&self, tree_path: gtk::TreePath) {
+        self.send(UIEvent::StreamToggled(tree_path));
+    }
+
+    pub fn subtitle_style_changed(&self) {
+        self.send(UIEvent::SubtitleStyleChanged);
+    }
+
+    pub fn switch_perspective(&self, name: String) {
+        self.send(UIEvent::SwitchPerspective(name));
     }
 
     pub fn switch_to(&self, ctx: UIFocusContext) {
         self.send(UIEvent::SwitchTo(ctx));
     }
 
+    pub fn take_chapter_snapshots(&self) {
+        self.send(UIEvent::TakeChapterSnapshots);
+    }
+
+    pub fn take_snapshot(&self) {
+        self.send(UIEvent::TakeSnapshot);
+    }
+
     // Call `restore_context` to retrieve initial state
     pub fn temporarily_switch_to(&self, ctx: UIFocusContext) {
         self.send(UIEvent::TemporarilySwitchTo(ctx));
     }
 
+    pub fn toc_read(&self, reading: TocReading) {
+        self.send(UIEvent::TocRead(reading));
+    }
+
+    pub fn toc_updated(&self, toc: gst::Toc) {
+        self.send(UIEvent::TocUpdated(toc));
+    }
+
     pub fn toggle_chapter_list(&self, must_show: bool) {
         self.send(UIEvent::ToggleChapterList(must_show));
     }
 
+    pub fn toggle_flat_chapters(&self, is_flat: bool) {
+        self.send(UIEvent::ToggleFlatChapters(is_flat));
+    }
+
+    pub fn toggle_mini_player(&self, is_mini: bool) {
+        self.send(UIEvent::ToggleMiniPlayer(is_mini));
+    }
+
     pub fn toggle_repeat(&self, must_repeat: bool) {
         self.send(UIEvent::ToggleRepeat(must_repeat));
     }
 
+    pub fn toggle_stop_at_chapter_end(&self, must_stop: bool) {
+        self.send(UIEvent::ToggleStopAtChapterEnd(must_stop));
+    }
+
+    pub fn update_chapter_title(&self, chapter_title: Option<String>) {
+        self.send(UIEvent::UpdateChapterTitle(chapter_title));
+    }
+
     pub fn update_focus(&self) {
         self.send(UIEvent::UpdateFocus);
     }
+
+    pub fn waveform_generated(&self, generation: WaveformGeneration) {
+        self.send(UIEvent::WaveformGenerated(generation));
+    }
+
+    pub fn window_resized(&self, width: i32) {
+        self.send(UIEvent::WindowResized(width));
+    }
+}
+
+type SyncEvent = Box<dyn FnOnce(&UIEventSender) + Send>;
+
+/// A `Send` + `Sync` facade to the `UIEventSender`.
+///
+/// Background subsystems use it to send `UIEvent`s from any thread:
+/// the closures are executed on the main context with the `UIEventSender`.
+#[derive(Clone)]
+pub struct UIEventSyncSender(glib::Sender<SyncEvent>);
+
+impl UIEventSyncSender {
+    pub fn send<F>(&self, func: F)
+    where
+        F: FnOnce(&UIEventSender) + Send + 'static,
+    {
+        let _ = self.0.send(Box::new(func));
+    }
 }
 
 pub fn new_pair() -> (UIEventSender, async_mpsc::UnboundedReceiver<UIEvent>) {
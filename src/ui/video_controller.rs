@@ -4,11 +4,32 @@ use log::debug;
 
 use crate::{
     application::{CommandLineArguments, CONFIG},
-    metadata::MediaInfo,
+    metadata::{MediaInfo, StreamSelectionDelta},
 };
 
 use super::UIController;
 
+// Monitors whose pixel aspect ratio is closer to 1/1 than this are considered square
+const SQUARE_PAR_TOLERANCE: f64 = 0.02;
+
+/// Computes the pixel aspect ratio of a monitor from its geometry in logical pixels
+/// and its physical size in millimeters.
+///
+/// The logical geometry doesn't depend on the scale factor, be it fractional or not.
+fn monitor_par(width: i32, height: i32, width_mm: i32, height_mm: i32) -> gst::Fraction {
+    // Some monitors, e.g. projectors, don't report their physical size
+    if width <= 0 || height <= 0 || width_mm <= 0 || height_mm <= 0 {
+        return gst::Fraction::new(1, 1);
+    }
+
+    let par = (f64::from(width_mm) / f64::from(width)) / (f64::from(height_mm) / f64::from(height));
+    if (par - 1f64).abs() < SQUARE_PAR_TOLERANCE {
+        gst::Fraction::new(1, 1)
+    } else {
+        gst::Fraction::new((par * 1000f64).round() as i32, 1000)
+    }
+}
+
 pub struct VideoOutput {
     sink: gst::Element,
     pub(super) widget: gtk::Widget,
@@ -42,10 +63,19 @@ impl UIController for VideoController {
         }
     }
 
-    fn streams_changed(&mut self, info: &MediaInfo) {
+    fn streams_changed(&mut self, info: &MediaInfo, delta: &StreamSelectionDelta) {
         if let Some(video_output) = self.video_output.as_ref() {
-            if let Some(cleaner_id) = self.cleaner_id.take() {
-                self.container.get_children()[0].disconnect(cleaner_id);
+            let was_cleaned = match self.cleaner_id.take() {
+                Some(cleaner_id) => {
+                    self.container.get_children()[0].disconnect(cleaner_id);
+                    true
+                }
+                None => false,
+            };
+
+            // Keep the widget as is when only the audio or text streams changed
+            if !was_cleaned && !delta.video.is_changed() {
+                return;
             }
 
             if info.streams.is_video_selected() {
@@ -105,9 +135,22 @@ impl VideoController {
         });
 
         if let Some(video_output) = video_output.as_ref() {
-            container.pack_start(&video_output.widget, true, true, 0);
-            container.reorder_child(&video_output.widget, 0);
-            video_output.widget.show();
+            let widget = &video_output.widget;
+            // The sink letterboxes the video according to the pixel aspect ratio from the caps
+            if widget.find_property("force-aspect-ratio").is_some() {
+                widget.set_property("force-aspect-ratio", &true).unwrap();
+            }
+
+            widget.connect_realize(Self::adapt_to_monitor);
+            // The scale factor changes when the window moves to another monitor
+            widget.connect_property_scale_factor_notify(|widget| {
+                Self::adapt_to_monitor(widget);
+                widget.queue_resize();
+            });
+
+            container.pack_start(widget, true, true, 0);
+            container.reorder_child(widget, 0);
+            widget.show();
         };
 
         let mut video_ctrl = VideoController {
@@ -127,9 +170,54 @@ impl VideoController {
             .map(|video_output| video_output.sink.clone())
     }
 
+    /// Sets the pixel aspect ratio of the monitor displaying the video widget.
+    fn adapt_to_monitor(widget: &gtk::Widget) {
+        if widget.find_property("pixel-aspect-ratio").is_none() {
+            return;
+        }
+
+        let monitor = widget
+            .get_window()
+            .and_then(|window| widget.get_display().get_monitor_at_window(&window));
+        let monitor = match monitor {
+            Some(monitor) => monitor,
+            None => return,
+        };
+
+        let geometry = monitor.get_geometry();
+        let par = monitor_par(
+            geometry.width,
+            geometry.height,
+            monitor.get_width_mm(),
+            monitor.get_height_mm(),
+        );
+        debug!(
+            "video widget on monitor with scale factor {}, pixel aspect ratio {:?}",
+            widget.get_scale_factor(),
+            par,
+        );
+        widget.set_property("pixel-aspect-ratio", &par).unwrap();
+    }
+
     fn video_widget(&self) -> Option<gtk::Widget> {
         self.video_output
             .as_ref()
             .map(|video_output| video_output.widget.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn par() {
+        assert_eq!(gst::Fraction::new(1, 1), monitor_par(1920, 1080, 530, 300));
+        // Fractional scaling: the logical geometry keeps the monitor proportions
+        assert_eq!(gst::Fraction::new(1, 1), monitor_par(2048, 1152, 600, 340));
+        // Unknown physical size
+        assert_eq!(gst::Fraction::new(1, 1), monitor_par(1920, 1080, 0, 0));
+        // 4:3 monitor driven at a 16:9 resolution
+        assert_eq!(gst::Fraction::new(3, 4), monitor_par(1280, 720, 400, 300));
+    }
+}
@@ -0,0 +1,146 @@
+use glib::clone;
+use gtk::prelude::*;
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    media::{Timestamp, Waveform},
+    metadata::Duration,
+};
+
+const WAVEFORM_ALPHA: f64 = 0.45;
+const CHAPTER_ALPHA: f64 = 0.7;
+const CHAPTER_DASH: f64 = 2f64;
+
+#[derive(Default)]
+struct StripContent {
+    waveform: Option<Waveform>,
+    duration: Duration,
+    position: Timestamp,
+    chapter_starts: Vec<Timestamp>,
+}
+
+impl StripContent {
+    fn x(&self, ts: Timestamp, width: f64) -> f64 {
+        if self.duration == Duration::default() {
+            return 0f64;
+        }
+
+        (ts.as_f64() / self.duration.as_f64() * width).floor() + 0.5f64
+    }
+
+    fn draw(&self, drawingarea: &gtk::DrawingArea, cr: &cairo::Context) {
+        let waveform = match self.waveform.as_ref() {
+            Some(waveform) if self.duration > Duration::default() => waveform,
+            _ => return,
+        };
+
+        let allocation = drawingarea.get_allocation();
+        let width = f64::from(allocation.width);
+        let height = f64::from(allocation.height);
+        let half_height = height / 2f64;
+        let color = drawingarea
+            .get_style_context()
+            .get_color(gtk::StateFlags::NORMAL);
+
+        // One column per pixel
+        let duration = self.duration.as_u64();
+        let columns = allocation.width as u64;
+        cr.set_source_rgba(color.red, color.green, color.blue, WAVEFORM_ALPHA);
+        for x in 0..columns {
+            let start = Timestamp::new(duration * x / columns);
+            let end = Timestamp::new(duration * (x + 1) / columns);
+            let peak_height = (waveform.peak(start, end) * half_height).max(0.5f64);
+            cr.rectangle(
+                x as f64,
+                half_height - peak_height,
+                1f64,
+                2f64 * peak_height,
+            );
+        }
+        cr.fill();
+
+        cr.set_line_width(1f64);
+        cr.set_source_rgba(color.red, color.green, color.blue, CHAPTER_ALPHA);
+        cr.set_dash(&[CHAPTER_DASH], 0f64);
+        for start in &self.chapter_starts {
+            let x = self.x(*start, width);
+            cr.move_to(x, 0f64);
+            cr.line_to(x, height);
+        }
+        cr.stroke();
+
+        cr.set_dash(&[], 0f64);
+        cr.set_source_rgba(color.red, color.green, color.blue, 1f64);
+        let x = self.x(self.position, width);
+        cr.move_to(x, 0f64);
+        cr.line_to(x, height);
+        cr.stroke();
+    }
+}
+
+/// Displays the `Waveform` of the media under the timeline.
+///
+/// The current position and the chapter boundaries are drawn over the waveform.
+/// The strip is hidden as long as no `Waveform` is available.
+pub struct WaveformStrip {
+    drawingarea: gtk::DrawingArea,
+    content: Rc<RefCell<StripContent>>,
+}
+
+impl WaveformStrip {
+    pub fn new(drawingarea: gtk::DrawingArea) -> Self {
+        let content = Rc::new(RefCell::new(StripContent::default()));
+        drawingarea.connect_draw(clone!(@strong content => move |drawingarea, cr| {
+            content.borrow().draw(drawingarea, cr);
+            Inhibit(true)
+        }));
+
+        WaveformStrip {
+            drawingarea,
+            content,
+        }
+    }
+
+    pub fn set_duration(&self, duration: Duration) {
+        self.content.borrow_mut().duration = duration;
+        self.drawingarea.queue_draw();
+    }
+
+    pub fn set_waveform(&self, waveform: Waveform) {
+        if waveform.is_empty() {
+            return;
+        }
+
+        self.content.borrow_mut().waveform = Some(waveform);
+        self.drawingarea.show();
+        self.drawingarea.queue_draw();
+    }
+
+    pub fn set_chapter_starts(&self, chapter_starts: impl Iterator<Item = Timestamp>) {
+        let mut content = self.content.borrow_mut();
+        content.chapter_starts.clear();
+        content.chapter_starts.extend(chapter_starts);
+        self.drawingarea.queue_draw();
+    }
+
+    pub fn update_position(&self, ts: Timestamp) {
+        let mut content = self.content.borrow_mut();
+        if content.waveform.is_none() {
+            return;
+        }
+
+        // Only redraw when the position moves by at least a pixel
+        let width = f64::from(self.drawingarea.get_allocated_width());
+        let prev_x = content.x(content.position, width);
+        content.position = ts;
+        if (content.x(ts, width) - prev_x).abs() >= 1f64 {
+            self.drawingarea.queue_draw();
+        }
+    }
+
+    pub fn cleanup(&self) {
+        *self.content.borrow_mut() = StripContent::default();
+        self.drawingarea.hide();
+    }
+}